@@ -0,0 +1,61 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! Compares [`Statement::bind_parameter_index`]'s cached lookups
+//! against scanning the statement's parameters on every call,
+//! which is what `sqlite3_bind_parameter_index` does.
+//! Run with `cargo bench --bench bind_by_name`.
+
+use std::ffi::{CStr, CString};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use zombiezen_sqlite::{Connection, OpenFlags, Statement};
+
+const ITERATIONS: u32 = 100_000;
+
+fn main() {
+    let conn =
+        Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+    for n_params in [1, 8, 32] {
+        let names = (0..n_params)
+            .map(|i| format!(":param{}", i))
+            .collect::<Vec<_>>();
+        let sql = format!("SELECT {};", names.join(", "));
+        let stmt = conn.prepare(&sql).0.unwrap().unwrap();
+
+        let raw = time(|| {
+            for name in &names {
+                black_box(scan_parameter_index(&stmt, black_box(name)));
+            }
+        });
+        let cached = time(|| {
+            for name in &names {
+                black_box(stmt.bind_parameter_index(black_box(name)));
+            }
+        });
+        let per_lookup = |d: Duration| d / (ITERATIONS * n_params);
+        println!(
+            "{:2} parameters: scan {:?}/lookup, cached {:?}/lookup",
+            n_params,
+            per_lookup(raw),
+            per_lookup(cached),
+        );
+    }
+}
+
+/// Looks up a parameter the way `sqlite3_bind_parameter_index` does:
+/// converting the name to a C string and comparing it against each parameter name.
+fn scan_parameter_index(stmt: &Statement<'_>, name: &str) -> Option<usize> {
+    let name = CString::new(name).ok()?;
+    (1..=stmt.bind_parameter_count())
+        .find(|&i| stmt.bind_parameter_name(i) == Some(name.as_c_str()))
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed()
+}
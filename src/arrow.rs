@@ -0,0 +1,342 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow_array::builder::{BinaryBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType as ArrowDataType, Field, Schema, SchemaRef};
+
+use crate::*;
+
+impl<'c> Statement<'c> {
+    /// Returns an iterator that evaluates the statement
+    /// and collects the resulting rows into Apache Arrow [`RecordBatch`]es
+    /// of at most `batch_size` rows each.
+    ///
+    /// SQLite columns are dynamically typed,
+    /// so the Arrow schema is inferred from the values in the first batch:
+    /// a column containing only `INTEGER` values becomes [`Int64`][ArrowDataType::Int64],
+    /// a mix of `INTEGER` and `REAL` values becomes [`Float64`][ArrowDataType::Float64],
+    /// and so on.
+    /// If a column only contains `NULL`s in the first batch,
+    /// then its type is derived from the column's declared type,
+    /// falling back to [`Utf8`][ArrowDataType::Utf8].
+    /// Once the schema is determined,
+    /// values in later batches are [converted] to the column's type.
+    /// Every field in the schema is nullable.
+    ///
+    /// [converted]: https://www.sqlite.org/c3ref/column_blob.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    pub fn to_record_batches(&mut self, batch_size: usize) -> RecordBatches<'_, 'c> {
+        assert!(batch_size > 0, "batch_size must be positive");
+        RecordBatches {
+            stmt: self,
+            batch_size,
+            schema: None,
+            done: false,
+        }
+    }
+}
+
+/// An iterator of Apache Arrow [`RecordBatch`]es
+/// returned by [`Statement::to_record_batches`].
+#[derive(Debug)]
+pub struct RecordBatches<'s, 'c> {
+    stmt: &'s mut Statement<'c>,
+    batch_size: usize,
+    schema: Option<SchemaRef>,
+    done: bool,
+}
+
+impl<'s, 'c> RecordBatches<'s, 'c> {
+    /// Returns the schema of the batches,
+    /// or `None` if the first batch has not been read yet.
+    pub fn schema(&self) -> Option<SchemaRef> {
+        self.schema.clone()
+    }
+
+    /// Steps the statement, reporting whether a row is available.
+    fn step(&mut self) -> Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        let has_row = self
+            .stmt
+            .step()
+            .inspect_err(|_| self.done = true)?
+            .has_row();
+        self.done = !has_row;
+        Ok(has_row)
+    }
+
+    /// Reads the first batch, using its values to infer the schema.
+    fn first_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let n = self.stmt.column_count();
+        let mut rows = Vec::new();
+        while rows.len() < self.batch_size && self.step()? {
+            rows.push(
+                (0..n)
+                    .map(|i| self.stmt.column_value(i).dup())
+                    .collect::<Vec<_>>(),
+            );
+        }
+        if rows.is_empty() {
+            return Ok(None);
+        }
+        let fields = (0..n)
+            .map(|i| {
+                let data_type = infer_type(
+                    rows.iter().map(|row| row[i].r#type()),
+                    self.stmt.column_decltype(i).as_deref(),
+                );
+                Field::new(
+                    self.stmt.column_name(i).unwrap_or_default(),
+                    data_type,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+        let schema = Arc::new(Schema::new(fields));
+        self.schema = Some(schema.clone());
+
+        let mut columns = new_column_builders(&schema, rows.len());
+        for row in rows.iter_mut() {
+            for (col, value) in columns.iter_mut().zip(row.iter_mut()) {
+                col.append_value(&mut value.as_mut());
+            }
+        }
+        finish_batch(schema, columns).map(Some)
+    }
+
+    fn next_batch(&mut self, schema: SchemaRef) -> Result<Option<RecordBatch>> {
+        let mut columns = new_column_builders(&schema, self.batch_size);
+        let mut n_rows = 0;
+        while n_rows < self.batch_size && self.step()? {
+            for (i, col) in columns.iter_mut().enumerate() {
+                col.append_column(self.stmt, i);
+            }
+            n_rows += 1;
+        }
+        if n_rows == 0 {
+            return Ok(None);
+        }
+        finish_batch(schema, columns).map(Some)
+    }
+}
+
+impl<'s, 'c> Iterator for RecordBatches<'s, 'c> {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.schema.clone() {
+            None => self.first_batch(),
+            Some(schema) => self.next_batch(schema),
+        }
+        .transpose()
+    }
+}
+
+/// Chooses an Arrow type for a column
+/// given the SQLite types of the values observed in it.
+fn infer_type(types: impl Iterator<Item = DataType>, decltype: Option<&str>) -> ArrowDataType {
+    let types = types.filter(|t| !t.is_null()).collect::<HashSet<_>>();
+    if types.is_empty() {
+        return decltype.map_or(ArrowDataType::Utf8, decltype_to_arrow);
+    }
+    let only = |allowed: &[DataType]| types.iter().all(|t| allowed.contains(t));
+    if only(&[DataType::Integer]) {
+        ArrowDataType::Int64
+    } else if only(&[DataType::Integer, DataType::Float]) {
+        ArrowDataType::Float64
+    } else if types.contains(&DataType::Blob) {
+        ArrowDataType::Binary
+    } else {
+        ArrowDataType::Utf8
+    }
+}
+
+/// Maps a declared column type to an Arrow type
+/// using SQLite's [column affinity] rules.
+///
+/// [column affinity]: https://www.sqlite.org/datatype3.html#determination_of_column_affinity
+fn decltype_to_arrow(decltype: &str) -> ArrowDataType {
+    match Affinity::from_decltype(decltype) {
+        Affinity::Integer => ArrowDataType::Int64,
+        Affinity::Text => ArrowDataType::Utf8,
+        Affinity::Blob => ArrowDataType::Binary,
+        Affinity::Real | Affinity::Numeric => ArrowDataType::Float64,
+    }
+}
+
+fn new_column_builders(schema: &Schema, capacity: usize) -> Vec<ColumnBuilder> {
+    schema
+        .fields()
+        .iter()
+        .map(|field| ColumnBuilder::new(field.data_type(), capacity))
+        .collect()
+}
+
+fn finish_batch(schema: SchemaRef, columns: Vec<ColumnBuilder>) -> Result<RecordBatch> {
+    let columns = columns.into_iter().map(ColumnBuilder::finish).collect();
+    RecordBatch::try_new(schema, columns)
+        .map_err(|err| Error::new(ResultCode::ERROR, err.to_string()))
+}
+
+/// An Arrow array builder for one of the types produced by [`infer_type`].
+enum ColumnBuilder {
+    Int64(Int64Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    Binary(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &ArrowDataType, capacity: usize) -> Self {
+        match data_type {
+            ArrowDataType::Int64 => ColumnBuilder::Int64(Int64Builder::with_capacity(capacity)),
+            ArrowDataType::Float64 => {
+                ColumnBuilder::Float64(Float64Builder::with_capacity(capacity))
+            }
+            ArrowDataType::Binary => {
+                ColumnBuilder::Binary(BinaryBuilder::with_capacity(capacity, 0))
+            }
+            _ => ColumnBuilder::Utf8(StringBuilder::with_capacity(capacity, 0)),
+        }
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            ColumnBuilder::Int64(b) => b.append_null(),
+            ColumnBuilder::Float64(b) => b.append_null(),
+            ColumnBuilder::Utf8(b) => b.append_null(),
+            ColumnBuilder::Binary(b) => b.append_null(),
+        }
+    }
+
+    /// Appends the `i`th column of the statement's current row.
+    fn append_column(&mut self, stmt: &mut Statement<'_>, i: usize) {
+        if stmt.column_type(i).is_null() {
+            self.append_null();
+            return;
+        }
+        match self {
+            ColumnBuilder::Int64(b) => b.append_value(stmt.column_i64(i)),
+            ColumnBuilder::Float64(b) => b.append_value(stmt.column_f64(i)),
+            ColumnBuilder::Utf8(b) => b.append_value(stmt.column_text(i).to_string_lossy()),
+            ColumnBuilder::Binary(b) => b.append_value(stmt.column_blob(i)),
+        }
+    }
+
+    fn append_value(&mut self, value: &mut ProtectedValue<'_>) {
+        if value.is_null() {
+            self.append_null();
+            return;
+        }
+        match self {
+            ColumnBuilder::Int64(b) => b.append_value(value.to_i64()),
+            ColumnBuilder::Float64(b) => b.append_value(value.to_f64()),
+            // sqlite3_value_blob returns the UTF-8 text for TEXT values.
+            ColumnBuilder::Utf8(b) => b.append_value(String::from_utf8_lossy(value.to_blob())),
+            ColumnBuilder::Binary(b) => b.append_value(value.to_blob()),
+        }
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Int64(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Float64(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Utf8(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Binary(mut b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{Array, Int64Array, StringArray};
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_to_record_batches() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare(
+                "WITH t(id, name) AS (VALUES (1, 'foo'), (NULL, 'bar'), (3, NULL)) \
+                SELECT id, name FROM t;",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let batches = stmt
+            .to_record_batches(100)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 3);
+
+        let schema = batch.schema();
+        assert_eq!(schema.field(0).name(), "id");
+        assert_eq!(schema.field(0).data_type(), &ArrowDataType::Int64);
+        assert_eq!(schema.field(1).name(), "name");
+        assert_eq!(schema.field(1).data_type(), &ArrowDataType::Utf8);
+
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.null_count(), 1);
+        assert_eq!(ids.value(0), 1);
+        assert!(ids.is_null(1));
+        assert_eq!(ids.value(2), 3);
+
+        let names = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(names.null_count(), 1);
+        assert_eq!(names.value(0), "foo");
+        assert_eq!(names.value(1), "bar");
+        assert!(names.is_null(2));
+    }
+
+    #[test]
+    fn test_to_record_batches_splits_rows() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT * FROM (VALUES (1), (2), (3.5));")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let batches = stmt
+            .to_record_batches(2)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            batches
+                .iter()
+                .map(RecordBatch::num_rows)
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        // Schema is fixed by the first batch.
+        assert_eq!(
+            batches[1].schema().field(0).data_type(),
+            &ArrowDataType::Int64
+        );
+    }
+}
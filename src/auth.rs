@@ -3,6 +3,7 @@
 
 use std::ffi::{c_char, c_int, c_void, CStr};
 use std::mem;
+use std::ops::Deref;
 use std::ptr;
 
 use libsqlite3_sys::{
@@ -28,38 +29,154 @@ impl Connection {
     /// are being compiled by [`Conn::prepare`].
     #[doc(alias = "sqlite3_set_authorizer")]
     pub fn set_authorizer(&mut self, f: impl Fn(AuthAction) -> AuthResult + 'static) -> Result<()> {
-        const BOX_SIZE: c_int = mem::size_of::<AuthorizerFn>() as c_int;
-        let f = Box::new(f);
-        let user_data = unsafe {
-            let user_data = sqlite3_malloc(BOX_SIZE).cast::<AuthorizerFn>();
-            ptr::write(user_data, f);
-            user_data
-        };
-        let rc = ResultCode(unsafe {
-            sqlite3_set_authorizer(self.as_ptr(), Some(authorizer_callback), user_data.cast())
-        });
-        if !rc.is_success() {
-            return Err(self.as_ref().error().unwrap());
-        }
+        let prev = self.replace_authorizer(new_authorizer(f))?;
         unsafe {
-            free_authorizer(self.authorizer);
+            free_authorizer(prev);
         }
-        self.authorizer = user_data;
         Ok(())
     }
 
     /// Disable the authorizer.
     pub fn clear_authorizer(&mut self) -> Result<()> {
-        let rc =
-            ResultCode(unsafe { sqlite3_set_authorizer(self.as_ptr(), None, ptr::null_mut()) });
+        let prev = self.replace_authorizer(ptr::null_mut())?;
+        unsafe {
+            free_authorizer(prev);
+        }
+        Ok(())
+    }
+
+    /// Installs an authorizer that denies any action that would write to the database
+    /// until the returned guard is dropped,
+    /// at which point the previous authorizer (if any) is restored.
+    /// While the guard is active,
+    /// actions that do not write are passed to the previous authorizer.
+    ///
+    /// Statements prepared through the guard that try to modify the database
+    /// (including schema changes, `ATTACH`, `ANALYZE`, and any pragma that is not a known read;
+    /// see [`AuthOp::is_write`])
+    /// fail with [`ResultCode::AUTH`].
+    /// This is a lighter-weight alternative to opening a separate read-only connection.
+    pub fn read_only_guard(&mut self) -> Result<ReadOnlyGuard<'_>> {
+        let prev = self.replace_authorizer(ptr::null_mut())?;
+        let f = new_authorizer(move |action| {
+            if action.operation().is_write() {
+                AuthResult::Deny
+            } else if let Some(prev) = unsafe { prev.as_ref() } {
+                prev(action)
+            } else {
+                AuthResult::Ok
+            }
+        });
+        if let Err(err) = self.replace_authorizer(f) {
+            unsafe {
+                free_authorizer(f);
+            }
+            let _ = self.replace_authorizer(prev);
+            return Err(err);
+        }
+        Ok(ReadOnlyGuard { conn: self, prev })
+    }
+
+    /// Installs an authorizer, replacing any previously set authorizer,
+    /// that confines statements to reading and writing the named tables.
+    /// Table names are compared case-insensitively like SQLite identifiers.
+    ///
+    /// `SELECT` statements, function calls, recursive queries,
+    /// and transaction and savepoint statements are allowed.
+    /// Reading or modifying any other table
+    /// (including through a view or trigger)
+    /// and all other statements
+    /// (such as schema changes, `PRAGMA`, and `ATTACH`)
+    /// fail with [`ResultCode::AUTH`] when prepared.
+    /// This is suitable for running untrusted SQL
+    /// against a subset of a database.
+    /// Use [`Connection::clear_authorizer`] to lift the restriction.
+    pub fn restrict_to_tables(&mut self, tables: &[&str]) -> Result<()> {
+        let tables: Vec<Vec<u8>> = tables
+            .iter()
+            .map(|name| name.as_bytes().to_ascii_lowercase())
+            .collect();
+        self.set_authorizer(move |action| {
+            match action.operation() {
+                AuthOp::Select
+                | AuthOp::Function { .. }
+                | AuthOp::Recursive
+                | AuthOp::Transaction { .. }
+                | AuthOp::Savepoint { .. } => AuthResult::Ok,
+                // Reads from common table expressions and subqueries
+                // are not associated with a database.
+                AuthOp::Read { .. } if action.database().is_none() => AuthResult::Ok,
+                AuthOp::Read { table_name, .. }
+                | AuthOp::Insert { table_name }
+                | AuthOp::Update { table_name, .. }
+                | AuthOp::Delete { table_name } => {
+                    let allowed = tables
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(table_name.to_bytes()));
+                    if allowed {
+                        AuthResult::Ok
+                    } else {
+                        AuthResult::Deny
+                    }
+                }
+                _ => AuthResult::Deny,
+            }
+        })
+    }
+
+    /// Installs the given authorizer and returns the previously installed one
+    /// without freeing it.
+    fn replace_authorizer(&mut self, user_data: *mut AuthorizerFn) -> Result<*mut AuthorizerFn> {
+        let rc = ResultCode(unsafe {
+            if user_data.is_null() {
+                sqlite3_set_authorizer(self.as_ptr(), None, ptr::null_mut())
+            } else {
+                sqlite3_set_authorizer(self.as_ptr(), Some(authorizer_callback), user_data.cast())
+            }
+        });
         if !rc.is_success() {
             return Err(self.as_ref().error().unwrap());
         }
-        unsafe {
-            free_authorizer(self.authorizer);
+        Ok(mem::replace(&mut self.authorizer, user_data))
+    }
+}
+
+/// A guard returned by [`Connection::read_only_guard`]
+/// that prevents statements prepared through it from writing to the database.
+#[derive(Debug)]
+pub struct ReadOnlyGuard<'a> {
+    conn: &'a mut Connection,
+    prev: *mut AuthorizerFn,
+}
+
+impl<'a> Deref for ReadOnlyGuard<'a> {
+    type Target = Conn;
+
+    fn deref(&self) -> &Conn {
+        self.conn
+    }
+}
+
+impl<'a> Drop for ReadOnlyGuard<'a> {
+    fn drop(&mut self) {
+        // If the authorizer can't be replaced,
+        // then the guard's authorizer still references the previous one,
+        // so the previous authorizer is leaked.
+        if let Ok(guard_authorizer) = self.conn.replace_authorizer(self.prev) {
+            unsafe {
+                free_authorizer(guard_authorizer);
+            }
         }
-        self.authorizer = ptr::null_mut();
-        Ok(())
+    }
+}
+
+fn new_authorizer(f: impl Fn(AuthAction) -> AuthResult + 'static) -> *mut AuthorizerFn {
+    const BOX_SIZE: c_int = mem::size_of::<AuthorizerFn>() as c_int;
+    let f: AuthorizerFn = Box::new(f);
+    unsafe {
+        let user_data = sqlite3_malloc(BOX_SIZE).cast::<AuthorizerFn>();
+        ptr::write(user_data, f);
+        user_data
     }
 }
 
@@ -231,8 +348,9 @@ impl<'a> AuthAction<'a> {
                 table_name: arg1?,
                 module_name: arg2?,
             },
+            // The function name is passed as the second argument.
             SQLITE_FUNCTION => AuthOp::Function {
-                function_name: arg1?,
+                function_name: arg2?,
             },
             SQLITE_SAVEPOINT => AuthOp::Savepoint {
                 operation: arg1?,
@@ -438,6 +556,29 @@ impl<'a> AuthOp<'a> {
         }
     }
 
+    /// Reports whether the operation modifies the database,
+    /// its schema, or the set of attached databases.
+    /// Transaction and savepoint statements are not considered writes.
+    /// A pragma is only considered a read if it is known to have no side effects:
+    /// a pragma that reports information like `table_info(t)`,
+    /// or a setting like `user_version` read without an argument.
+    /// Any other pragma, including actions like `optimize` or `wal_checkpoint`,
+    /// is considered a write.
+    pub fn is_write(self) -> bool {
+        match self {
+            AuthOp::Pragma { pragma_name, arg } => !pragma_name
+                .to_str()
+                .is_ok_and(|name| crate::pragma::is_read_only_pragma(name, arg.is_some())),
+            AuthOp::Read { .. }
+            | AuthOp::Select
+            | AuthOp::Transaction { .. }
+            | AuthOp::Function { .. }
+            | AuthOp::Savepoint { .. }
+            | AuthOp::Recursive => false,
+            _ => true,
+        }
+    }
+
     /// Returns the name of the virtual table module this action affects if any.
     pub fn module_name(self) -> Option<&'a CStr> {
         match self {
@@ -495,4 +636,105 @@ mod tests {
             &result
         );
     }
+
+    #[test]
+    fn test_read_only_guard() {
+        let mut db =
+            Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+        db.execute("CREATE TABLE foo(bar);", ()).unwrap();
+        db.set_authorizer(|action| match action.operation() {
+            AuthOp::Read { column_name, .. } if column_name.to_bytes() == b"bar" => {
+                AuthResult::Deny
+            }
+            _ => AuthResult::Ok,
+        })
+        .unwrap();
+
+        {
+            let guard = db.read_only_guard().unwrap();
+            let err = guard
+                .execute("INSERT INTO foo VALUES (1);", ())
+                .unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::AUTH);
+            let err = guard.execute("PRAGMA user_version = 1;", ()).unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::AUTH);
+            let column = guard
+                .query_row("PRAGMA table_info(foo);", (), |stmt| {
+                    Ok(stmt.column_text(1).unwrap().to_string())
+                })
+                .unwrap();
+            assert_eq!(column.as_deref(), Some("bar"));
+            guard.execute("PRAGMA integrity_check(10);", ()).unwrap();
+            guard.execute("PRAGMA Table_Info(foo);", ()).unwrap();
+            guard.execute("PRAGMA USER_VERSION;", ()).unwrap();
+            for sql in [
+                "PRAGMA optimize;",
+                "PRAGMA OPTIMIZE;",
+                "PRAGMA wal_checkpoint;",
+                "PRAGMA incremental_vacuum;",
+                "PRAGMA shrink_memory;",
+            ] {
+                let err = guard.execute(sql, ()).unwrap_err();
+                assert_eq!(err.result_code(), ResultCode::AUTH, "{}", sql);
+            }
+            let n = guard
+                .query_row("SELECT count(*) FROM foo;", (), |stmt| {
+                    Ok(stmt.column_i64(0))
+                })
+                .unwrap();
+            assert_eq!(n, Some(0));
+            // The previous authorizer is still consulted.
+            let err = guard.execute("SELECT bar FROM foo;", ()).unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::AUTH);
+        }
+
+        db.execute("INSERT INTO foo VALUES (1);", ()).unwrap();
+    }
+
+    #[test]
+    fn test_restrict_to_tables() {
+        let mut db =
+            Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+        db.execute("CREATE TABLE allowed (x);", ()).unwrap();
+        db.execute("CREATE TABLE secret (y);", ()).unwrap();
+        db.execute("INSERT INTO secret VALUES (42);", ()).unwrap();
+        db.execute("CREATE VIEW leak AS SELECT y FROM secret;", ())
+            .unwrap();
+        db.restrict_to_tables(&["Allowed"]).unwrap();
+
+        db.execute("INSERT INTO allowed VALUES (abs(-1));", ())
+            .unwrap();
+        db.execute("UPDATE allowed SET x = x + 1;", ()).unwrap();
+        let got = db
+            .query_row(
+                "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 3) \
+                SELECT sum(x) FROM allowed, c;",
+                (),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap();
+        assert_eq!(got, Some(6));
+
+        for sql in [
+            "SELECT y FROM secret;",
+            "SELECT x FROM allowed JOIN secret ON x = y;",
+            "SELECT * FROM leak;",
+            "DELETE FROM secret;",
+            "CREATE TABLE t (z);",
+            "PRAGMA user_version = 1;",
+        ] {
+            let result = db.prepare(sql).0;
+            assert!(
+                result
+                    .as_ref()
+                    .is_err_and(|err| err.result_code() == ResultCode::AUTH),
+                "prepare({:?}) = {:?}; want AUTH error",
+                sql,
+                &result
+            );
+        }
+
+        db.clear_authorizer().unwrap();
+        db.execute("DELETE FROM secret;", ()).unwrap();
+    }
 }
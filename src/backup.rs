@@ -0,0 +1,177 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use libsqlite3_sys::{
+    sqlite3_backup_finish, sqlite3_backup_init, sqlite3_backup_step, SQLITE_DONE,
+};
+
+use crate::*;
+
+impl Conn {
+    /// Writes a copy of the main database to the file at `path`,
+    /// replacing any existing file atomically.
+    ///
+    /// The database is copied with the [online backup API]
+    /// into a temporary file in the same directory as `path`,
+    /// which is flushed to disk and then renamed to `path`.
+    /// If the process crashes partway through,
+    /// `path` contains either its previous contents or the complete copy,
+    /// never a partial database.
+    /// This is intended for applications that work with an in-memory database
+    /// and periodically save a snapshot to disk,
+    /// but it works for any database.
+    ///
+    /// `path` should not be open by any other connection,
+    /// since replacing the file underneath it can corrupt the database.
+    ///
+    /// [online backup API]: https://www.sqlite.org/backup.html
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE foo (x INTEGER);", ())?;
+    /// conn.persist_to("snapshot.db".as_ref())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "sqlite3_backup_init")]
+    pub fn persist_to(&self, path: &Path) -> Result<()> {
+        let tmp_path = persist_temp_path(path);
+        let result = self.backup_to_file(&tmp_path).and_then(|_| {
+            // Windows only allows flushing a handle opened for writing.
+            fs::OpenOptions::new()
+                .write(true)
+                .open(&tmp_path)
+                .and_then(|f| f.sync_all())
+                .and_then(|_| fs::rename(&tmp_path, path))
+                .map_err(persist_io_error)
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+            return result;
+        }
+        #[cfg(unix)]
+        {
+            // Make the rename itself durable.
+            // A relative path with no directory component is in the current directory.
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            fs::File::open(dir)
+                .and_then(|f| f.sync_all())
+                .map_err(persist_io_error)?;
+        }
+        Ok(())
+    }
+
+    /// Copies the main database into a new database file at `path`.
+    fn backup_to_file(&self, path: &Path) -> Result<()> {
+        let filename = path
+            .to_str()
+            .and_then(|s| CString::new(s).ok())
+            .ok_or_else(|| {
+                Error::new(
+                    ResultCode::MISUSE,
+                    format!("invalid database path {}", path.display()),
+                )
+            })?;
+        let dest = Connection::open(&filename, OpenFlags::default())?;
+        let main = CString::new("main").unwrap();
+        unsafe {
+            let backup =
+                sqlite3_backup_init(dest.as_ptr(), main.as_ptr(), self.as_ptr(), main.as_ptr());
+            if backup.is_null() {
+                return Err(dest
+                    .error()
+                    .expect("sqlite3_backup_init failed without an error"));
+            }
+            let step_rc = sqlite3_backup_step(backup, -1);
+            let finish_rc = ResultCode(sqlite3_backup_finish(backup));
+            if step_rc != SQLITE_DONE {
+                return Err(dest
+                    .error()
+                    .unwrap_or_else(|| Error::new(ResultCode(step_rc), "")));
+            }
+            finish_rc.to_result()?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns a path for a temporary file next to `path`.
+fn persist_temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".tmp-{}-{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+fn persist_io_error(err: std::io::Error) -> Error {
+    Error::new(ResultCode::IOERR, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_persist_to() {
+        let dir = TempDir::new("persist");
+        let path = dir.join("snapshot.db");
+        let path_cstr = dir.join_cstr("snapshot.db");
+
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (1), (2);", ()).unwrap();
+        conn.persist_to(&path).unwrap();
+        {
+            let disk = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            let sum = disk
+                .query_row("SELECT sum(x) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap();
+            assert_eq!(sum, Some(3));
+        }
+
+        // Persisting again replaces the existing file.
+        conn.execute("INSERT INTO t VALUES (3);", ()).unwrap();
+        conn.persist_to(&path).unwrap();
+        {
+            let disk = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            let sum = disk
+                .query_row("SELECT sum(x) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap();
+            assert_eq!(sum, Some(6));
+            let check = disk
+                .query_row("PRAGMA integrity_check;", (), |stmt| {
+                    Ok(stmt.column_text(0).unwrap().to_string())
+                })
+                .unwrap();
+            assert_eq!(check.as_deref(), Some("ok"));
+        }
+        let names = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["snapshot.db"]);
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use crate::*;
+
+impl Conn {
+    /// Begins a transaction with [`BEGIN CONCURRENT`],
+    /// which allows several connections to write to a database at the same time.
+    /// Unlike with `BEGIN`, writing does not take the database's write lock.
+    /// Instead, the lock is only taken while the transaction commits,
+    /// and `COMMIT` fails with a [`ResultCode::BUSY`] error (`SQLITE_BUSY_SNAPSHOT`)
+    /// if another connection has committed a change to any page
+    /// that the transaction read or wrote since it began.
+    /// After such a failure, the transaction is still open,
+    /// and the caller should roll it back and try again.
+    /// Transactions that write to disjoint sets of pages all commit,
+    /// which can greatly increase throughput for write-heavy workloads.
+    ///
+    /// `BEGIN CONCURRENT` is only available in builds of SQLite
+    /// from the [begin-concurrent branch],
+    /// and only on databases in [WAL mode].
+    /// If SQLite was built without it,
+    /// then a [`ResultCode::ERROR`] error is returned
+    /// and no transaction is started.
+    /// The caller is responsible for ending the transaction
+    /// with `COMMIT` or `ROLLBACK`.
+    ///
+    /// [`BEGIN CONCURRENT`]: https://www.sqlite.org/src/doc/begin-concurrent/doc/begin_concurrent.md
+    /// [begin-concurrent branch]: https://www.sqlite.org/src/timeline?r=begin-concurrent
+    /// [WAL mode]: https://www.sqlite.org/wal.html
+    #[doc(alias = "BEGIN CONCURRENT")]
+    pub fn begin_concurrent(&self) -> Result<()> {
+        if !self.get_autocommit() {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "begin_concurrent called inside a transaction",
+            ));
+        }
+        if !self.has_begin_concurrent() {
+            return Err(Error::new(
+                ResultCode::ERROR,
+                format!(
+                    "BEGIN CONCURRENT is not supported by this build of SQLite ({})",
+                    version()
+                ),
+            ));
+        }
+        self.execute("BEGIN CONCURRENT;", ()).map(|_| ())
+    }
+
+    /// Reports whether SQLite understands `BEGIN CONCURRENT`.
+    /// There is no compile option for the begin-concurrent branch,
+    /// so this checks whether the statement compiles.
+    fn has_begin_concurrent(&self) -> bool {
+        self.prepare_single("BEGIN CONCURRENT;").is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_begin_concurrent() {
+        let dir = TempDir::new("begin-concurrent");
+        let filename = dir.join_cstr("test.db");
+        let a = Connection::open(&filename, OpenFlags::default()).unwrap();
+        let b = Connection::open(&filename, OpenFlags::default()).unwrap();
+
+        if !a.has_begin_concurrent() {
+            let err = a.begin_concurrent().unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::ERROR);
+            assert!(
+                err.message().contains("BEGIN CONCURRENT is not supported"),
+                "{}",
+                err.message()
+            );
+            assert!(a.get_autocommit());
+        } else {
+            a.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+            // Pad the rows so that the first and last rows are on different pages.
+            a.execute(
+                "CREATE TABLE t (id INTEGER PRIMARY KEY, pad TEXT, n INTEGER);",
+                (),
+            )
+            .unwrap();
+            a.execute(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000) \
+                INSERT INTO t SELECT x, printf('%.500c', 'x'), 0 FROM c;",
+                (),
+            )
+            .unwrap();
+
+            a.begin_concurrent().unwrap();
+            b.begin_concurrent().unwrap();
+            a.execute("UPDATE t SET n = 1 WHERE id = 1;", ()).unwrap();
+            b.execute("UPDATE t SET n = 2 WHERE id = 1000;", ())
+                .unwrap();
+            a.execute("COMMIT;", ()).unwrap();
+            b.execute("COMMIT;", ()).unwrap();
+
+            let sum = a
+                .query_row("SELECT sum(n) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap();
+            assert_eq!(sum, Some(3));
+        }
+
+        a.execute("BEGIN;", ()).unwrap();
+        assert_eq!(
+            a.begin_concurrent().unwrap_err().result_code(),
+            ResultCode::MISUSE
+        );
+        a.execute("ROLLBACK;", ()).unwrap();
+    }
+}
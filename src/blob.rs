@@ -0,0 +1,340 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_int, CString};
+use std::fmt::{self, Debug};
+use std::io;
+use std::ptr::{self, NonNull};
+
+use libsqlite3_sys::{
+    sqlite3_blob, sqlite3_blob_bytes, sqlite3_blob_close, sqlite3_blob_open, sqlite3_blob_read,
+    sqlite3_blob_reopen, sqlite3_blob_write,
+};
+
+use crate::connection::DbMutexGuard;
+use crate::*;
+
+/// A handle for [incremental I/O] on a single `BLOB` or `TEXT` value,
+/// opened with [`Conn::open_blob`].
+/// The handle can be moved to another row of the same column
+/// with [`Blob::reopen`],
+/// which is much cheaper than opening a new handle.
+///
+/// `Blob` implements [`io::Read`],
+/// which reads sequentially from the start of the value.
+///
+/// [incremental I/O]: https://www.sqlite.org/c3ref/blob_open.html
+#[doc(alias = "sqlite3_blob")]
+pub struct Blob<'c> {
+    ptr: NonNull<sqlite3_blob>,
+    conn: &'c Conn,
+    pos: usize,
+}
+
+impl Conn {
+    /// Opens a handle to the value in the column named `column`
+    /// of the row with the given `rowid` in the table `table` of the database `schema`.
+    /// If `writable` is false, then the handle is read-only.
+    ///
+    /// The value must be a `BLOB` or `TEXT`;
+    /// opening any other type of value returns an error.
+    /// The handle cannot change the size of the value.
+    #[doc(alias = "sqlite3_blob_open")]
+    pub fn open_blob(
+        &self,
+        schema: &str,
+        table: &str,
+        column: &str,
+        rowid: i64,
+        writable: bool,
+    ) -> Result<Blob<'_>> {
+        let [schema, table, column] = [schema, table, column].map(|s| {
+            CString::new(s).map_err(|_| {
+                Error::new(
+                    ResultCode::MISUSE,
+                    format!("name {:?} contains a NUL byte", s),
+                )
+            })
+        });
+        let (schema, table, column) = (schema?, table?, column?);
+        let mut ptr = ptr::null_mut();
+        let _guard = DbMutexGuard::lock(self.as_ptr());
+        let rc = ResultCode(unsafe {
+            sqlite3_blob_open(
+                self.as_ptr(),
+                schema.as_ptr(),
+                table.as_ptr(),
+                column.as_ptr(),
+                rowid,
+                writable as c_int,
+                &mut ptr,
+            )
+        });
+        match NonNull::new(ptr) {
+            Some(ptr) if rc.is_success() => Ok(Blob {
+                ptr,
+                conn: self,
+                pos: 0,
+            }),
+            _ => Err(self.error().unwrap_or_else(|| Error::new(rc, ""))),
+        }
+    }
+
+    /// Calls `f` with a read-only handle to the value in the column named `column`
+    /// of each row in the main database's table `table` whose rowid is yielded by `rowids`.
+    /// A single handle is [reopened][Blob::reopen] for each row,
+    /// which is much faster than opening a handle per row
+    /// when streaming many values, such as thumbnails.
+    /// Returns the number of values passed to `f`.
+    ///
+    /// Rows that do not exist and rows whose value is `NULL` or a number are skipped.
+    /// If `f` returns an error, then iteration stops and the error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use std::io::Read;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE images (id INTEGER PRIMARY KEY, thumbnail BLOB);", ())?;
+    /// conn.execute("INSERT INTO images VALUES (1, x'89504e47'), (2, NULL);", ())?;
+    /// let mut thumbnails = Vec::new();
+    /// conn.stream_blob_column("images", "thumbnail", [1, 2], |id, blob| {
+    ///     let mut data = Vec::new();
+    ///     blob.read_to_end(&mut data).unwrap();
+    ///     thumbnails.push((id, data));
+    ///     Ok(())
+    /// })?;
+    /// assert_eq!(thumbnails, [(1, b"\x89PNG".to_vec())]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stream_blob_column(
+        &self,
+        table: &str,
+        column: &str,
+        rowids: impl IntoIterator<Item = i64>,
+        mut f: impl FnMut(i64, &mut Blob<'_>) -> Result<()>,
+    ) -> Result<usize> {
+        let mut blob: Option<Blob<'_>> = None;
+        let mut n = 0;
+        for rowid in rowids {
+            let result = match blob.as_mut() {
+                Some(b) => b.reopen(rowid),
+                None => self
+                    .open_blob("main", table, column, rowid, false)
+                    .map(|b| blob = Some(b)),
+            };
+            if let Err(err) = result {
+                // A failed reopen aborts the handle, so open a fresh one for the next row.
+                blob = None;
+                if self.has_blob_value(table, column, rowid)? {
+                    return Err(err);
+                }
+                continue;
+            }
+            f(rowid, blob.as_mut().unwrap())?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    /// Reports whether the row exists and its value can be opened as a blob.
+    fn has_blob_value(&self, table: &str, column: &str, rowid: i64) -> Result<bool> {
+        let value_type = self.query_row(
+            &format!(
+                "SELECT typeof({}) FROM main.{} WHERE rowid = ?1;",
+                Quote::as_id(column),
+                Quote::as_id(table)
+            ),
+            (rowid,),
+            |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+        )?;
+        Ok(matches!(value_type.as_deref(), Some("blob" | "text")))
+    }
+}
+
+impl<'c> Blob<'c> {
+    /// Returns the size of the value in bytes.
+    #[doc(alias = "sqlite3_blob_bytes")]
+    pub fn len(&self) -> usize {
+        unsafe { sqlite3_blob_bytes(self.ptr.as_ptr()) as usize }
+    }
+
+    /// Reports whether the value has a size of zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Moves the handle to the row with the given `rowid`
+    /// in the same table and column,
+    /// and resets the [`io::Read`] position to the start of the value.
+    ///
+    /// If the row does not exist or its value is not a `BLOB` or `TEXT`,
+    /// then an error is returned
+    /// and all further operations on the handle fail.
+    #[doc(alias = "sqlite3_blob_reopen")]
+    pub fn reopen(&mut self, rowid: i64) -> Result<()> {
+        let _guard = DbMutexGuard::lock(self.conn.as_ptr());
+        let rc = ResultCode(unsafe { sqlite3_blob_reopen(self.ptr.as_ptr(), rowid) });
+        self.pos = 0;
+        self.check(rc)
+    }
+
+    /// Reads `buf.len()` bytes of the value starting at `offset` into `buf`.
+    /// Returns an error if the range extends past the end of the value.
+    #[doc(alias = "sqlite3_blob_read")]
+    pub fn read_at(&self, buf: &mut [u8], offset: usize) -> Result<()> {
+        let (n, offset) = blob_range(buf.len(), offset)?;
+        let _guard = DbMutexGuard::lock(self.conn.as_ptr());
+        let rc = ResultCode(unsafe {
+            sqlite3_blob_read(self.ptr.as_ptr(), buf.as_mut_ptr().cast(), n, offset)
+        });
+        self.check(rc)
+    }
+
+    /// Writes `data` to the value starting at `offset`.
+    /// Returns an error if the handle is read-only
+    /// or if the range extends past the end of the value.
+    #[doc(alias = "sqlite3_blob_write")]
+    pub fn write_at(&mut self, data: &[u8], offset: usize) -> Result<()> {
+        let (n, offset) = blob_range(data.len(), offset)?;
+        let _guard = DbMutexGuard::lock(self.conn.as_ptr());
+        let rc = ResultCode(unsafe {
+            sqlite3_blob_write(self.ptr.as_ptr(), data.as_ptr().cast(), n, offset)
+        });
+        self.check(rc)
+    }
+
+    fn check(&self, rc: ResultCode) -> Result<()> {
+        if rc.is_success() {
+            Ok(())
+        } else {
+            Err(self.conn.error().unwrap_or_else(|| Error::new(rc, "")))
+        }
+    }
+}
+
+fn blob_range(len: usize, offset: usize) -> Result<(c_int, c_int)> {
+    match (c_int::try_from(len), c_int::try_from(offset)) {
+        (Ok(len), Ok(offset)) => Ok((len, offset)),
+        _ => Err(Error::new(ResultCode::TOOBIG, "blob range too large")),
+    }
+}
+
+impl<'c> io::Read for Blob<'c> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.len().saturating_sub(self.pos));
+        self.read_at(&mut buf[..n], self.pos)
+            .map_err(io::Error::other)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<'c> Debug for Blob<'c> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Blob")
+            .field("ptr", &self.ptr)
+            .field("pos", &self.pos)
+            .finish()
+    }
+}
+
+impl<'c> Drop for Blob<'c> {
+    fn drop(&mut self) {
+        unsafe { sqlite3_blob_close(self.ptr.as_ptr()) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_stream_blob_column() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, data BLOB);", ())
+            .unwrap();
+        conn.execute(
+            "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 100) \
+            INSERT INTO t SELECT n, randomblob(n) FROM c;",
+            (),
+        )
+        .unwrap();
+        let want = conn
+            .query_stream("SELECT id, data FROM t ORDER BY id;", (), |stmt| {
+                Ok((stmt.column_i64(0), stmt.column_blob(1).to_vec()))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+
+        let mut got = Vec::new();
+        let mut handles = Vec::new();
+        let n = conn
+            .stream_blob_column("t", "data", 1..=100, |id, blob| {
+                handles.push(blob.ptr);
+                let mut data = Vec::new();
+                blob.read_to_end(&mut data).unwrap();
+                got.push((id, data));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(n, 100);
+        assert_eq!(got, want);
+        assert!(handles.iter().all(|&h| h == handles[0]));
+
+        // NULL values and missing rows are skipped.
+        conn.execute("UPDATE t SET data = NULL WHERE id IN (1, 3);", ())
+            .unwrap();
+        let mut ids = Vec::new();
+        let n = conn
+            .stream_blob_column("t", "data", [1, 2, 3, 4, 500, 5], |id, blob| {
+                assert_eq!(blob.len(), id as usize);
+                ids.push(id);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(ids, [2, 4, 5]);
+
+        assert!(conn
+            .stream_blob_column("nosuchtable", "data", [1], |_, _| Ok(()))
+            .is_err());
+        let err = conn
+            .stream_blob_column("t", "data", [2, 4], |_, _| {
+                Err(Error::new(ResultCode::ABORT, "stop"))
+            })
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ABORT);
+    }
+
+    #[test]
+    fn test_blob_read_write() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (data BLOB);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (x'00000000');", ())
+            .unwrap();
+        let mut blob = conn.open_blob("main", "t", "data", 1, true).unwrap();
+        assert_eq!(blob.len(), 4);
+        blob.write_at(b"hi", 1).unwrap();
+        assert!(blob.write_at(b"hi", 3).is_err());
+        let mut buf = [0u8; 3];
+        blob.read_at(&mut buf, 0).unwrap();
+        assert_eq!(&buf, b"\0hi");
+        drop(blob);
+
+        let mut blob = conn.open_blob("main", "t", "data", 1, false).unwrap();
+        assert!(blob.write_at(b"x", 0).is_err());
+        assert!(conn.open_blob("main", "t", "data", 2, false).is_err());
+    }
+}
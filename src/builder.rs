@@ -0,0 +1,137 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CStr;
+
+use crate::*;
+
+/// Options for opening a [`Connection`]
+/// that need to be applied before the connection is used.
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{ConnectionBuilder, OpenFlags};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let conn = ConnectionBuilder::new()
+///     .flags(OpenFlags::default() | OpenFlags::MEMORY)
+///     .page_size(16384)
+///     .open(<&CStr>::default())?;
+/// assert_eq!(conn.page_size()?, 16384);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionBuilder {
+    flags: OpenFlags,
+    page_size: Option<u32>,
+    validate_uri: bool,
+}
+
+impl ConnectionBuilder {
+    /// Returns a builder with the default options.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the flags passed to [`Connection::open`].
+    /// Defaults to [`OpenFlags::default`].
+    #[inline]
+    pub fn flags(&mut self, flags: OpenFlags) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Sets the [page size] in bytes of the main database.
+    /// The page size must be a power of two between 512 and 65536.
+    ///
+    /// SQLite only uses the page size when the database is created,
+    /// so [`open`][ConnectionBuilder::open] returns an error
+    /// if the database already exists with a different page size.
+    ///
+    /// [page size]: https://www.sqlite.org/pragma.html#pragma_page_size
+    #[inline]
+    pub fn page_size(&mut self, n: u32) -> &mut Self {
+        self.page_size = Some(n);
+        self
+    }
+
+    /// Sets whether [`open`][ConnectionBuilder::open] checks `file:` URI filenames
+    /// with [`validate_uri`] before opening the database.
+    /// Defaults to false.
+    #[inline]
+    pub fn validate_uri(&mut self, validate: bool) -> &mut Self {
+        self.validate_uri = validate;
+        self
+    }
+
+    /// Opens a connection to the database named by `filename`
+    /// and applies the builder's options.
+    pub fn open(&self, filename: impl AsRef<CStr>) -> Result<Connection> {
+        if self.validate_uri {
+            validate_uri(filename.as_ref())?;
+        }
+        let conn = Connection::open(filename, self.flags)?;
+        if let Some(n) = self.page_size {
+            conn.set_page_size(n)?;
+            let actual = conn.page_size()?;
+            if actual != n {
+                return Err(Error::new(
+                    ResultCode::ERROR,
+                    format!(
+                        "could not set page size to {} (database uses {})",
+                        n, actual
+                    ),
+                ));
+            }
+        }
+        Ok(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_page_size() {
+        let dir = TempDir::new("page-size");
+        let path_cstr = dir.join_cstr("test.db");
+
+        let conn = ConnectionBuilder::new()
+            .page_size(16384)
+            .open(&path_cstr)
+            .unwrap();
+        conn.execute("CREATE TABLE t (x);", ()).unwrap();
+        assert_eq!(conn.page_size().unwrap(), 16384);
+        drop(conn);
+
+        // Reopening with the same page size is fine.
+        ConnectionBuilder::new()
+            .page_size(16384)
+            .open(&path_cstr)
+            .unwrap();
+        // But the page size of an existing database can't be changed.
+        let err = ConnectionBuilder::new()
+            .page_size(4096)
+            .open(&path_cstr)
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+
+    #[test]
+    fn test_invalid_page_size() {
+        let err = ConnectionBuilder::new()
+            .page_size(1000)
+            .open(MEMORY)
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_int, c_void};
+use std::mem;
+use std::ptr;
+
+use libsqlite3_sys::{sqlite3_commit_hook, sqlite3_free, sqlite3_malloc, sqlite3_rollback_hook};
+
+use crate::*;
+
+pub(crate) type TransactionEndFn = Box<dyn FnMut(bool) + 'static>;
+
+impl Connection {
+    /// Register a callback that is invoked whenever a transaction ends,
+    /// replacing any previously set callback on the connection.
+    /// The callback is passed `true` when a transaction is committed
+    /// and `false` when a transaction is rolled back,
+    /// either explicitly or because of an error.
+    ///
+    /// The callback is invoked while the commit or rollback is in progress,
+    /// so it must not use the connection.
+    /// Rolling back to a savepoint does not end the transaction
+    /// and does not invoke the callback,
+    /// nor does the implicit rollback when the connection is closed.
+    #[doc(alias("sqlite3_commit_hook", "sqlite3_rollback_hook"))]
+    pub fn on_transaction_end(&mut self, f: impl FnMut(bool) + 'static) {
+        const BOX_SIZE: c_int = mem::size_of::<TransactionEndFn>() as c_int;
+        let f: TransactionEndFn = Box::new(f);
+        unsafe {
+            let user_data = sqlite3_malloc(BOX_SIZE).cast::<TransactionEndFn>();
+            ptr::write(user_data, f);
+            // Both hooks share the same closure.
+            sqlite3_commit_hook(self.as_ptr(), Some(commit_hook_callback), user_data.cast());
+            sqlite3_rollback_hook(
+                self.as_ptr(),
+                Some(rollback_hook_callback),
+                user_data.cast(),
+            );
+            free_transaction_end_hook(mem::replace(&mut self.transaction_end_hook, user_data));
+        }
+    }
+
+    /// Remove the callback set by [`Connection::on_transaction_end`].
+    pub fn clear_transaction_end_hook(&mut self) {
+        unsafe {
+            sqlite3_commit_hook(self.as_ptr(), None, ptr::null_mut());
+            sqlite3_rollback_hook(self.as_ptr(), None, ptr::null_mut());
+            free_transaction_end_hook(mem::replace(
+                &mut self.transaction_end_hook,
+                ptr::null_mut(),
+            ));
+        }
+    }
+}
+
+unsafe extern "C" fn commit_hook_callback(user_data: *mut c_void) -> c_int {
+    let f = user_data.cast::<TransactionEndFn>().as_mut().unwrap();
+    f(true);
+    // A non-zero return value would turn the commit into a rollback.
+    0
+}
+
+unsafe extern "C" fn rollback_hook_callback(user_data: *mut c_void) {
+    let f = user_data.cast::<TransactionEndFn>().as_mut().unwrap();
+    f(false);
+}
+
+unsafe fn free_transaction_end_hook(p: *mut TransactionEndFn) {
+    if p.is_null() {
+        return;
+    }
+    ptr::drop_in_place(p);
+    sqlite3_free(p.cast());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_on_transaction_end() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER PRIMARY KEY);", ())
+            .unwrap();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        {
+            let events = events.clone();
+            conn.on_transaction_end(move |committed| events.borrow_mut().push(committed));
+        }
+
+        conn.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        assert_eq!(*events.borrow(), [true]);
+
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (2);", ()).unwrap();
+        conn.execute("ROLLBACK;", ()).unwrap();
+        assert_eq!(*events.borrow(), [true, false]);
+
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("SAVEPOINT sp;", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (3);", ()).unwrap();
+        conn.execute("ROLLBACK TO sp;", ()).unwrap();
+        conn.execute("COMMIT;", ()).unwrap();
+        assert_eq!(*events.borrow(), [true, false, true]);
+
+        // A constraint violation in an autocommit statement rolls back.
+        conn.execute("INSERT INTO t VALUES (1);", ()).unwrap_err();
+        assert_eq!(*events.borrow(), [true, false, true, false]);
+
+        conn.clear_transaction_end_hook();
+        conn.execute("INSERT INTO t VALUES (4);", ()).unwrap();
+        assert_eq!(events.borrow().len(), 4);
+        assert_eq!(Rc::strong_count(&events), 1);
+    }
+}
@@ -0,0 +1,622 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use libsqlite3_sys::sqlite3_changes;
+
+use crate::connection::DbMutexGuard;
+use crate::tokenize::{tokenize, TokenKind};
+use crate::*;
+
+/// A [conflict resolution algorithm] for `INSERT` and `UPDATE` statements
+/// used by [`Conn::run_on_conflict`].
+///
+/// [conflict resolution algorithm]: https://www.sqlite.org/lang_conflict.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Conflict {
+    /// Abort the statement with [`ResultCode::CONSTRAINT`]
+    /// and roll back the current transaction.
+    Rollback,
+    /// Abort the statement with [`ResultCode::CONSTRAINT`]
+    /// and back out any changes made by the statement.
+    /// This is SQLite's default behavior.
+    Abort,
+    /// Abort the statement with [`ResultCode::CONSTRAINT`]
+    /// but keep any changes the statement made before the conflict.
+    Fail,
+    /// Skip the row that caused the conflict and continue the statement.
+    Ignore,
+    /// Delete the pre-existing rows that caused the conflict
+    /// before inserting or updating the row.
+    Replace,
+}
+
+impl Conflict {
+    /// Returns the keyword for the algorithm as used in an `OR` clause.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Conflict::Rollback => "ROLLBACK",
+            Conflict::Abort => "ABORT",
+            Conflict::Fail => "FAIL",
+            Conflict::Ignore => "IGNORE",
+            Conflict::Replace => "REPLACE",
+        }
+    }
+}
+
+impl Conn {
+    /// Runs `sql` (which must contain exactly one `INSERT` or `UPDATE` statement)
+    /// with the given conflict resolution algorithm
+    /// and returns the number of rows modified.
+    ///
+    /// The algorithm is applied by rewriting the statement
+    /// to begin with `INSERT OR ...` or `UPDATE OR ...`.
+    /// Returns a [`ResultCode::MISUSE`] error if the statement
+    /// does not begin with `INSERT` or `UPDATE`
+    /// (including statements that begin with a `WITH` clause)
+    /// or already specifies a conflict resolution algorithm.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Conflict, Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE foo (id INTEGER PRIMARY KEY);", ())?;
+    /// let sql = "INSERT INTO foo (id) VALUES (?1);";
+    /// assert_eq!(conn.run_on_conflict(sql, (1,), Conflict::Ignore)?, 1);
+    /// assert_eq!(conn.run_on_conflict(sql, (1,), Conflict::Ignore)?, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_on_conflict(
+        &self,
+        sql: &str,
+        params: impl Params,
+        resolution: Conflict,
+    ) -> Result<usize> {
+        let sql = add_conflict_clause(sql, resolution)?;
+        self.execute(&sql, params)
+    }
+}
+
+impl Conn {
+    /// Inserts a row into `table`,
+    /// or updates the existing row with the same `key_columns`,
+    /// and returns the number of rows modified.
+    /// `values` lists the row's column names and values,
+    /// and must include every column in `key_columns` and `update_columns`.
+    /// If a row with the same key already exists,
+    /// then only the columns in `update_columns` are set to their new values;
+    /// if `update_columns` is empty, the existing row is left unchanged.
+    ///
+    /// This builds an `INSERT ... ON CONFLICT (key_columns) DO UPDATE SET ...` statement
+    /// with quoted identifiers and bound values.
+    /// Returns a [`ResultCode::ERROR`] error if `key_columns` are not
+    /// the table's primary key or the columns of a (non-partial) unique index,
+    /// since SQLite requires the conflict target to match a uniqueness constraint,
+    /// or a [`ResultCode::MISUSE`] error if a key or update column is missing from `values`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE users (email TEXT UNIQUE, name TEXT);", ())?;
+    /// conn.upsert(
+    ///     "users",
+    ///     &["email"],
+    ///     &[("email", &"alice@example.com"), ("name", &"Alice")],
+    ///     &["name"],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn upsert(
+        &self,
+        table: &str,
+        key_columns: &[&str],
+        values: &[(&str, &dyn ToSql)],
+        update_columns: &[&str],
+    ) -> Result<usize> {
+        for col in key_columns.iter().chain(update_columns) {
+            if !values
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case(col))
+            {
+                return Err(Error::new(
+                    ResultCode::MISUSE,
+                    format!("upsert column {} has no value", col),
+                ));
+            }
+        }
+        self.check_upsert_key(table, key_columns)?;
+
+        let columns = join_ids(values.iter().map(|(name, _)| *name));
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({}) {};",
+            Quote::as_id(table),
+            columns,
+            placeholders(values.len()),
+            upsert_clause(key_columns, update_columns)
+        );
+        let params = values.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+        self.execute(&sql, params.as_slice())
+    }
+
+    /// Inserts each of `records` into `table`,
+    /// or updates the existing row with the same `key_columns`,
+    /// and returns the total number of rows modified.
+    /// Each record binds a value for every column of `table`
+    /// in the order the columns were declared,
+    /// as in `INSERT INTO table VALUES (...)`.
+    /// If a row with the same key already exists,
+    /// then only the columns in `update_columns` are set to their new values;
+    /// if `update_columns` is empty, the existing row is left unchanged.
+    ///
+    /// This is the efficient way to sync a batch of records into a table:
+    /// a single `INSERT ... ON CONFLICT (key_columns) DO UPDATE SET ...` statement
+    /// is prepared once and run for each record inside a savepoint.
+    /// If any record fails, then the savepoint is rolled back,
+    /// so none of the records are written, and the first error is returned.
+    /// If `records` is empty, the table is left unchanged and 0 is returned.
+    /// Errors for invalid `key_columns` are the same as for [`Conn::upsert`],
+    /// and a [`ResultCode::MISUSE`] error is returned
+    /// if a key or update column is not a column of `table`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE prices (sku TEXT PRIMARY KEY, cents INTEGER);", ())?;
+    /// let n = conn.upsert_many(
+    ///     "prices",
+    ///     &["sku"],
+    ///     &["cents"],
+    ///     [("apple", 50), ("pear", 75), ("apple", 60)],
+    /// )?;
+    /// assert_eq!(n, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn upsert_many<P: Params>(
+        &self,
+        table: &str,
+        key_columns: &[&str],
+        update_columns: &[&str],
+        records: impl IntoIterator<Item = P>,
+    ) -> Result<usize> {
+        self.execute("SAVEPOINT upsert_many;", ())?;
+        let result = self.upsert_many_internal(table, key_columns, update_columns, records);
+        if result.is_err() {
+            let _ = self.execute("ROLLBACK TO upsert_many;", ());
+        }
+        let release_result = self.execute("RELEASE upsert_many;", ());
+        let n = result?;
+        release_result?;
+        Ok(n)
+    }
+
+    fn upsert_many_internal<P: Params>(
+        &self,
+        table: &str,
+        key_columns: &[&str],
+        update_columns: &[&str],
+        records: impl IntoIterator<Item = P>,
+    ) -> Result<usize> {
+        let table_columns = self
+            .query_stream(
+                "SELECT name FROM pragma_table_info(?1);",
+                (table,),
+                |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        if table_columns.is_empty() {
+            return Err(Error::new(
+                ResultCode::ERROR,
+                format!("no such table: {}", table),
+            ));
+        }
+        for col in key_columns.iter().chain(update_columns) {
+            if !table_columns
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(col))
+            {
+                return Err(Error::new(
+                    ResultCode::MISUSE,
+                    format!("upsert column {} is not a column of {}", col, table),
+                ));
+            }
+        }
+        self.check_upsert_key(table, key_columns)?;
+
+        let mut stmt = self.prepare_single(&format!(
+            "INSERT INTO {} VALUES ({}) {};",
+            Quote::as_id(table),
+            placeholders(table_columns.len()),
+            upsert_clause(key_columns, update_columns)
+        ))?;
+        let mut n = 0;
+        for record in records {
+            stmt.clear_bindings();
+            record.bind(&mut stmt)?;
+            let _guard = DbMutexGuard::lock(self.as_ptr());
+            stmt.step()?;
+            stmt.reset()?;
+            n += unsafe { sqlite3_changes(self.as_ptr()) } as usize;
+        }
+        Ok(n)
+    }
+
+    /// Returns an error unless `key_columns` can be used as the conflict target
+    /// of an upsert into `table`.
+    fn check_upsert_key(&self, table: &str, key_columns: &[&str]) -> Result<()> {
+        if key_columns.is_empty() {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "upsert requires key columns",
+            ));
+        }
+        if !self.is_unique_key(table, key_columns)? {
+            return Err(Error::new(
+                ResultCode::ERROR,
+                format!(
+                    "({}) is not a primary key or unique constraint of {}",
+                    key_columns.join(", "),
+                    table
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reports whether `columns` are the primary key of `table`
+    /// or the columns of one of its unique indices.
+    fn is_unique_key(&self, table: &str, columns: &[&str]) -> Result<bool> {
+        let same_columns = |other: &[String]| {
+            other.len() == columns.len()
+                && other
+                    .iter()
+                    .all(|o| columns.iter().any(|c| c.eq_ignore_ascii_case(o)))
+        };
+        let pk = self
+            .query_stream(
+                "SELECT name FROM pragma_table_info(?1) WHERE pk > 0;",
+                (table,),
+                |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        if same_columns(&pk) {
+            return Ok(true);
+        }
+        let indices = self
+            .query_stream(
+                "SELECT name FROM pragma_index_list(?1) WHERE \"unique\" AND NOT partial;",
+                (table,),
+                |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        for index in indices {
+            let index_columns = self
+                .query_stream(
+                    "SELECT name FROM pragma_index_info(?1);",
+                    (&index,),
+                    |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+                )?
+                .collect::<Result<Vec<_>>>()?;
+            if same_columns(&index_columns) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Returns the `ON CONFLICT` clause of an upsert.
+fn upsert_clause(key_columns: &[&str], update_columns: &[&str]) -> String {
+    let action = if update_columns.is_empty() {
+        String::from("NOTHING")
+    } else {
+        let assignments = update_columns
+            .iter()
+            .map(|col| format!("{0} = excluded.{0}", Quote::as_id(col)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("UPDATE SET {}", assignments)
+    };
+    format!(
+        "ON CONFLICT ({}) DO {}",
+        join_ids(key_columns.iter().copied()),
+        action
+    )
+}
+
+fn join_ids<'a>(cols: impl IntoIterator<Item = &'a str>) -> String {
+    cols.into_iter()
+        .map(|col| Quote::as_id(col).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn placeholders(n: usize) -> String {
+    (1..=n)
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Inserts an `OR` clause after the leading `INSERT` or `UPDATE` keyword of `sql`.
+fn add_conflict_clause(sql: &str, resolution: Conflict) -> Result<String> {
+    let (start, end) = next_word(sql, 0).ok_or_else(not_insert_or_update)?;
+    let verb = &sql[start..end];
+    if !verb.eq_ignore_ascii_case("INSERT") && !verb.eq_ignore_ascii_case("UPDATE") {
+        return Err(not_insert_or_update());
+    }
+    if let Some((or_start, or_end)) = next_word(sql, end) {
+        if sql[or_start..or_end].eq_ignore_ascii_case("OR") {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "statement already has a conflict resolution algorithm",
+            ));
+        }
+    }
+    Ok(format!(
+        "{} OR {}{}",
+        &sql[..end],
+        resolution.as_str(),
+        &sql[end..]
+    ))
+}
+
+fn not_insert_or_update() -> Error {
+    Error::new(
+        ResultCode::MISUSE,
+        "conflict resolution requires an INSERT or UPDATE statement",
+    )
+}
+
+/// Skips whitespace and comments in `sql` starting at byte offset `pos`
+/// and returns the byte range of the following keyword,
+/// or `None` if the next token is not a keyword.
+fn next_word(sql: &str, pos: usize) -> Option<(usize, usize)> {
+    let token = tokenize(&sql[pos..]).find(|token| !token.is_trivia())?;
+    (token.kind == TokenKind::Word).then(|| (pos + token.start, pos + token.end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_add_conflict_clause() {
+        assert_eq!(
+            add_conflict_clause(
+                " -- hi\n/* x */ insert INTO t VALUES (1);",
+                Conflict::Ignore
+            )
+            .unwrap(),
+            " -- hi\n/* x */ insert OR IGNORE INTO t VALUES (1);"
+        );
+        assert_eq!(
+            add_conflict_clause("UPDATE t SET x = 1;", Conflict::Replace).unwrap(),
+            "UPDATE OR REPLACE t SET x = 1;"
+        );
+        for sql in [
+            "SELECT 1;",
+            "REPLACE INTO t VALUES (1);",
+            "INSERT OR ABORT INTO t VALUES (1);",
+            "WITH c AS (SELECT 1) INSERT INTO t SELECT * FROM c;",
+            "",
+        ] {
+            let err = add_conflict_clause(sql, Conflict::Ignore).unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::MISUSE, "sql = {:?}", sql);
+        }
+    }
+
+    #[test]
+    fn test_run_on_conflict() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT);", ())
+            .unwrap();
+        let insert = "INSERT INTO t (id, name) VALUES (?1, ?2);";
+        assert_eq!(
+            conn.run_on_conflict(insert, (1, "a"), Conflict::Ignore)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            conn.run_on_conflict(insert, (1, "b"), Conflict::Ignore)
+                .unwrap(),
+            0
+        );
+        let err = conn
+            .run_on_conflict(insert, (1, "c"), Conflict::Abort)
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert_eq!(
+            conn.run_on_conflict(insert, (1, "d"), Conflict::Replace)
+                .unwrap(),
+            1
+        );
+        let name = conn
+            .query_row("SELECT name FROM t WHERE id = 1;", (), |stmt| {
+                Ok(stmt.column_text(0).unwrap().to_string())
+            })
+            .unwrap();
+        assert_eq!(name.as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn test_upsert() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, \"e-mail\" TEXT, name TEXT, visits INTEGER, \
+            UNIQUE (\"e-mail\"));",
+            (),
+        )
+        .unwrap();
+        let n = conn
+            .upsert(
+                "users",
+                &["e-mail"],
+                &[
+                    ("e-mail", &"alice@example.com"),
+                    ("name", &"Alice"),
+                    ("visits", &1),
+                ],
+                &["visits"],
+            )
+            .unwrap();
+        assert_eq!(n, 1);
+        let n = conn
+            .upsert(
+                "users",
+                &["e-mail"],
+                &[
+                    ("e-mail", &"alice@example.com"),
+                    ("name", &"Alicia"),
+                    ("visits", &2),
+                ],
+                &["visits"],
+            )
+            .unwrap();
+        assert_eq!(n, 1);
+        conn.upsert("users", &["id"], &[("id", &1), ("name", &"ignored")], &[])
+            .unwrap();
+
+        let rows = conn
+            .query_stream(
+                "SELECT id, \"e-mail\", name, visits FROM users;",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_i64(0),
+                        stmt.column_text(1).unwrap().to_string(),
+                        stmt.column_text(2).unwrap().to_string(),
+                        stmt.column_i64(3),
+                    ))
+                },
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![(
+                1,
+                String::from("alice@example.com"),
+                String::from("Alice"),
+                2
+            )]
+        );
+
+        let err = conn
+            .upsert("users", &["name"], &[("name", &"Bob")], &[])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        let err = conn
+            .upsert("users", &["e-mail"], &[("name", &"Bob")], &[])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+    }
+
+    #[test]
+    fn test_upsert_many() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute(
+            "CREATE TABLE items (sku TEXT PRIMARY KEY, name TEXT, qty INTEGER);",
+            (),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO items VALUES ('a', 'Apple', 1), ('b', 'Banana', 2), ('c', 'Cherry', 3);",
+            (),
+        )
+        .unwrap();
+        let items = |conn: &Conn| {
+            conn.query_stream(
+                "SELECT sku, name, qty FROM items ORDER BY sku;",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_text(0).unwrap().to_string(),
+                        stmt.column_text(1).unwrap().to_string(),
+                        stmt.column_i64(2),
+                    ))
+                },
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+        };
+
+        let n = conn
+            .upsert_many(
+                "items",
+                &["sku"],
+                &["qty"],
+                [
+                    ("b", "Blueberry", 20),
+                    ("d", "Date", 4),
+                    ("a", "Avocado", 10),
+                    ("e", "Elderberry", 5),
+                ],
+            )
+            .unwrap();
+        assert_eq!(n, 4);
+        let want = vec![
+            (String::from("a"), String::from("Apple"), 10),
+            (String::from("b"), String::from("Banana"), 20),
+            (String::from("c"), String::from("Cherry"), 3),
+            (String::from("d"), String::from("Date"), 4),
+            (String::from("e"), String::from("Elderberry"), 5),
+        ];
+        assert_eq!(items(&conn), want);
+
+        let n = conn
+            .upsert_many("items", &["sku"], &["qty"], Vec::<(&str, &str, i64)>::new())
+            .unwrap();
+        assert_eq!(n, 0);
+
+        // A failing record rolls back the whole batch.
+        conn.execute(
+            "CREATE TRIGGER no_zero BEFORE INSERT ON items WHEN NEW.qty = 0 \
+            BEGIN SELECT RAISE(ABORT, 'zero quantity'); END;",
+            (),
+        )
+        .unwrap();
+        let err = conn
+            .upsert_many(
+                "items",
+                &["sku"],
+                &["qty"],
+                [("a", "Apple", 100), ("f", "Fig", 0), ("g", "Grape", 7)],
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert!(conn.get_autocommit());
+        assert_eq!(items(&conn), want);
+
+        let err = conn
+            .upsert_many("items", &["name"], &["qty"], [("a", "Apple", 1)])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        let err = conn
+            .upsert_many("items", &["sku"], &["price"], [("a", "Apple", 1)])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        let err = conn
+            .upsert_many("nosuchtable", &["sku"], &[], [("a",)])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+}
@@ -2,19 +2,27 @@
 // SPDX-License-Identifier: MIT
 
 use std::borrow::Borrow;
+use std::cell::Cell;
 use std::ffi::{c_int, CStr};
 use std::fmt::Debug;
 use std::mem::{self, MaybeUninit};
 use std::ops::Deref;
 use std::ptr::{self, NonNull};
+use std::rc::Rc;
 
 use bitflags::bitflags;
 use libsqlite3_sys::{
-    sqlite3, sqlite3_close, sqlite3_db_config, sqlite3_db_readonly, sqlite3_get_autocommit,
-    sqlite3_open_v2, SQLITE_OPEN_CREATE, SQLITE_OPEN_MEMORY, SQLITE_OPEN_NOMUTEX,
-    SQLITE_OPEN_PRIVATECACHE, SQLITE_OPEN_READONLY, SQLITE_OPEN_READWRITE, SQLITE_OPEN_URI,
+    sqlite3, sqlite3_close, sqlite3_close_v2, sqlite3_db_config, sqlite3_db_mutex,
+    sqlite3_db_readonly, sqlite3_finalize, sqlite3_get_autocommit, sqlite3_mutex,
+    sqlite3_mutex_enter, sqlite3_mutex_leave, sqlite3_next_stmt, sqlite3_open_v2,
+    SQLITE_OPEN_CREATE, SQLITE_OPEN_MEMORY, SQLITE_OPEN_NOMUTEX, SQLITE_OPEN_PRIVATECACHE,
+    SQLITE_OPEN_READONLY, SQLITE_OPEN_READWRITE, SQLITE_OPEN_SHAREDCACHE, SQLITE_OPEN_URI,
 };
 
+use crate::commit_hook::TransactionEndFn;
+use crate::progress::ProgressHandlerFn;
+use crate::update_hook::UpdateHookFn;
+use crate::wal::WalHookFn;
 use crate::*;
 
 /// An owned connection to a SQLite database.
@@ -22,6 +30,11 @@ use crate::*;
 pub struct Connection {
     ptr: NonNull<sqlite3>,
     pub(crate) authorizer: *mut AuthorizerFn,
+    pub(crate) progress_handler: *mut ProgressHandlerFn,
+    pub(crate) progress_handler_ops: c_int,
+    pub(crate) wal_hook: *mut WalHookFn,
+    pub(crate) transaction_end_hook: *mut TransactionEndFn,
+    pub(crate) update_hook: *mut UpdateHookFn,
 }
 
 impl Connection {
@@ -32,13 +45,41 @@ impl Connection {
 
     /// Open a SQLite database as specified by the `filename` argument.
     pub fn open(filename: impl AsRef<CStr>, flags: OpenFlags) -> Result<Connection> {
+        Self::open_v2(filename.as_ref(), flags, SQLITE_OPEN_NOMUTEX, None)
+    }
+
+    /// Opens a new private [temporary database].
+    /// Unlike an in-memory database,
+    /// a temporary database is backed by a file that SQLite creates on demand
+    /// when its page cache fills up,
+    /// so it can hold intermediate data larger than available memory.
+    /// The file is deleted automatically when the connection is closed,
+    /// and it cannot be opened by any other connection.
+    ///
+    /// [temporary database]: https://www.sqlite.org/inmemorydb.html#temp_db
+    pub fn open_temp() -> Result<Connection> {
+        Self::open(<&CStr>::default(), OpenFlags::default())
+    }
+
+    /// Opens a database with the given threading mode flag
+    /// and VFS (or the default VFS if `None`).
+    pub(crate) fn open_v2(
+        filename: &CStr,
+        flags: OpenFlags,
+        threading: c_int,
+        vfs: Option<&CStr>,
+    ) -> Result<Connection> {
         let mut db = MaybeUninit::uninit();
+        let mut flags = flags.bits() as c_int | threading;
+        if flags & SQLITE_OPEN_SHAREDCACHE == 0 {
+            flags |= SQLITE_OPEN_PRIVATECACHE;
+        }
         let rc = ResultCode(unsafe {
             sqlite3_open_v2(
-                filename.as_ref().as_ptr(),
+                filename.as_ptr(),
                 db.as_mut_ptr(),
-                (flags.bits() as c_int) | SQLITE_OPEN_NOMUTEX | SQLITE_OPEN_PRIVATECACHE,
-                ptr::null(),
+                flags,
+                vfs.map_or(ptr::null(), CStr::as_ptr),
             )
         });
         let db = match NonNull::new(unsafe { db.assume_init() }) {
@@ -49,6 +90,11 @@ impl Connection {
         let mut conn = Connection {
             ptr: db,
             authorizer: ptr::null_mut(),
+            progress_handler: ptr::null_mut(),
+            progress_handler_ops: 0,
+            wal_hook: ptr::null_mut(),
+            transaction_end_hook: ptr::null_mut(),
+            update_hook: ptr::null_mut(),
         }; // Now will drop properly.
         if rc != ResultCode::OK {
             return Err(conn.as_ref().error().unwrap());
@@ -73,6 +119,43 @@ impl Connection {
         });
         rc.to_result().map(|_| ())
     }
+
+    /// Configures the connection's [lookaside memory allocator],
+    /// which speeds up the small, short-lived allocations that SQLite makes frequently.
+    /// The allocator is given `slot_count` slots of `slot_size` bytes each,
+    /// allocated from the heap.
+    /// `slot_size` is rounded down to a multiple of 8.
+    /// A `slot_size` or `slot_count` of zero disables the lookaside allocator.
+    ///
+    /// This should be called before the connection does significant work:
+    /// if any lookaside memory is in use (for example, by a prepared statement),
+    /// then the configuration is not changed and [`ResultCode::BUSY`] is returned.
+    /// If SQLite was compiled with `SQLITE_OMIT_LOOKASIDE`,
+    /// then this succeeds but has no effect.
+    ///
+    /// [lookaside memory allocator]: https://www.sqlite.org/malloc.html#lookaside
+    #[doc(alias = "SQLITE_DBCONFIG_LOOKASIDE")]
+    pub fn set_lookaside(&mut self, slot_size: i32, slot_count: i32) -> Result<()> {
+        if slot_size < 0 || slot_count < 0 {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "lookaside slot size and count must not be negative",
+            ));
+        }
+        let rc = ResultCode(unsafe {
+            sqlite3_db_config(
+                self.as_ptr(),
+                libsqlite3_sys::SQLITE_DBCONFIG_LOOKASIDE,
+                ptr::null_mut::<std::ffi::c_void>(),
+                slot_size as c_int,
+                slot_count as c_int,
+            )
+        });
+        if rc == ResultCode::BUSY {
+            return Err(Error::new(rc, "lookaside memory is in use"));
+        }
+        rc.to_result().map(|_| ())
+    }
 }
 
 /// Connections can be used by a single thread at a time,
@@ -100,12 +183,84 @@ impl Borrow<Conn> for Connection {
     }
 }
 
+impl Connection {
+    /// Finalizes every prepared statement on the connection,
+    /// returning the number of statements finalized.
+    ///
+    /// Every [`Statement`] borrows its connection,
+    /// so while the connection is borrowed mutably,
+    /// the only outstanding statements are ones whose ownership was lost,
+    /// such as statements passed to [`std::mem::forget`]
+    /// or leaked while unwinding from a panic.
+    /// Such statements prevent [`Connection::close`] from succeeding,
+    /// so this is a last-resort cleanup to run before closing.
+    #[doc(alias = "sqlite3_next_stmt")]
+    pub fn finalize_all_statements(&mut self) -> usize {
+        let mut n = 0;
+        unsafe {
+            loop {
+                let stmt = sqlite3_next_stmt(self.as_ptr(), ptr::null_mut());
+                if stmt.is_null() {
+                    break;
+                }
+                sqlite3_finalize(stmt);
+                n += 1;
+            }
+        }
+        n
+    }
+
+    /// Closes the connection, reporting any error.
+    /// Dropping a `Connection` closes it as well,
+    /// but panics if the connection cannot be closed.
+    ///
+    /// Returns a [`ResultCode::BUSY`] error if the connection has
+    /// unfinalized prepared statements
+    /// (see [`Connection::finalize_all_statements`]).
+    /// In that case, the connection is closed
+    /// as soon as the last statement is finalized.
+    #[doc(alias = "sqlite3_close")]
+    pub fn close(self) -> Result<()> {
+        let mut conn = mem::ManuallyDrop::new(self);
+        unsafe {
+            conn.release_callbacks();
+            let db = conn.as_ptr();
+            let rc = ResultCode(sqlite3_close(db));
+            if rc.is_success() {
+                return Ok(());
+            }
+            let err = conn.as_ref().error().unwrap_or_else(|| Error::new(rc, ""));
+            // Leave the connection as a zombie that is freed
+            // once its statements are finalized.
+            sqlite3_close_v2(db);
+            Err(err)
+        }
+    }
+
+    /// Unregisters and frees all callbacks stored on the connection.
+    unsafe fn release_callbacks(&mut self) {
+        if !self.authorizer.is_null() {
+            let _ = self.clear_authorizer();
+        }
+        if !self.progress_handler.is_null() {
+            self.clear_progress_handler();
+        }
+        if !self.wal_hook.is_null() {
+            self.clear_wal_hook();
+        }
+        if !self.transaction_end_hook.is_null() {
+            self.clear_transaction_end_hook();
+        }
+        if !self.update_hook.is_null() {
+            self.clear_update_hook();
+        }
+    }
+}
+
 impl Drop for Connection {
     fn drop(&mut self) {
         unsafe {
-            if !self.authorizer.is_null() {
-                let _ = self.clear_authorizer();
-            }
+            self.release_callbacks();
             assert_eq!(
                 ResultCode(sqlite3_close(self.ptr.as_ptr() as *mut sqlite3)),
                 ResultCode::OK
@@ -114,6 +269,29 @@ impl Drop for Connection {
     }
 }
 
+/// A guard that holds a connection's mutex
+/// so that an API call and the error it reports are not interleaved
+/// with calls from other threads.
+/// Connections opened with `SQLITE_OPEN_NOMUTEX` have no mutex,
+/// in which case the guard does nothing.
+pub(crate) struct DbMutexGuard(*mut sqlite3_mutex);
+
+impl DbMutexGuard {
+    pub(crate) fn lock(db: *mut sqlite3) -> Self {
+        unsafe {
+            let mutex = sqlite3_db_mutex(db);
+            sqlite3_mutex_enter(mutex);
+            DbMutexGuard(mutex)
+        }
+    }
+}
+
+impl Drop for DbMutexGuard {
+    fn drop(&mut self) {
+        unsafe { sqlite3_mutex_leave(self.0) }
+    }
+}
+
 /// A reference to a [`Connection`].
 #[repr(transparent)]
 #[derive(Debug)]
@@ -168,6 +346,121 @@ impl Conn {
         }
     }
 
+    /// Returns the lock that the connection holds on the given schema's database file,
+    /// for diagnosing lock contention between connections.
+    ///
+    /// The lock is read from the VFS with `SQLITE_FCNTL_LOCKSTATE` if it supports it.
+    /// Otherwise, the lock is inferred from the [transaction state][Conn::txn_state]:
+    /// no transaction is [`LockState::Unlocked`],
+    /// a read transaction is [`LockState::Shared`],
+    /// and a write transaction is [`LockState::Reserved`],
+    /// even if the connection has escalated to a stronger lock to write to the file.
+    /// Databases in WAL mode use separate locks in the WAL index,
+    /// so their file lock does not change for write transactions.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "SQLITE_FCNTL_LOCKSTATE")]
+    pub fn lock_state(&self, schema: &(impl AsRef<CStr> + ?Sized)) -> Result<LockState> {
+        let schema = schema.as_ref();
+        let state = self.txn_state(Some(schema)).ok_or_else(|| {
+            Error::new(
+                ResultCode::ERROR,
+                format!("unknown database {}", schema.to_string_lossy()),
+            )
+        })?;
+        let mut lock: c_int = -1;
+        let rc = unsafe {
+            libsqlite3_sys::sqlite3_file_control(
+                self.as_ptr(),
+                schema.as_ptr(),
+                libsqlite3_sys::SQLITE_FCNTL_LOCKSTATE,
+                ptr::addr_of_mut!(lock).cast(),
+            )
+        };
+        if rc == libsqlite3_sys::SQLITE_OK {
+            match lock {
+                libsqlite3_sys::SQLITE_LOCK_NONE => return Ok(LockState::Unlocked),
+                libsqlite3_sys::SQLITE_LOCK_SHARED => return Ok(LockState::Shared),
+                libsqlite3_sys::SQLITE_LOCK_RESERVED => return Ok(LockState::Reserved),
+                libsqlite3_sys::SQLITE_LOCK_PENDING => return Ok(LockState::Pending),
+                libsqlite3_sys::SQLITE_LOCK_EXCLUSIVE => return Ok(LockState::Exclusive),
+                _ => {}
+            }
+        }
+        Ok(match state {
+            TransactionState::None => LockState::Unlocked,
+            TransactionState::Read => LockState::Shared,
+            TransactionState::Write => LockState::Reserved,
+        })
+    }
+
+    /// Returns the operating system error code (`errno` on Unix)
+    /// of the most recent I/O error on the connection,
+    /// or `None` if there is none.
+    /// Such errors are all reported as [`ResultCode::IOERR`] or [`ResultCode::CANTOPEN`],
+    /// so the error code can help diagnose problems
+    /// like a full disk or missing permissions.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_system_errno")]
+    pub fn system_errno(&self) -> Option<i32> {
+        match unsafe { libsqlite3_sys::sqlite3_system_errno(self.as_ptr()) } {
+            0 => None,
+            errno => Some(errno),
+        }
+    }
+
+    /// Returns the name of the schema at position `index` on the connection,
+    /// or `None` if `index` is out of range.
+    /// Index 0 is always `main`, index 1 is always `temp`,
+    /// and attached databases follow in the order they were attached.
+    /// This is cheaper than querying `PRAGMA database_list`.
+    ///
+    /// The name is copied because SQLite frees it
+    /// if the database is detached.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_db_name")]
+    pub fn schema_name(&self, index: i32) -> Option<String> {
+        unsafe {
+            let ptr = libsqlite3_sys::sqlite3_db_name(self.as_ptr(), index);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+            }
+        }
+    }
+
+    /// Removes all [virtual table modules] registered on the connection
+    /// except those named in `keep`,
+    /// so that statements can no longer create or use virtual tables
+    /// implemented by the removed modules.
+    /// This is useful for restricting the capabilities of untrusted SQL.
+    /// Modules cannot be restored once they have been removed.
+    ///
+    /// Returns a [`ResultCode::MISUSE`] error if a name contains a NUL byte.
+    ///
+    /// [virtual table modules]: https://www.sqlite.org/vtab.html
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_drop_modules")]
+    pub fn drop_modules(&self, keep: &[&str]) -> Result<()> {
+        let keep = keep
+            .iter()
+            .map(|name| {
+                std::ffi::CString::new(*name)
+                    .map_err(|_| Error::new(ResultCode::MISUSE, "module name contains a NUL byte"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mut keep_ptrs = keep
+            .iter()
+            .map(|name| name.as_ptr())
+            .chain(std::iter::once(ptr::null()))
+            .collect::<Vec<_>>();
+        ResultCode(unsafe {
+            libsqlite3_sys::sqlite3_drop_modules(self.as_ptr(), keep_ptrs.as_mut_ptr())
+        })
+        .to_result()?;
+        Ok(())
+    }
+
     /// Returns the current value of the given database configuration flag.
     pub fn get_config(&self, flag: ConfigFlag) -> Result<bool> {
         unsafe {
@@ -191,6 +484,68 @@ impl Conn {
     pub fn get_autocommit(&self) -> bool {
         unsafe { sqlite3_get_autocommit(self.as_ptr()) != 0 }
     }
+
+    /// Reports whether the connection's [autocommit mode][Conn::get_autocommit]
+    /// differs from `prev`, a value previously returned by `get_autocommit`.
+    /// A change from `false` to `true` means that a transaction ended,
+    /// possibly implicitly (e.g. by a `COMMIT` run as raw SQL or an error that rolled back).
+    ///
+    /// [`AutocommitTracker`] keeps track of the previous value.
+    pub fn autocommit_changed_since(&self, prev: bool) -> bool {
+        self.get_autocommit() != prev
+    }
+
+    /// Reads the database schema to verify that the database files are valid.
+    /// SQLite does not read a database file when it is opened,
+    /// so opening a file that is not a database (or is encrypted)
+    /// succeeds and [`ResultCode::NOTADB`] is only reported on first access.
+    /// `validate` forces this check so that such errors can be reported early.
+    pub fn validate(&self) -> Result<()> {
+        self.execute("SELECT count(*) FROM sqlite_master;", ())
+            .map(|_| ())
+    }
+
+    /// Scans every row of the rowid table named `table`
+    /// and returns the rowid and column name of each text value
+    /// that is not valid UTF-8.
+    /// Such values can be stored by clients that bind text without validating it
+    /// or that cast blobs to text,
+    /// and they cause [`Statement::column_text`] to return an error.
+    pub fn validate_text_encoding(&self, table: &str) -> Result<Vec<(i64, String)>> {
+        let columns = self
+            .query_stream(
+                "SELECT name FROM pragma_table_info(?1);",
+                (table,),
+                |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        if columns.is_empty() {
+            return Err(Error::new(
+                ResultCode::ERROR,
+                format!("no such table: {}", table),
+            ));
+        }
+        let sql = format!(
+            "SELECT rowid, {} FROM {};",
+            columns
+                .iter()
+                .map(|name| Quote::as_id(name).to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            Quote::as_id(table)
+        );
+
+        let mut stmt = self.prepare_single(&sql)?;
+        let mut invalid = Vec::new();
+        while stmt.step()?.has_row() {
+            for (i, name) in columns.iter().enumerate() {
+                if stmt.column_type(i + 1) == DataType::Text && stmt.column_text(i + 1).is_err() {
+                    invalid.push((stmt.column_i64(0), name.clone()));
+                }
+            }
+        }
+        Ok(invalid)
+    }
 }
 
 bitflags! {
@@ -214,6 +569,11 @@ bitflags! {
         /// The database will be opened as an in-memory database.
         /// The `filename` argument is ignored.
         const MEMORY = SQLITE_OPEN_MEMORY;
+        /// The database is opened with [shared cache] enabled.
+        /// By default, connections use a private cache.
+        ///
+        /// [shared cache]: https://www.sqlite.org/sharedcache.html
+        const SHARED_CACHE = SQLITE_OPEN_SHAREDCACHE;
     }
 }
 
@@ -233,6 +593,28 @@ pub enum TransactionState {
     Write = libsqlite3_sys::SQLITE_TXN_WRITE as c_int,
 }
 
+/// A [lock] on a database file, as returned by [`Conn::lock_state`].
+/// Locks are ordered from weakest to strongest.
+///
+/// [lock]: https://www.sqlite.org/lockingv3.html
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LockState {
+    /// No lock is held.
+    Unlocked,
+    /// The database may be read but not written.
+    Shared,
+    /// The connection plans to write to the database
+    /// while other connections may continue reading.
+    Reserved,
+    /// The connection is waiting for readers to finish
+    /// so that it can write to the database.
+    Pending,
+    /// The connection is writing to the database
+    /// and no other connection may read it.
+    Exclusive,
+}
+
 /// Enumeration of boolean [database connection configuration options].
 ///
 /// [database connection configuration options]: https://www.sqlite.org/c3ref/c_dbconfig_defensive.html
@@ -272,3 +654,321 @@ pub enum ConfigFlag {
     #[cfg(feature = "buildtime_bindgen")]
     ReverseScanOrder = libsqlite3_sys::SQLITE_DBCONFIG_REVERSE_SCANORDER as i32,
 }
+
+/// Tracks a connection's [autocommit mode][Conn::get_autocommit]
+/// and the transactions it commits or rolls back
+/// to detect transactions that ended outside of the caller's control.
+///
+/// Code that opens a transaction and later commits or rolls it back
+/// (such as a transaction guard)
+/// can use a tracker to avoid ending a transaction a second time
+/// after a statement has already ended it.
+///
+/// The tracker counts transactions with the connection's commit and rollback hooks,
+/// so it notices a transaction that ended
+/// even if another one began before the tracker was next updated,
+/// as with `COMMIT; BEGIN;` run as a single script.
+/// SQLite does not call the commit hook for transactions that made no changes,
+/// so such transactions are only noticed through the autocommit mode.
+#[derive(Clone, Debug)]
+pub struct AutocommitTracker {
+    autocommit: bool,
+    ended: Rc<Cell<u64>>,
+    seen: u64,
+}
+
+impl AutocommitTracker {
+    /// Returns a tracker that starts with the connection's current autocommit mode.
+    /// This replaces any callback set with [`Connection::on_transaction_end`].
+    pub fn new(conn: &mut Connection) -> Self {
+        let ended = Rc::new(Cell::new(0));
+        let counter = ended.clone();
+        conn.on_transaction_end(move |_| counter.set(counter.get() + 1));
+        AutocommitTracker {
+            autocommit: conn.get_autocommit(),
+            ended,
+            seen: 0,
+        }
+    }
+
+    /// Returns the autocommit mode observed by the last call to
+    /// [`AutocommitTracker::new`] or [`AutocommitTracker::update`].
+    pub fn autocommit(&self) -> bool {
+        self.autocommit
+    }
+
+    /// Records the connection's current autocommit mode,
+    /// returning whether it changed
+    /// or a transaction was committed or rolled back
+    /// since the last observation.
+    pub fn update(&mut self, conn: &Conn) -> bool {
+        let ended = self.ended.get();
+        let changed = conn.autocommit_changed_since(self.autocommit) || ended != self.seen;
+        self.autocommit = conn.get_autocommit();
+        self.seen = ended;
+        changed
+    }
+
+    /// Records the connection's current autocommit mode,
+    /// returning whether a transaction that was open at the last observation
+    /// has since ended.
+    pub fn transaction_ended(&mut self, conn: &Conn) -> bool {
+        let was_in_transaction = !self.autocommit;
+        self.update(conn) && was_in_transaction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::fs;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_set_lookaside() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.set_lookaside(256, 32).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        let stmt = conn
+            .prepare("SELECT x FROM t WHERE x > 1;")
+            .0
+            .unwrap()
+            .unwrap();
+        let (used, highwater) = conn.db_status(DbStatus::LookasideUsed, false).unwrap();
+        let option = CString::new("OMIT_LOOKASIDE").unwrap();
+        if unsafe { libsqlite3_sys::sqlite3_compileoption_used(option.as_ptr()) } == 0 {
+            assert!(used > 0, "used = {}", used);
+        } else {
+            assert_eq!(used, 0);
+        }
+        assert!(highwater <= 32, "highwater = {}", highwater);
+        drop(stmt);
+
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        assert!(conn.set_lookaside(-1, 10).is_err());
+        conn.set_lookaside(0, 0).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        let _stmt = conn
+            .prepare("SELECT x FROM t WHERE x > 1;")
+            .0
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            conn.db_status(DbStatus::LookasideUsed, false).unwrap(),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    fn test_lock_state() {
+        let dir = TempDir::new("lock-state");
+        let path_cstr = dir.join_cstr("test.db");
+        let main = const_cstr!("main").as_cstr();
+        let writer = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        let reader = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        writer.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        assert_eq!(writer.lock_state(main).unwrap(), LockState::Unlocked);
+        assert_eq!(reader.lock_state(main).unwrap(), LockState::Unlocked);
+
+        writer.execute("BEGIN IMMEDIATE;", ()).unwrap();
+        writer.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        assert!(writer.lock_state(main).unwrap() >= LockState::Reserved);
+        assert_eq!(reader.lock_state(main).unwrap(), LockState::Unlocked);
+        reader.execute("BEGIN;", ()).unwrap();
+        reader.execute("SELECT count(*) FROM t;", ()).unwrap();
+        assert_eq!(reader.lock_state(main).unwrap(), LockState::Shared);
+        reader.execute("COMMIT;", ()).unwrap();
+        writer.execute("COMMIT;", ()).unwrap();
+        assert_eq!(writer.lock_state(main).unwrap(), LockState::Unlocked);
+        assert_eq!(reader.lock_state(main).unwrap(), LockState::Unlocked);
+
+        assert!(writer
+            .lock_state(const_cstr!("nosuchdb").as_cstr())
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    fn test_system_errno() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        assert_eq!(conn.system_errno(), None);
+
+        let dir = TempDir::new("system-errno");
+        let missing = dir.join("missing/foo.db");
+        let err = conn
+            .execute("ATTACH ?1 AS other;", (missing.to_str().unwrap(),))
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CANTOPEN);
+        #[cfg(unix)]
+        assert_eq!(conn.system_errno(), Some(2)); // ENOENT
+    }
+
+    #[test]
+    fn test_open_temp() {
+        let conn = Connection::open_temp().unwrap();
+        conn.execute("CREATE TABLE t (x TEXT);", ()).unwrap();
+        conn.execute("PRAGMA cache_size = 10;", ()).unwrap();
+        // Much larger than the cache, so the database spills to disk.
+        let n = conn
+            .execute(
+                "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 5000) \
+                INSERT INTO t SELECT printf('%.200c', n) FROM c;",
+                (),
+            )
+            .unwrap();
+        assert_eq!(n, 5000);
+        // The file has no name, so nothing can refer to it after close.
+        let file = conn
+            .query_row(
+                "SELECT file FROM pragma_database_list WHERE name = 'main';",
+                (),
+                |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+            )
+            .unwrap();
+        assert_eq!(file.as_deref(), Some(""));
+
+        let other = Connection::open_temp().unwrap();
+        assert!(other.execute("SELECT * FROM t;", ()).is_err());
+    }
+
+    #[test]
+    fn test_validate_text_encoding() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (a TEXT, \"b c\" TEXT, n INTEGER);", ())
+            .unwrap();
+        conn.execute(
+            "INSERT INTO t VALUES ('ok', 'ok', 1), \
+            ('ok', CAST(x'ff' AS TEXT), 2), \
+            (x'ff', CAST(x'c328' AS TEXT), 3);",
+            (),
+        )
+        .unwrap();
+        assert_eq!(
+            conn.validate_text_encoding("t").unwrap(),
+            [(2, String::from("b c")), (3, String::from("b c"))]
+        );
+        assert!(conn.validate_text_encoding("missing").is_err());
+    }
+
+    #[test]
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    fn test_schema_name() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("ATTACH ':memory:' AS aux;", ()).unwrap();
+        assert_eq!(conn.schema_name(0).as_deref(), Some("main"));
+        assert_eq!(conn.schema_name(1).as_deref(), Some("temp"));
+        assert_eq!(conn.schema_name(2).as_deref(), Some("aux"));
+        assert_eq!(conn.schema_name(3), None);
+        assert_eq!(conn.schema_name(-1), None);
+
+        conn.execute("DETACH aux;", ()).unwrap();
+        assert_eq!(conn.schema_name(2), None);
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[test]
+    fn test_drop_modules() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.drop_modules(&["rtree"]).unwrap();
+        let err = conn
+            .execute("CREATE VIRTUAL TABLE docs USING fts5(body);", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(
+            err.message().contains("no such module"),
+            "message = {:?}",
+            err.message()
+        );
+        conn.execute(
+            "CREATE VIRTUAL TABLE boxes USING rtree(id, min_x, max_x);",
+            (),
+        )
+        .unwrap();
+
+        let err = conn.drop_modules(&["rt\0ree"]).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+    }
+
+    #[test]
+    fn test_validate() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.validate().unwrap();
+
+        let dir = TempDir::new("validate");
+        fs::write(dir.join("test.db"), [0x5a; 4096]).unwrap();
+        let conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        let err = conn.validate().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::NOTADB);
+    }
+
+    #[test]
+    fn test_autocommit_tracker() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut tracker = AutocommitTracker::new(&mut conn);
+        assert!(tracker.autocommit());
+        assert!(!tracker.transaction_ended(&conn));
+
+        conn.execute("BEGIN;", ()).unwrap();
+        assert!(conn.autocommit_changed_since(true));
+        assert!(!tracker.transaction_ended(&conn));
+        assert!(!tracker.autocommit());
+
+        // A raw COMMIT ends the transaction behind the tracker's back,
+        // so a guard using it must not commit again.
+        conn.execute("COMMIT;", ()).unwrap();
+        assert!(tracker.transaction_ended(&conn));
+        assert!(tracker.autocommit());
+        assert!(!tracker.transaction_ended(&conn));
+        assert!(!conn.autocommit_changed_since(true));
+
+        // A transaction that ends and is replaced by another
+        // before the tracker is updated is still detected.
+        conn.execute("CREATE TABLE t (x);", ()).unwrap();
+        conn.execute("BEGIN;", ()).unwrap();
+        assert!(!tracker.transaction_ended(&conn));
+        conn.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        conn.execute("COMMIT;", ()).unwrap();
+        conn.execute("BEGIN;", ()).unwrap();
+        assert!(tracker.transaction_ended(&conn));
+        assert!(!tracker.autocommit());
+        conn.execute("INSERT INTO t VALUES (2);", ()).unwrap();
+        conn.execute("ROLLBACK;", ()).unwrap();
+        conn.execute("BEGIN;", ()).unwrap();
+        assert!(tracker.transaction_ended(&conn));
+        conn.execute("COMMIT;", ()).unwrap();
+    }
+
+    #[test]
+    fn test_finalize_all_statements() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for sql in ["SELECT 1;", "SELECT 2;"] {
+            let mut stmt = conn
+                .prepare(sql)
+                .0
+                .unwrap()
+                .expect("statement is not empty");
+            stmt.step().unwrap();
+            mem::forget(stmt);
+        }
+        assert_eq!(conn.finalize_all_statements(), 2);
+        assert_eq!(conn.finalize_all_statements(), 0);
+        conn.close().unwrap();
+
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let stmt = conn
+            .prepare("SELECT 1;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        mem::forget(stmt);
+        let err = conn.close().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::BUSY);
+    }
+}
@@ -0,0 +1,732 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use libsqlite3_sys::sqlite3_changes;
+
+use crate::connection::DbMutexGuard;
+use crate::tokenize::{tokenize, Token, TokenKind};
+use crate::*;
+
+impl Conn {
+    /// Copies the table named `table` in the attached database `src_schema`
+    /// to the attached database `dst_schema`, along with all its rows.
+    ///
+    /// Unlike `CREATE TABLE ... AS SELECT`,
+    /// the table is created from its original `CREATE TABLE` statement
+    /// so that column types, constraints, and the primary key are preserved.
+    /// The table's indices and triggers are recreated as well
+    /// after the rows have been copied,
+    /// and if the table uses `AUTOINCREMENT`,
+    /// its `sqlite_sequence` entry is copied
+    /// so that new rows do not reuse rowids of deleted rows.
+    /// It is an error if `dst_schema` already has a table of the same name.
+    /// Virtual tables cannot be copied.
+    /// The copy happens inside a savepoint,
+    /// so if any step fails, then `dst_schema` is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("ATTACH ':memory:' AS backup;", ())?;
+    /// conn.execute("CREATE TABLE main.foo (id INTEGER PRIMARY KEY);", ())?;
+    /// conn.copy_table("main", "backup", "foo")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn copy_table(&self, src_schema: &str, dst_schema: &str, table: &str) -> Result<()> {
+        self.execute("SAVEPOINT copy_table;", ())?;
+        let result = self.copy_table_internal(src_schema, dst_schema, table);
+        if result.is_err() {
+            let _ = self.execute("ROLLBACK TO copy_table;", ());
+        }
+        let release_result = self.execute("RELEASE copy_table;", ()).map(|_| ());
+        result.and(release_result)
+    }
+
+    fn copy_table_internal(&self, src_schema: &str, dst_schema: &str, table: &str) -> Result<()> {
+        let mut table_sql = None;
+        let mut other_sql = Vec::new();
+        let stream = self.query_stream(
+            &format!(
+                "SELECT type = 'table', sql FROM {}.sqlite_master \
+                WHERE tbl_name = ?1 AND sql IS NOT NULL ORDER BY rowid;",
+                Quote::as_id(src_schema)
+            ),
+            (table,),
+            |stmt| {
+                Ok((
+                    stmt.column_i64(0) != 0,
+                    stmt.column_text(1).to_string_lossy().into_owned(),
+                ))
+            },
+        )?;
+        for row in stream {
+            let (is_table, sql) = row?;
+            let sql = qualify_create(&sql, dst_schema)?;
+            if is_table {
+                table_sql = Some(sql);
+            } else {
+                other_sql.push(sql);
+            }
+        }
+        let table_sql = table_sql.ok_or_else(|| {
+            Error::new(
+                ResultCode::ERROR,
+                format!("no such table: {}.{}", src_schema, table),
+            )
+        })?;
+
+        self.execute(&table_sql, ())?;
+        self.execute(
+            &format!(
+                "INSERT INTO {dst}.{table} SELECT * FROM {src}.{table};",
+                src = Quote::as_id(src_schema),
+                dst = Quote::as_id(dst_schema),
+                table = Quote::as_id(table),
+            ),
+            (),
+        )?;
+        self.copy_sequence(src_schema, dst_schema, table)?;
+        for sql in other_sql {
+            self.execute(&sql, ())?;
+        }
+        Ok(())
+    }
+
+    /// Copies the `sqlite_sequence` entry for `table`, if any,
+    /// which records the largest rowid ever used by an `AUTOINCREMENT` table.
+    fn copy_sequence(&self, src_schema: &str, dst_schema: &str, table: &str) -> Result<()> {
+        let (src, dst) = (Quote::as_id(src_schema), Quote::as_id(dst_schema));
+        // sqlite_sequence only exists once a database has an AUTOINCREMENT table.
+        let has_sequence = self
+            .query_row(
+                &format!("SELECT 1 FROM {src}.sqlite_master WHERE name = 'sqlite_sequence';"),
+                (),
+                |_| Ok(()),
+            )?
+            .is_some();
+        if !has_sequence {
+            return Ok(());
+        }
+        let seq = self.query_row(
+            &format!("SELECT seq FROM {src}.sqlite_sequence WHERE name = ?1;"),
+            (table,),
+            |stmt| Ok(stmt.column_i64(0)),
+        )?;
+        if let Some(seq) = seq {
+            // Copying the rows has already added an entry for the table.
+            self.execute(
+                &format!("DELETE FROM {dst}.sqlite_sequence WHERE name = ?1;"),
+                (table,),
+            )?;
+            self.execute(
+                &format!("INSERT INTO {dst}.sqlite_sequence (name, seq) VALUES (?1, ?2);"),
+                (table, seq),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Conn {
+    /// Runs the query `select_sql` with `params` bound
+    /// and inserts each row it returns into the table `dest_table`,
+    /// returning the number of rows inserted.
+    /// The rows are inserted with a single prepared
+    /// `INSERT INTO dest_table VALUES (...)` statement
+    /// with one parameter per column of the query,
+    /// so the query's columns must match the table's columns in order.
+    ///
+    /// This is useful where `INSERT ... SELECT` cannot be used,
+    /// such as when each row needs to be processed in Rust
+    /// (see [`Conn::insert_from_query_with`]).
+    /// The copy happens inside a savepoint,
+    /// so if any row fails to insert, then `dest_table` is left unchanged.
+    pub fn insert_from_query(
+        &self,
+        dest_table: &str,
+        select_sql: &str,
+        params: impl Params,
+    ) -> Result<usize> {
+        self.insert_from_query_savepoint(dest_table, select_sql, params, |row, insert| {
+            for i in 0..row.column_count() {
+                insert.bind_value(i + 1, &row.column_value(i))?;
+            }
+            Ok(true)
+        })
+    }
+
+    /// Like [`Conn::insert_from_query`],
+    /// but calls `transform` for each row returned by the query
+    /// to produce the parameters of the `INSERT` statement.
+    /// If `transform` returns `None`, then the row is skipped.
+    /// The `INSERT` statement's bindings are cleared before each row,
+    /// so parameters that `transform` does not bind are `NULL`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags, ResultExt};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE names (name TEXT);", ())?;
+    /// conn.execute("CREATE TABLE shouted (name TEXT);", ())?;
+    /// conn.execute("INSERT INTO names VALUES ('alice'), ('bob');", ())?;
+    /// let n = conn.insert_from_query_with("shouted", "SELECT name FROM names;", (), |row| {
+    ///     Ok(Some((row.column_text(0).to_string_lossy().to_uppercase(),)))
+    /// })?;
+    /// assert_eq!(n, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_from_query_with<F, P>(
+        &self,
+        dest_table: &str,
+        select_sql: &str,
+        params: impl Params,
+        mut transform: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(&mut Statement<'_>) -> Result<Option<P>>,
+        P: Params,
+    {
+        self.insert_from_query_savepoint(dest_table, select_sql, params, |row, insert| {
+            let Some(params) = transform(row)? else {
+                return Ok(false);
+            };
+            params.bind(insert)?;
+            Ok(true)
+        })
+    }
+
+    fn insert_from_query_savepoint(
+        &self,
+        dest_table: &str,
+        select_sql: &str,
+        params: impl Params,
+        bind_row: impl FnMut(&mut Statement<'_>, &mut Statement<'_>) -> Result<bool>,
+    ) -> Result<usize> {
+        self.execute("SAVEPOINT insert_from_query;", ())?;
+        let result = self.insert_from_query_internal(dest_table, select_sql, params, bind_row);
+        if result.is_err() {
+            let _ = self.execute("ROLLBACK TO insert_from_query;", ());
+        }
+        let release_result = self.execute("RELEASE insert_from_query;", ());
+        let n = result?;
+        release_result?;
+        Ok(n)
+    }
+
+    fn insert_from_query_internal(
+        &self,
+        dest_table: &str,
+        select_sql: &str,
+        params: impl Params,
+        mut bind_row: impl FnMut(&mut Statement<'_>, &mut Statement<'_>) -> Result<bool>,
+    ) -> Result<usize> {
+        let mut select = self.prepare_single(select_sql)?;
+        params.bind(&mut select)?;
+        let placeholders = vec!["?"; select.column_count()].join(", ");
+        let mut insert = self.prepare_single(&format!(
+            "INSERT INTO {} VALUES ({});",
+            Quote::as_id(dest_table),
+            placeholders
+        ))?;
+        let mut n = 0;
+        while select.step()?.has_row() {
+            insert.clear_bindings();
+            if !bind_row(&mut select, &mut insert)? {
+                continue;
+            }
+            let _guard = DbMutexGuard::lock(self.as_ptr());
+            insert.step()?;
+            insert.reset()?;
+            n += unsafe { sqlite3_changes(self.as_ptr()) } as usize;
+        }
+        Ok(n)
+    }
+}
+
+impl Conn {
+    /// Drops the indices on the table named `table` in the main database,
+    /// calls `f`, and then recreates the indices.
+    /// Bulk loading a table without indices
+    /// and building the indices afterward
+    /// is much faster than updating every index for each inserted row.
+    ///
+    /// The indices are recreated from their original `CREATE INDEX` statements,
+    /// so uniqueness, collations, and partial index predicates are preserved.
+    /// Indices that SQLite creates for `UNIQUE` and `PRIMARY KEY` constraints
+    /// cannot be dropped and are maintained as usual.
+    ///
+    /// Everything happens inside a savepoint,
+    /// so `f` must not begin or commit a transaction.
+    /// If `f` returns an error or an index cannot be recreated
+    /// (for example, because `f` inserted rows that violate a unique index),
+    /// then the savepoint is rolled back,
+    /// which restores the indices and discards any changes made by `f`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE t (x INTEGER);", ())?;
+    /// conn.execute("CREATE INDEX t_x ON t (x);", ())?;
+    /// conn.with_indexes_dropped("t", |conn| {
+    ///     conn.execute(
+    ///         "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 1000) \
+    ///         INSERT INTO t SELECT n FROM c;",
+    ///         (),
+    ///     )
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_indexes_dropped<T>(
+        &self,
+        table: &str,
+        f: impl FnOnce(&Conn) -> Result<T>,
+    ) -> Result<T> {
+        self.execute("SAVEPOINT with_indexes_dropped;", ())?;
+        let result = self.with_indexes_dropped_internal(table, f);
+        if result.is_err() {
+            let _ = self.execute("ROLLBACK TO with_indexes_dropped;", ());
+        }
+        let release_result = self.execute("RELEASE with_indexes_dropped;", ());
+        let x = result?;
+        release_result?;
+        Ok(x)
+    }
+
+    fn with_indexes_dropped_internal<T>(
+        &self,
+        table: &str,
+        f: impl FnOnce(&Conn) -> Result<T>,
+    ) -> Result<T> {
+        let mut table_exists = false;
+        let mut indexes = Vec::new();
+        let stream = self.query_stream(
+            "SELECT type = 'table', name, sql FROM main.sqlite_master \
+            WHERE tbl_name = ?1 AND type IN ('table', 'index') ORDER BY rowid;",
+            (table,),
+            |stmt| {
+                Ok((
+                    stmt.column_i64(0) != 0,
+                    stmt.column_text(1).to_string_lossy().into_owned(),
+                    match stmt.column_type(2) {
+                        DataType::Null => None,
+                        _ => Some(stmt.column_text(2).to_string_lossy().into_owned()),
+                    },
+                ))
+            },
+        )?;
+        for row in stream {
+            match row? {
+                (true, _, _) => table_exists = true,
+                // Automatic indices have no SQL and can't be dropped.
+                (false, _, None) => {}
+                (false, name, Some(sql)) => indexes.push((name, qualify_create(&sql, "main")?)),
+            }
+        }
+        if !table_exists {
+            return Err(Error::new(
+                ResultCode::ERROR,
+                format!("no such table: main.{}", table),
+            ));
+        }
+
+        for (name, _) in &indexes {
+            self.execute(&format!("DROP INDEX main.{};", Quote::as_id(name)), ())?;
+        }
+        let x = f(self)?;
+        for (_, sql) in &indexes {
+            self.execute(sql, ())?;
+        }
+        Ok(x)
+    }
+}
+
+/// Qualifies the name of the object created by a `CREATE TABLE`, `CREATE INDEX`,
+/// or `CREATE TRIGGER` statement read from the `sqlite_schema` table
+/// with `schema`, replacing any existing qualifier.
+/// Any `IF NOT EXISTS` clause is removed
+/// so that the statement fails if the object already exists.
+fn qualify_create(sql: &str, schema: &str) -> Result<String> {
+    let cannot_copy = || Error::new(ResultCode::ERROR, format!("cannot copy {:?}", sql));
+    let tokens = tokenize(sql)
+        .filter(|token| !token.is_trivia())
+        .collect::<Vec<_>>();
+    let mut tokens = tokens.iter();
+    let mut next = || tokens.next().ok_or_else(cannot_copy);
+    if !next()?.is_keyword("CREATE") {
+        return Err(cannot_copy());
+    }
+    let mut kind = next()?;
+    if kind.is_keyword("VIRTUAL") {
+        return Err(Error::new(ResultCode::ERROR, "cannot copy a virtual table"));
+    }
+    if kind.is_keyword("UNIQUE") {
+        kind = next()?;
+    }
+    if !["TABLE", "INDEX", "TRIGGER"]
+        .iter()
+        .any(|&keyword| kind.is_keyword(keyword))
+    {
+        return Err(cannot_copy());
+    }
+    let mut name = next()?;
+    if name.is_keyword("IF") {
+        if !next()?.is_keyword("NOT") || !next()?.is_keyword("EXISTS") {
+            return Err(cannot_copy());
+        }
+        name = next()?;
+    }
+    let is_dot = |token: &Token<'_>| token.kind == TokenKind::Other && token.text == ".";
+    let name = match (next(), next()) {
+        (Ok(dot), Ok(unqualified)) if is_dot(dot) => unqualified,
+        _ => name,
+    };
+    Ok(format!(
+        "{} {}.{}",
+        &sql[..kind.end()],
+        Quote::as_id(schema),
+        &sql[name.start..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_copy_table() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for sql in [
+            "ATTACH ':memory:' AS src;",
+            "ATTACH ':memory:' AS dst;",
+            "CREATE TABLE src.users (id INTEGER PRIMARY KEY, email TEXT NOT NULL);",
+            "CREATE UNIQUE INDEX src.users_email ON users (email);",
+            "INSERT INTO src.users (id, email) VALUES (1, 'alice@example.com'), (2, 'bob@example.com');",
+        ] {
+            conn.execute(sql, ()).unwrap();
+        }
+
+        conn.copy_table("src", "dst", "users").unwrap();
+
+        let rows = conn
+            .query_stream("SELECT id, email FROM dst.users ORDER BY id;", (), |stmt| {
+                Ok((stmt.column_i64(0), stmt.column_text(1).unwrap().to_string()))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (1, String::from("alice@example.com")),
+                (2, String::from("bob@example.com")),
+            ]
+        );
+        let index_count = conn
+            .query_row(
+                "SELECT count(*) FROM dst.sqlite_master WHERE type = 'index' AND name = 'users_email';",
+                (),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap();
+        assert_eq!(index_count, Some(1));
+
+        let err = conn
+            .execute(
+                "INSERT INTO dst.users (id, email) VALUES (3, 'alice@example.com');",
+                (),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        let err = conn
+            .execute(
+                "INSERT INTO dst.users (id, email) VALUES (1, 'carol@example.com');",
+                (),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        let err = conn
+            .execute("INSERT INTO dst.users (id) VALUES (4);", ())
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+    }
+
+    #[test]
+    fn test_copy_table_missing() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("ATTACH ':memory:' AS dst;", ()).unwrap();
+        let err = conn.copy_table("main", "dst", "nope").unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(conn.get_autocommit());
+    }
+
+    #[test]
+    fn test_copy_table_autoincrement() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for sql in [
+            "ATTACH ':memory:' AS dst;",
+            "CREATE TABLE log (id INTEGER PRIMARY KEY AUTOINCREMENT, msg TEXT);",
+            "INSERT INTO log (msg) VALUES ('a'), ('b'), ('c');",
+            "DELETE FROM log WHERE id = 3;",
+        ] {
+            conn.execute(sql, ()).unwrap();
+        }
+
+        conn.copy_table("main", "dst", "log").unwrap();
+        conn.execute("INSERT INTO dst.log (msg) VALUES ('d');", ())
+            .unwrap();
+        let id = conn
+            .query_row("SELECT max(id) FROM dst.log;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(id, Some(4));
+
+        // The destination table must not already exist.
+        let err = conn.copy_table("main", "dst", "log").unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(err.message().contains("already exists"), "{}", err);
+    }
+
+    #[test]
+    fn test_copy_virtual_table() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("ATTACH ':memory:' AS dst;", ()).unwrap();
+        conn.execute("CREATE VIRTUAL TABLE docs USING fts5(body);", ())
+            .unwrap();
+        let err = conn.copy_table("main", "dst", "docs").unwrap_err();
+        assert!(err.message().contains("virtual table"), "{}", err);
+        assert!(conn.get_autocommit());
+    }
+
+    #[test]
+    fn test_qualify_create() {
+        for (sql, want) in [
+            ("CREATE TABLE t (x)", "CREATE TABLE \"dst\".t (x)"),
+            (
+                "CREATE TABLE IF NOT EXISTS \"my table\" (x)",
+                "CREATE TABLE \"dst\".\"my table\" (x)",
+            ),
+            (
+                "CREATE UNIQUE INDEX if not exists src.i ON t (x)",
+                "CREATE UNIQUE INDEX \"dst\".i ON t (x)",
+            ),
+            (
+                "CREATE TRIGGER /* main.tr */ tr AFTER INSERT ON t BEGIN SELECT 1; END",
+                "CREATE TRIGGER \"dst\".tr AFTER INSERT ON t BEGIN SELECT 1; END",
+            ),
+        ] {
+            assert_eq!(qualify_create(sql, "dst").unwrap(), want, "sql = {:?}", sql);
+        }
+        for sql in [
+            "CREATE VIRTUAL TABLE v USING fts5(x)",
+            "CREATE VIEW v AS SELECT 1",
+        ] {
+            assert!(qualify_create(sql, "dst").is_err(), "sql = {:?}", sql);
+        }
+    }
+
+    #[test]
+    fn test_insert_from_query() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for sql in [
+            "CREATE TABLE src (id INTEGER PRIMARY KEY, name TEXT, score REAL);",
+            "CREATE TABLE dst (id INTEGER PRIMARY KEY, name TEXT, score REAL);",
+            "INSERT INTO src VALUES (1, 'alice', 3.5), (2, 'bob', 1.0), (3, 'carol', 4.0), (4, 'dave', NULL);",
+        ] {
+            conn.execute(sql, ()).unwrap();
+        }
+
+        let n = conn
+            .insert_from_query_with(
+                "dst",
+                "SELECT id, name, score FROM src WHERE score >= ?1 ORDER BY id;",
+                (2.0,),
+                |row| {
+                    if row.column_text(1).to_string_lossy() == "carol" {
+                        return Ok(None);
+                    }
+                    Ok(Some((
+                        row.column_i64(0) * 10,
+                        row.column_text(1).to_string_lossy().to_uppercase(),
+                        row.column_f64(2) * 2.0,
+                    )))
+                },
+            )
+            .unwrap();
+        assert_eq!(n, 1);
+        let n = conn
+            .insert_from_query("dst", "SELECT * FROM src WHERE id > 3;", ())
+            .unwrap();
+        assert_eq!(n, 1);
+
+        let rows = conn
+            .query_stream("SELECT id, name, score FROM dst ORDER BY id;", (), |stmt| {
+                Ok((
+                    stmt.column_i64(0),
+                    stmt.column_text(1).unwrap().to_string(),
+                    stmt.column_type(2),
+                    stmt.column_f64(2),
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (4, String::from("dave"), DataType::Null, 0.0),
+                (10, String::from("ALICE"), DataType::Float, 7.0),
+            ]
+        );
+
+        // A failing row rolls back the rows inserted before it.
+        let err = conn
+            .insert_from_query(
+                "dst",
+                "SELECT id * 10, name, score FROM src ORDER BY id;",
+                (),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        let count = conn
+            .query_row("SELECT count(*) FROM dst;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(count, Some(2));
+        assert!(conn.get_autocommit());
+    }
+
+    #[test]
+    fn test_with_indexes_dropped() {
+        const SCHEMA: [&str; 4] = [
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, a TEXT, b INTEGER, c TEXT UNIQUE);",
+            "CREATE INDEX t_a ON t (a COLLATE NOCASE);",
+            "CREATE UNIQUE INDEX t_ab ON t (a, b);",
+            "CREATE INDEX t_b ON t (b) WHERE b > 0;",
+        ];
+        const LOAD: &str = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 50000) \
+            INSERT INTO t (a, b, c) SELECT hex(randomblob(8)), i % 100 - 50, printf('%08d', i) FROM n;";
+        let indexes = |conn: &Conn| {
+            conn.query_stream(
+                "SELECT name, sql FROM sqlite_master WHERE type = 'index' ORDER BY name;",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_text(0).unwrap().to_string(),
+                        stmt.column_text(1).ok().map(String::from),
+                    ))
+                },
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+        };
+
+        // A small page cache makes per-row index maintenance
+        // read and write pages in random order,
+        // which is the case where dropping the indices helps most.
+        let dir = TempDir::new("indexes-dropped");
+        let open = |name: &str| {
+            let conn = Connection::open(dir.join_cstr(name), OpenFlags::default()).unwrap();
+            conn.execute("PRAGMA cache_size = 20;", ()).unwrap();
+            for sql in SCHEMA {
+                conn.execute(sql, ()).unwrap();
+            }
+            conn
+        };
+        let baseline = open("baseline.db");
+        let conn = open("dropped.db");
+        let before = indexes(&conn);
+        assert_eq!(before.len(), 4);
+
+        let mut baseline_stats = baseline.cache_stats().unwrap();
+        baseline.execute(LOAD, ()).unwrap();
+        let baseline_stats = baseline_stats.delta(&baseline).unwrap();
+        let mut dropped_stats = conn.cache_stats().unwrap();
+        let n = conn
+            .with_indexes_dropped("t", |conn| {
+                assert_eq!(indexes(conn).len(), 1, "only the automatic index remains");
+                conn.execute(LOAD, ())
+            })
+            .unwrap();
+        let dropped_stats = dropped_stats.delta(&conn).unwrap();
+        assert_eq!(n, 50000);
+        assert!(
+            dropped_stats.misses * 10 < baseline_stats.misses,
+            "with_indexes_dropped: {:?}, baseline: {:?}",
+            dropped_stats,
+            baseline_stats
+        );
+
+        assert_eq!(indexes(&conn), before);
+        let check = conn
+            .query_row("PRAGMA integrity_check;", (), |stmt| {
+                Ok(stmt.column_text(0).unwrap().to_string())
+            })
+            .unwrap();
+        assert_eq!(check.as_deref(), Some("ok"));
+        let mut stmt = conn
+            .prepare("SELECT c FROM t WHERE b = 10 AND b > 0;")
+            .0
+            .unwrap()
+            .unwrap();
+        let mut rows = 0;
+        while stmt.step().unwrap().has_row() {
+            rows += 1;
+        }
+        assert_eq!(rows, 500);
+        assert!(!stmt.had_full_scan());
+        drop(stmt);
+        let err = conn
+            .execute("INSERT INTO t (a, b) SELECT a, b FROM t WHERE id = 1;", ())
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+
+        // Errors restore the indices and discard the load.
+        let err = conn
+            .with_indexes_dropped("t", |conn| {
+                conn.execute("DELETE FROM t;", ())?;
+                Err::<(), _>(Error::new(ResultCode::ABORT, "oops"))
+            })
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ABORT);
+        assert_eq!(indexes(&conn), before);
+        let count = conn
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(count, Some(50000));
+
+        // Failing to recreate a unique index also rolls back.
+        let err = conn
+            .with_indexes_dropped("t", |conn| {
+                conn.execute("INSERT INTO t (a, b) SELECT a, b FROM t WHERE id = 1;", ())
+            })
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert_eq!(indexes(&conn), before);
+        assert!(conn.get_autocommit());
+
+        let err = conn.with_indexes_dropped("nope", |_| Ok(())).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+}
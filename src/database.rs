@@ -0,0 +1,426 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_int, CStr};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::reader::{set_busy_timeout, ReaderOptions};
+use crate::*;
+
+/// A database shared among threads
+/// using one write connection and a pool of read-only connections.
+///
+/// This follows the concurrency model of SQLite's [WAL mode]:
+/// only one connection can write at a time,
+/// but readers do not block the writer or each other.
+/// Writes are serialized by a mutex around the write connection,
+/// while each read uses a read-only connection from the pool,
+/// opening a new one if none are idle.
+/// Readers are opened with the same VFS and URI parameters as the write connection
+/// and share its [busy timeout][Database::set_busy_timeout].
+///
+/// [WAL mode]: https://www.sqlite.org/wal.html
+///
+/// # Example
+///
+/// ```no_run
+/// # use std::ffi::CString;
+/// # use zombiezen_sqlite::Database;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = Database::open(CString::new("app.db")?, 4)?;
+/// db.execute("CREATE TABLE IF NOT EXISTS foo (x INTEGER);", ())?;
+/// db.execute("INSERT INTO foo VALUES (?1);", (42,))?;
+/// let xs: Vec<(i64,)> = db.query("SELECT x FROM foo;", ())?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Database {
+    writer: Mutex<Connection>,
+    reader_options: ReaderOptions,
+    /// The busy timeout in milliseconds
+    /// most recently set with [`Database::set_busy_timeout`].
+    busy_timeout: AtomicI32,
+    readers: Mutex<ReaderPool>,
+    reader_available: Condvar,
+    max_readers: usize,
+    /// The number of calls to [`Database::write`] that have finished.
+    write_generation: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct ReaderPool {
+    idle: Vec<Connection>,
+    /// The number of reader connections that are open, including idle ones.
+    open: usize,
+}
+
+impl Database {
+    /// Opens the database file at `filename` for reading and writing,
+    /// creating it if it does not exist,
+    /// and switches it to WAL mode.
+    /// At most `max_readers` read-only connections are opened.
+    ///
+    /// Returns a [`ResultCode::MISUSE`] error if `filename` names
+    /// a temporary or in-memory database,
+    /// since such databases cannot be shared among connections.
+    pub fn open(filename: impl AsRef<CStr>, max_readers: usize) -> Result<Database> {
+        if max_readers == 0 {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "database needs at least one reader",
+            ));
+        }
+        let writer = Connection::open(filename, OpenFlags::default())?;
+        let reader_options = writer.reader_options(OpenFlags::default())?;
+        let journal_mode = writer
+            .query_row("PRAGMA journal_mode = wal;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })?
+            .unwrap_or_default();
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            return Err(Error::new(
+                ResultCode::ERROR,
+                format!(
+                    "could not enable WAL mode (journal mode is {})",
+                    journal_mode
+                ),
+            ));
+        }
+        Ok(Database {
+            writer: Mutex::new(writer),
+            busy_timeout: AtomicI32::new(reader_options.busy_timeout),
+            reader_options,
+            readers: Mutex::new(ReaderPool::default()),
+            reader_available: Condvar::new(),
+            max_readers,
+            write_generation: AtomicU64::new(0),
+        })
+    }
+
+    /// Sets the [busy timeout] of the write connection and every reader.
+    /// When a connection would otherwise fail with [`ResultCode::BUSY`],
+    /// it retries until the timeout elapses.
+    /// Waits for the write connection like [`Database::write`].
+    /// Readers that are in use pick up the new timeout
+    /// the next time they are taken from the pool.
+    ///
+    /// [busy timeout]: https://www.sqlite.org/c3ref/busy_timeout.html
+    #[doc(alias = "sqlite3_busy_timeout")]
+    pub fn set_busy_timeout(&self, timeout: Duration) {
+        let ms = c_int::try_from(timeout.as_millis()).unwrap_or(c_int::MAX);
+        let writer = self.writer.lock().expect("write connection poisoned");
+        set_busy_timeout(&writer, ms);
+        self.busy_timeout.store(ms, Ordering::Relaxed);
+    }
+
+    /// Calls `f` with the write connection,
+    /// waiting until no other thread is using it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a previous call to `f` panicked.
+    pub fn write<T>(&self, f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+        let mut writer = self.writer.lock().expect("write connection poisoned");
+        let result = f(&mut writer);
+        self.write_generation.fetch_add(1, Ordering::Release);
+        result
+    }
+
+    /// Calls `f` with a read-only connection from the pool.
+    /// If all `max_readers` connections are in use,
+    /// then `read` waits until one is returned to the pool.
+    pub fn read<T>(&self, f: impl FnOnce(&Conn) -> Result<T>) -> Result<T> {
+        let reader = self.reader()?;
+        f(&reader)
+    }
+
+    /// Takes a read-only connection from the pool,
+    /// waiting like [`Database::read`] if none are available.
+    /// The connection is returned to the pool when the [`PooledReader`] is dropped.
+    pub fn reader(&self) -> Result<PooledReader<'_>> {
+        let mut reader = self.acquire_reader()?;
+        reader.generation = self.write_generation.load(Ordering::Acquire);
+        Ok(reader)
+    }
+
+    /// Runs `sql` on the write connection.
+    /// See [`Conn::execute`] for details.
+    pub fn execute(&self, sql: &str, params: impl Params) -> Result<usize> {
+        self.write(|conn| conn.execute(sql, params))
+    }
+
+    /// Runs `sql` on a read-only connection.
+    /// See [`Conn::query`] for details.
+    pub fn query<P: Params, R: FromRow>(&self, sql: &str, params: P) -> Result<Vec<R>> {
+        self.read(|conn| conn.query(sql, params))
+    }
+
+    fn acquire_reader(&self) -> Result<PooledReader<'_>> {
+        let conn = self.take_reader_connection()?;
+        set_busy_timeout(&conn, self.busy_timeout.load(Ordering::Relaxed));
+        Ok(PooledReader {
+            db: self,
+            conn: Some(conn),
+            generation: 0,
+        })
+    }
+
+    /// Takes an idle reader connection from the pool
+    /// or opens a new one if there is room.
+    fn take_reader_connection(&self) -> Result<Connection> {
+        let mut pool = self.lock_readers();
+        loop {
+            if let Some(conn) = pool.idle.pop() {
+                return Ok(conn);
+            }
+            if pool.open < self.max_readers {
+                break;
+            }
+            pool = self
+                .reader_available
+                .wait(pool)
+                .expect("reader pool poisoned");
+        }
+        pool.open += 1;
+        drop(pool);
+        let result = self.reader_options.open();
+        if result.is_err() {
+            self.lock_readers().open -= 1;
+            self.reader_available.notify_one();
+        }
+        result
+    }
+
+    fn lock_readers(&self) -> MutexGuard<'_, ReaderPool> {
+        self.readers.lock().expect("reader pool poisoned")
+    }
+}
+
+/// A read-only connection borrowed from a [`Database`]'s pool
+/// that is returned to the pool when dropped.
+///
+/// A read transaction sees the database as it was when the transaction started,
+/// so a reader that holds a transaction open across several queries
+/// will not see writes committed in the meantime.
+/// Call [`PooledReader::ensure_fresh`] before a query
+/// that must observe all writes made through the [`Database`] so far.
+///
+/// If the reader is dropped while still inside a transaction,
+/// its connection is closed instead of being returned to the pool.
+#[derive(Debug)]
+pub struct PooledReader<'a> {
+    db: &'a Database,
+    conn: Option<Connection>,
+    /// The database's write generation as of the reader's last refresh.
+    generation: u64,
+}
+
+impl<'a> PooledReader<'a> {
+    /// Ensures that the reader's next query sees every write
+    /// that finished on the [`Database`] before this call,
+    /// returning whether the reader had to be refreshed.
+    ///
+    /// Outside of a transaction, each query already reads the latest data,
+    /// so this only records the current state.
+    /// If the reader is inside a transaction
+    /// and a write has finished since the reader was taken from the pool
+    /// or last refreshed,
+    /// then the transaction is rolled back and a new read transaction is started,
+    /// whose snapshot is established by reading `PRAGMA data_version`.
+    pub fn ensure_fresh(&mut self) -> Result<bool> {
+        let generation = self.db.write_generation.load(Ordering::Acquire);
+        let stale = generation != self.generation;
+        self.generation = generation;
+        let conn = self.conn.as_ref().unwrap();
+        if !stale || conn.get_autocommit() {
+            return Ok(false);
+        }
+        conn.execute("ROLLBACK;", ())?;
+        conn.execute("BEGIN;", ())?;
+        // BEGIN is deferred, so the read transaction starts on first read.
+        conn.execute("PRAGMA data_version;", ())?;
+        Ok(true)
+    }
+}
+
+impl<'a> Deref for PooledReader<'a> {
+    type Target = Conn;
+
+    #[inline]
+    fn deref(&self) -> &Conn {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl<'a> Drop for PooledReader<'a> {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        let mut pool = self.db.lock_readers();
+        if conn.get_autocommit() {
+            pool.idle.push(conn);
+        } else {
+            // The caller left a transaction open, so don't reuse the connection.
+            pool.open -= 1;
+            drop(conn);
+        }
+        drop(pool);
+        self.db.reader_available.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_read_during_write() {
+        let dir = TempDir::new("database-read-during-write");
+        let path_cstr = dir.join_cstr("test.db");
+        let db = Database::open(&path_cstr, 2).unwrap();
+        db.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        db.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+
+        let (in_txn_tx, in_txn_rx) = mpsc::channel::<()>();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let db = &db;
+        thread::scope(|s| {
+            let writer = s.spawn(move || {
+                db.write(|conn| {
+                    conn.execute("BEGIN IMMEDIATE;", ())?;
+                    conn.execute("INSERT INTO t VALUES (2);", ())?;
+                    in_txn_tx.send(()).unwrap();
+                    done_rx.recv().unwrap();
+                    conn.execute("COMMIT;", ())?;
+                    Ok(())
+                })
+            });
+
+            in_txn_rx.recv().unwrap();
+            // The write transaction is open, but readers can still proceed.
+            let rows: Vec<(i64,)> = db.query("SELECT count(*) FROM t;", ()).unwrap();
+            assert_eq!(rows, [(1,)]);
+            done_tx.send(()).unwrap();
+            writer.join().unwrap().unwrap();
+        });
+        let rows: Vec<(i64,)> = db.query("SELECT count(*) FROM t;", ()).unwrap();
+        assert_eq!(rows, [(2,)]);
+    }
+
+    #[test]
+    fn test_writes_serialize() {
+        let dir = TempDir::new("database-writes-serialize");
+        let path_cstr = dir.join_cstr("test.db");
+        let db = Database::open(&path_cstr, 4).unwrap();
+        db.execute("CREATE TABLE counter (n INTEGER);", ()).unwrap();
+        db.execute("INSERT INTO counter VALUES (0);", ()).unwrap();
+
+        thread::scope(|s| {
+            for _ in 0..8 {
+                s.spawn(|| {
+                    for _ in 0..5 {
+                        db.write(|conn| {
+                            // A read-modify-write without a transaction
+                            // only works if writers are serialized.
+                            let n = conn
+                                .query_row("SELECT n FROM counter;", (), |stmt| {
+                                    Ok(stmt.column_i64(0))
+                                })?
+                                .unwrap();
+                            thread::sleep(Duration::from_millis(1));
+                            conn.execute("UPDATE counter SET n = ?1;", (n + 1,))?;
+                            Ok(())
+                        })
+                        .unwrap();
+                        let rows: Vec<(i64,)> = db.query("SELECT n FROM counter;", ()).unwrap();
+                        assert!(rows[0].0 > 0);
+                    }
+                });
+            }
+        });
+        let rows: Vec<(i64,)> = db.query("SELECT n FROM counter;", ()).unwrap();
+        assert_eq!(rows, [(40,)]);
+        assert!(db.lock_readers().open <= 4);
+    }
+
+    #[test]
+    fn test_ensure_fresh() {
+        let dir = TempDir::new("database-ensure-fresh");
+        let path_cstr = dir.join_cstr("test.db");
+        let db = Database::open(&path_cstr, 1).unwrap();
+        db.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        db.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        let count = |conn: &Conn| {
+            conn.query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap()
+                .unwrap()
+        };
+
+        let mut reader = db.reader().unwrap();
+        assert!(!reader.ensure_fresh().unwrap());
+        reader.execute("BEGIN;", ()).unwrap();
+        assert_eq!(count(&reader), 1);
+
+        db.execute("INSERT INTO t VALUES (2);", ()).unwrap();
+        // The reader's transaction still sees the old snapshot.
+        assert_eq!(count(&reader), 1);
+        assert!(reader.ensure_fresh().unwrap());
+        assert!(!reader.get_autocommit());
+        assert_eq!(count(&reader), 2);
+        assert!(!reader.ensure_fresh().unwrap());
+        reader.execute("COMMIT;", ()).unwrap();
+
+        // Outside a transaction, reads routed through the pool see the write immediately.
+        db.execute("INSERT INTO t VALUES (3);", ()).unwrap();
+        assert!(!reader.ensure_fresh().unwrap());
+        assert_eq!(count(&reader), 3);
+        drop(reader);
+        db.execute("INSERT INTO t VALUES (4);", ()).unwrap();
+        let rows: Vec<(i64,)> = db.query("SELECT count(*) FROM t;", ()).unwrap();
+        assert_eq!(rows, [(4,)]);
+    }
+
+    #[test]
+    fn test_set_busy_timeout() {
+        let dir = TempDir::new("database-set-busy-timeout");
+        let path_cstr = dir.join_cstr("test.db");
+        let db = Database::open(&path_cstr, 2).unwrap();
+        let busy_timeout = |conn: &Conn| {
+            conn.query_row("PRAGMA busy_timeout;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap()
+                .unwrap()
+        };
+        // Put a connection in the pool before changing the timeout.
+        let idle = db.reader().unwrap();
+        drop(idle);
+
+        db.set_busy_timeout(Duration::from_millis(250));
+        db.write(|conn| {
+            assert_eq!(busy_timeout(conn), 250);
+            Ok(())
+        })
+        .unwrap();
+        let first = db.reader().unwrap();
+        let second = db.reader().unwrap();
+        assert_eq!(busy_timeout(&first), 250);
+        assert_eq!(busy_timeout(&second), 250);
+        drop((first, second));
+    }
+
+    #[test]
+    fn test_open_memory() {
+        let err = Database::open(CString::new(":memory:").unwrap(), 1).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+    }
+}
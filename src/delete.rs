@@ -0,0 +1,120 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use libsqlite3_sys::sqlite3_changes;
+
+use crate::connection::DbMutexGuard;
+use crate::*;
+
+impl Conn {
+    /// Deletes the rows of `table` that match the SQL expression `where_clause`
+    /// at most `batch_size` rows at a time,
+    /// returning the total number of rows deleted.
+    /// `params` is bound to `where_clause` for every batch.
+    /// After each batch, `f` is called with the number of rows deleted so far.
+    ///
+    /// Outside of a transaction, each batch is committed separately,
+    /// so other connections can write to the database between batches
+    /// and the WAL can be checkpointed,
+    /// instead of a single large delete holding the write lock until it finishes.
+    /// If the connection is already inside a transaction,
+    /// then the batches become part of it and nothing is committed.
+    /// If an error occurs, batches that were already committed stay deleted.
+    ///
+    /// `table` must be a rowid table.
+    /// `where_clause` is inserted into the generated SQL as-is,
+    /// so it must not come from untrusted input.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE log (created INTEGER, msg TEXT);", ())?;
+    /// let n = conn.delete_in_batches("log", "created < ?1", (1_700_000_000,), 500, |n| {
+    ///     println!("deleted {} rows", n);
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn delete_in_batches(
+        &self,
+        table: &str,
+        where_clause: &str,
+        params: impl Params,
+        batch_size: usize,
+        mut f: impl FnMut(u64),
+    ) -> Result<u64> {
+        if batch_size == 0 {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "delete_in_batches called with a zero batch size",
+            ));
+        }
+        let table = Quote::as_id(table);
+        let mut stmt = self.prepare_single(&format!(
+            "DELETE FROM {table} WHERE rowid IN \
+            (SELECT rowid FROM {table} WHERE ({where_clause}) LIMIT {batch_size});",
+        ))?;
+        params.bind(&mut stmt)?;
+        let mut total = 0;
+        loop {
+            let n = {
+                let _guard = DbMutexGuard::lock(self.as_ptr());
+                while stmt.step()?.has_row() {}
+                stmt.reset()?;
+                unsafe { sqlite3_changes(self.as_ptr()) as u64 }
+            };
+            if n == 0 {
+                return Ok(total);
+            }
+            total += n;
+            f(total);
+            if n < batch_size as u64 {
+                return Ok(total);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_delete_in_batches() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (n INTEGER);", ()).unwrap();
+        conn.execute(
+            "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 15000) \
+            INSERT INTO t SELECT n FROM c;",
+            (),
+        )
+        .unwrap();
+
+        let mut progress = Vec::new();
+        let n = conn
+            .delete_in_batches("t", "n > ?1", (5000,), 1000, |n| progress.push(n))
+            .unwrap();
+        assert_eq!(n, 10000);
+        assert_eq!(progress, (1..=10).map(|i| i * 1000).collect::<Vec<u64>>());
+        let remaining = conn
+            .query_row("SELECT count(*), max(n) FROM t;", (), |stmt| {
+                Ok((stmt.column_i64(0), stmt.column_i64(1)))
+            })
+            .unwrap();
+        assert_eq!(remaining, Some((5000, 5000)));
+
+        let n = conn
+            .delete_in_batches("t", "n > 5000", (), 1000, |_| panic!("nothing to delete"))
+            .unwrap();
+        assert_eq!(n, 0);
+        assert!(conn.delete_in_batches("t", "true", (), 0, |_| {}).is_err());
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::cmp::Ordering;
+
+use crate::*;
+
+/// The differences between two databases
+/// returned by [`Conn::diff`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaDiff {
+    /// Schema objects that exist only in the other database.
+    pub added: Vec<SchemaObject>,
+    /// Schema objects that exist only in the receiver's database.
+    pub removed: Vec<SchemaObject>,
+    /// Schema objects that exist in both databases with different definitions,
+    /// as `(before, after)` pairs.
+    pub changed: Vec<(SchemaObject, SchemaObject)>,
+    /// Row differences in tables that have the same definition in both databases.
+    pub rows: Vec<RowDiff>,
+}
+
+impl SchemaDiff {
+    /// Reports whether the databases are identical.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.rows.is_empty()
+    }
+}
+
+/// An entry in a database's `sqlite_schema` table.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SchemaObject {
+    /// The type of the object:
+    /// `"table"`, `"index"`, `"view"`, or `"trigger"`.
+    pub kind: String,
+    /// The name of the object.
+    pub name: String,
+    /// The `CREATE` statement that defines the object.
+    pub sql: String,
+}
+
+/// A row that differs between two databases.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RowDiff {
+    /// The name of the table that contains the row.
+    pub table: String,
+    /// The row's primary key (or rowid),
+    /// formatted as a comma-separated list of SQL literals.
+    pub key: String,
+    /// How the row differs.
+    pub change: RowChange,
+}
+
+/// The kind of difference described by a [`RowDiff`].
+/// Rows are formatted as a comma-separated list of SQL literals
+/// of the row's columns in table order.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RowChange {
+    /// The row exists only in the other database.
+    Added(String),
+    /// The row exists only in the receiver's database.
+    Removed(String),
+    /// The row exists in both databases with different values.
+    Changed {
+        /// The row in the receiver's database.
+        before: String,
+        /// The row in the other database.
+        after: String,
+    },
+}
+
+impl Conn {
+    /// Compares the schema and contents of the main database of `self`
+    /// to the main database of `other`,
+    /// reporting the changes needed to go from `self` to `other`.
+    /// Internal objects whose names begin with `sqlite_` are ignored.
+    ///
+    /// Rows are only compared for tables that have identical definitions in both databases.
+    /// Rows are matched by primary key
+    /// (or rowid for tables without an explicit primary key)
+    /// and reported in primary key order,
+    /// comparing text keys with the `BINARY` collation.
+    /// Tables are read one row at a time,
+    /// so large tables can be compared without loading them into memory.
+    /// Use [`Conn::diff_schema`] to skip comparing rows.
+    pub fn diff(&self, other: &Conn) -> Result<SchemaDiff> {
+        let mut diff = self.diff_schema(other)?;
+        let changed_tables = diff
+            .changed
+            .iter()
+            .map(|(before, _)| before)
+            .chain(&diff.removed)
+            .filter(|obj| obj.kind == "table")
+            .map(|obj| obj.name.clone())
+            .collect::<Vec<_>>();
+        for obj in self.schema_objects()? {
+            if obj.kind != "table" || changed_tables.contains(&obj.name) {
+                continue;
+            }
+            diff_rows(self, other, &obj.name, &mut diff.rows)?;
+        }
+        Ok(diff)
+    }
+
+    /// Compares the schema of the main database of `self`
+    /// to the main database of `other`
+    /// without comparing the databases' contents.
+    /// See [`Conn::diff`] for details.
+    pub fn diff_schema(&self, other: &Conn) -> Result<SchemaDiff> {
+        let before = self.schema_objects()?;
+        let after = other.schema_objects()?;
+        let mut diff = SchemaDiff::default();
+        for obj in &before {
+            match after
+                .iter()
+                .find(|o| o.kind == obj.kind && o.name == obj.name)
+            {
+                Some(o) if o.sql != obj.sql => diff.changed.push((obj.clone(), o.clone())),
+                Some(_) => {}
+                None => diff.removed.push(obj.clone()),
+            }
+        }
+        diff.added = after
+            .into_iter()
+            .filter(|o| !before.iter().any(|b| b.kind == o.kind && b.name == o.name))
+            .collect();
+        Ok(diff)
+    }
+
+    fn schema_objects(&self) -> Result<Vec<SchemaObject>> {
+        self.query_stream(
+            "SELECT type, name, sql FROM main.sqlite_master \
+            WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' \
+            ORDER BY type, name;",
+            (),
+            |stmt| {
+                Ok(SchemaObject {
+                    kind: stmt.column_text(0).to_string_lossy().into_owned(),
+                    name: stmt.column_text(1).to_string_lossy().into_owned(),
+                    sql: stmt.column_text(2).to_string_lossy().into_owned(),
+                })
+            },
+        )?
+        .collect()
+    }
+}
+
+/// Appends the differences in the rows of `table` between `a` and `b` to `out`.
+/// Both tables are read in primary key order and merged,
+/// so only one row from each database is held in memory at a time.
+fn diff_rows(a: &Conn, b: &Conn, table: &str, out: &mut Vec<RowDiff>) -> Result<()> {
+    // Both tables have the same definition,
+    // so the same query works for both.
+    let (query, n_key) = row_query(a, table)?;
+    let read_row = |stmt: &mut Statement<'_>| -> Result<DiffRow> {
+        Ok(DiffRow {
+            key_values: (0..n_key).map(|i| KeyValue::read(stmt, i)).collect(),
+            key: stmt.column_text(n_key).to_string_lossy().into_owned(),
+            row: stmt.column_text(n_key + 1).to_string_lossy().into_owned(),
+        })
+    };
+    let mut before = a.query_stream(&query, (), read_row)?;
+    let mut after = b.query_stream(&query, (), read_row)?;
+    let mut next_before = before.next().transpose()?;
+    let mut next_after = after.next().transpose()?;
+    loop {
+        let order = match (&next_before, &next_after) {
+            (None, None) => return Ok(()),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(x), Some(y)) => compare_keys(&x.key_values, &y.key_values),
+        };
+        let (key, change) = match order {
+            Ordering::Less => {
+                let x = next_before.take().unwrap();
+                next_before = before.next().transpose()?;
+                (x.key, RowChange::Removed(x.row))
+            }
+            Ordering::Greater => {
+                let y = next_after.take().unwrap();
+                next_after = after.next().transpose()?;
+                (y.key, RowChange::Added(y.row))
+            }
+            Ordering::Equal => {
+                let x = next_before.take().unwrap();
+                let y = next_after.take().unwrap();
+                next_before = before.next().transpose()?;
+                next_after = after.next().transpose()?;
+                if x.row == y.row {
+                    continue;
+                }
+                (
+                    x.key,
+                    RowChange::Changed {
+                        before: x.row,
+                        after: y.row,
+                    },
+                )
+            }
+        };
+        out.push(RowDiff {
+            table: table.to_string(),
+            key,
+            change,
+        });
+    }
+}
+
+/// A row read by [`diff_rows`].
+struct DiffRow {
+    key_values: Vec<KeyValue>,
+    key: String,
+    row: String,
+}
+
+/// The value of a primary key column.
+enum KeyValue {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Text(Vec<u8>),
+    Blob(Vec<u8>),
+}
+
+impl KeyValue {
+    fn read(stmt: &mut Statement<'_>, i: usize) -> Self {
+        match stmt.column_type(i) {
+            DataType::Null => KeyValue::Null,
+            DataType::Integer => KeyValue::Integer(stmt.column_i64(i)),
+            DataType::Float => KeyValue::Float(stmt.column_f64(i)),
+            DataType::Text => KeyValue::Text(stmt.column_text_raw(i, Encoding::Utf8).into_owned()),
+            DataType::Blob => KeyValue::Blob(stmt.column_blob(i).to_vec()),
+        }
+    }
+
+    /// Returns the position of the value's storage class in SQLite's sort order.
+    fn class_rank(&self) -> u8 {
+        match self {
+            KeyValue::Null => 0,
+            KeyValue::Integer(_) | KeyValue::Float(_) => 1,
+            KeyValue::Text(_) => 2,
+            KeyValue::Blob(_) => 3,
+        }
+    }
+}
+
+/// Compares two primary keys the same way as an `ORDER BY`
+/// on the key columns with the `BINARY` collation.
+fn compare_keys(a: &[KeyValue], b: &[KeyValue]) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| match (x, y) {
+            (KeyValue::Integer(x), KeyValue::Integer(y)) => x.cmp(y),
+            (KeyValue::Float(x), KeyValue::Float(y)) => x.total_cmp(y),
+            (KeyValue::Integer(x), KeyValue::Float(y)) => compare_int_float(*x, *y),
+            (KeyValue::Float(x), KeyValue::Integer(y)) => compare_int_float(*y, *x).reverse(),
+            (KeyValue::Text(x), KeyValue::Text(y)) | (KeyValue::Blob(x), KeyValue::Blob(y)) => {
+                x.cmp(y)
+            }
+            _ => x.class_rank().cmp(&y.class_rank()),
+        })
+        .find(|&order| order != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Compares an integer to a floating-point number without losing precision,
+/// like SQLite's `sqlite3IntFloatCompare`.
+fn compare_int_float(i: i64, r: f64) -> Ordering {
+    if r < -9223372036854775808.0 {
+        return Ordering::Greater;
+    }
+    if r >= 9223372036854775808.0 {
+        return Ordering::Less;
+    }
+    match i.cmp(&(r as i64)) {
+        Ordering::Equal => (i as f64).total_cmp(&r),
+        order => order,
+    }
+}
+
+/// Builds a query that returns the values of the primary key columns
+/// followed by the formatted key and contents
+/// of every row in `table`, in primary key order.
+/// Also returns the number of primary key columns.
+fn row_query(conn: &Conn, table: &str) -> Result<(String, usize)> {
+    let columns = conn
+        .query_stream(
+            "SELECT name, pk FROM pragma_table_info(?1, 'main') ORDER BY cid;",
+            (table,),
+            |stmt| {
+                Ok((
+                    stmt.column_text(0).to_string_lossy().into_owned(),
+                    stmt.column_i64(1),
+                ))
+            },
+        )?
+        .collect::<Result<Vec<_>>>()?;
+    let mut pk = columns.iter().filter(|(_, pk)| *pk > 0).collect::<Vec<_>>();
+    pk.sort_by_key(|(_, pk)| *pk);
+    let pk = if pk.is_empty() {
+        vec![String::from("_rowid_")]
+    } else {
+        pk.into_iter()
+            .map(|(name, _)| Quote::as_id(name).to_string())
+            .collect()
+    };
+    let quote_all = |cols: &[String]| {
+        cols.iter()
+            .map(|col| format!("quote({})", col))
+            .collect::<Vec<_>>()
+            .join(" || ',' || ")
+    };
+    let all = columns
+        .iter()
+        .map(|(name, _)| Quote::as_id(name).to_string())
+        .collect::<Vec<_>>();
+    // The rows are merged with compare_keys,
+    // so they must be sorted without the columns' own collations.
+    let query = format!(
+        "SELECT {}, {}, {} FROM main.{} ORDER BY {};",
+        pk.join(", "),
+        quote_all(&pk),
+        quote_all(&all),
+        Quote::as_id(table),
+        pk.iter()
+            .map(|col| format!("{} COLLATE BINARY", col))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok((query, pk.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    fn open(sql: &[&str]) -> Connection {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for sql in sql {
+            conn.execute(sql, ()).unwrap();
+        }
+        conn
+    }
+
+    const SETUP: &[&str] = &[
+        "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);",
+        "CREATE TABLE tags (name TEXT NOT NULL);",
+        "INSERT INTO users VALUES (1, 'alice'), (2, 'bob');",
+        "INSERT INTO tags VALUES ('x');",
+    ];
+
+    #[test]
+    fn test_diff_identical() {
+        let a = open(SETUP);
+        let b = open(SETUP);
+        assert!(a.diff(&b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let a = open(SETUP);
+        let b = open(SETUP);
+        b.execute("CREATE INDEX users_name ON users (name);", ())
+            .unwrap();
+        b.execute("UPDATE users SET name = 'robert' WHERE id = 2;", ())
+            .unwrap();
+        b.execute("INSERT INTO users VALUES (3, 'carol');", ())
+            .unwrap();
+        a.execute("INSERT INTO tags VALUES ('y');", ()).unwrap();
+
+        let diff = a.diff(&b).unwrap();
+        assert_eq!(
+            diff.added,
+            vec![SchemaObject {
+                kind: String::from("index"),
+                name: String::from("users_name"),
+                sql: String::from("CREATE INDEX users_name ON users (name)"),
+            }]
+        );
+        assert_eq!(diff.removed, vec![]);
+        assert_eq!(diff.changed, vec![]);
+        assert_eq!(
+            diff.rows,
+            vec![
+                RowDiff {
+                    table: String::from("tags"),
+                    key: String::from("2"),
+                    change: RowChange::Removed(String::from("'y'")),
+                },
+                RowDiff {
+                    table: String::from("users"),
+                    key: String::from("2"),
+                    change: RowChange::Changed {
+                        before: String::from("2,'bob'"),
+                        after: String::from("2,'robert'"),
+                    },
+                },
+                RowDiff {
+                    table: String::from("users"),
+                    key: String::from("3"),
+                    change: RowChange::Added(String::from("3,'carol'")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_key_order() {
+        let setup = ["CREATE TABLE kv (k PRIMARY KEY, v);"];
+        let a = open(&setup);
+        let b = open(&setup);
+        a.execute("INSERT INTO kv VALUES (2, 'x'), (10, 'x'), ('b', 'x');", ())
+            .unwrap();
+        b.execute(
+            "INSERT INTO kv VALUES (1, 'x'), (10.0, 'y'), ('a', 'x'), ('c', 'x');",
+            (),
+        )
+        .unwrap();
+
+        let diff = a.diff(&b).unwrap();
+        let got = diff
+            .rows
+            .iter()
+            .map(|row| (row.key.as_str(), &row.change))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            [
+                ("1", &RowChange::Added(String::from("1,'x'"))),
+                ("2", &RowChange::Removed(String::from("2,'x'"))),
+                (
+                    "10",
+                    &RowChange::Changed {
+                        before: String::from("10,'x'"),
+                        after: String::from("10.0,'y'"),
+                    }
+                ),
+                ("'a'", &RowChange::Added(String::from("'a','x'"))),
+                ("'b'", &RowChange::Removed(String::from("'b','x'"))),
+                ("'c'", &RowChange::Added(String::from("'c','x'"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_changed_table() {
+        let a = open(SETUP);
+        let b = open(&["CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, email TEXT);"]);
+        let diff = a.diff(&b).unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0.name, "users");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name, "tags");
+        assert_eq!(diff.rows, vec![]);
+    }
+}
@@ -0,0 +1,412 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashMap;
+
+use crate::*;
+
+/// The number of rows that the query planner assumes a table has
+/// when there are no statistics from `ANALYZE`.
+const DEFAULT_TABLE_ROWS: f64 = 1048576.0;
+
+/// The number of rows that the query planner assumes
+/// an equality constraint on an index matches
+/// when there are no statistics from `ANALYZE`.
+const DEFAULT_INDEX_EQ_ROWS: f64 = 10.0;
+
+/// A single instruction of a compiled statement's [bytecode program]
+/// as returned by [`Conn::explain`].
+///
+/// The meaning of the operands depends on the opcode.
+/// See the [opcode reference] for details.
+///
+/// [bytecode program]: https://www.sqlite.org/opcode.html
+/// [opcode reference]: https://www.sqlite.org/opcode.html#the_opcodes
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VdbeOp {
+    /// The address of the instruction in the program.
+    pub addr: i64,
+    /// The name of the instruction, like `"Init"` or `"Halt"`.
+    pub opcode: String,
+    /// The first operand.
+    pub p1: i64,
+    /// The second operand.
+    pub p2: i64,
+    /// The third operand.
+    pub p3: i64,
+    /// The fourth operand rendered as text,
+    /// or `None` if the instruction does not use it.
+    pub p4: Option<String>,
+    /// The fifth operand, typically a set of flags.
+    pub p5: i64,
+    /// A human-readable description of the instruction,
+    /// or `None` if SQLite was compiled without `SQLITE_ENABLE_EXPLAIN_COMMENTS`.
+    pub comment: Option<String>,
+}
+
+impl Conn {
+    /// Compiles `sql` (which must contain exactly one SQL statement)
+    /// and returns the [bytecode program] that SQLite would run to evaluate it.
+    /// The statement is not evaluated.
+    ///
+    /// This is the output of `EXPLAIN`,
+    /// not the higher-level summary from `EXPLAIN QUERY PLAN`.
+    /// The exact instructions are not part of SQLite's stable interface
+    /// and may change between versions.
+    ///
+    /// [bytecode program]: https://www.sqlite.org/opcode.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let program = conn.explain("SELECT 1;")?;
+    /// assert!(program.iter().any(|op| op.opcode == "ResultRow"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "EXPLAIN")]
+    pub fn explain(&self, sql: &str) -> Result<Vec<VdbeOp>> {
+        let mut stmt = self.prepare_single(&format!("EXPLAIN {}", sql))?;
+        let mut program = Vec::new();
+        while stmt.step()?.has_row() {
+            program.push(VdbeOp {
+                addr: stmt.column_i64(0),
+                opcode: stmt.column_text(1).to_string_lossy().into_owned(),
+                p1: stmt.column_i64(2),
+                p2: stmt.column_i64(3),
+                p3: stmt.column_i64(4),
+                p4: optional_text(&mut stmt, 5),
+                p5: stmt.column_i64(6),
+                comment: optional_text(&mut stmt, 7),
+            });
+        }
+        Ok(program)
+    }
+
+    /// Compiles `sql` (which must contain exactly one SQL statement),
+    /// binds `params` to it,
+    /// and returns a rough estimate of the number of rows
+    /// that SQLite will visit to evaluate it.
+    /// The statement is not evaluated.
+    ///
+    /// Each loop of the query plan contributes its estimated row count
+    /// multiplied by the estimated row counts of the loops it is nested inside.
+    /// When the `buildtime_bindgen` feature is enabled
+    /// and SQLite was compiled with `SQLITE_ENABLE_STMT_SCANSTATUS`,
+    /// the estimates come from the query planner
+    /// via `Statement::scan_status`.
+    /// Otherwise, the estimates are derived from the `EXPLAIN QUERY PLAN` output
+    /// using the same defaults that the query planner uses for tables without statistics:
+    /// a full scan visits about a million rows,
+    /// an equality lookup on an index visits about ten rows,
+    /// and a lookup by rowid visits one row.
+    ///
+    /// The result is only useful for comparing plans of different queries
+    /// against the same schema and data:
+    /// it is not measured in any particular unit,
+    /// it does not account for sorting, temporary tables, or the cost of evaluating expressions,
+    /// and the fallback estimate ignores statistics gathered by `ANALYZE`.
+    /// The query planner's heuristics can change between SQLite versions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE t (x INTEGER PRIMARY KEY, y TEXT);", ())?;
+    /// let lookup = conn.estimated_cost("SELECT y FROM t WHERE x = ?;", [42])?;
+    /// let scan = conn.estimated_cost("SELECT y FROM t WHERE y = ?;", ["foo"])?;
+    /// assert!(lookup < scan);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn estimated_cost(&self, sql: &str, params: impl Params) -> Result<f64> {
+        let mut stmt = self.prepare_single(sql)?;
+        params.bind(&mut stmt)?;
+        #[cfg(feature = "buildtime_bindgen")]
+        if stmt.scan_status(0).is_some() {
+            let loops = (0..)
+                .map_while(|i| stmt.scan_status(i))
+                .map(|status| (i64::from(status.parent_id), status.estimated_rows));
+            return Ok(nested_loop_cost(loops));
+        }
+
+        // Bound parameter values don't affect the plan
+        // unless SQLite was compiled with SQLITE_ENABLE_STAT4.
+        let mut stmt = self.prepare_single(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+        let mut loops = Vec::new();
+        while stmt.step()?.has_row() {
+            let detail = stmt.column_text(3).to_string_lossy();
+            if let Some(rows) = estimated_loop_rows(&detail) {
+                loops.push((stmt.column_i64(1), rows));
+            }
+        }
+        Ok(nested_loop_cost(loops))
+    }
+
+    /// Compiles `sql` (which must contain exactly one SQL statement) with `params` bound
+    /// and checks that its `EXPLAIN QUERY PLAN` output uses the index named `index_name`.
+    /// If it does not, then an error containing the full plan is returned.
+    /// The statement is not evaluated.
+    ///
+    /// This is intended for test suites
+    /// to guard against schema or query changes
+    /// that silently cause the query planner to fall back to a full table scan.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT);", ())?;
+    /// conn.execute("CREATE INDEX users_email ON users (email);", ())?;
+    /// conn.assert_uses_index(
+    ///     "SELECT id FROM users WHERE email = ?;",
+    ///     ["alice@example.com"],
+    ///     "users_email",
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "EXPLAIN QUERY PLAN")]
+    pub fn assert_uses_index(
+        &self,
+        sql: &str,
+        params: impl Params,
+        index_name: &str,
+    ) -> Result<()> {
+        let mut stmt = self.prepare_single(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+        params.bind(&mut stmt)?;
+        let mut depths = HashMap::<i64, usize>::new();
+        let mut plan = String::new();
+        let mut found = false;
+        while stmt.step()?.has_row() {
+            let id = stmt.column_i64(0);
+            let depth = depths.get(&stmt.column_i64(1)).map_or(0, |d| d + 1);
+            depths.insert(id, depth);
+            let detail = stmt.column_text(3).to_string_lossy();
+            found = found || mentions_index(&detail, index_name);
+            plan.push_str(&"  ".repeat(depth));
+            plan.push_str(&detail);
+            plan.push('\n');
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ResultCode::ERROR,
+                format!(
+                    "query does not use index {}\nQUERY PLAN\n{}",
+                    index_name, plan
+                ),
+            ))
+        }
+    }
+}
+
+/// Reports whether an `EXPLAIN QUERY PLAN` detail
+/// describes a loop using the index named `index_name`.
+fn mentions_index(detail: &str, index_name: &str) -> bool {
+    detail.match_indices(" INDEX ").any(|(i, m)| {
+        let rest = &detail[i + m.len()..];
+        rest.strip_prefix(index_name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    })
+}
+
+/// Returns the estimated number of rows visited
+/// by each run of a loop described by an `EXPLAIN QUERY PLAN` detail
+/// or `None` if the detail does not describe a loop.
+fn estimated_loop_rows(detail: &str) -> Option<f64> {
+    if detail == "SCAN CONSTANT ROW" {
+        return Some(1.0);
+    }
+    if detail.starts_with("SCAN ") {
+        return Some(DEFAULT_TABLE_ROWS);
+    }
+    if !detail.starts_with("SEARCH ") {
+        return None;
+    }
+    let constraints = match (detail.find('('), detail.rfind(')')) {
+        (Some(start), Some(end)) if start < end => &detail[start + 1..end],
+        _ => return Some(DEFAULT_TABLE_ROWS),
+    };
+    let has_eq = constraints.split(" AND ").any(|term| {
+        term.strip_suffix("=?")
+            .is_some_and(|lhs| !lhs.ends_with(['<', '>', '!']))
+    });
+    if !has_eq {
+        // The query planner assumes that each range bound
+        // reduces the number of rows by a factor of four.
+        return Some(DEFAULT_TABLE_ROWS / 4.0);
+    }
+    if detail.contains("USING INTEGER PRIMARY KEY")
+        || detail.contains("USING PRIMARY KEY")
+        || constraints.starts_with("rowid=")
+    {
+        Some(1.0)
+    } else {
+        Some(DEFAULT_INDEX_EQ_ROWS)
+    }
+}
+
+/// Sums the cost of the loops in a query plan,
+/// given as pairs of parent identifier and estimated rows per run.
+/// Loops with the same parent run nested inside one another
+/// in the order they are given.
+fn nested_loop_cost(loops: impl IntoIterator<Item = (i64, f64)>) -> f64 {
+    let mut runs = HashMap::<i64, f64>::new();
+    let mut total = 0.0;
+    for (parent, rows) in loops {
+        let visited = runs.entry(parent).or_insert(1.0);
+        *visited *= rows.max(1.0);
+        total += *visited;
+    }
+    total
+}
+
+fn optional_text(stmt: &mut Statement<'_>, i: usize) -> Option<String> {
+    match stmt.column_type(i) {
+        DataType::Null => None,
+        _ => Some(stmt.column_text(i).to_string_lossy().into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_explain() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        let program = conn.explain("SELECT x FROM t WHERE x > 1;").unwrap();
+        assert!(!program.is_empty());
+        assert_eq!(program[0].addr, 0);
+        assert_eq!(program[0].opcode, "Init");
+        assert!(
+            program.iter().any(|op| op.opcode == "Halt"),
+            "no Halt in {:?}",
+            program
+        );
+        for (i, op) in program.iter().enumerate() {
+            assert_eq!(op.addr, i as i64);
+        }
+        assert_eq!(
+            conn.query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap(),
+            Some(0)
+        );
+
+        assert!(conn.explain("SELECT 1; SELECT 2;").is_err());
+        assert!(conn.explain("SELECT * FROM nosuchtable;").is_err());
+    }
+
+    #[test]
+    fn test_estimated_cost() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER, y INTEGER);", ())
+            .unwrap();
+        conn.execute("CREATE INDEX t_x ON t (x);", ()).unwrap();
+        conn.execute(
+            "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 100) \
+            INSERT INTO t SELECT n, n FROM c;",
+            (),
+        )
+        .unwrap();
+
+        let indexed = conn
+            .estimated_cost("SELECT y FROM t WHERE x = ?;", [42])
+            .unwrap();
+        let scan = conn.estimated_cost("SELECT y FROM t;", ()).unwrap();
+        let unindexed = conn
+            .estimated_cost("SELECT y FROM t WHERE y = ?;", [42])
+            .unwrap();
+        assert!(indexed > 0.0);
+        assert!(indexed < scan, "indexed = {}, scan = {}", indexed, scan);
+        assert!(indexed < unindexed);
+        let join = conn
+            .estimated_cost("SELECT * FROM t AS a, t AS b WHERE b.y = a.y;", ())
+            .unwrap();
+        assert!(join > scan);
+
+        assert!(conn.estimated_cost("SELECT 1; SELECT 2;", ()).is_err());
+    }
+
+    #[test]
+    fn test_assert_uses_index() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER, y INTEGER);", ())
+            .unwrap();
+        conn.execute("CREATE INDEX t_x ON t (x);", ()).unwrap();
+        conn.execute("CREATE INDEX t_x2 ON t (x, y);", ()).unwrap();
+
+        conn.assert_uses_index("SELECT y FROM t WHERE x = ?;", [1], "t_x2")
+            .unwrap();
+        conn.assert_uses_index("SELECT * FROM t WHERE x > ?1;", [1], "t_x2")
+            .unwrap();
+        // A prefix of the name used by the plan does not match.
+        let err = conn
+            .assert_uses_index("SELECT y FROM t WHERE x = ?;", [1], "t_x")
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+
+        let err = conn
+            .assert_uses_index("SELECT x FROM t WHERE y = ?;", [1], "t_y")
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(
+            err.message().contains("does not use index t_y"),
+            "{}",
+            err.message()
+        );
+        assert!(
+            err.message().contains("\nQUERY PLAN\n"),
+            "{}",
+            err.message()
+        );
+        assert!(err.message().contains("SCAN t"), "{}", err.message());
+
+        assert!(conn
+            .assert_uses_index("SELECT * FROM nosuchtable;", (), "t_x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_mentions_index() {
+        assert!(mentions_index("SEARCH t USING INDEX t_x (x=?)", "t_x"));
+        assert!(mentions_index("SCAN t USING COVERING INDEX t_x", "t_x"));
+        assert!(!mentions_index("SCAN t USING COVERING INDEX t_x2", "t_x"));
+        assert!(!mentions_index("SCAN t", "t"));
+    }
+
+    #[test]
+    fn test_estimated_loop_rows() {
+        assert_eq!(estimated_loop_rows("SCAN t"), Some(DEFAULT_TABLE_ROWS));
+        assert_eq!(
+            estimated_loop_rows("SEARCH t USING INDEX t_x (x=?)"),
+            Some(DEFAULT_INDEX_EQ_ROWS)
+        );
+        assert_eq!(
+            estimated_loop_rows("SEARCH t USING INTEGER PRIMARY KEY (rowid=?)"),
+            Some(1.0)
+        );
+        assert_eq!(
+            estimated_loop_rows("SEARCH t USING COVERING INDEX t_x (x>?)"),
+            Some(DEFAULT_TABLE_ROWS / 4.0)
+        );
+        assert_eq!(estimated_loop_rows("USE TEMP B-TREE FOR ORDER BY"), None);
+    }
+}
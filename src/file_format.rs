@@ -0,0 +1,158 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use crate::*;
+
+impl Conn {
+    /// Reports whether the main database uses a [legacy schema format]
+    /// that can be rewritten with [`Connection::upgrade_file_format`].
+    /// Legacy formats are written by versions of SQLite before 3.3.0
+    /// or when [`ConfigFlag::LegacyFileFormat`] is enabled,
+    /// and do not support features like descending indexes.
+    ///
+    /// SQLite does not expose the schema format number,
+    /// so this compiles (but does not run) a `CREATE INDEX` statement
+    /// with a descending column on one of the database's tables
+    /// and checks whether SQLite honored the `DESC`,
+    /// which it only does for the modern format.
+    /// Neither the database nor the connection is modified.
+    /// A database without any tables is reported as not needing an upgrade.
+    ///
+    /// [legacy schema format]: https://www.sqlite.org/fileformat2.html#schemaformat
+    pub fn needs_upgrade(&self) -> Result<bool> {
+        let probe = self.query_row(
+            "SELECT m.name, c.name \
+            FROM main.sqlite_master AS m, pragma_table_info(m.name, 'main') AS c \
+            WHERE m.type = 'table' \
+            AND m.name NOT LIKE 'sqlite\\_%' ESCAPE '\\' \
+            AND m.sql NOT LIKE 'CREATE VIRTUAL %' \
+            LIMIT 1;",
+            (),
+            |stmt| {
+                Ok((
+                    stmt.column_text(0).to_string_lossy().into_owned(),
+                    stmt.column_text(1).to_string_lossy().into_owned(),
+                ))
+            },
+        )?;
+        let Some((table, column)) = probe else {
+            return Ok(false);
+        };
+        let program = self.explain(&format!(
+            "CREATE INDEX main.zombiezen_sqlite_format_probe ON {}({} COLLATE BINARY DESC);",
+            Quote::as_id(&table),
+            Quote::as_id(&column),
+        ))?;
+        // Index keys are shown in P4 like "k(1,-B)",
+        // where "-" marks a descending column.
+        let descending = program.iter().any(|op| {
+            op.p4
+                .as_deref()
+                .is_some_and(|p4| p4.starts_with("k(") && p4.contains(",-"))
+        });
+        Ok(!descending)
+    }
+}
+
+impl Connection {
+    /// Rewrites the main database in the modern schema format
+    /// if [`Conn::needs_upgrade`] reports that it uses a legacy format,
+    /// returning whether the database was rewritten.
+    ///
+    /// The database is rewritten with `VACUUM`,
+    /// so it must not be in a transaction
+    /// and the upgrade takes time and space proportional to the size of the database.
+    /// [`ConfigFlag::LegacyFileFormat`] is temporarily disabled during the upgrade.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    pub fn upgrade_file_format(&mut self) -> Result<bool> {
+        if !self.needs_upgrade()? {
+            return Ok(false);
+        }
+        let legacy = self.get_config(ConfigFlag::LegacyFileFormat)?;
+        self.config(ConfigFlag::LegacyFileFormat, false)?;
+        let result = self.execute("VACUUM;", ());
+        let restore_result = self.config(ConfigFlag::LegacyFileFormat, legacy);
+        result?;
+        restore_result?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[test]
+    fn test_upgrade_file_format() {
+        let dir = TempDir::new("file-format");
+        let path_cstr = dir.join_cstr("test.db");
+        {
+            let mut conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            assert!(!conn.needs_upgrade().unwrap());
+            conn.config(ConfigFlag::LegacyFileFormat, true).unwrap();
+            conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+            conn.execute("INSERT INTO t VALUES (1), (2), (3);", ())
+                .unwrap();
+        }
+
+        let mut conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        assert!(conn.needs_upgrade().unwrap());
+        assert!(conn.upgrade_file_format().unwrap());
+        assert!(!conn.needs_upgrade().unwrap());
+        assert!(!conn.upgrade_file_format().unwrap());
+        let n = conn
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(n, Some(3));
+        drop(conn);
+
+        let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        assert!(!conn.needs_upgrade().unwrap());
+    }
+
+    #[test]
+    fn test_needs_upgrade_wal() {
+        let dir = TempDir::new("file-format-wal");
+        let conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        conn.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        assert!(!conn.needs_upgrade().unwrap());
+    }
+
+    #[test]
+    fn test_needs_upgrade_memory() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        assert!(!conn.needs_upgrade().unwrap());
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[test]
+    fn test_needs_upgrade_legacy_memory() {
+        let mut legacy = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        assert!(!legacy.needs_upgrade().unwrap());
+        legacy.config(ConfigFlag::LegacyFileFormat, true).unwrap();
+        legacy
+            .execute("CREATE TABLE \"odd \"\"name\"\"\" (\"x y\" INTEGER);", ())
+            .unwrap();
+        let schema = legacy
+            .query_row("SELECT group_concat(sql) FROM sqlite_master;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })
+            .unwrap();
+        assert!(legacy.needs_upgrade().unwrap());
+        // The check did not create an index.
+        let schema_after = legacy
+            .query_row("SELECT group_concat(sql) FROM sqlite_master;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })
+            .unwrap();
+        assert_eq!(schema_after, schema);
+    }
+}
@@ -2,7 +2,8 @@
 // SPDX-License-Identifier: MIT
 
 use std::any::Any;
-use std::ffi::{c_char, c_int, c_uchar, c_void, CStr};
+use std::ffi::{c_char, c_int, c_uchar, c_void, CStr, CString};
+use std::marker::PhantomData;
 use std::mem;
 use std::ptr::{self, NonNull};
 use std::slice;
@@ -10,16 +11,23 @@ use std::str;
 
 use bitflags::bitflags;
 use libsqlite3_sys::{
-    sqlite3_context, sqlite3_context_db_handle, sqlite3_create_function_v2, sqlite3_free,
-    sqlite3_get_auxdata, sqlite3_malloc, sqlite3_result_blob64, sqlite3_result_double,
-    sqlite3_result_error, sqlite3_result_error_code, sqlite3_result_error_toobig,
-    sqlite3_result_int64, sqlite3_result_null, sqlite3_result_text64, sqlite3_result_value,
-    sqlite3_set_auxdata, sqlite3_user_data, sqlite3_value, SQLITE_DETERMINISTIC, SQLITE_UTF8,
+    sqlite3_aggregate_context, sqlite3_context, sqlite3_context_db_handle,
+    sqlite3_create_function_v2, sqlite3_free, sqlite3_get_auxdata, sqlite3_malloc,
+    sqlite3_malloc64, sqlite3_result_blob64, sqlite3_result_double, sqlite3_result_error,
+    sqlite3_result_error_code, sqlite3_result_error_nomem, sqlite3_result_error_toobig,
+    sqlite3_result_int64, sqlite3_result_null, sqlite3_result_subtype, sqlite3_result_text64,
+    sqlite3_result_value, sqlite3_result_zeroblob64, sqlite3_set_auxdata, sqlite3_user_data,
+    sqlite3_value, SQLITE_DETERMINISTIC, SQLITE_OK, SQLITE_UTF8,
 };
 
 use crate::*;
 
-type ScalarFn = Box<dyn Fn(Context, &mut dyn ExactSizeIterator<Item = ProtectedValue>) + 'static>;
+/// A boxed scalar SQL function implementation
+/// accepted by [`Connection::register_functions`].
+pub type BoxScalarFunction =
+    Box<dyn Fn(Context, &mut dyn ExactSizeIterator<Item = ProtectedValue>) + 'static>;
+
+type ScalarFn = BoxScalarFunction;
 
 impl Connection {
     #[doc(alias = "sqlite3_create_function_v2")]
@@ -41,7 +49,11 @@ impl Connection {
                 n_arg.map_or(-1, |n| n as c_int),
                 SQLITE_UTF8 | flags.bits(),
                 app as *mut c_void,
-                Some(scalar_callback),
+                Some(if flags.contains(FunctionFlags::RESULT_SUBTYPE) {
+                    scalar_callback::<true>
+                } else {
+                    scalar_callback::<false>
+                }),
                 None,
                 None,
                 Some(destroy_scalar),
@@ -53,9 +65,146 @@ impl Connection {
             Err(self.as_ref().error().unwrap())
         }
     }
+
+    /// Registers a group of scalar SQL functions,
+    /// each given as a name, number of arguments (or `None` for any number),
+    /// flags, and implementation,
+    /// as with [`Connection::create_scalar_function`].
+    ///
+    /// If any function fails to register,
+    /// then the functions registered earlier in the same call are removed
+    /// and the error is returned.
+    /// (Functions that they replaced are not restored.)
+    /// SQLite frees each implementation when its function is replaced or removed
+    /// or when the connection is closed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{BoxScalarFunction, Connection, FunctionFlags, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let answer: BoxScalarFunction = Box::new(|mut ctx, _| ctx.result_i64(42));
+    /// let double: BoxScalarFunction = Box::new(|mut ctx, args| {
+    ///     let n = args.next().unwrap().to_i64();
+    ///     ctx.result_i64(n * 2);
+    /// });
+    /// conn.register_functions([
+    ///     ("answer", Some(0), FunctionFlags::default(), answer),
+    ///     ("double", Some(1), FunctionFlags::default(), double),
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_functions<'a>(
+        &mut self,
+        functions: impl IntoIterator<Item = (&'a str, Option<u8>, FunctionFlags, BoxScalarFunction)>,
+    ) -> Result<()> {
+        let mut registered = Vec::new();
+        for (name, n_arg, flags, f) in functions {
+            let result = CString::new(name)
+                .map_err(|_| {
+                    Error::new(
+                        ResultCode::MISUSE,
+                        format!("function name {:?} contains a NUL byte", name),
+                    )
+                })
+                .and_then(|name| {
+                    self.create_scalar_function(&name, n_arg, flags, f)?;
+                    Ok(name)
+                });
+            match result {
+                Ok(name) => registered.push((name, n_arg)),
+                Err(err) => {
+                    for (name, n_arg) in registered {
+                        self.remove_function(&name, n_arg);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the function with the given name and number of arguments.
+    fn remove_function(&mut self, name: &CStr, n_arg: Option<u8>) {
+        unsafe {
+            sqlite3_create_function_v2(
+                self.as_ptr(),
+                name.as_ptr(),
+                n_arg.map_or(-1, |n| n as c_int),
+                SQLITE_UTF8,
+                ptr::null_mut(),
+                None,
+                None,
+                None,
+                None,
+            );
+        }
+    }
+
+    /// Registers an aggregate SQL function.
+    ///
+    /// For each group of rows, `init` is called to create a new accumulator,
+    /// `step` is called for each row in the group,
+    /// and then `finalize` is called with the accumulator to produce the result.
+    /// If the group has no rows, `finalize` is called with a fresh accumulator from `init`.
+    ///
+    /// The accumulator is owned by SQLite's [aggregate context]
+    /// and is always passed to `finalize` (and thus dropped),
+    /// even if the query is interrupted or reset before the group finishes.
+    /// In that case, any result set by `finalize` is discarded.
+    ///
+    /// [aggregate context]: https://www.sqlite.org/c3ref/aggregate_context.html
+    #[doc(alias = "sqlite3_create_function_v2")]
+    pub fn create_aggregate_function<A: 'static>(
+        &mut self,
+        name: &(impl AsRef<CStr> + ?Sized),
+        n_arg: Option<u8>,
+        flags: FunctionFlags,
+        init: impl Fn() -> A + 'static,
+        step: impl Fn(&mut A, Context, &mut dyn ExactSizeIterator<Item = ProtectedValue>) + 'static,
+        finalize: impl Fn(A, Context) + 'static,
+    ) -> Result<()> {
+        const BOX_SIZE: c_int = mem::size_of::<AggregateFn>() as c_int;
+        let f: AggregateFn = Box::new(Aggregate {
+            init,
+            step,
+            finalize,
+            accumulator: PhantomData,
+        });
+        let rc = ResultCode(unsafe {
+            let app = sqlite3_malloc(BOX_SIZE) as *mut AggregateFn;
+            ptr::write(app, f);
+            sqlite3_create_function_v2(
+                self.as_ptr(),
+                name.as_ref().as_ptr(),
+                n_arg.map_or(-1, |n| n as c_int),
+                SQLITE_UTF8 | flags.bits(),
+                app as *mut c_void,
+                None,
+                Some(aggregate_step_callback),
+                Some(if flags.contains(FunctionFlags::RESULT_SUBTYPE) {
+                    aggregate_final_callback::<true>
+                } else {
+                    aggregate_final_callback::<false>
+                }),
+                Some(destroy_aggregate),
+            )
+        });
+        if rc.is_success() {
+            Ok(())
+        } else {
+            Err(self.as_ref().error().unwrap())
+        }
+    }
 }
 
-unsafe extern "C" fn scalar_callback(
+/// Calls the [`ScalarFn`] stored in `ctx`'s user data.
+/// `RESULT_SUBTYPE` reports whether the function was registered
+/// with [`FunctionFlags::RESULT_SUBTYPE`].
+unsafe extern "C" fn scalar_callback<const RESULT_SUBTYPE: bool>(
     ctx: *mut sqlite3_context,
     argc: c_int,
     argv: *mut *mut sqlite3_value,
@@ -63,6 +212,7 @@ unsafe extern "C" fn scalar_callback(
     let mut ctx = Context {
         ctx: NonNull::new(ctx).unwrap(),
         db: Conn::new(NonNull::new(sqlite3_context_db_handle(ctx)).unwrap()),
+        result_subtype: RESULT_SUBTYPE,
     };
     let app = match NonNull::new(ctx.user_data()) {
         Some(ptr) => ptr.cast::<ScalarFn>(),
@@ -88,15 +238,135 @@ unsafe extern "C" fn destroy_scalar(app: *mut c_void) {
     sqlite3_free(app);
 }
 
+type AggregateFn = Box<dyn AggregateImpl + 'static>;
+
+/// Type-erased callbacks of an aggregate function.
+trait AggregateImpl {
+    /// Calls the step function with the accumulator stored in `ctx`'s aggregate context,
+    /// creating it if needed.
+    unsafe fn step(&self, ctx: Context, args: &mut dyn ExactSizeIterator<Item = ProtectedValue>);
+
+    /// Takes the accumulator out of `ctx`'s aggregate context
+    /// and calls the finalize function with it.
+    unsafe fn finalize(&self, ctx: Context);
+}
+
+struct Aggregate<A, I, S, F> {
+    init: I,
+    step: S,
+    finalize: F,
+    accumulator: PhantomData<fn() -> A>,
+}
+
+impl<A, I, S, F> AggregateImpl for Aggregate<A, I, S, F>
+where
+    I: Fn() -> A,
+    S: Fn(&mut A, Context, &mut dyn ExactSizeIterator<Item = ProtectedValue>),
+    F: Fn(A, Context),
+{
+    unsafe fn step(&self, ctx: Context, args: &mut dyn ExactSizeIterator<Item = ProtectedValue>) {
+        // The aggregate context is zeroed on first use,
+        // so it starts out as a null pointer.
+        let slot = sqlite3_aggregate_context(ctx.as_ptr(), mem::size_of::<*mut A>() as c_int)
+            .cast::<*mut A>();
+        if slot.is_null() {
+            sqlite3_result_error_nomem(ctx.as_ptr());
+            return;
+        }
+        if (*slot).is_null() {
+            *slot = Box::into_raw(Box::new((self.init)()));
+        }
+        (self.step)(&mut **slot, ctx, args);
+    }
+
+    unsafe fn finalize(&self, ctx: Context) {
+        // SQLite calls xFinal for every aggregate context it allocated,
+        // including when the statement is interrupted or reset mid-group,
+        // so this is where the accumulator is freed.
+        let slot = sqlite3_aggregate_context(ctx.as_ptr(), 0).cast::<*mut A>();
+        let acc = if slot.is_null() || (*slot).is_null() {
+            (self.init)()
+        } else {
+            let acc = Box::from_raw(*slot);
+            *slot = ptr::null_mut();
+            *acc
+        };
+        (self.finalize)(acc, ctx);
+    }
+}
+
+unsafe fn aggregate_context_parts(
+    ctx: *mut sqlite3_context,
+    result_subtype: bool,
+) -> (Context, Option<NonNull<AggregateFn>>) {
+    let ctx = Context {
+        ctx: NonNull::new(ctx).unwrap(),
+        db: Conn::new(NonNull::new(sqlite3_context_db_handle(ctx)).unwrap()),
+        result_subtype,
+    };
+    let app = NonNull::new(ctx.user_data()).map(NonNull::cast::<AggregateFn>);
+    (ctx, app)
+}
+
+unsafe extern "C" fn aggregate_step_callback(
+    ctx: *mut sqlite3_context,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) {
+    let (mut ctx, app) = aggregate_context_parts(ctx, false);
+    let app = match app {
+        Some(app) => app,
+        None => {
+            ctx.result_error(ResultCode::ERROR, "null context user data");
+            return;
+        }
+    };
+    let arg_slice = slice::from_raw_parts_mut(argv, argc as usize);
+    let mut arg_iter = arg_slice
+        .iter()
+        .copied()
+        .map(|ptr| ProtectedValue::new(NonNull::new(ptr).unwrap()));
+    app.as_ref().step(ctx, &mut arg_iter);
+}
+
+unsafe extern "C" fn aggregate_final_callback<const RESULT_SUBTYPE: bool>(
+    ctx: *mut sqlite3_context,
+) {
+    let (mut ctx, app) = aggregate_context_parts(ctx, RESULT_SUBTYPE);
+    match app {
+        Some(app) => app.as_ref().finalize(ctx),
+        None => ctx.result_error(ResultCode::ERROR, "null context user data"),
+    }
+}
+
+unsafe extern "C" fn destroy_aggregate(app: *mut c_void) {
+    {
+        let app = NonNull::new(app).unwrap().cast::<AggregateFn>();
+        ptr::drop_in_place(app.as_ptr());
+    }
+    sqlite3_free(app);
+}
+
 /// The context of a user-defined SQL function.
 #[derive(Debug)]
 #[doc(alias = "sqlite3_context")]
 pub struct Context {
     ctx: NonNull<sqlite3_context>,
     db: Conn,
+    result_subtype: bool,
 }
 
 impl Context {
+    /// Wraps a context passed to a callback other than a user-defined function,
+    /// such as a virtual table's `xColumn` method.
+    pub(crate) unsafe fn new(ctx: NonNull<sqlite3_context>) -> Self {
+        Context {
+            ctx,
+            db: Conn::new(NonNull::new(sqlite3_context_db_handle(ctx.as_ptr())).unwrap()),
+            result_subtype: false,
+        }
+    }
+
     #[inline]
     fn as_ptr(&self) -> *mut sqlite3_context {
         self.ctx.as_ptr()
@@ -137,6 +407,26 @@ impl Context {
         }
     }
 
+    /// Cause the function call to throw an [`ResultCode::TOOBIG`] exception
+    /// once the callback returns,
+    /// indicating that a string or `BLOB` is too large to represent.
+    /// This is useful for aggregate functions that accumulate a result
+    /// that would exceed SQLite's [length limit].
+    ///
+    /// [length limit]: https://www.sqlite.org/limits.html#max_length
+    #[doc(alias = "sqlite3_result_error_toobig")]
+    pub fn set_error_toobig(&mut self) {
+        unsafe { sqlite3_result_error_toobig(self.as_ptr()) }
+    }
+
+    /// Cause the function call to throw an [`ResultCode::NOMEM`] exception
+    /// once the callback returns,
+    /// indicating that a memory allocation failed.
+    #[doc(alias = "sqlite3_result_error_nomem")]
+    pub fn set_error_nomem(&mut self) {
+        unsafe { sqlite3_result_error_nomem(self.as_ptr()) }
+    }
+
     /// Sets the result of the application-defined function
     /// to be a copy of the (possibly unprotected) value object.
     #[doc(alias = "sqlite3_result_value")]
@@ -198,6 +488,65 @@ impl Context {
         }
     }
 
+    /// Sets the result of the application-defined function
+    /// to be a `BLOB` of `len` bytes that is filled in by `f`.
+    ///
+    /// The buffer passed to `f` is zero-initialized
+    /// and is allocated by SQLite and handed over to it as-is,
+    /// so unlike [`result_blob`][Context::result_blob],
+    /// the result is never copied or reallocated.
+    /// This is useful for functions that generate large binary outputs.
+    #[doc(alias = "sqlite3_result_blob64")]
+    pub fn result_blob_with(&mut self, len: usize, f: impl FnOnce(&mut [u8])) {
+        if len == 0 {
+            f(&mut []);
+            self.result_zeroblob(0);
+            return;
+        }
+        unsafe {
+            let ptr = sqlite3_malloc64(len as u64).cast::<u8>();
+            if ptr.is_null() {
+                sqlite3_result_error_nomem(self.as_ptr());
+                return;
+            }
+            ptr::write_bytes(ptr, 0, len);
+            // If f panics, the buffer is leaked rather than freed,
+            // but it can't be observed by SQLite.
+            f(slice::from_raw_parts_mut(ptr, len));
+            sqlite3_result_blob64(self.as_ptr(), ptr.cast(), len as u64, Some(sqlite3_free));
+        }
+    }
+
+    /// Sets the result of the application-defined function
+    /// to be a `BLOB` containing `n` bytes of zeroes.
+    #[doc(alias = "sqlite3_result_zeroblob64")]
+    pub fn result_zeroblob(&mut self, n: u64) {
+        let rc = unsafe { sqlite3_result_zeroblob64(self.as_ptr(), n) };
+        if rc != SQLITE_OK {
+            unsafe {
+                sqlite3_result_error_toobig(self.as_ptr());
+            }
+        }
+    }
+
+    /// Sets the [subtype] of the function's result to the low 8 bits of `subtype`.
+    /// The result must be set before calling `set_result_subtype`.
+    /// For example, JSON functions treat text with the subtype `'J'` (74)
+    /// as JSON rather than as a string to be quoted.
+    ///
+    /// Newer versions of SQLite ignore subtypes set by functions
+    /// that were not registered with [`FunctionFlags::RESULT_SUBTYPE`].
+    /// For consistency across SQLite versions,
+    /// this method has no effect in such functions.
+    ///
+    /// [subtype]: https://www.sqlite.org/c3ref/result_subtype.html
+    #[doc(alias = "sqlite3_result_subtype")]
+    pub fn set_result_subtype(&mut self, subtype: u32) {
+        if self.result_subtype {
+            unsafe { sqlite3_result_subtype(self.as_ptr(), subtype) };
+        }
+    }
+
     /// Retrieves auxiliary data associated with the argument
     /// using [`set_auxdata`][Context::set_auxdata].
     ///
@@ -222,6 +571,40 @@ impl Context {
     /// can be stored as metadata associated with the pattern string.
     /// Then as long as the pattern string remains the same,
     /// the compiled regular expression can be reused on multiple invocations of the same function.
+    ///
+    /// SQLite is free to drop the data at any time, including before `set_auxdata` returns,
+    /// so any use of the data for the current invocation should happen before calling `set_auxdata`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::{CStr, CString};
+    /// # use zombiezen_sqlite::{strglob, Connection, FunctionFlags, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let mut conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.create_scalar_function(
+    ///     &CString::new("glob_match")?,
+    ///     Some(2),
+    ///     FunctionFlags::default() | FunctionFlags::DETERMINISTIC,
+    ///     |mut ctx, args| {
+    ///         let mut pattern = args.next().unwrap();
+    ///         let mut s = args.next().unwrap();
+    ///         let s = CString::new(s.to_blob()).unwrap_or_default();
+    ///         let matched = match ctx.auxdata(0).and_then(|data| data.downcast_ref::<CString>()) {
+    ///             Some(compiled) => strglob(compiled, &s),
+    ///             None => {
+    ///                 let compiled = CString::new(pattern.to_blob()).unwrap_or_default();
+    ///                 let matched = strglob(&compiled, &s);
+    ///                 ctx.set_auxdata(0, Box::new(compiled));
+    ///                 matched
+    ///             }
+    ///         };
+    ///         ctx.result_i64(matched.into());
+    ///     },
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
     #[doc(alias = "sqlite3_set_auxdata")]
     pub fn set_auxdata(&mut self, arg: usize, data: Box<dyn Any>) {
         let Ok(arg) = c_int::try_from(arg) else {
@@ -255,9 +638,21 @@ bitflags! {
         const DIRECTONLY = libsqlite3_sys::SQLITE_DIRECTONLY;
 
         const DETERMINISTIC = SQLITE_DETERMINISTIC;
+
+        /// The function may call `sqlite3_value_subtype` on its arguments.
+        #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+        const SUBTYPE = libsqlite3_sys::SQLITE_SUBTYPE;
+
+        /// The function may call [`Context::set_result_subtype`].
+        /// Older versions of SQLite ignore this flag.
+        const RESULT_SUBTYPE = SQLITE_RESULT_SUBTYPE;
     }
 }
 
+/// Defined in SQLite 3.45.0,
+/// which is newer than the bundled bindings.
+const SQLITE_RESULT_SUBTYPE: c_int = 0x0100_0000;
+
 impl FunctionFlags {
     #[inline(always)]
     #[cfg(not(any(feature = "modern", feature = "buildtime_bindgen")))]
@@ -281,6 +676,11 @@ impl Default for FunctionFlags {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::ffi::CString;
+    use std::ops::ControlFlow;
+    use std::rc::Rc;
+
     use zombiezen_const_cstr::{const_cstr, ConstCStr};
 
     use crate::*;
@@ -312,6 +712,131 @@ mod tests {
         assert_eq!(stmt.step().unwrap(), StepResult::Done);
     }
 
+    #[test]
+    fn test_register_functions() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let state = Rc::new(Cell::new(0i64));
+        let counter: BoxScalarFunction = {
+            let state = state.clone();
+            Box::new(move |mut ctx, _| {
+                state.set(state.get() + 1);
+                ctx.result_i64(state.get());
+            })
+        };
+        let add: BoxScalarFunction = {
+            let state = state.clone();
+            Box::new(move |mut ctx, args| {
+                let _ = &state;
+                let sum = args.map(|mut arg| arg.to_i64()).sum::<i64>();
+                ctx.result_i64(sum);
+            })
+        };
+        let shout: BoxScalarFunction = {
+            let state = state.clone();
+            Box::new(move |mut ctx, args| {
+                let _ = &state;
+                let mut arg = args.next().unwrap();
+                let s = arg.to_text().unwrap().to_uppercase();
+                ctx.result_text(s + "!");
+            })
+        };
+        conn.register_functions([
+            ("counter", Some(0), FunctionFlags::default(), counter),
+            ("add_all", None, FunctionFlags::DETERMINISTIC, add),
+            ("shout", Some(1), FunctionFlags::DETERMINISTIC, shout),
+        ])
+        .unwrap();
+        assert_eq!(Rc::strong_count(&state), 4);
+
+        let got = conn
+            .query_row(
+                "SELECT counter(), counter(), add_all(1, 2, 3), shout('hi');",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_i64(0) + stmt.column_i64(1),
+                        stmt.column_i64(2),
+                        stmt.column_text(3).unwrap().to_string(),
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(got, Some((3, 6, String::from("HI!"))));
+
+        drop(conn);
+        assert_eq!(Rc::strong_count(&state), 1);
+    }
+
+    #[test]
+    fn test_register_functions_error() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let one: BoxScalarFunction = Box::new(|mut ctx, _| ctx.result_i64(1));
+        let bad: BoxScalarFunction = Box::new(|mut ctx, _| ctx.result_i64(2));
+        let err = conn
+            .register_functions([
+                ("one", Some(0), FunctionFlags::default(), one),
+                ("b\0d", Some(0), FunctionFlags::default(), bad),
+            ])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        let err = conn.execute("SELECT one();", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+
+    #[test]
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    fn test_value_is_from_bind() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.create_scalar_function(
+            const_cstr!("from_bind").as_cstr(),
+            Some(1),
+            FunctionFlags::default(),
+            |mut ctx, args| {
+                let arg = args.next().unwrap();
+                ctx.result_i64(arg.is_from_bind().into());
+            },
+        )
+        .unwrap();
+        let mut stmt = conn
+            .prepare("SELECT from_bind(?1), from_bind(123), from_bind(?1 + 1);")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        stmt.bind_i64(1, 42).unwrap();
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_i64(0), 1);
+        assert_eq!(stmt.column_i64(1), 0);
+        assert_eq!(stmt.column_i64(2), 0);
+    }
+
+    #[test]
+    fn test_result_subtype_json() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for (name, flags) in [
+            ("json_obj", FunctionFlags::RESULT_SUBTYPE),
+            ("plain_obj", FunctionFlags::empty()),
+        ] {
+            conn.create_scalar_function(
+                &CString::new(name).unwrap(),
+                Some(0),
+                flags,
+                |mut ctx, _| {
+                    ctx.result_text(r#"{"a":1}"#);
+                    ctx.set_result_subtype(u32::from(b'J'));
+                },
+            )
+            .unwrap();
+        }
+        let mut stmt = conn
+            .prepare("SELECT json_array(json_obj()), json_array(plain_obj());")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_text(0).unwrap(), r#"[{"a":1}]"#);
+        assert_eq!(stmt.column_text(1).unwrap(), r#"["{\"a\":1}"]"#);
+    }
+
     #[test]
     fn test_scalar_function_text() {
         let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
@@ -336,4 +861,231 @@ mod tests {
 
         assert_eq!(stmt.step().unwrap(), StepResult::Done);
     }
+
+    #[test]
+    fn test_aggregate_function() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.create_aggregate_function(
+            const_cstr!("my_sum").as_cstr(),
+            Some(1),
+            FunctionFlags::default() | FunctionFlags::DETERMINISTIC,
+            || 0i64,
+            |acc, _, args| *acc += args.next().unwrap().to_i64(),
+            |acc, mut ctx| ctx.result_i64(acc),
+        )
+        .unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT my_sum(column1) FROM (VALUES (1), (2), (3)) \
+                UNION ALL SELECT my_sum(1) WHERE false;",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_i64(0), 6);
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_i64(0), 0);
+        assert_eq!(stmt.step().unwrap(), StepResult::Done);
+    }
+
+    #[test]
+    fn test_aggregate_function_toobig() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        unsafe {
+            libsqlite3_sys::sqlite3_limit(conn.as_ptr(), libsqlite3_sys::SQLITE_LIMIT_LENGTH, 1000);
+        }
+        conn.create_aggregate_function(
+            const_cstr!("my_concat").as_cstr(),
+            Some(1),
+            FunctionFlags::default() | FunctionFlags::DETERMINISTIC,
+            String::new,
+            |acc, mut ctx, args| {
+                let limit = unsafe {
+                    libsqlite3_sys::sqlite3_limit(
+                        ctx.db_handle().as_ptr(),
+                        libsqlite3_sys::SQLITE_LIMIT_LENGTH,
+                        -1,
+                    )
+                };
+                let mut arg = args.next().unwrap();
+                let s = arg.to_text().to_string_lossy();
+                if acc.len() + s.len() > limit as usize {
+                    ctx.set_error_toobig();
+                    return;
+                }
+                acc.push_str(&s);
+            },
+            |acc, mut ctx| ctx.result_text(acc),
+        )
+        .unwrap();
+
+        let result = conn.query_row(
+            "SELECT my_concat(printf('%.400c', 'x')) FROM (VALUES (1), (2));",
+            (),
+            |stmt| Ok(stmt.column_text(0).unwrap().len()),
+        );
+        assert_eq!(result.unwrap(), Some(800));
+
+        let err = conn
+            .query_row(
+                "SELECT my_concat(printf('%.400c', 'x')) FROM (VALUES (1), (2), (3));",
+                (),
+                |stmt| Ok(stmt.column_text(0).unwrap().len()),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::TOOBIG);
+    }
+
+    #[test]
+    fn test_aggregate_function_interrupted_drops_accumulator() {
+        struct Accumulator(Rc<Cell<usize>>);
+
+        impl Drop for Accumulator {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let inits = Rc::new(Cell::new(0usize));
+        let drops = Rc::new(Cell::new(0usize));
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        {
+            let inits = inits.clone();
+            let drops = drops.clone();
+            conn.create_aggregate_function(
+                const_cstr!("counter").as_cstr(),
+                Some(1),
+                FunctionFlags::default(),
+                move || {
+                    inits.set(inits.get() + 1);
+                    Accumulator(drops.clone())
+                },
+                |_, _, _| {},
+                |_, mut ctx| ctx.result_null(),
+            )
+            .unwrap();
+        }
+        let steps = Rc::new(Cell::new(0usize));
+        {
+            let steps = steps.clone();
+            conn.set_progress_handler(100, move || {
+                steps.set(steps.get() + 1);
+                if steps.get() >= 10 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+        }
+        let mut stmt = conn
+            .prepare(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) \
+                SELECT counter(x) FROM c;",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let err = stmt.step().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::INTERRUPT);
+        drop(stmt);
+        assert!(inits.get() > 0);
+        assert_eq!(drops.get(), inits.get());
+    }
+
+    #[test]
+    fn test_result_blob_with() {
+        const SIZE: usize = 1 << 20;
+        // Each call records the buffer's length and whether it was zeroed,
+        // to be checked after the query returns.
+        let calls = Rc::new(RefCell::new(Vec::<(usize, bool)>::new()));
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        {
+            let calls = calls.clone();
+            conn.create_scalar_function(
+                const_cstr!("big_blob").as_cstr(),
+                Some(1),
+                FunctionFlags::default(),
+                move |mut ctx, args| {
+                    let n = args.next().unwrap().to_i64() as usize;
+                    ctx.result_blob_with(n, |buf| {
+                        let zeroed = buf.iter().all(|&b| b == 0);
+                        calls.borrow_mut().push((buf.len(), zeroed));
+                        for (i, b) in buf.iter_mut().enumerate() {
+                            *b = i as u8;
+                        }
+                    });
+                },
+            )
+            .unwrap();
+        }
+        let mut stmt = conn
+            .prepare("SELECT big_blob(?1);")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+
+        stmt.bind_i64(1, SIZE as i64).unwrap();
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(*calls.borrow(), [(SIZE, true)]);
+        assert_eq!(stmt.column_type(0), DataType::Blob);
+        let blob = stmt.column_blob(0);
+        assert_eq!(blob.len(), SIZE);
+        assert!(blob.iter().enumerate().all(|(i, &b)| b == i as u8));
+
+        stmt.reset().unwrap();
+        stmt.bind_i64(1, 0).unwrap();
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(*calls.borrow(), [(SIZE, true), (0, true)]);
+        assert_eq!(stmt.column_type(0), DataType::Blob);
+        assert!(stmt.column_blob(0).is_empty());
+    }
+
+    #[test]
+    fn test_auxdata_caches_compiled_pattern() {
+        let compile_count = Rc::new(Cell::new(0usize));
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        {
+            let compile_count = compile_count.clone();
+            conn.create_scalar_function(
+                const_cstr!("glob_match").as_cstr(),
+                Some(2),
+                FunctionFlags::default() | FunctionFlags::DETERMINISTIC,
+                move |mut ctx, args| {
+                    let mut pattern = args.next().unwrap();
+                    let mut s = args.next().unwrap();
+                    let s = CString::new(s.to_blob()).unwrap();
+                    let matched = match ctx
+                        .auxdata(0)
+                        .and_then(|data| data.downcast_ref::<CString>())
+                    {
+                        Some(compiled) => strglob(compiled, &s),
+                        None => {
+                            compile_count.set(compile_count.get() + 1);
+                            let compiled = CString::new(pattern.to_blob()).unwrap();
+                            let matched = strglob(&compiled, &s);
+                            ctx.set_auxdata(0, Box::new(compiled));
+                            matched
+                        }
+                    };
+                    ctx.result_i64(matched.into());
+                },
+            )
+            .unwrap();
+        }
+        let mut stmt = conn
+            .prepare(
+                "SELECT glob_match('b*', column1) \
+                FROM (VALUES ('bar'), ('foo'), ('baz'), ('qux'));",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let mut got = Vec::new();
+        while stmt.step().unwrap().has_row() {
+            got.push(stmt.column_i64(0));
+        }
+        assert_eq!(got, vec![1, 0, 1, 0]);
+        assert_eq!(compile_count.get(), 1);
+    }
 }
@@ -0,0 +1,123 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::cell::Cell;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use libsqlite3_sys::{sqlite3_hard_heap_limit64, sqlite3_memory_used, sqlite3_soft_heap_limit64};
+
+use crate::*;
+
+/// Serializes changes to the process-wide heap limits
+/// so that concurrent calls to [`Conn::with_memory_limit`]
+/// restore the limits in the order they were set.
+static HEAP_LIMIT_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    /// Whether the current thread holds [`HEAP_LIMIT_LOCK`],
+    /// so that nested calls don't deadlock.
+    static HOLDS_HEAP_LIMIT_LOCK: Cell<bool> = const { Cell::new(false) };
+}
+
+impl Conn {
+    /// Calls `f` with SQLite's [hard heap limit] set
+    /// so that SQLite's heap can grow by at most `bytes` while `f` runs,
+    /// then restores the previous limits.
+    /// Allocations that would exceed the limit fail,
+    /// so statements run by `f` that need more memory
+    /// fail with [`ResultCode::NOMEM`].
+    /// This bounds the memory used by a single risky operation,
+    /// such as running untrusted SQL.
+    /// If a lower hard heap limit is already set, it is kept.
+    ///
+    /// The heap limit is global to the process, not specific to the connection.
+    /// While `f` runs, allocations by every connection in every thread
+    /// count toward the limit and fail if it is exceeded,
+    /// so other threads using SQLite at the same time may see spurious `NOMEM` errors.
+    /// Concurrent calls to `with_memory_limit` from different threads run one at a time.
+    /// Calls nested inside `f` on the same thread are allowed
+    /// and can only lower the limit.
+    /// Code that sets the heap limits directly while `f` runs
+    /// will have its changes undone.
+    ///
+    /// [hard heap limit]: https://www.sqlite.org/c3ref/hard_heap_limit64.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags, ResultCode};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let err = conn
+    ///     .with_memory_limit(1 << 20, |conn| {
+    ///         conn.execute("SELECT length(zeroblob(10000000) || 'x');", ())
+    ///     })
+    ///     .unwrap_err();
+    /// assert_eq!(err.result_code(), ResultCode::NOMEM);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "sqlite3_hard_heap_limit64")]
+    pub fn with_memory_limit<T>(
+        &self,
+        bytes: usize,
+        f: impl FnOnce(&Conn) -> Result<T>,
+    ) -> Result<T> {
+        let _lock = HeapLimitLock::acquire();
+        let (prev_hard, prev_soft) =
+            unsafe { (sqlite3_hard_heap_limit64(-1), sqlite3_soft_heap_limit64(-1)) };
+        let _restore = RestoreHeapLimits {
+            hard: prev_hard,
+            soft: prev_soft,
+        };
+        let mut limit = unsafe { sqlite3_memory_used() }
+            .saturating_add(i64::try_from(bytes).unwrap_or(i64::MAX));
+        if prev_hard > 0 {
+            limit = limit.min(prev_hard);
+        }
+        unsafe { sqlite3_hard_heap_limit64(limit) };
+        f(self)
+    }
+}
+
+/// A reentrant guard for [`HEAP_LIMIT_LOCK`].
+struct HeapLimitLock(Option<MutexGuard<'static, ()>>);
+
+impl HeapLimitLock {
+    fn acquire() -> Self {
+        if HOLDS_HEAP_LIMIT_LOCK.with(Cell::get) {
+            return HeapLimitLock(None);
+        }
+        let guard = HEAP_LIMIT_LOCK
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        HOLDS_HEAP_LIMIT_LOCK.with(|held| held.set(true));
+        HeapLimitLock(Some(guard))
+    }
+}
+
+impl Drop for HeapLimitLock {
+    fn drop(&mut self) {
+        if self.0.is_some() {
+            HOLDS_HEAP_LIMIT_LOCK.with(|held| held.set(false));
+        }
+    }
+}
+
+/// Restores the heap limits when dropped.
+/// Setting the hard heap limit can lower the soft heap limit,
+/// so both are restored.
+struct RestoreHeapLimits {
+    hard: i64,
+    soft: i64,
+}
+
+impl Drop for RestoreHeapLimits {
+    fn drop(&mut self) {
+        unsafe {
+            sqlite3_hard_heap_limit64(self.hard);
+            sqlite3_soft_heap_limit64(self.soft);
+        }
+    }
+}
@@ -0,0 +1,338 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::any;
+use std::fmt::{self, Debug};
+
+use crate::row::check_column_type;
+use crate::*;
+
+/// Reads the `i`th column of the current row of `stmt`
+/// as an enum stored as an integer.
+/// The leftmost column is number 0.
+///
+/// Unlike casting the result of [`Statement::column_i64`],
+/// this never produces an invalid or wrong variant:
+/// it returns a [`ResultCode::RANGE`] error
+/// if `E`'s [`TryFrom`] implementation rejects the integer,
+/// or a [`ResultCode::MISMATCH`] error if the value is not an integer
+/// (including `NULL`; read into an `Option` to allow it).
+/// This is intended for implementing [`FromColumn`];
+/// see [`IntEnumMap`] for enums that don't implement `TryFrom<i64>`.
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{
+/// #     to_int_enum, try_from_int_enum, Connection, FromColumn, OpenFlags, Result, ResultCode,
+/// #     Statement, ToSql,
+/// # };
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// enum Status {
+///     Active = 1,
+///     Archived = 2,
+/// }
+///
+/// impl TryFrom<i64> for Status {
+///     type Error = ();
+///
+///     fn try_from(n: i64) -> std::result::Result<Self, ()> {
+///         match n {
+///             1 => Ok(Status::Active),
+///             2 => Ok(Status::Archived),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// impl From<Status> for i64 {
+///     fn from(status: Status) -> i64 {
+///         status as i64
+///     }
+/// }
+///
+/// impl FromColumn for Status {
+///     fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+///         try_from_int_enum(stmt, i)
+///     }
+/// }
+///
+/// impl ToSql for Status {
+///     fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+///         to_int_enum(*self, stmt, i)
+///     }
+/// }
+///
+/// # fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
+/// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// let rows = conn.query::<_, (Status,)>("SELECT ?1;", (Status::Archived,))?;
+/// assert_eq!(rows, [(Status::Archived,)]);
+/// let err = conn.query::<_, (Status,)>("SELECT 3;", ()).unwrap_err();
+/// assert_eq!(err.result_code(), ResultCode::RANGE);
+/// # Ok(())
+/// # }
+/// ```
+pub fn try_from_int_enum<E: TryFrom<i64>>(stmt: &mut Statement<'_>, i: usize) -> Result<E> {
+    let n = column_int(stmt, i)?;
+    E::try_from(n).map_err(|_| invalid_variant::<E>(stmt, i, n))
+}
+
+/// Binds an enum to the `i`th host parameter of `stmt` as an integer.
+/// The first host parameter has an index of 1.
+/// This is intended for implementing [`ToSql`]
+/// alongside [`try_from_int_enum`].
+pub fn to_int_enum<E: Into<i64>>(value: E, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+    stmt.bind_i64(i, value.into())
+}
+
+/// A mapping between an enum's variants and the integers they are stored as,
+/// for enums that don't implement `TryFrom<i64>`.
+/// Build one with [`IntEnumMap::new`] and [`IntEnumMap::variant`].
+///
+/// # Example
+///
+/// ```
+/// # use zombiezen_sqlite::IntEnumMap;
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// enum Color {
+///     Red,
+///     Green,
+/// }
+///
+/// let colors = IntEnumMap::new()
+///     .variant(10, Color::Red)
+///     .variant(20, Color::Green);
+/// assert_eq!(colors.to_i64(&Color::Green), Some(20));
+/// assert_eq!(colors.get(10), Some(&Color::Red));
+/// assert_eq!(colors.get(30), None);
+/// ```
+#[derive(Clone)]
+pub struct IntEnumMap<E> {
+    variants: Vec<(i64, E)>,
+}
+
+impl<E> IntEnumMap<E> {
+    /// Returns a mapping with no variants.
+    pub fn new() -> Self {
+        IntEnumMap {
+            variants: Vec::new(),
+        }
+    }
+
+    /// Adds a variant stored as `n`, replacing any variant previously mapped to `n`.
+    pub fn variant(mut self, n: i64, value: E) -> Self {
+        self.variants.retain(|(m, _)| *m != n);
+        self.variants.push((n, value));
+        self
+    }
+
+    /// Returns the variant stored as `n`.
+    pub fn get(&self, n: i64) -> Option<&E> {
+        self.variants.iter().find(|(m, _)| *m == n).map(|(_, v)| v)
+    }
+
+    /// Reads the `i`th column of the current row of `stmt` as a variant.
+    /// The leftmost column is number 0.
+    /// Errors are the same as for [`try_from_int_enum`].
+    pub fn from_column(&self, stmt: &mut Statement<'_>, i: usize) -> Result<E>
+    where
+        E: Clone,
+    {
+        let n = column_int(stmt, i)?;
+        self.get(n)
+            .cloned()
+            .ok_or_else(|| invalid_variant::<E>(stmt, i, n))
+    }
+}
+
+impl<E: PartialEq> IntEnumMap<E> {
+    /// Returns the integer that `value` is stored as.
+    pub fn to_i64(&self, value: &E) -> Option<i64> {
+        self.variants
+            .iter()
+            .find(|(_, v)| v == value)
+            .map(|(n, _)| *n)
+    }
+
+    /// Binds `value` to the `i`th host parameter of `stmt` as an integer.
+    /// The first host parameter has an index of 1.
+    /// Returns a [`ResultCode::MISUSE`] error if `value` is not in the mapping.
+    pub fn bind(&self, value: &E, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        let n = self.to_i64(value).ok_or_else(|| {
+            Error::new(
+                ResultCode::MISUSE,
+                format!("{} variant has no integer value", any::type_name::<E>()),
+            )
+        })?;
+        stmt.bind_i64(i, n)
+    }
+}
+
+impl<E> Default for IntEnumMap<E> {
+    fn default() -> Self {
+        IntEnumMap::new()
+    }
+}
+
+impl<E: Debug> Debug for IntEnumMap<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.variants.iter().map(|(n, v)| (n, v)))
+            .finish()
+    }
+}
+
+/// Returns the `i`th column as an integer,
+/// or an error if it is not stored as one.
+fn column_int(stmt: &mut Statement<'_>, i: usize) -> Result<i64> {
+    check_column_type(stmt, i, &[DataType::Integer], "an integer")?;
+    Ok(stmt.column_i64(i))
+}
+
+fn invalid_variant<E>(stmt: &Statement<'_>, i: usize, n: i64) -> Error {
+    Error::new(
+        ResultCode::RANGE,
+        format!(
+            "column {} ({}) value {} is not a valid {}",
+            i,
+            stmt.column_name(i).unwrap_or_default(),
+            n,
+            any::type_name::<E>()
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Priority {
+        Low = 1,
+        High = 5,
+    }
+
+    impl TryFrom<i64> for Priority {
+        type Error = ();
+
+        fn try_from(n: i64) -> std::result::Result<Self, ()> {
+            match n {
+                1 => Ok(Priority::Low),
+                5 => Ok(Priority::High),
+                _ => Err(()),
+            }
+        }
+    }
+
+    impl From<Priority> for i64 {
+        fn from(p: Priority) -> i64 {
+            p as i64
+        }
+    }
+
+    impl FromColumn for Priority {
+        fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+            try_from_int_enum(stmt, i)
+        }
+    }
+
+    impl ToSql for Priority {
+        fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+            to_int_enum(*self, stmt, i)
+        }
+    }
+
+    fn open() -> Connection {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE tasks (id INTEGER PRIMARY KEY, p);", ())
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_try_from_int_enum() {
+        let conn = open();
+        conn.execute(
+            "INSERT INTO tasks VALUES (1, ?1), (2, ?2), (3, NULL);",
+            (Priority::Low, Priority::High),
+        )
+        .unwrap();
+        let rows = conn
+            .query::<_, (Option<Priority>,)>("SELECT p FROM tasks ORDER BY id;", ())
+            .unwrap();
+        assert_eq!(
+            rows,
+            [(Some(Priority::Low),), (Some(Priority::High),), (None,)]
+        );
+
+        conn.execute("INSERT INTO tasks VALUES (4, 2);", ())
+            .unwrap();
+        let err = conn
+            .query::<_, (Priority,)>("SELECT p FROM tasks WHERE id = 4;", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::RANGE);
+        assert!(
+            err.message()
+                .contains("column 0 (p) value 2 is not a valid"),
+            "{}",
+            err.message()
+        );
+        assert!(err.message().contains("Priority"), "{}", err.message());
+
+        for sql in [
+            "SELECT p FROM tasks WHERE id = 3;",
+            "SELECT '1';",
+            "SELECT 1.0;",
+        ] {
+            let err = conn.query::<_, (Priority,)>(sql, ()).unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::MISMATCH, "sql = {:?}", sql);
+        }
+    }
+
+    #[test]
+    fn test_int_enum_map() {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        enum Color {
+            Red,
+            Green,
+            Blue,
+        }
+
+        let colors = IntEnumMap::new()
+            .variant(1, Color::Red)
+            .variant(2, Color::Blue)
+            .variant(2, Color::Green);
+        assert_eq!(colors.get(2), Some(&Color::Green));
+        assert_eq!(colors.to_i64(&Color::Blue), None);
+
+        let conn = open();
+        let mut stmt = conn
+            .prepare_single("INSERT INTO tasks (p) VALUES (?1);")
+            .unwrap();
+        colors.bind(&Color::Green, &mut stmt, 1).unwrap();
+        stmt.step().unwrap();
+        let err = colors.bind(&Color::Blue, &mut stmt, 1).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        drop(stmt);
+        conn.execute("INSERT INTO tasks (p) VALUES (3);", ())
+            .unwrap();
+
+        let got = conn
+            .query_stream("SELECT p FROM tasks ORDER BY id;", (), |stmt| {
+                colors.from_column(stmt, 0)
+            })
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(got.len(), 2);
+        assert_eq!(*got[0].as_ref().unwrap(), Color::Green);
+        assert_eq!(
+            got[1].as_ref().unwrap_err().result_code(),
+            ResultCode::RANGE
+        );
+    }
+}
@@ -0,0 +1,299 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::fmt::Write;
+use std::io;
+
+use crate::*;
+
+/// A slice bound as a JSON array text parameter.
+///
+/// Combined with SQLite's [`json_each`] table-valued function,
+/// this allows a collection to be passed into a query with a single parameter.
+///
+/// [`json_each`]: https://www.sqlite.org/json1.html#jeach
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{Connection, JsonArray, OpenFlags};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// let ids = vec![1i64, 3, 5];
+/// let n = conn.query_row(
+///     "SELECT count(*) FROM (VALUES (1), (2), (3)) \
+///     WHERE column1 IN (SELECT value FROM json_each(?1));",
+///     (JsonArray(&ids),),
+///     |stmt| Ok(stmt.column_i64(0)),
+/// )?;
+/// assert_eq!(n, Some(2));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct JsonArray<'a, T>(pub &'a [T]);
+
+impl<'a, T: JsonElement> JsonArray<'a, T> {
+    /// Formats the slice as a JSON array.
+    pub fn to_json(&self) -> String {
+        let mut s = String::from("[");
+        for (i, elem) in self.0.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            elem.write_json(&mut s);
+        }
+        s.push(']');
+        s
+    }
+}
+
+impl<'a, T: JsonElement> ToSql for JsonArray<'a, T> {
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_text(i, self.to_json())
+    }
+}
+
+/// A value that can be written as an element of a [`JsonArray`].
+pub trait JsonElement {
+    /// Appends the value's JSON representation to `out`.
+    fn write_json(&self, out: &mut String);
+}
+
+impl<T: JsonElement + ?Sized> JsonElement for &T {
+    #[inline]
+    fn write_json(&self, out: &mut String) {
+        (**self).write_json(out)
+    }
+}
+
+impl<T: JsonElement> JsonElement for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(v) => v.write_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+macro_rules! impl_json_element_display {
+    ( $($t:ty),* ) => {
+        $(
+            impl JsonElement for $t {
+                #[inline]
+                fn write_json(&self, out: &mut String) {
+                    write!(out, "{}", self).unwrap();
+                }
+            }
+        )*
+    };
+}
+
+impl_json_element_display!(i8, i16, i32, i64, u8, u16, u32, u64, bool);
+
+impl JsonElement for f64 {
+    fn write_json(&self, out: &mut String) {
+        if self.is_finite() {
+            write!(out, "{}", self).unwrap();
+        } else {
+            // JSON has no representation for NaN or infinities.
+            out.push_str("null");
+        }
+    }
+}
+
+impl JsonElement for f32 {
+    #[inline]
+    fn write_json(&self, out: &mut String) {
+        f64::from(*self).write_json(out)
+    }
+}
+
+impl JsonElement for str {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if c < ' ' => write!(out, "\\u{:04x}", c as u32).unwrap(),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl JsonElement for String {
+    #[inline]
+    fn write_json(&self, out: &mut String) {
+        self.as_str().write_json(out)
+    }
+}
+
+impl<'c> Statement<'c> {
+    /// Evaluates the statement to completion,
+    /// writing the rows it returns to `writer`
+    /// as a JSON array of objects keyed by column name.
+    /// `INTEGER` and `REAL` values are written as JSON numbers
+    /// (non-finite numbers are written as `null`),
+    /// `TEXT` values as JSON strings,
+    /// `BLOB` values as JSON strings of lowercase hexadecimal digits,
+    /// and `NULL` as JSON `null`.
+    ///
+    /// Each row is written as soon as it is read,
+    /// so large result sets are not buffered in memory.
+    /// If an error occurs, then `writer` may contain a partial JSON array.
+    /// Errors from `writer` are reported as [`ResultCode::IOERR`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let mut stmt = conn.prepare("SELECT 1 AS id, 'alice' AS name;").0?.unwrap();
+    /// let mut buf = Vec::new();
+    /// stmt.to_json_rows(&mut buf)?;
+    /// assert_eq!(String::from_utf8(buf)?, r#"[{"id":1,"name":"alice"}]"#);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_json_rows(&mut self, mut writer: impl io::Write) -> Result<()> {
+        let mut keys = Vec::with_capacity(self.column_count());
+        for i in 0..self.column_count() {
+            let mut key = String::new();
+            self.column_name(i).unwrap_or_default().write_json(&mut key);
+            keys.push(key);
+        }
+
+        let mut buf = String::from("[");
+        let mut first = true;
+        while self.step()?.has_row() {
+            if !first {
+                buf.push(',');
+            }
+            first = false;
+            buf.push('{');
+            for (i, key) in keys.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                buf.push_str(key);
+                buf.push(':');
+                match self.column_type(i) {
+                    DataType::Null => buf.push_str("null"),
+                    DataType::Integer => self.column_i64(i).write_json(&mut buf),
+                    DataType::Float => self.column_f64(i).write_json(&mut buf),
+                    DataType::Text => self
+                        .column_text(i)
+                        .to_string_lossy()
+                        .as_ref()
+                        .write_json(&mut buf),
+                    DataType::Blob => {
+                        buf.push('"');
+                        for b in self.column_blob(i) {
+                            write!(buf, "{:02x}", b).unwrap();
+                        }
+                        buf.push('"');
+                    }
+                }
+            }
+            buf.push('}');
+            writer.write_all(buf.as_bytes()).map_err(json_io_error)?;
+            buf.clear();
+        }
+        buf.push(']');
+        writer.write_all(buf.as_bytes()).map_err(json_io_error)?;
+        writer.flush().map_err(json_io_error)
+    }
+}
+
+fn json_io_error(err: io::Error) -> Error {
+    Error::new(ResultCode::IOERR, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_json_array_each() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let ids: Vec<i64> = vec![2, 4, 5, 100];
+        let got = conn
+            .query_stream(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 10) \
+                SELECT x FROM c WHERE x IN (SELECT value FROM json_each(?1));",
+                (JsonArray(&ids),),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(got, vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn test_json_array_strings() {
+        let strs = ["a\"b", "c\\d\n", "\u{1}", "é"];
+        assert_eq!(
+            JsonArray(&strs).to_json(),
+            r#"["a\"b","c\\d\n","\u0001","é"]"#
+        );
+
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let got = conn
+            .query_stream(
+                "SELECT value FROM json_each(?1);",
+                (JsonArray(&strs),),
+                |stmt| Ok(stmt.column_text(0).unwrap().to_string()),
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(got, strs);
+
+        assert_eq!(
+            JsonArray(&[Some(1.5), None, Some(f64::NAN)]).to_json(),
+            "[1.5,null,null]"
+        );
+    }
+
+    #[test]
+    fn test_to_json_rows() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT column1 AS \"id\", column2 AS \"na\"\"me\" \
+                FROM (VALUES (1, 'a\"b'), (2.5, NULL), (NULL, x'00ff'));",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let mut buf = Vec::new();
+        stmt.to_json_rows(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"[{"id":1,"na\"me":"a\"b"},{"id":2.5,"na\"me":null},{"id":null,"na\"me":"00ff"}]"#
+        );
+
+        let mut stmt = conn
+            .prepare("SELECT 1 WHERE false;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let mut buf = Vec::new();
+        stmt.to_json_rows(&mut buf).unwrap();
+        assert_eq!(buf, b"[]");
+    }
+}
@@ -10,25 +10,101 @@ use std::str;
 
 use libsqlite3_sys::sqlite3_libversion;
 
+#[cfg(feature = "arrow")]
+mod arrow;
 mod auth;
+mod backup;
+#[cfg(feature = "begin_concurrent")]
+mod begin_concurrent;
+mod blob;
+mod builder;
 mod bytearray;
 pub mod column_metadata;
+mod commit_hook;
+mod conflict;
 mod connection;
+mod copy_table;
+mod database;
+mod delete;
+mod diff;
+mod explain;
+mod file_format;
 mod function;
 mod glob;
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+mod heap_limit;
+mod int_enum;
+mod json;
+#[cfg(feature = "math_functions")]
+mod math_functions;
+mod migrate;
+mod multi_write;
+mod params;
+mod pragma;
+mod progress;
+mod query;
+mod query_set;
 mod quote;
+mod reader;
+#[cfg(feature = "regexp")]
+mod regexp;
 mod result;
+mod retry;
+mod row;
+#[cfg(feature = "seeded_random")]
+mod seeded_random;
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+mod serialize;
+mod shared;
+mod sql_buffer;
 mod statement;
+mod status;
+#[cfg(test)]
+mod test_util;
+mod tokenize;
+#[cfg(feature = "unicode_like")]
+mod unicode_like;
+mod unlock_notify;
+mod update_hook;
+mod uri;
 mod value;
+mod vtab;
+mod wal;
 
+#[cfg(feature = "arrow")]
+pub use arrow::*;
 pub use auth::*;
+pub use blob::*;
+pub use builder::*;
+pub use conflict::*;
 pub use connection::*;
+pub use database::*;
+pub use diff::*;
+pub use explain::*;
 pub use function::*;
 pub use glob::*;
+pub use int_enum::*;
+pub use json::*;
+pub use params::*;
+pub use pragma::*;
+pub use query::*;
+pub use query_set::*;
 pub use quote::*;
+pub use reader::*;
 pub use result::*;
+pub use retry::*;
+pub use row::*;
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+pub use serialize::*;
+pub use shared::*;
+pub use sql_buffer::*;
 pub use statement::*;
+pub use status::*;
+pub use update_hook::*;
+pub use uri::*;
 pub use value::*;
+pub use vtab::*;
+pub use wal::*;
 
 /// Extension trait for `Result<&str, TextError>`.
 pub trait ResultExt<'a> {
@@ -0,0 +1,232 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::f64::consts::PI;
+use std::ffi::CString;
+
+use libsqlite3_sys::{
+    sqlite3_compileoption_used, sqlite3_value_numeric_type, SQLITE_FLOAT, SQLITE_INTEGER,
+};
+
+use crate::*;
+
+type UnaryFn = fn(f64) -> f64;
+type BinaryFn = fn(f64, f64) -> f64;
+
+/// The [math functions] that take one argument.
+///
+/// [math functions]: https://www.sqlite.org/lang_mathfunc.html
+const UNARY_FUNCTIONS: [(&str, UnaryFn); 20] = [
+    ("acos", f64::acos),
+    ("acosh", f64::acosh),
+    ("asin", f64::asin),
+    ("asinh", f64::asinh),
+    ("atan", f64::atan),
+    ("atanh", f64::atanh),
+    ("cos", f64::cos),
+    ("cosh", f64::cosh),
+    ("degrees", f64::to_degrees),
+    ("exp", f64::exp),
+    ("ln", |x| positive(x).ln()),
+    ("log", |x| positive(x).log10()),
+    ("log10", |x| positive(x).log10()),
+    ("log2", |x| positive(x).log2()),
+    ("radians", f64::to_radians),
+    ("sin", f64::sin),
+    ("sinh", f64::sinh),
+    ("sqrt", f64::sqrt),
+    ("tan", f64::tan),
+    ("tanh", f64::tanh),
+];
+
+/// The math functions that round their argument to an integer.
+/// Integer arguments are returned unchanged.
+const ROUNDING_FUNCTIONS: [(&str, UnaryFn); 4] = [
+    ("ceil", f64::ceil),
+    ("ceiling", f64::ceil),
+    ("floor", f64::floor),
+    ("trunc", f64::trunc),
+];
+
+/// The math functions that take two arguments.
+const BINARY_FUNCTIONS: [(&str, BinaryFn); 5] = [
+    ("atan2", f64::atan2),
+    ("log", |b, x| positive(x).ln() / positive(b).ln()),
+    ("mod", |x, y| x % y),
+    ("pow", f64::powf),
+    ("power", f64::powf),
+];
+
+impl Connection {
+    /// Registers Rust implementations of SQLite's built-in [math functions]
+    /// (like `sqrt`, `sin`, `log`, and `pow`)
+    /// if SQLite was compiled without `SQLITE_ENABLE_MATH_FUNCTIONS`.
+    /// This allows SQL written for SQLite builds that include the math functions
+    /// to run on builds that don't.
+    /// Returns whether the functions were registered;
+    /// if SQLite already provides them, then the connection is left unchanged.
+    ///
+    /// As with the built-in versions,
+    /// arguments are converted to numbers as if by numeric affinity,
+    /// the functions return `NULL` if any argument is not a number,
+    /// and results that are not a number (such as `sqrt(-1)`) are `NULL`.
+    ///
+    /// [math functions]: https://www.sqlite.org/lang_mathfunc.html
+    #[doc(alias = "sqlite3_compileoption_used")]
+    pub fn enable_math_functions(&mut self) -> Result<bool> {
+        let option = CString::new("ENABLE_MATH_FUNCTIONS").unwrap();
+        if unsafe { sqlite3_compileoption_used(option.as_ptr()) } != 0 {
+            return Ok(false);
+        }
+        self.register_math_functions()?;
+        Ok(true)
+    }
+
+    fn register_math_functions(&mut self) -> Result<()> {
+        let flags = FunctionFlags::DETERMINISTIC;
+        let mut functions = Vec::<(&str, Option<u8>, FunctionFlags, BoxScalarFunction)>::new();
+        functions.push((
+            "pi",
+            Some(0),
+            flags,
+            Box::new(|mut ctx, _| ctx.result_f64(PI)),
+        ));
+        for (name, f) in UNARY_FUNCTIONS {
+            functions.push((
+                name,
+                Some(1),
+                flags,
+                Box::new(move |mut ctx, args| match numeric_arg(args) {
+                    Some((_, x)) => ctx.result_f64(f(x)),
+                    None => ctx.result_null(),
+                }),
+            ));
+        }
+        for (name, f) in ROUNDING_FUNCTIONS {
+            functions.push((
+                name,
+                Some(1),
+                flags,
+                Box::new(move |mut ctx, args| match numeric_arg(args) {
+                    Some((Some(i), _)) => ctx.result_i64(i),
+                    Some((None, x)) => ctx.result_f64(f(x)),
+                    None => ctx.result_null(),
+                }),
+            ));
+        }
+        for (name, f) in BINARY_FUNCTIONS {
+            functions.push((
+                name,
+                Some(2),
+                flags,
+                Box::new(
+                    move |mut ctx, args| match (numeric_arg(args), numeric_arg(args)) {
+                        (Some((_, x)), Some((_, y))) => ctx.result_f64(f(x, y)),
+                        _ => ctx.result_null(),
+                    },
+                ),
+            ));
+        }
+        self.register_functions(functions)
+    }
+}
+
+/// Reads the next argument as a number,
+/// returning its integer value (if it is an integer) and its floating point value,
+/// or `None` if the argument is not a number.
+fn numeric_arg(
+    args: &mut dyn ExactSizeIterator<Item = ProtectedValue>,
+) -> Option<(Option<i64>, f64)> {
+    let mut arg = args.next()?;
+    match unsafe { sqlite3_value_numeric_type(arg.as_ptr()) } {
+        SQLITE_INTEGER => Some((Some(arg.to_i64()), arg.to_f64())),
+        SQLITE_FLOAT => Some((None, arg.to_f64())),
+        _ => None,
+    }
+}
+
+/// Returns `x` if it is positive or NaN otherwise,
+/// since SQLite's logarithm functions return `NULL` for non-positive arguments.
+fn positive(x: f64) -> f64 {
+    if x > 0.0 {
+        x
+    } else {
+        f64::NAN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    fn eval(conn: &Conn, expr: &str) -> Option<f64> {
+        conn.query_row(&format!("SELECT {};", expr), (), |stmt| {
+            Ok(match stmt.column_type(0) {
+                DataType::Null => None,
+                _ => Some(stmt.column_f64(0)),
+            })
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_math_functions() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        // Register the shim even if this build has native math functions.
+        conn.register_math_functions().unwrap();
+
+        assert_eq!(eval(&conn, "sqrt(16)"), Some(4.0));
+        assert_eq!(eval(&conn, "sqrt('16')"), Some(4.0));
+        assert_eq!(eval(&conn, "sqrt(-1)"), None);
+        assert_eq!(eval(&conn, "sqrt(NULL)"), None);
+        assert_eq!(eval(&conn, "sqrt('abc')"), None);
+        assert_eq!(eval(&conn, "pow(2, 10)"), Some(1024.0));
+        assert_eq!(eval(&conn, "power(2, 0.5)"), Some(2f64.sqrt()));
+        assert_eq!(eval(&conn, "log(100)"), Some(2.0));
+        assert_eq!(eval(&conn, "log(2, 8)"), Some(3.0));
+        assert_eq!(eval(&conn, "ln(0)"), None);
+        assert_eq!(eval(&conn, "log2(8)"), Some(3.0));
+        assert_eq!(eval(&conn, "mod(7, 3)"), Some(1.0));
+        assert_eq!(eval(&conn, "mod(7, 0)"), None);
+        assert_eq!(eval(&conn, "pi()"), Some(PI));
+        assert_eq!(eval(&conn, "degrees(pi())"), Some(180.0));
+        assert_eq!(eval(&conn, "cos(0)"), Some(1.0));
+        assert_eq!(eval(&conn, "atan2(1, 1)"), Some(PI / 4.0));
+
+        let rounded = conn
+            .query_row(
+                "SELECT typeof(ceil(5)), ceil(1.2), floor(-1.2), trunc(-1.7);",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_text(0).unwrap().to_string(),
+                        stmt.column_f64(1),
+                        stmt.column_f64(2),
+                        stmt.column_f64(3),
+                    ))
+                },
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(rounded, (String::from("integer"), 2.0, -2.0, -1.0));
+    }
+
+    #[test]
+    fn test_enable_math_functions() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let native = eval_native(&conn);
+        let registered = conn.enable_math_functions().unwrap();
+        assert_eq!(registered, !native);
+        assert_eq!(eval(&conn, "sqrt(16)"), Some(4.0));
+    }
+
+    /// Reports whether `sqrt` is available before any functions are registered.
+    fn eval_native(conn: &Conn) -> bool {
+        conn.execute("SELECT sqrt(16);", ()).is_ok()
+    }
+}
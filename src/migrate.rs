@@ -0,0 +1,501 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CString;
+use std::iter;
+
+use crate::tokenize::{tokenize, TokenKind};
+use crate::*;
+
+impl Conn {
+    /// Brings the main database's schema up to date with the schema script `sql`,
+    /// returning the statements that were run.
+    /// Running `ensure_schema` again with the same script does nothing.
+    ///
+    /// The script is evaluated in a scratch in-memory database
+    /// and the resulting schema is [compared][Conn::diff_schema] to the live one:
+    ///
+    /// - Indices, views, and triggers whose definitions changed are dropped and recreated.
+    /// - Columns added to the end of an existing table are added with `ALTER TABLE`.
+    /// - Tables, indices, views, and triggers that are missing are created.
+    /// - Objects that are not in the script are left alone.
+    ///
+    /// Any other change to an existing table results in a [`ResultCode::ERROR`],
+    /// since applying it would require rebuilding the table.
+    /// The changes are applied inside a savepoint,
+    /// so if any statement fails, then the database is left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let schema = "CREATE TABLE foo (id INTEGER PRIMARY KEY);";
+    /// assert_eq!(conn.ensure_schema(schema)?.len(), 1);
+    /// assert!(conn.ensure_schema(schema)?.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ensure_schema(&self, sql: &str) -> Result<Vec<String>> {
+        let desired = Connection::open(
+            CString::new(":memory:").unwrap(),
+            OpenFlags::default() | OpenFlags::MEMORY,
+        )?;
+        let mut results = desired.query_multi(sql, iter::empty::<()>());
+        while results.next_result_set()?.is_some() {}
+        let statements = self.migration_statements(&desired)?;
+        if statements.is_empty() {
+            return Ok(statements);
+        }
+
+        self.execute("SAVEPOINT ensure_schema;", ())?;
+        let result = statements
+            .iter()
+            .try_for_each(|stmt| self.execute(stmt, ()).map(|_| ()));
+        if result.is_err() {
+            let _ = self.execute("ROLLBACK TO ensure_schema;", ());
+        }
+        let release_result = self.execute("RELEASE ensure_schema;", ()).map(|_| ());
+        result.and(release_result)?;
+        Ok(statements)
+    }
+
+    /// Returns the statements needed to bring the schema of `self` to that of `desired`.
+    fn migration_statements(&self, desired: &Conn) -> Result<Vec<String>> {
+        let diff = self.diff_schema(desired)?;
+        let mut statements = Vec::new();
+        let mut alters = Vec::new();
+        let mut creates = Vec::new();
+        for (before, after) in &diff.changed {
+            if after.kind == "table" {
+                alters.extend(add_columns(before, after)?);
+            } else {
+                statements.push(format!(
+                    "DROP {} {};",
+                    after.kind.to_ascii_uppercase(),
+                    Quote::as_id(&before.name)
+                ));
+                creates.push(after);
+            }
+        }
+        statements.extend(alters);
+        for obj in &diff.added {
+            if obj.kind == "table" {
+                statements.push(format!("{};", obj.sql));
+            } else {
+                creates.push(obj);
+            }
+        }
+
+        // Create objects in the order the script created them,
+        // since views and triggers can depend on one another.
+        let order = desired
+            .query_stream(
+                "SELECT type, name FROM main.sqlite_master ORDER BY rowid;",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_text(0).to_string_lossy().into_owned(),
+                        stmt.column_text(1).to_string_lossy().into_owned(),
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>>>()?;
+        creates.sort_by_key(|obj| {
+            order
+                .iter()
+                .position(|(kind, name)| *kind == obj.kind && *name == obj.name)
+        });
+        statements.extend(creates.into_iter().map(|obj| format!("{};", obj.sql)));
+        Ok(statements)
+    }
+
+    /// Renames the column `old` of the main database's table `table` to `new`
+    /// with `ALTER TABLE ... RENAME COLUMN`,
+    /// returning the names of the indices, views, and triggers
+    /// whose definitions referred to the column and were rewritten to use the new name.
+    ///
+    /// If [`legacy_alter_table`] is enabled,
+    /// SQLite leaves views and triggers referring to the old name,
+    /// which breaks them.
+    /// `rename_column` disables it for the duration of the rename
+    /// and restores the previous setting afterward.
+    /// The rename happens inside a savepoint,
+    /// so if a dependent object cannot be rewritten
+    /// (for example, because it is already broken),
+    /// then the database is left unchanged and the error is returned.
+    ///
+    /// [`legacy_alter_table`]: https://www.sqlite.org/pragma.html#pragma_legacy_alter_table
+    pub fn rename_column(&self, table: &str, old: &str, new: &str) -> Result<Vec<String>> {
+        let legacy = self
+            .query_row("PRAGMA legacy_alter_table;", (), |stmt| {
+                Ok(stmt.column_i64(0) != 0)
+            })?
+            .unwrap_or_default();
+        if legacy {
+            self.execute("PRAGMA legacy_alter_table = OFF;", ())?;
+        }
+
+        let result = self.execute("SAVEPOINT rename_column;", ()).and_then(|_| {
+            let result = self.rename_column_internal(table, old, new);
+            if result.is_err() {
+                let _ = self.execute("ROLLBACK TO rename_column;", ());
+            }
+            let release_result = self.execute("RELEASE rename_column;", ()).map(|_| ());
+            result.and_then(|dependents| release_result.map(|_| dependents))
+        });
+
+        if legacy {
+            let restore_result = self.execute("PRAGMA legacy_alter_table = ON;", ());
+            return result.and_then(|dependents| restore_result.map(|_| dependents));
+        }
+        result
+    }
+
+    fn rename_column_internal(&self, table: &str, old: &str, new: &str) -> Result<Vec<String>> {
+        let definitions = || {
+            self.query_stream(
+                "SELECT name, sql FROM main.sqlite_master \
+                WHERE type IN ('index', 'view', 'trigger') AND sql IS NOT NULL \
+                ORDER BY rowid;",
+                (),
+                |stmt| {
+                    Ok((
+                        stmt.column_text(0).to_string_lossy().into_owned(),
+                        stmt.column_text(1).to_string_lossy().into_owned(),
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>>>()
+        };
+        let before = definitions()?;
+        self.execute(
+            &format!(
+                "ALTER TABLE main.{} RENAME COLUMN {} TO {};",
+                Quote::as_id(table),
+                Quote::as_id(old),
+                Quote::as_id(new)
+            ),
+            (),
+        )?;
+        let after = definitions()?;
+        Ok(after
+            .into_iter()
+            .filter(|obj| !before.contains(obj))
+            .map(|(name, _)| name)
+            .collect())
+    }
+}
+
+/// Returns `ALTER TABLE` statements that add the columns
+/// that the table `after` has at the end of the table `before`,
+/// or an error if the table changed in any other way.
+///
+/// The column definitions are copied verbatim from `after`,
+/// so constraints like `REFERENCES` and `COLLATE` are preserved.
+/// To check that nothing else changed,
+/// the statements are applied to a copy of `before` in a scratch database
+/// and the resulting definition is compared to `after`
+/// with whitespace, comments, and the case of keywords ignored.
+fn add_columns(before: &SchemaObject, after: &SchemaObject) -> Result<Vec<String>> {
+    let rebuild_error = || {
+        Error::new(
+            ResultCode::ERROR,
+            format!(
+                "table {} changed in a way that requires rebuilding it",
+                after.name
+            ),
+        )
+    };
+    let live = column_definitions(&before.sql);
+    let want = column_definitions(&after.sql);
+    if want.len() < live.len() {
+        return Err(rebuild_error());
+    }
+    let statements = want[live.len()..]
+        .iter()
+        .map(|def| {
+            format!(
+                "ALTER TABLE {} ADD COLUMN {};",
+                Quote::as_id(&after.name),
+                def
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let scratch = Connection::open(
+        CString::new(":memory:").unwrap(),
+        OpenFlags::default() | OpenFlags::MEMORY,
+    )?;
+    scratch.execute(&before.sql, ())?;
+    for stmt in &statements {
+        scratch.execute(stmt, ()).map_err(|_| rebuild_error())?;
+    }
+    let migrated = scratch
+        .query_row(
+            "SELECT sql FROM main.sqlite_master WHERE type = 'table' AND name = ?1;",
+            (after.name.as_str(),),
+            |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+        )?
+        .unwrap_or_default();
+    let normalize = |sql: &str| {
+        sql_tokens(sql)
+            .into_iter()
+            .map(|(_, token)| token)
+            .collect::<Vec<_>>()
+    };
+    if normalize(&migrated) != normalize(&after.sql) {
+        return Err(rebuild_error());
+    }
+    Ok(statements)
+}
+
+/// Returns the text of each column definition in a `CREATE TABLE` statement,
+/// excluding any table constraints that follow them.
+fn column_definitions(sql: &str) -> Vec<&str> {
+    let mut defs = Vec::new();
+    let mut depth = 0;
+    let mut start = None;
+    let mut end = 0;
+    for (span, token) in sql_tokens(sql) {
+        match token.as_str() {
+            "(" => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            ")" if depth == 1 => {
+                defs.extend(start.map(|start| &sql[start..end]));
+                break;
+            }
+            ")" => depth -= 1,
+            "," if depth == 1 => {
+                defs.extend(start.take().map(|start| &sql[start..end]));
+                continue;
+            }
+            _ => {}
+        }
+        if depth >= 1 {
+            start.get_or_insert(span.0);
+            end = span.1;
+        }
+    }
+    let is_table_constraint = |def: &&str| {
+        let first = sql_tokens(def)
+            .into_iter()
+            .next()
+            .map(|(_, token)| token)
+            .unwrap_or_default();
+        ["constraint", "primary", "unique", "check", "foreign"].contains(&first.as_str())
+    };
+    defs.into_iter()
+        .take_while(|def| !is_table_constraint(def))
+        .collect()
+}
+
+/// Splits `sql` into tokens, skipping whitespace and comments.
+/// Each token is returned with its byte range in `sql`.
+/// Words are lowercased; other tokens are returned verbatim.
+fn sql_tokens(sql: &str) -> Vec<((usize, usize), String)> {
+    tokenize(sql)
+        .filter(|token| !token.is_trivia())
+        .map(|token| {
+            let text = if token.kind == TokenKind::Word {
+                token.text.to_ascii_lowercase()
+            } else {
+                token.text.to_string()
+            };
+            ((token.start, token.end()), text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    const SCHEMA_V1: &str = "
+        CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL);
+        CREATE INDEX people_name ON people (name);
+        CREATE VIEW names AS SELECT name FROM people;
+    ";
+
+    #[test]
+    fn test_ensure_schema() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let applied = conn.ensure_schema(SCHEMA_V1).unwrap();
+        assert_eq!(applied.len(), 3, "applied = {:?}", applied);
+        conn.execute("INSERT INTO people (name) VALUES ('alice');", ())
+            .unwrap();
+        assert_eq!(conn.ensure_schema(SCHEMA_V1).unwrap(), Vec::<String>::new());
+
+        let schema_v2 = format!(
+            "{}\nCREATE TABLE pets (id INTEGER PRIMARY KEY, owner INTEGER REFERENCES people);",
+            SCHEMA_V1
+        );
+        assert_eq!(
+            conn.ensure_schema(&schema_v2).unwrap(),
+            ["CREATE TABLE pets (id INTEGER PRIMARY KEY, owner INTEGER REFERENCES people);"]
+        );
+        assert!(conn.ensure_schema(&schema_v2).unwrap().is_empty());
+
+        let n = conn
+            .query_row("SELECT count(*) FROM names;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(n, Some(1));
+    }
+
+    #[test]
+    fn test_ensure_schema_add_column() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.ensure_schema(SCHEMA_V1).unwrap();
+        conn.execute("INSERT INTO people (name) VALUES ('alice');", ())
+            .unwrap();
+
+        let schema_v2 = "
+            CREATE TABLE people (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                email TEXT NOT NULL DEFAULT ''
+            );
+            CREATE INDEX people_name ON people (name, email);
+            CREATE VIEW names AS SELECT name FROM people;
+        ";
+        assert_eq!(
+            conn.ensure_schema(schema_v2).unwrap(),
+            [
+                "DROP INDEX \"people_name\";",
+                "ALTER TABLE \"people\" ADD COLUMN email TEXT NOT NULL DEFAULT '';",
+                "CREATE INDEX people_name ON people (name, email);",
+            ]
+        );
+        assert!(conn.ensure_schema(schema_v2).unwrap().is_empty());
+        let email = conn
+            .query_row("SELECT email FROM people;", (), |stmt| {
+                Ok(stmt.column_text(0).unwrap().to_string())
+            })
+            .unwrap();
+        assert_eq!(email.as_deref(), Some(""));
+
+        let err = conn
+            .ensure_schema("CREATE TABLE people (id INTEGER PRIMARY KEY);")
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+
+    #[test]
+    fn test_ensure_schema_table_constraints() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.ensure_schema(SCHEMA_V1).unwrap();
+
+        for people in [
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL, UNIQUE (name));",
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL CHECK (name <> ''));",
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL COLLATE NOCASE);",
+            "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL, x, CHECK (x > 0));",
+        ] {
+            let err = conn.ensure_schema(people).unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::ERROR, "{}", people);
+        }
+
+        // Formatting and comments are not changes.
+        let reformatted = "
+            -- The people.
+            create table people (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL /* required */
+            );
+            CREATE INDEX people_name ON people (name);
+            CREATE VIEW names AS SELECT name FROM people;
+        ";
+        assert!(conn.ensure_schema(reformatted).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ensure_schema_add_column_constraints() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.ensure_schema(SCHEMA_V1).unwrap();
+        conn.execute("INSERT INTO people (name) VALUES ('alice');", ())
+            .unwrap();
+
+        let schema_v2 = "
+            CREATE TABLE teams (id INTEGER PRIMARY KEY);
+            CREATE TABLE people (
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                team INTEGER REFERENCES teams (id),
+                nick TEXT COLLATE NOCASE
+            );
+            CREATE INDEX people_name ON people (name);
+            CREATE VIEW names AS SELECT name FROM people;
+        ";
+        assert_eq!(
+            conn.ensure_schema(schema_v2).unwrap(),
+            [
+                "ALTER TABLE \"people\" ADD COLUMN team INTEGER REFERENCES teams (id);",
+                "ALTER TABLE \"people\" ADD COLUMN nick TEXT COLLATE NOCASE;",
+                "CREATE TABLE teams (id INTEGER PRIMARY KEY);",
+            ]
+        );
+        assert!(conn.ensure_schema(schema_v2).unwrap().is_empty());
+
+        let parent = conn
+            .query_row(
+                "SELECT \"table\" FROM pragma_foreign_key_list('people');",
+                (),
+                |stmt| Ok(stmt.column_text(0).unwrap().to_string()),
+            )
+            .unwrap();
+        assert_eq!(parent.as_deref(), Some("teams"));
+        conn.execute("UPDATE people SET nick = 'Al';", ()).unwrap();
+        let n = conn
+            .query_row(
+                "SELECT count(*) FROM people WHERE nick = 'AL';",
+                (),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap();
+        assert_eq!(n, Some(1));
+    }
+
+    #[test]
+    fn test_rename_column() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut results = conn.query_multi(SCHEMA_V1, iter::empty::<()>());
+        while results.next_result_set().unwrap().is_some() {}
+        conn.execute("CREATE TABLE other (x INTEGER);", ()).unwrap();
+        conn.execute("CREATE VIEW xs AS SELECT x FROM other;", ())
+            .unwrap();
+        conn.execute("INSERT INTO people (name) VALUES ('Alice');", ())
+            .unwrap();
+        conn.execute("PRAGMA legacy_alter_table = ON;", ()).unwrap();
+
+        let dependents = conn.rename_column("people", "name", "full_name").unwrap();
+        assert_eq!(dependents, ["people_name", "names"]);
+        let name = conn
+            .query_row("SELECT * FROM names;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })
+            .unwrap();
+        assert_eq!(name.as_deref(), Some("Alice"));
+        let legacy = conn
+            .query_row("PRAGMA legacy_alter_table;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(legacy, Some(1));
+
+        let err = conn.rename_column("people", "nope", "x").unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(conn.get_autocommit());
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{CStr, CString};
+
+use libsqlite3_sys::sqlite3_db_filename;
+
+use crate::*;
+
+impl Conn {
+    /// Begins a transaction and acquires the write lock
+    /// on each of the attached databases named in `schemas`,
+    /// like `BEGIN IMMEDIATE` does for every database on the connection.
+    ///
+    /// The locks are acquired one database at a time
+    /// in order of the databases' filenames,
+    /// regardless of the order of `schemas` or the names the databases are attached under.
+    /// If every transaction that writes to several database files
+    /// acquires its locks this way,
+    /// then no transaction can hold a lock that another is waiting for
+    /// while waiting for a lock that the other holds,
+    /// so with a [busy timeout] set,
+    /// concurrent transactions wait for each other instead of failing with [`ResultCode::BUSY`].
+    ///
+    /// A write lock is acquired by running a `DELETE` that matches no rows
+    /// against one of the database's tables,
+    /// so each database must contain at least one ordinary table.
+    /// If a lock cannot be acquired,
+    /// then the transaction is rolled back and the error is returned.
+    /// Otherwise, the caller is responsible for ending the transaction
+    /// with `COMMIT` or `ROLLBACK`.
+    ///
+    /// [busy timeout]: https://www.sqlite.org/pragma.html#pragma_busy_timeout
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("ATTACH ':memory:' AS archive;", ())?;
+    /// conn.execute("CREATE TABLE main.items (x INTEGER);", ())?;
+    /// conn.execute("CREATE TABLE archive.items (x INTEGER);", ())?;
+    /// conn.begin_multi_write(&["archive", "main"])?;
+    /// conn.execute("INSERT INTO archive.items SELECT x FROM main.items;", ())?;
+    /// conn.execute("DELETE FROM main.items;", ())?;
+    /// conn.execute("COMMIT;", ())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn begin_multi_write(&self, schemas: &[&str]) -> Result<()> {
+        if !self.get_autocommit() {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "begin_multi_write called inside a transaction",
+            ));
+        }
+        let mut locks = Vec::with_capacity(schemas.len());
+        for &schema in schemas {
+            let filename = self.schema_filename(schema)?;
+            // Find the table before the transaction begins,
+            // since reading inside the transaction would take a read lock first
+            // and SQLite does not wait to upgrade a read lock to a write lock.
+            let table = self
+                .query_row(
+                    &format!(
+                        "SELECT name FROM {}.sqlite_master \
+                        WHERE type = 'table' AND name NOT LIKE 'sqlite\\_%' ESCAPE '\\' \
+                        AND sql NOT LIKE 'CREATE VIRTUAL%' \
+                        ORDER BY rowid LIMIT 1;",
+                        Quote::as_id(schema)
+                    ),
+                    (),
+                    |stmt| Ok(stmt.column_text(0).to_string_lossy().into_owned()),
+                )?
+                .ok_or_else(|| {
+                    Error::new(
+                        ResultCode::ERROR,
+                        format!("cannot lock database {}: it has no tables", schema),
+                    )
+                })?;
+            locks.push((filename, schema, table));
+        }
+        locks.sort();
+        locks.dedup_by(|a, b| a.1 == b.1);
+
+        self.execute("BEGIN;", ())?;
+        let result = locks.iter().try_for_each(|(_, schema, table)| {
+            self.execute(
+                &format!(
+                    "DELETE FROM {}.{} WHERE false;",
+                    Quote::as_id(schema),
+                    Quote::as_id(table)
+                ),
+                (),
+            )
+            .map(|_| ())
+        });
+        if result.is_err() && !self.get_autocommit() {
+            let _ = self.execute("ROLLBACK;", ());
+        }
+        result
+    }
+
+    /// Returns the filename of the attached database `schema`,
+    /// which is empty for temporary and in-memory databases.
+    fn schema_filename(&self, schema: &str) -> Result<String> {
+        let no_such_database =
+            || Error::new(ResultCode::ERROR, format!("no such database: {}", schema));
+        let schema_cstr = CString::new(schema).map_err(|_| no_such_database())?;
+        unsafe {
+            let ptr = sqlite3_db_filename(self.as_ptr(), schema_cstr.as_ptr());
+            if ptr.is_null() {
+                return Err(no_such_database());
+            }
+            Ok(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_begin_multi_write() {
+        let dir = TempDir::new("multi-write");
+        let path_a = dir.join("a.db");
+        let path_b = dir.join("b.db");
+        {
+            let conn = Connection::open(
+                CString::new(path_a.to_str().unwrap()).unwrap(),
+                OpenFlags::default(),
+            )
+            .unwrap();
+            conn.execute("CREATE TABLE t (n INTEGER);", ()).unwrap();
+            conn.execute("ATTACH ?1 AS b;", (path_b.to_str().unwrap(),))
+                .unwrap();
+            conn.execute("CREATE TABLE b.t (n INTEGER);", ()).unwrap();
+        }
+
+        // The threads attach the files under swapped names,
+        // so they name the same files in opposite orders.
+        let run = |first: &std::path::Path, second: &std::path::Path| {
+            let conn = Connection::open(
+                CString::new(first.to_str().unwrap()).unwrap(),
+                OpenFlags::default(),
+            )
+            .unwrap();
+            conn.execute("ATTACH ?1 AS other;", (second.to_str().unwrap(),))
+                .unwrap();
+            conn.execute("PRAGMA busy_timeout = 10000;", ()).unwrap();
+            for _ in 0..20 {
+                conn.begin_multi_write(&["main", "other"]).unwrap();
+                conn.execute("INSERT INTO main.t VALUES (1);", ()).unwrap();
+                thread::sleep(Duration::from_millis(1));
+                conn.execute("INSERT INTO other.t VALUES (1);", ()).unwrap();
+                conn.execute("COMMIT;", ()).unwrap();
+            }
+        };
+        thread::scope(|s| {
+            s.spawn(|| run(&path_a, &path_b));
+            s.spawn(|| run(&path_b, &path_a));
+        });
+
+        let conn = Connection::open(
+            CString::new(path_a.to_str().unwrap()).unwrap(),
+            OpenFlags::default(),
+        )
+        .unwrap();
+        conn.execute("ATTACH ?1 AS b;", (path_b.to_str().unwrap(),))
+            .unwrap();
+        let counts = conn
+            .query_row(
+                "SELECT (SELECT count(*) FROM main.t), (SELECT count(*) FROM b.t);",
+                (),
+                |stmt| Ok((stmt.column_i64(0), stmt.column_i64(1))),
+            )
+            .unwrap();
+        assert_eq!(counts, Some((40, 40)));
+
+        // While waiting for the lock on a.db,
+        // a transaction that also needs b.db must not hold b.db.
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("INSERT INTO main.t VALUES (2);", ()).unwrap();
+        thread::scope(|s| {
+            let waiter = s.spawn(|| run(&path_b, &path_a));
+            thread::sleep(Duration::from_millis(100));
+            conn.execute("INSERT INTO b.t VALUES (2);", ())
+                .expect("b.db locked by waiting transaction");
+            conn.execute("COMMIT;", ()).unwrap();
+            waiter.join().unwrap();
+        });
+
+        let err = conn.begin_multi_write(&["main", "nope"]).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(conn.get_autocommit());
+        conn.execute("BEGIN;", ()).unwrap();
+        let err = conn.begin_multi_write(&["main"]).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        conn.execute("ROLLBACK;", ()).unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use crate::*;
+
+/// A type that can be bound to a statement's host parameter.
+pub trait ToSql {
+    /// Binds the value to the `i`th host parameter of `stmt`.
+    /// The first host parameter has an index of 1.
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()>;
+}
+
+impl<T: ToSql + ?Sized> ToSql for &T {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        (**self).bind_to(stmt, i)
+    }
+}
+
+impl<T: ToSql> ToSql for Option<T> {
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        match self {
+            Some(v) => v.bind_to(stmt, i),
+            None => stmt.bind_null(i),
+        }
+    }
+}
+
+macro_rules! impl_to_sql_int {
+    ( $($t:ty),* ) => {
+        $(
+            impl ToSql for $t {
+                #[inline]
+                fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+                    stmt.bind_i64(i, i64::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+impl_to_sql_int!(i8, i16, i32, i64, u8, u16, u32, bool);
+
+impl ToSql for f32 {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_f64(i, f64::from(*self))
+    }
+}
+
+impl ToSql for f64 {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_f64(i, *self)
+    }
+}
+
+impl ToSql for str {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_text(i, self)
+    }
+}
+
+impl ToSql for String {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_text(i, self.as_str())
+    }
+}
+
+impl ToSql for [u8] {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_blob(i, self)
+    }
+}
+
+impl ToSql for Vec<u8> {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_blob(i, self.as_slice())
+    }
+}
+
+impl ToSql for DupValue {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_value(i, self)
+    }
+}
+
+impl<'a> ToSql for ProtectedValue<'a> {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_value(i, self)
+    }
+}
+
+impl<'a> ToSql for UnprotectedValue<'a> {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>, i: usize) -> Result<()> {
+        stmt.bind_value(i, self)
+    }
+}
+
+/// A set of values to bind to a statement's host parameters.
+///
+/// `Params` is implemented for:
+///
+/// - `()`, which binds nothing.
+/// - Tuples of up to 12 [`ToSql`] values,
+///   which are bound to host parameters 1 through N.
+/// - Slices and arrays of [`ToSql`] values (like `&[&dyn ToSql]`),
+///   which are bound to host parameters 1 through N.
+/// - Slices and arrays of `(name, value)` pairs,
+///   which are bound by [parameter name][Statement::bind_parameter_index].
+///   The name must include the initial `":"`, `"$"`, `"@"`, or `"?"`.
+pub trait Params {
+    /// Binds the values to the statement.
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()>;
+}
+
+impl Params for () {
+    #[inline]
+    fn bind(self, _: &mut Statement<'_>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<T: ToSql> Params for &[T] {
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        for (i, v) in self.iter().enumerate() {
+            v.bind_to(stmt, i + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: ToSql, const N: usize> Params for &[T; N] {
+    #[inline]
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        self.as_slice().bind(stmt)
+    }
+}
+
+impl<T: ToSql, const N: usize> Params for [T; N] {
+    #[inline]
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        self.as_slice().bind(stmt)
+    }
+}
+
+impl<T: ToSql> Params for &[(&str, T)] {
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        for (name, v) in self {
+            stmt.bind_by_name(name, v)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: ToSql, const N: usize> Params for &[(&str, T); N] {
+    #[inline]
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        self.as_slice().bind(stmt)
+    }
+}
+
+impl<T: ToSql, const N: usize> Params for [(&str, T); N] {
+    #[inline]
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        self.as_slice().bind(stmt)
+    }
+}
+
+macro_rules! impl_params_tuple {
+    ( $($t:ident $i:tt),+ ) => {
+        impl<$($t: ToSql),+> Params for ($($t,)+) {
+            fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+                $(
+                    self.$i.bind_to(stmt, $i + 1)?;
+                )+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_params_tuple!(A 0);
+impl_params_tuple!(A 0, B 1);
+impl_params_tuple!(A 0, B 1, C 2);
+impl_params_tuple!(A 0, B 1, C 2, D 3);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_params_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+/// A record whose fields can be bound to a statement's named host parameters.
+///
+/// Implementations typically bind each field to the parameter `:field_name`
+/// using the [`bind_fields!`] macro.
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{bind_fields, BindStruct, Connection, OpenFlags, Record, Result, Statement};
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// impl BindStruct for User {
+///     fn bind_to(&self, stmt: &mut Statement<'_>) -> Result<()> {
+///         bind_fields!(stmt, self, { id, name });
+///         Ok(())
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);", ())?;
+/// let user = User { id: 1, name: String::from("alice") };
+/// conn.execute("INSERT INTO users VALUES (:id, :name);", Record(&user))?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait BindStruct {
+    /// Binds the record's fields to the statement.
+    fn bind_to(&self, stmt: &mut Statement<'_>) -> Result<()>;
+}
+
+impl<T: BindStruct + ?Sized> BindStruct for &T {
+    #[inline]
+    fn bind_to(&self, stmt: &mut Statement<'_>) -> Result<()> {
+        (**self).bind_to(stmt)
+    }
+}
+
+/// A [`BindStruct`] used as [`Params`].
+#[derive(Clone, Copy, Debug)]
+pub struct Record<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: BindStruct + ?Sized> Params for Record<'a, T> {
+    #[inline]
+    fn bind(self, stmt: &mut Statement<'_>) -> Result<()> {
+        self.0.bind_to(stmt)
+    }
+}
+
+/// Binds fields of a struct to the named host parameters of a statement.
+/// Each field is bound to the parameter with the field's name prefixed by `":"`
+/// using [`Statement::bind_by_name`].
+/// The macro returns early with an error
+/// if the statement does not have a parameter for a field.
+///
+/// See [`BindStruct`] for an example.
+#[macro_export]
+macro_rules! bind_fields {
+    ( $stmt:expr, $record:expr, { $($field:ident),* $(,)? } ) => {
+        $(
+            $stmt.bind_by_name(concat!(":", stringify!($field)), &$record.$field)?;
+        )*
+    };
+}
+
+impl<'c> Statement<'c> {
+    /// Clears the statement's existing bindings
+    /// and binds the fields of `record` to its host parameters.
+    pub fn bind_struct<T: BindStruct + ?Sized>(&mut self, record: &T) -> Result<()> {
+        self.clear_bindings();
+        record.bind_to(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_bind_tuple() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT ?1, ?2, ?3, ?4;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        (42, "foo", Option::<i64>::None, 1.5)
+            .bind(&mut stmt)
+            .unwrap();
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_i64(0), 42);
+        assert_eq!(stmt.column_text(1).unwrap(), "foo");
+        assert_eq!(stmt.column_type(2), DataType::Null);
+        assert_eq!(stmt.column_f64(3), 1.5);
+    }
+
+    #[test]
+    fn test_bind_named() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT :a - :b;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        [(":b", 2), (":a", 5)].bind(&mut stmt).unwrap();
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_i64(0), 3);
+
+        let err = [(":c", 1)].bind(&mut stmt).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::RANGE);
+    }
+
+    struct Point {
+        x: i64,
+        label: Option<String>,
+    }
+
+    impl BindStruct for Point {
+        fn bind_to(&self, stmt: &mut Statement<'_>) -> Result<()> {
+            bind_fields!(stmt, self, { x, label });
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_bind_struct() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE points (x INTEGER, label TEXT);", ())
+            .unwrap();
+        let mut stmt = conn
+            .prepare("INSERT INTO points (label, x) VALUES (:label, :x);")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let points = [
+            Point {
+                x: 1,
+                label: Some(String::from("a")),
+            },
+            Point { x: 2, label: None },
+        ];
+        for p in &points {
+            stmt.bind_struct(p).unwrap();
+            stmt.step().unwrap();
+            stmt.reset().unwrap();
+        }
+        drop(stmt);
+        conn.execute(
+            "INSERT INTO points VALUES (:x, :label);",
+            Record(&Point {
+                x: 3,
+                label: Some(String::from("c")),
+            }),
+        )
+        .unwrap();
+
+        let got = conn
+            .query_stream("SELECT x, label FROM points ORDER BY x;", (), |stmt| {
+                Ok((
+                    stmt.column_i64(0),
+                    match stmt.column_type(1) {
+                        DataType::Null => None,
+                        _ => Some(stmt.column_text(1).unwrap().to_string()),
+                    },
+                ))
+            })
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            got,
+            vec![
+                (1, Some(String::from("a"))),
+                (2, None),
+                (3, Some(String::from("c"))),
+            ]
+        );
+
+        let mut stmt = conn
+            .prepare("SELECT :x;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let err = stmt.bind_struct(&points[0]).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::RANGE);
+    }
+}
@@ -0,0 +1,957 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use crate::*;
+
+/// The [auto-vacuum] mode of a database.
+///
+/// [auto-vacuum]: https://www.sqlite.org/pragma.html#pragma_auto_vacuum
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AutoVacuum {
+    /// Freed pages are kept on the freelist
+    /// and the database file never shrinks until a `VACUUM`.
+    None,
+    /// Freed pages are moved to the end of the database file
+    /// and the file is truncated on every commit.
+    Full,
+    /// Freed pages are kept on the freelist
+    /// until reclaimed with [`Conn::incremental_vacuum`].
+    Incremental,
+}
+
+impl AutoVacuum {
+    fn from_i64(n: i64) -> Option<AutoVacuum> {
+        match n {
+            0 => Some(AutoVacuum::None),
+            1 => Some(AutoVacuum::Full),
+            2 => Some(AutoVacuum::Incremental),
+            _ => None,
+        }
+    }
+
+    fn as_i64(self) -> i64 {
+        match self {
+            AutoVacuum::None => 0,
+            AutoVacuum::Full => 1,
+            AutoVacuum::Incremental => 2,
+        }
+    }
+}
+
+/// The [text encoding] of a database.
+///
+/// [text encoding]: https://www.sqlite.org/pragma.html#pragma_encoding
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// UTF-8.
+    Utf8,
+    /// UTF-16 in little-endian byte order.
+    Utf16le,
+    /// UTF-16 in big-endian byte order.
+    Utf16be,
+}
+
+impl Encoding {
+    /// Returns the encoding with the name used by `PRAGMA encoding`.
+    fn from_name(name: &str) -> Option<Encoding> {
+        match name {
+            "UTF-8" => Some(Encoding::Utf8),
+            "UTF-16le" => Some(Encoding::Utf16le),
+            "UTF-16be" => Some(Encoding::Utf16be),
+            _ => None,
+        }
+    }
+
+    /// Returns the UTF-16 encoding that uses the platform's byte order.
+    pub(crate) fn utf16_native() -> Encoding {
+        if cfg!(target_endian = "little") {
+            Encoding::Utf16le
+        } else {
+            Encoding::Utf16be
+        }
+    }
+}
+
+impl Conn {
+    /// Returns the [text encoding] of the main database.
+    /// Text stored in a UTF-16 database is converted
+    /// when read with [`Statement::column_text`];
+    /// use [`Statement::column_text_raw`] to avoid the conversion.
+    ///
+    /// [text encoding]: https://www.sqlite.org/pragma.html#pragma_encoding
+    pub fn encoding(&self) -> Result<Encoding> {
+        let name = self
+            .query_row("PRAGMA main.encoding;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })?
+            .unwrap_or_default();
+        Encoding::from_name(&name)
+            .ok_or_else(|| Error::new(ResultCode::ERROR, format!("unknown encoding {}", name)))
+    }
+
+    /// Returns the names and number of arguments of the SQL functions
+    /// available on the connection,
+    /// including application-defined functions,
+    /// sorted by name.
+    /// Functions that accept a variable number of arguments
+    /// are reported with an arity of -1.
+    #[doc(alias = "function_list")]
+    pub fn registered_functions(&self) -> Result<Vec<(String, i32)>> {
+        let mut functions = self
+            .query_stream("SELECT name, narg FROM pragma_function_list;", (), |stmt| {
+                Ok((
+                    stmt.column_text(0).to_string_lossy().into_owned(),
+                    stmt.column_i64(1) as i32,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        // Functions are listed once per text encoding they are registered for.
+        functions.sort();
+        functions.dedup();
+        Ok(functions)
+    }
+
+    /// Returns the [auto-vacuum] mode of the main database.
+    ///
+    /// [auto-vacuum]: https://www.sqlite.org/pragma.html#pragma_auto_vacuum
+    pub fn auto_vacuum(&self) -> Result<AutoVacuum> {
+        let n = self
+            .query_row("PRAGMA auto_vacuum;", (), |stmt| Ok(stmt.column_i64(0)))?
+            .unwrap_or_default();
+        AutoVacuum::from_i64(n)
+            .ok_or_else(|| Error::new(ResultCode::ERROR, format!("unknown auto_vacuum mode {}", n)))
+    }
+
+    /// Sets the [auto-vacuum] mode of the main database.
+    /// The mode can only be changed between [`AutoVacuum::None`] and the other modes
+    /// before any tables are created
+    /// or by running `VACUUM` afterward.
+    /// Switching between [`AutoVacuum::Full`] and [`AutoVacuum::Incremental`]
+    /// takes effect immediately.
+    ///
+    /// [auto-vacuum]: https://www.sqlite.org/pragma.html#pragma_auto_vacuum
+    pub fn set_auto_vacuum(&self, mode: AutoVacuum) -> Result<()> {
+        self.execute(&format!("PRAGMA auto_vacuum = {};", mode.as_i64()), ())?;
+        Ok(())
+    }
+
+    /// Returns the [page size] in bytes of the main database.
+    ///
+    /// [page size]: https://www.sqlite.org/pragma.html#pragma_page_size
+    pub fn page_size(&self) -> Result<u32> {
+        let n = self
+            .query_row("PRAGMA page_size;", (), |stmt| Ok(stmt.column_i64(0)))?
+            .unwrap_or_default();
+        Ok(n as u32)
+    }
+
+    /// Requests a [page size] in bytes for the main database.
+    /// This only has an effect before the database is created
+    /// or on the next `VACUUM` outside of WAL mode.
+    /// Use [`ConnectionBuilder::page_size`] to have a mismatch reported as an error.
+    ///
+    /// [page size]: https://www.sqlite.org/pragma.html#pragma_page_size
+    pub(crate) fn set_page_size(&self, n: u32) -> Result<()> {
+        self.execute(&format!("PRAGMA page_size = {};", n), ())?;
+        Ok(())
+    }
+
+    /// Reports whether [recursive triggers] are enabled for the connection.
+    ///
+    /// [recursive triggers]: https://www.sqlite.org/pragma.html#pragma_recursive_triggers
+    pub fn recursive_triggers(&self) -> Result<bool> {
+        let n = self
+            .query_row("PRAGMA recursive_triggers;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })?
+            .unwrap_or_default();
+        Ok(n != 0)
+    }
+
+    /// Enables or disables [recursive triggers] for the connection.
+    /// When disabled (the default),
+    /// a trigger does not fire for changes made by its own body
+    /// or by a trigger it caused to fire.
+    /// Cascading trigger logic that relies on recursion must enable this explicitly.
+    /// Changing the setting causes all prepared statements to be recompiled.
+    ///
+    /// [recursive triggers]: https://www.sqlite.org/pragma.html#pragma_recursive_triggers
+    pub fn set_recursive_triggers(&self, enabled: bool) -> Result<()> {
+        self.execute(
+            if enabled {
+                "PRAGMA recursive_triggers = ON;"
+            } else {
+                "PRAGMA recursive_triggers = OFF;"
+            },
+            (),
+        )?;
+        Ok(())
+    }
+
+    /// Removes up to `pages` pages from the freelist of the main database
+    /// and truncates the database file accordingly,
+    /// or the entire freelist if `pages` is `None`.
+    /// This has no effect unless the database is in [`AutoVacuum::Incremental`] mode.
+    ///
+    /// Unlike `VACUUM`, the incremental vacuum does not rebuild the database,
+    /// so it can be run periodically without holding locks for long.
+    pub fn incremental_vacuum(&self, pages: Option<u32>) -> Result<()> {
+        let sql = match pages {
+            Some(n) => format!("PRAGMA incremental_vacuum({});", n),
+            None => String::from("PRAGMA incremental_vacuum;"),
+        };
+        // Pages are freed as the statement is stepped.
+        for row in self.query_stream(&sql, (), |_| Ok(()))? {
+            row?;
+        }
+        Ok(())
+    }
+
+    /// Runs [`PRAGMA optimize`] on all attached databases,
+    /// which gathers statistics for the query planner
+    /// on tables that are likely to benefit from them.
+    /// Applications should call `optimize` periodically
+    /// or before closing long-lived connections.
+    ///
+    /// If `analysis_limit` is not `None`,
+    /// then each index is analyzed by examining approximately that many rows
+    /// instead of the whole index,
+    /// which keeps `optimize` fast on large tables.
+    /// SQLite recommends a limit of around 400 for routine optimization.
+    /// The connection's previous [analysis limit] is restored afterward.
+    ///
+    /// [`PRAGMA optimize`]: https://www.sqlite.org/pragma.html#pragma_optimize
+    /// [analysis limit]: https://www.sqlite.org/pragma.html#pragma_analysis_limit
+    pub fn optimize(&self, analysis_limit: Option<u32>) -> Result<()> {
+        self.with_analysis_limit(analysis_limit, || {
+            self.execute("PRAGMA optimize;", ())?;
+            Ok(())
+        })
+    }
+
+    /// Runs [`ANALYZE`] on all attached databases,
+    /// unconditionally gathering statistics on every table and index.
+    ///
+    /// If `analysis_limit` is not `None`,
+    /// then each index is analyzed by examining approximately that many rows
+    /// instead of the whole index.
+    /// The connection's previous [analysis limit] is restored afterward.
+    ///
+    /// [`ANALYZE`]: https://www.sqlite.org/lang_analyze.html
+    /// [analysis limit]: https://www.sqlite.org/pragma.html#pragma_analysis_limit
+    pub fn analyze(&self, analysis_limit: Option<u32>) -> Result<()> {
+        self.with_analysis_limit(analysis_limit, || {
+            self.execute("ANALYZE;", ())?;
+            Ok(())
+        })
+    }
+
+    fn with_analysis_limit(
+        &self,
+        analysis_limit: Option<u32>,
+        f: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let Some(limit) = analysis_limit else {
+            return f();
+        };
+        let prev = self
+            .query_row("PRAGMA analysis_limit;", (), |stmt| Ok(stmt.column_i64(0)))?
+            .unwrap_or_default();
+        self.execute(&format!("PRAGMA analysis_limit = {};", limit), ())?;
+        let result = f();
+        let restore_result = self.execute(&format!("PRAGMA analysis_limit = {};", prev), ());
+        result?;
+        restore_result?;
+        Ok(())
+    }
+
+    /// Runs `f` inside a savepoint with [`PRAGMA defer_foreign_keys`] enabled,
+    /// so that foreign key constraints are not checked
+    /// until the outermost transaction commits.
+    /// This allows `f` to temporarily violate foreign key constraints,
+    /// for example by inserting child rows before their parents,
+    /// as long as the constraints are satisfied by the time the transaction commits.
+    ///
+    /// If `f` returns an error, then its changes are rolled back.
+    /// If `defer_foreign_keys` started a new transaction
+    /// and constraints are still violated when it is committed,
+    /// then the transaction is rolled back and a [`ResultCode::CONSTRAINT`] error is returned.
+    /// SQLite turns `defer_foreign_keys` off automatically
+    /// at the end of the outermost transaction.
+    ///
+    /// [`PRAGMA defer_foreign_keys`]: https://www.sqlite.org/pragma.html#pragma_defer_foreign_keys
+    pub fn defer_foreign_keys<T>(&self, f: impl FnOnce(&Conn) -> Result<T>) -> Result<T> {
+        let started_transaction = self.get_autocommit();
+        self.execute("SAVEPOINT defer_foreign_keys;", ())?;
+        let result = self
+            .execute("PRAGMA defer_foreign_keys = ON;", ())
+            .and_then(|_| f(self));
+        if result.is_err() {
+            let _ = self.execute("ROLLBACK TO defer_foreign_keys;", ());
+        }
+        let release_result = self.execute("RELEASE defer_foreign_keys;", ());
+        if release_result.is_err() && started_transaction && !self.get_autocommit() {
+            // A failed commit leaves the transaction open.
+            let _ = self.execute("ROLLBACK;", ());
+        }
+        let value = result?;
+        release_result?;
+        Ok(value)
+    }
+
+    /// Runs the [pragma] `name` as a table-valued function,
+    /// calling `f` for each row it returns.
+    /// `arg` and `schema` are bound as parameters
+    /// to the pragma's argument and schema hidden columns if they are not `None`.
+    /// Only pragmas that return results and have no side effects
+    /// can be run this way,
+    /// and pragmas that do not take an argument or schema
+    /// return an error if `arg` or `schema` is given.
+    ///
+    /// Returns a [`ResultCode::MISUSE`] error if `name` is not a valid pragma name
+    /// (i.e. it does not match `[A-Za-z_][A-Za-z0-9_]*`).
+    ///
+    /// [pragma]: https://www.sqlite.org/pragma.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags, ResultExt};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE foo (id INTEGER PRIMARY KEY, name TEXT);", ())?;
+    /// let columns = conn.pragma_query(None, "table_info", Some("foo"), |stmt| {
+    ///     Ok(stmt.column_text(1).to_string_lossy().into_owned())
+    /// })?;
+    /// assert_eq!(columns, ["id", "name"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pragma_query<T>(
+        &self,
+        schema: Option<&str>,
+        name: &str,
+        arg: Option<&str>,
+        mut f: impl FnMut(&mut Statement<'_>) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        check_pragma_name(name)?;
+        let mut sql = format!("SELECT * FROM pragma_{} WHERE true", name);
+        if arg.is_some() {
+            sql.push_str(" AND arg = ?1");
+        }
+        if schema.is_some() {
+            sql.push_str(" AND schema = ?2");
+        }
+        sql.push(';');
+        let mut stmt = self.prepare_single(&sql)?;
+        if let Some(arg) = arg {
+            stmt.bind_text(1, arg)?;
+        }
+        if let Some(schema) = schema {
+            stmt.bind_text(2, schema)?;
+        }
+        let mut rows = Vec::new();
+        while stmt.step()?.has_row() {
+            rows.push(f(&mut stmt)?);
+        }
+        Ok(rows)
+    }
+
+    /// Sets the [pragma] `name` to `value`.
+    ///
+    /// SQLite does not accept bound parameters in `PRAGMA` statements,
+    /// so `value` is validated instead:
+    /// integers (like `-2000`) and keywords (like `wal` or `ON`)
+    /// are used as-is,
+    /// and any other value is quoted as a string literal.
+    /// Returns a [`ResultCode::MISUSE`] error if `name` is not a valid pragma name
+    /// (i.e. it does not match `[A-Za-z_][A-Za-z0-9_]*`).
+    ///
+    /// [pragma]: https://www.sqlite.org/pragma.html
+    pub fn set_pragma(&self, schema: Option<&str>, name: &str, value: &str) -> Result<()> {
+        check_pragma_name(name)?;
+        let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+        let value = if is_pragma_name(value)
+            || (!digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+        {
+            value.to_string()
+        } else {
+            Quote::as_text(value).to_string()
+        };
+        let sql = match schema {
+            Some(schema) => format!("PRAGMA {}.{} = {};", Quote::as_id(schema), name, value),
+            None => format!("PRAGMA {} = {};", name, value),
+        };
+        // Some pragmas return the new value, so step through any rows.
+        for row in self.query_stream(&sql, (), |_| Ok(()))? {
+            row?;
+        }
+        Ok(())
+    }
+
+    /// Sets each [pragma] in `settings` to its value in order
+    /// as if by [`Conn::set_pragma`],
+    /// returning the name, previous value, and new value of each one
+    /// so that configuration changes can be logged.
+    /// Values are reported as SQLite returns them,
+    /// so for example `synchronous` is reported as a number.
+    ///
+    /// Only pragmas that report a setting when run without an argument,
+    /// like `journal_mode` or `user_version`, are accepted,
+    /// since the previous and new values are read back that way.
+    /// All names are checked before any pragma is set,
+    /// and any other name, such as an action like `optimize` or `wal_checkpoint`,
+    /// results in a [`ResultCode::MISUSE`] error.
+    /// If setting a pragma fails,
+    /// then the pragmas before it remain set.
+    ///
+    /// [pragma]: https://www.sqlite.org/pragma.html
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::ffi::CString;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let conn = Connection::open(CString::new("app.db")?, OpenFlags::default())?;
+    /// let changes = conn.apply_pragmas(&[("journal_mode", "wal"), ("synchronous", "normal")])?;
+    /// for (name, old, new) in changes {
+    ///     println!("{}: {} -> {}", name, old, new);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_pragmas(
+        &self,
+        settings: &[(&str, &str)],
+    ) -> Result<Vec<(String, String, String)>> {
+        for (name, _) in settings {
+            check_pragma_name(name)?;
+            if !is_setting_pragma(name) {
+                return Err(Error::new(
+                    ResultCode::MISUSE,
+                    format!("pragma {} is not a readable setting", name),
+                ));
+            }
+        }
+        let get = |name: &str| -> Result<String> {
+            let value = self.query_row(&format!("PRAGMA {};", name), (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })?;
+            Ok(value.unwrap_or_default())
+        };
+        let mut changes = Vec::with_capacity(settings.len());
+        for &(name, value) in settings {
+            let old = get(name)?;
+            self.set_pragma(None, name, value)?;
+            let new = get(name)?;
+            changes.push((name.to_string(), old, new));
+        }
+        Ok(changes)
+    }
+
+    /// Runs [`PRAGMA integrity_check`] on the main database
+    /// and returns the numbers of the pages that its errors mention,
+    /// sorted and without duplicates.
+    /// An empty result means that no problems were found.
+    /// Repair tools can use the page numbers to localize corruption
+    /// instead of treating the whole database as lost.
+    ///
+    /// An error about a page's contents often mentions the page that refers to it as well,
+    /// so the result can include intact parent pages of a damaged page.
+    /// Problems that integrity_check reports without a page number,
+    /// such as a missing index entry for a row,
+    /// are not reflected in the result.
+    /// Some damage, like an unreadable page header,
+    /// makes integrity_check fail with [`ResultCode::CORRUPT`] partway through:
+    /// the pages reported up to that point are returned,
+    /// or the error if there were none.
+    ///
+    /// [`PRAGMA integrity_check`]: https://www.sqlite.org/pragma.html#pragma_integrity_check
+    pub fn verify_pages(&self) -> Result<Vec<u32>> {
+        let messages = self.query_stream("PRAGMA main.integrity_check(1000000);", (), |stmt| {
+            Ok(stmt.column_text(0).to_string_lossy().into_owned())
+        })?;
+        let mut pages = Vec::new();
+        for msg in messages {
+            match msg {
+                Ok(msg) => pages.extend(page_numbers(&msg)),
+                // Some damage stops integrity_check partway through.
+                Err(err)
+                    if err.result_code().to_primary() == ResultCode::CORRUPT
+                        && !pages.is_empty() =>
+                {
+                    break
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        pages.sort_unstable();
+        pages.dedup();
+        Ok(pages)
+    }
+
+    /// Returns the names of the collating sequences available on the connection,
+    /// including application-defined collations,
+    /// sorted by name.
+    #[doc(alias = "collation_list")]
+    pub fn registered_collations(&self) -> Result<Vec<String>> {
+        let mut collations = self
+            .query_stream("SELECT name FROM pragma_collation_list;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        collations.sort();
+        collations.dedup();
+        Ok(collations)
+    }
+}
+
+/// Returns the numbers that follow the word "page" in an integrity_check message,
+/// like the 3 in "Tree 2 page 3 cell 0: ..." or "Page 3 is never used".
+fn page_numbers(msg: &str) -> impl Iterator<Item = u32> + '_ {
+    let words = msg.split(|c: char| c.is_whitespace() || c == ':' || c == ',');
+    words
+        .clone()
+        .zip(words.skip(1))
+        .filter(|(word, _)| word.eq_ignore_ascii_case("page"))
+        .filter_map(|(_, n)| n.parse().ok())
+}
+
+/// Reports whether `name` matches `[A-Za-z_][A-Za-z0-9_]*`.
+fn is_pragma_name(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    matches!(bytes.next(), Some(b) if b.is_ascii_alphabetic() || b == b'_')
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+fn check_pragma_name(name: &str) -> Result<()> {
+    if is_pragma_name(name) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ResultCode::MISUSE,
+            format!("invalid pragma name {:?}", name),
+        ))
+    }
+}
+
+/// Pragmas that only report information about the database or connection,
+/// with or without an argument.
+/// Their argument, if any, names the object to inspect or limits the output.
+const QUERY_PRAGMAS: &[&str] = &[
+    "collation_list",
+    "compile_options",
+    "data_version",
+    "database_list",
+    "foreign_key_check",
+    "foreign_key_list",
+    "freelist_count",
+    "function_list",
+    "index_info",
+    "index_list",
+    "index_xinfo",
+    "integrity_check",
+    "module_list",
+    "page_count",
+    "pragma_list",
+    "quick_check",
+    "table_info",
+    "table_list",
+    "table_xinfo",
+];
+
+/// Pragmas that report a setting when run without an argument
+/// and change it when given one.
+const SETTING_PRAGMAS: &[&str] = &[
+    "analysis_limit",
+    "application_id",
+    "auto_vacuum",
+    "automatic_index",
+    "busy_timeout",
+    "cache_size",
+    "cache_spill",
+    "cell_size_check",
+    "checkpoint_fullfsync",
+    "defer_foreign_keys",
+    "encoding",
+    "foreign_keys",
+    "fullfsync",
+    "hard_heap_limit",
+    "ignore_check_constraints",
+    "journal_mode",
+    "journal_size_limit",
+    "legacy_alter_table",
+    "locking_mode",
+    "max_page_count",
+    "mmap_size",
+    "page_size",
+    "query_only",
+    "read_uncommitted",
+    "recursive_triggers",
+    "reverse_unordered_selects",
+    "schema_version",
+    "secure_delete",
+    "soft_heap_limit",
+    "synchronous",
+    "temp_store",
+    "threads",
+    "trusted_schema",
+    "user_version",
+    "wal_autocheckpoint",
+];
+
+/// Reports whether running the pragma `name` has no side effects.
+/// `has_arg` is whether the pragma is given an argument,
+/// either as `PRAGMA name = value` or `PRAGMA name(value)`.
+/// Pragmas that are not known to be free of side effects,
+/// like `optimize` or `wal_checkpoint`, return false.
+pub(crate) fn is_read_only_pragma(name: &str, has_arg: bool) -> bool {
+    QUERY_PRAGMAS.iter().any(|p| p.eq_ignore_ascii_case(name))
+        || (!has_arg && is_setting_pragma(name))
+}
+
+/// Reports whether `name` is one of the [`SETTING_PRAGMAS`].
+fn is_setting_pragma(name: &str) -> bool {
+    SETTING_PRAGMAS.iter().any(|p| p.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_registered_functions() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.create_scalar_function(
+            const_cstr!("my_func").as_cstr(),
+            Some(3),
+            FunctionFlags::default(),
+            |mut ctx, _| ctx.result_null(),
+        )
+        .unwrap();
+        let functions = conn.registered_functions().unwrap();
+        assert!(
+            functions.contains(&(String::from("my_func"), 3)),
+            "{:?}",
+            functions
+        );
+        assert!(functions.contains(&(String::from("abs"), 1)));
+
+        let collations = conn.registered_collations().unwrap();
+        assert!(collations.iter().any(|c| c == "NOCASE"), "{:?}", collations);
+    }
+
+    #[test]
+    fn test_incremental_vacuum() {
+        let dir = TempDir::new("incremental-vacuum");
+        let path = dir.join("test.db");
+        let conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        assert_eq!(conn.auto_vacuum().unwrap(), AutoVacuum::None);
+        conn.set_auto_vacuum(AutoVacuum::Incremental).unwrap();
+        assert_eq!(conn.auto_vacuum().unwrap(), AutoVacuum::Incremental);
+
+        let count = |sql: &str| {
+            conn.query_row(sql, (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap()
+                .unwrap()
+        };
+        conn.execute("CREATE TABLE t (x BLOB);", ()).unwrap();
+        conn.execute(
+            "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 500) \
+            INSERT INTO t SELECT zeroblob(4096) FROM c;",
+            (),
+        )
+        .unwrap();
+        conn.execute("DELETE FROM t;", ()).unwrap();
+        let size_before = std::fs::metadata(&path).unwrap().len();
+        let freelist_before = count("PRAGMA freelist_count;");
+        assert!(
+            freelist_before > 100,
+            "freelist_count = {}",
+            freelist_before
+        );
+
+        conn.incremental_vacuum(Some(10)).unwrap();
+        assert_eq!(count("PRAGMA freelist_count;"), freelist_before - 10);
+        conn.incremental_vacuum(None).unwrap();
+        assert_eq!(count("PRAGMA freelist_count;"), 0);
+        let size_after = std::fs::metadata(&path).unwrap().len();
+        assert!(
+            size_after < size_before,
+            "size_after = {}, size_before = {}",
+            size_after,
+            size_before
+        );
+
+        // Database is still usable.
+        assert_eq!(count("SELECT count(*) FROM t;"), 0);
+    }
+
+    #[test]
+    fn test_optimize_analysis_limit() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER, y INTEGER);", ())
+            .unwrap();
+        conn.execute("CREATE INDEX t_y ON t (y);", ()).unwrap();
+        conn.execute(
+            "INSERT INTO t \
+            WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 10000) \
+            SELECT x, x % 100 FROM c;",
+            (),
+        )
+        .unwrap();
+        conn.execute("PRAGMA analysis_limit = 1000;", ()).unwrap();
+        // optimize only analyzes tables that the query planner has used.
+        conn.execute("SELECT count(*) FROM t WHERE y = 5;", ())
+            .unwrap();
+        let stat = || {
+            conn.query_row(
+                "SELECT stat FROM sqlite_stat1 WHERE idx = 't_y';",
+                (),
+                |stmt| Ok(stmt.column_text(0).unwrap().to_string()),
+            )
+            .unwrap()
+        };
+        let limit = || {
+            conn.query_row("PRAGMA analysis_limit;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap()
+        };
+
+        conn.optimize(Some(100)).unwrap();
+        let optimized = stat().expect("sqlite_stat1 has no entry for t_y");
+        assert_eq!(limit(), Some(1000));
+
+        // A complete analysis (a limit of zero) finds exactly 100 rows per value of y.
+        // Sampling 100 rows only estimates that,
+        // so the statistics differ if the limit was applied.
+        conn.analyze(Some(0)).unwrap();
+        let full = stat().unwrap();
+        assert_eq!(full, "10000 100");
+        assert_ne!(optimized, full);
+
+        conn.analyze(Some(100)).unwrap();
+        assert_eq!(stat().unwrap(), optimized);
+        assert_eq!(limit(), Some(1000));
+    }
+
+    #[test]
+    fn test_defer_foreign_keys() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("PRAGMA foreign_keys = ON;", ()).unwrap();
+        conn.execute("CREATE TABLE parent (id INTEGER PRIMARY KEY);", ())
+            .unwrap();
+        conn.execute(
+            "CREATE TABLE child (id INTEGER PRIMARY KEY, \
+            parent_id INTEGER NOT NULL REFERENCES parent (id));",
+            (),
+        )
+        .unwrap();
+
+        let err = conn
+            .execute("INSERT INTO child VALUES (1, 1);", ())
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+
+        let n = conn
+            .defer_foreign_keys(|conn| {
+                let mut n = conn.execute("INSERT INTO child VALUES (1, 1);", ())?;
+                n += conn.execute("INSERT INTO parent VALUES (1);", ())?;
+                Ok(n)
+            })
+            .unwrap();
+        assert_eq!(n, 2);
+        assert!(conn.get_autocommit());
+        let defer = conn
+            .query_row("PRAGMA defer_foreign_keys;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(defer, Some(0));
+
+        let err = conn
+            .defer_foreign_keys(|conn| conn.execute("INSERT INTO child VALUES (2, 2);", ()))
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert!(conn.get_autocommit());
+        let count = conn
+            .query_row("SELECT count(*) FROM child;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(count, Some(1));
+    }
+
+    #[test]
+    fn test_pragma_name_injection() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+
+        for name in [
+            "foo; DROP TABLE t",
+            "table_info(t)",
+            "user_version = 1; DROP TABLE t; --",
+            "",
+            "1abc",
+        ] {
+            let err = conn.pragma_query(None, name, None, |_| Ok(())).unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::MISUSE, "name = {:?}", name);
+            let err = conn.set_pragma(None, name, "1").unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::MISUSE, "name = {:?}", name);
+        }
+
+        conn.set_pragma(None, "user_version", "1; DROP TABLE t")
+            .unwrap();
+        conn.set_pragma(Some("main"), "application_id", "-42")
+            .unwrap();
+        let app_id = conn
+            .pragma_query(None, "application_id", None, |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(app_id, [-42]);
+        let columns = conn
+            .pragma_query(
+                None,
+                "table_info",
+                Some("t'); DROP TABLE t; --"),
+                |_| Ok(()),
+            )
+            .unwrap();
+        assert!(columns.is_empty());
+        let columns = conn
+            .pragma_query(Some("main"), "table_info", Some("t"), |stmt| {
+                Ok(stmt.column_text(1).unwrap().to_string())
+            })
+            .unwrap();
+        assert_eq!(columns, ["x"]);
+    }
+
+    #[test]
+    fn test_recursive_triggers() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE countdown (n INTEGER);", ())
+            .unwrap();
+        conn.execute(
+            "CREATE TRIGGER countdown_next AFTER INSERT ON countdown WHEN new.n > 0 BEGIN \
+            INSERT INTO countdown VALUES (new.n - 1); \
+            END;",
+            (),
+        )
+        .unwrap();
+        let count = |conn: &Conn| {
+            conn.query_row("SELECT count(*) FROM countdown;", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap()
+        };
+
+        assert!(!conn.recursive_triggers().unwrap());
+        conn.execute("INSERT INTO countdown VALUES (5);", ())
+            .unwrap();
+        assert_eq!(count(&conn), Some(2));
+
+        conn.execute("DELETE FROM countdown;", ()).unwrap();
+        conn.set_recursive_triggers(true).unwrap();
+        assert!(conn.recursive_triggers().unwrap());
+        conn.execute("INSERT INTO countdown VALUES (5);", ())
+            .unwrap();
+        assert_eq!(count(&conn), Some(6));
+
+        conn.set_recursive_triggers(false).unwrap();
+        assert!(!conn.recursive_triggers().unwrap());
+    }
+
+    #[test]
+    fn test_apply_pragmas() {
+        let dir = TempDir::new("apply-pragmas");
+        let conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        conn.execute("PRAGMA synchronous = FULL;", ()).unwrap();
+
+        let changes = conn
+            .apply_pragmas(&[("journal_mode", "WAL"), ("synchronous", "NORMAL")])
+            .unwrap();
+        let strings =
+            |name: &str, old: &str, new: &str| (name.to_string(), old.to_string(), new.to_string());
+        assert_eq!(
+            changes,
+            [
+                strings("journal_mode", "delete", "wal"),
+                strings("synchronous", "2", "1"),
+            ]
+        );
+
+        let err = conn
+            .apply_pragmas(&[("synchronous", "OFF"), ("bad name", "1")])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        // Actions are rejected before anything runs.
+        for action in [
+            "wal_checkpoint",
+            "OPTIMIZE",
+            "incremental_vacuum",
+            "integrity_check",
+        ] {
+            let err = conn
+                .apply_pragmas(&[("synchronous", "OFF"), (action, "1")])
+                .unwrap_err();
+            assert_eq!(err.result_code(), ResultCode::MISUSE, "{}", action);
+        }
+        let synchronous = conn
+            .query_row("PRAGMA synchronous;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(synchronous, Some(1));
+    }
+
+    #[test]
+    fn test_verify_pages() {
+        let dir = TempDir::new("verify-pages");
+        let path = dir.join("test.db");
+        let path_cstr = dir.join_cstr("test.db");
+        let page_count = {
+            let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            conn.execute("CREATE TABLE t (x TEXT);", ()).unwrap();
+            conn.execute(
+                "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 200) \
+                INSERT INTO t SELECT printf('%.200c', n) FROM c;",
+                (),
+            )
+            .unwrap();
+            assert_eq!(conn.verify_pages().unwrap(), []);
+            conn.query_row("PRAGMA page_count;", (), |stmt| Ok(stmt.column_i64(0)))
+                .unwrap()
+                .unwrap() as u32
+        };
+        assert!(page_count > 3, "page_count = {}", page_count);
+
+        // Overwrite the cell pointers of the last page, which holds table rows.
+        let page_size = 4096;
+        let mut data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), page_count as usize * page_size);
+        let start = (page_count as usize - 1) * page_size;
+        data[start + 8..start + 12].fill(0xff);
+        std::fs::write(&path, data).unwrap();
+
+        let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        let pages = conn.verify_pages().unwrap();
+        assert!(pages.contains(&page_count), "pages = {:?}", pages);
+        assert!(!pages.contains(&1), "pages = {:?}", pages);
+    }
+
+    #[test]
+    fn test_page_numbers() {
+        let numbers = |msg| page_numbers(msg).collect::<Vec<_>>();
+        assert_eq!(numbers("Tree 2 page 3 cell 0: invalid page number 9"), [3]);
+        assert_eq!(numbers("Page 7 is never used"), [7]);
+        assert_eq!(
+            numbers("Page 5: btreeInitPage() returns error code 11"),
+            [5]
+        );
+        assert_eq!(numbers("2nd reference to page 4"), [4]);
+        assert_eq!(numbers("row 3 missing from index t_x"), Vec::<u32>::new());
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_int, c_void};
+use std::mem;
+use std::ops::ControlFlow;
+use std::ptr;
+
+use libsqlite3_sys::{sqlite3_free, sqlite3_malloc, sqlite3_progress_handler};
+
+use crate::*;
+
+pub(crate) type ProgressHandlerFn = Box<dyn FnMut() -> ControlFlow<()> + 'static>;
+
+impl Connection {
+    /// Register a progress handler callback,
+    /// replacing any previously set callback on the connection.
+    /// The callback is invoked periodically during long running calls to [`Statement::step`],
+    /// approximately every `n_ops` virtual machine instructions.
+    /// If `n_ops` is zero, the callback is never invoked.
+    ///
+    /// If the callback returns [`ControlFlow::Break`],
+    /// then the operation is interrupted
+    /// and fails with [`ResultCode::INTERRUPT`].
+    /// This can be used to implement timeouts or cancellation.
+    #[doc(alias = "sqlite3_progress_handler")]
+    pub fn set_progress_handler(
+        &mut self,
+        n_ops: u32,
+        f: impl FnMut() -> ControlFlow<()> + 'static,
+    ) {
+        const BOX_SIZE: c_int = mem::size_of::<ProgressHandlerFn>() as c_int;
+        let f: ProgressHandlerFn = Box::new(f);
+        let user_data = unsafe {
+            let user_data = sqlite3_malloc(BOX_SIZE).cast::<ProgressHandlerFn>();
+            ptr::write(user_data, f);
+            user_data
+        };
+        let n_ops = c_int::try_from(n_ops).unwrap_or(c_int::MAX);
+        let prev = self.replace_progress_handler(n_ops, user_data);
+        unsafe {
+            free_progress_handler(prev.1);
+        }
+    }
+
+    /// Disable the progress handler.
+    pub fn clear_progress_handler(&mut self) {
+        let prev = self.replace_progress_handler(0, ptr::null_mut());
+        unsafe {
+            free_progress_handler(prev.1);
+        }
+    }
+
+    /// Installs the given handler and returns the previously installed one
+    /// without freeing it.
+    pub(crate) fn replace_progress_handler(
+        &mut self,
+        n_ops: c_int,
+        user_data: *mut ProgressHandlerFn,
+    ) -> (c_int, *mut ProgressHandlerFn) {
+        unsafe {
+            if user_data.is_null() {
+                sqlite3_progress_handler(self.as_ptr(), 0, None, ptr::null_mut());
+            } else {
+                sqlite3_progress_handler(
+                    self.as_ptr(),
+                    n_ops,
+                    Some(progress_handler_callback),
+                    user_data.cast(),
+                );
+            }
+        }
+        let prev = (self.progress_handler_ops, self.progress_handler);
+        self.progress_handler_ops = n_ops;
+        self.progress_handler = user_data;
+        prev
+    }
+}
+
+unsafe extern "C" fn progress_handler_callback(user_data: *mut c_void) -> c_int {
+    let f = user_data.cast::<ProgressHandlerFn>().as_mut().unwrap();
+    match f() {
+        ControlFlow::Continue(()) => 0,
+        ControlFlow::Break(()) => 1,
+    }
+}
+
+pub(crate) unsafe fn free_progress_handler(p: *mut ProgressHandlerFn) {
+    if p.is_null() {
+        return;
+    }
+    ptr::drop_in_place(p);
+    sqlite3_free(p.cast());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_progress_handler_interrupts() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let calls = Rc::new(Cell::new(0usize));
+        {
+            let calls = calls.clone();
+            conn.set_progress_handler(100, move || {
+                calls.set(calls.get() + 1);
+                if calls.get() >= 3 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            });
+        }
+        let mut stmt = conn
+            .prepare(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) \
+                SELECT count(*) FROM c;",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let err = stmt.step().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::INTERRUPT);
+        assert_eq!(calls.get(), 3);
+    }
+}
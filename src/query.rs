@@ -0,0 +1,1082 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::cell::Cell;
+use std::ffi::c_int;
+use std::fmt::{self, Debug};
+use std::iter::FusedIterator;
+use std::ops::ControlFlow;
+use std::ptr;
+use std::rc::Rc;
+use std::sync::mpsc::SyncSender;
+use std::time::{Duration, Instant};
+
+use libsqlite3_sys::{sqlite3_changes, sqlite3_memory_used};
+
+use crate::connection::DbMutexGuard;
+use crate::progress::{free_progress_handler, ProgressHandlerFn};
+use crate::*;
+
+impl Conn {
+    /// Compiles `sql`, which must contain exactly one SQL statement.
+    pub(crate) fn prepare_single<'c>(&'c self, sql: &str) -> Result<Statement<'c>> {
+        let (stmt, tail) = self.prepare(sql);
+        let stmt = stmt?.ok_or_else(|| Error::new(ResultCode::MISUSE, "no SQL statement"))?;
+        if self.prepare(tail).0?.is_some() {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "multiple SQL statements where one was expected",
+            ));
+        }
+        Ok(stmt)
+    }
+
+    /// Compiles `sql` (which must contain exactly one SQL statement),
+    /// binds `params` to it,
+    /// and evaluates it to completion, discarding any rows it returns.
+    /// Returns the number of rows modified, inserted, or deleted
+    /// by the statement if it is an `INSERT`, `UPDATE`, or `DELETE`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE foo (x INTEGER);", ())?;
+    /// let n = conn.execute("INSERT INTO foo VALUES (?1), (?2);", (1, 2))?;
+    /// assert_eq!(n, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "sqlite3_changes")]
+    pub fn execute(&self, sql: &str, params: impl Params) -> Result<usize> {
+        let mut stmt = self.prepare_single(sql)?;
+        params.bind(&mut stmt)?;
+        let _guard = DbMutexGuard::lock(self.as_ptr());
+        while stmt.step()?.has_row() {}
+        Ok(unsafe { sqlite3_changes(self.as_ptr()) } as usize)
+    }
+
+    /// Compiles `sql` (which must contain exactly one SQL statement),
+    /// binds `params` to it,
+    /// and evaluates it until the first row is returned.
+    /// `f` is called with the statement positioned at the first row
+    /// and its return value is returned.
+    /// Returns `Ok(None)` if the statement does not return any rows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let n = conn.query_row("SELECT ?1 + ?2;", (2, 2), |stmt| Ok(stmt.column_i64(0)))?;
+    /// assert_eq!(n, Some(4));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_row<T>(
+        &self,
+        sql: &str,
+        params: impl Params,
+        f: impl FnOnce(&mut Statement<'_>) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let mut stmt = self.prepare_single(sql)?;
+        params.bind(&mut stmt)?;
+        if !stmt.step()?.has_row() {
+            return Ok(None);
+        }
+        f(&mut stmt).map(Some)
+    }
+
+    /// Compiles `sql` (which must contain exactly one SQL statement)
+    /// and binds `params` to it,
+    /// returning an iterator that calls `f` on each row as it is evaluated.
+    ///
+    /// The returned iterator owns the prepared statement,
+    /// so unlike a borrowed cursor,
+    /// it can be returned from a function that only borrows the connection.
+    /// Rows are not buffered:
+    /// each call to [`next`][Iterator::next] steps the statement once.
+    /// After the first error, the iterator returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Conn, Connection, OpenFlags, Result};
+    /// fn numbers(conn: &Conn, n: i64) -> Result<impl Iterator<Item = Result<i64>> + '_> {
+    ///     conn.query_stream(
+    ///         "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < ?1) \
+    ///         SELECT x FROM c;",
+    ///         (n,),
+    ///         |stmt| Ok(stmt.column_i64(0)),
+    ///     )
+    /// }
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let sum = numbers(&conn, 4)?.sum::<Result<i64>>()?;
+    /// assert_eq!(sum, 10);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_stream<'c, T, F>(
+        &'c self,
+        sql: &str,
+        params: impl Params,
+        f: F,
+    ) -> Result<QueryStream<'c, F>>
+    where
+        F: FnMut(&mut Statement<'c>) -> Result<T>,
+    {
+        let mut stmt = self.prepare_single(sql)?;
+        params.bind(&mut stmt)?;
+        Ok(QueryStream {
+            stmt,
+            f,
+            done: false,
+        })
+    }
+}
+
+impl Conn {
+    /// Compiles each of `queries` without evaluating them
+    /// so that the database schema is loaded and parsed
+    /// before the connection serves its first request.
+    /// Each query may contain several statements.
+    /// Application-defined functions and collations used by the queries
+    /// must be registered beforehand.
+    ///
+    /// Returns the first compilation error encountered,
+    /// which makes `warmup` useful for verifying at startup
+    /// that an application's queries are valid for the current schema.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);", ())?;
+    /// conn.warmup(&[
+    ///     "SELECT name FROM users WHERE id = ?1;",
+    ///     "INSERT INTO users (name) VALUES (?1);",
+    /// ])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warmup(&self, queries: &[&str]) -> Result<()> {
+        for sql in queries {
+            let mut tail = *sql;
+            loop {
+                let (stmt, rest) = self.prepare(tail);
+                if stmt?.is_none() {
+                    break;
+                }
+                tail = rest;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports whether any statement in the SQL script `sql` would write to the database,
+    /// as determined by [`Statement::is_readonly`].
+    /// The statements are compiled but not run,
+    /// so a connection pool can use this to decide
+    /// whether to send a query to its write connection or to a reader.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// conn.execute("CREATE TABLE foo (x INTEGER);", ())?;
+    /// assert!(!conn.query_is_write("SELECT x FROM foo;")?);
+    /// assert!(conn.query_is_write("SELECT 1; DELETE FROM foo;")?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_is_write(&self, sql: &str) -> Result<bool> {
+        let mut tail = sql;
+        loop {
+            let (stmt, rest) = self.prepare(tail);
+            match stmt? {
+                Some(stmt) if !stmt.is_readonly() => return Ok(true),
+                Some(_) => tail = rest,
+                None => return Ok(false),
+            }
+        }
+    }
+
+    /// Runs the query `sql` with `params` on the current thread
+    /// and sends each row of values to `sender`.
+    /// Since the channel is bounded,
+    /// sending blocks while the channel is full,
+    /// which keeps a fast query from getting ahead of a slow consumer
+    /// and buffering an unbounded number of rows in memory.
+    ///
+    /// If the receiver is dropped, the query stops at the next row
+    /// and `query_to_channel` returns `Ok(())`.
+    /// If the query fails, the error is sent to the receiver and also returned.
+    /// `sender` is dropped when the query finishes,
+    /// so a receiver with no other senders sees the end of the rows.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use std::sync::mpsc;
+    /// # use std::thread;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let (tx, rx) = mpsc::sync_channel(16);
+    /// let producer = thread::spawn(move || {
+    ///     let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    ///     conn.query_to_channel("SELECT 1 UNION ALL SELECT 2;", (), tx)
+    /// });
+    /// let mut sum = 0;
+    /// for row in rx {
+    ///     sum += row?[0].as_mut().to_i64();
+    /// }
+    /// producer.join().unwrap()?;
+    /// assert_eq!(sum, 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_to_channel(
+        &self,
+        sql: &str,
+        params: impl Params,
+        sender: SyncSender<Result<Vec<DupValue>>>,
+    ) -> Result<()> {
+        let result = self.prepare_single(sql).and_then(|mut stmt| {
+            params.bind(&mut stmt)?;
+            while stmt.step()?.has_row() {
+                let row = (0..stmt.column_count())
+                    .map(|i| stmt.column_value(i).dup())
+                    .collect();
+                if sender.send(Ok(row)).is_err() {
+                    // The receiver hung up.
+                    break;
+                }
+            }
+            Ok(())
+        });
+        if let Err(err) = &result {
+            let _ = sender.send(Err(err.clone()));
+        }
+        result
+    }
+
+    /// Evaluates each statement in the SQL script `sql` in order,
+    /// returning a cursor that yields one [`ResultSet`] per statement.
+    /// Empty statements (like comments or extra semicolons) are skipped.
+    /// `params` provides the parameters for each statement in turn:
+    /// the first item is bound to the first statement, the second to the second, and so on.
+    /// Statements after `params` runs out have no parameters bound.
+    ///
+    /// Rows are evaluated as the result set is iterated.
+    /// Advancing to the next result set evaluates the rest of the current statement,
+    /// so statements are run in order
+    /// regardless of whether their rows are used.
+    /// After the first error, [`QueryMulti::next_result_set`] returns `None`
+    /// and the rest of the script is not evaluated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags, Result};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let mut results = conn.query_multi(
+    ///     "SELECT ?1 AS a; SELECT ?1 AS b, ?2 AS c;",
+    ///     [&[1][..], &[2, 3][..]],
+    /// );
+    ///
+    /// let first = results.next_result_set()?.expect("two statements");
+    /// assert_eq!(first.columns(), ["a"]);
+    /// assert_eq!(first.count(), 1);
+    ///
+    /// let mut second = results.next_result_set()?.expect("two statements");
+    /// assert_eq!(second.columns(), ["b", "c"]);
+    /// let mut row = second.next().expect("one row")?;
+    /// assert_eq!(row[1].as_mut().to_i64(), 3);
+    ///
+    /// assert!(results.next_result_set()?.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_multi<'c, 's, I>(
+        &'c self,
+        sql: &'s str,
+        params: I,
+    ) -> QueryMulti<'c, 's, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Params,
+    {
+        QueryMulti {
+            conn: self,
+            tail: sql,
+            params: params.into_iter(),
+            stmt: None,
+            state: RowState::Done,
+        }
+    }
+}
+
+impl Connection {
+    /// Same as [`Conn::query_row`], but interrupts the statement
+    /// with [`ResultCode::INTERRUPT`]
+    /// if it does not finish within the given `timeout`.
+    ///
+    /// The deadline is enforced with a [progress handler][Connection::set_progress_handler]
+    /// that is only installed for the duration of the call.
+    /// Any previously set progress handler is restored afterward.
+    pub fn query_row_timeout<T>(
+        &mut self,
+        sql: &str,
+        params: impl Params,
+        timeout: Duration,
+        f: impl FnOnce(&mut Statement<'_>) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let deadline = Instant::now() + timeout;
+        self.query_row_with_progress_handler(sql, params, f, move || {
+            if Instant::now() < deadline {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        })
+    }
+
+    /// Same as [`Conn::query_row`], but interrupts the statement
+    /// with [`ResultCode::INTERRUPT`]
+    /// if SQLite's process-wide heap usage grows by more than `max_bytes` while it runs.
+    /// This protects against queries from untrusted input
+    /// that build enormous intermediate results.
+    ///
+    /// The limit is not specific to the statement or to the connection.
+    /// [`Statement::memory_used`] only covers the compiled statement,
+    /// so growth is measured with SQLite's process-wide memory counter
+    /// ([`sqlite3_memory_used`]).
+    /// While the statement runs, allocations by every connection in every thread
+    /// count against the limit and can cause the statement to be interrupted,
+    /// and memory freed by other threads offsets the statement's own growth.
+    /// Only use this where the statement is the main user of SQLite in the process.
+    ///
+    /// The limit is also best-effort.
+    /// As with [`query_row_timeout`][Connection::query_row_timeout],
+    /// it is enforced with a temporary progress handler
+    /// that only runs every so many virtual machine instructions,
+    /// so a statement can overshoot the limit before it is interrupted,
+    /// and a single large allocation is not prevented.
+    /// `Conn::with_memory_limit` sets a hard limit instead,
+    /// which is also process-wide.
+    ///
+    /// [`sqlite3_memory_used`]: https://www.sqlite.org/c3ref/memory_highwater.html
+    #[doc(alias = "sqlite3_memory_used")]
+    pub fn query_row_memory_limit<T>(
+        &mut self,
+        sql: &str,
+        params: impl Params,
+        max_bytes: usize,
+        f: impl FnOnce(&mut Statement<'_>) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let max_bytes = i64::try_from(max_bytes).unwrap_or(i64::MAX);
+        let baseline = unsafe { sqlite3_memory_used() };
+        let exceeded = Rc::new(Cell::new(false));
+        let result = {
+            let exceeded = exceeded.clone();
+            self.query_row_with_progress_handler(sql, params, f, move || {
+                let used = unsafe { sqlite3_memory_used() }.saturating_sub(baseline);
+                if used <= max_bytes {
+                    ControlFlow::Continue(())
+                } else {
+                    exceeded.set(true);
+                    ControlFlow::Break(())
+                }
+            })
+        };
+        match result {
+            Err(err) if exceeded.get() && err.result_code() == ResultCode::INTERRUPT => {
+                Err(Error::new(
+                    ResultCode::INTERRUPT,
+                    format!("SQLite heap grew past memory limit of {} bytes", max_bytes),
+                ))
+            }
+            result => result,
+        }
+    }
+
+    /// Runs [`Conn::query_row`] with `handler` installed as the progress handler,
+    /// restoring the previous progress handler afterward.
+    fn query_row_with_progress_handler<T>(
+        &mut self,
+        sql: &str,
+        params: impl Params,
+        f: impl FnOnce(&mut Statement<'_>) -> Result<T>,
+        handler: impl FnMut() -> ControlFlow<()> + 'static,
+    ) -> Result<Option<T>> {
+        let prev = self.replace_progress_handler(0, ptr::null_mut());
+        let guard = RestoreProgressHandler { conn: self, prev };
+        guard.conn.set_progress_handler(CHECK_OPS, handler);
+        guard.conn.as_ref().query_row(sql, params, f)
+    }
+}
+
+/// An iterator over the rows of a statement
+/// returned by [`Conn::query_stream`].
+pub struct QueryStream<'c, F> {
+    stmt: Statement<'c>,
+    f: F,
+    done: bool,
+}
+
+impl<'c, F> QueryStream<'c, F> {
+    /// Returns the underlying statement.
+    #[inline]
+    pub fn statement(&self) -> &Statement<'c> {
+        &self.stmt
+    }
+}
+
+impl<'c, F> Debug for QueryStream<'c, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryStream")
+            .field("stmt", &self.stmt)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'c, T, F> Iterator for QueryStream<'c, F>
+where
+    F: FnMut(&mut Statement<'c>) -> Result<T>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = match self.stmt.step() {
+            Ok(StepResult::Row) => (self.f)(&mut self.stmt),
+            Ok(StepResult::Done) => {
+                self.done = true;
+                return None;
+            }
+            Err(err) => Err(err),
+        };
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+impl<'c, T, F> FusedIterator for QueryStream<'c, F> where F: FnMut(&mut Statement<'c>) -> Result<T> {}
+
+/// A cursor over the result sets of a SQL script
+/// returned by [`Conn::query_multi`].
+pub struct QueryMulti<'c, 's, I> {
+    conn: &'c Conn,
+    tail: &'s str,
+    params: I,
+    stmt: Option<Statement<'c>>,
+    state: RowState,
+}
+
+/// How far the current statement of a [`QueryMulti`] has been evaluated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RowState {
+    Pending,
+    Done,
+    Failed,
+}
+
+impl<'c, 's, I> QueryMulti<'c, 's, I>
+where
+    I: Iterator,
+    I::Item: Params,
+{
+    /// Advances to the next statement in the script,
+    /// first evaluating the rest of the current statement.
+    /// Returns `None` at the end of the script
+    /// or once a statement has failed.
+    pub fn next_result_set(&mut self) -> Result<Option<ResultSet<'_, 'c>>> {
+        match self.advance() {
+            Ok(true) => Ok(Some(ResultSet {
+                stmt: self.stmt.as_mut().unwrap(),
+                state: &mut self.state,
+            })),
+            Ok(false) => Ok(None),
+            Err(err) => {
+                self.stmt = None;
+                self.tail = "";
+                Err(err)
+            }
+        }
+    }
+
+    fn advance(&mut self) -> Result<bool> {
+        if let Some(stmt) = &mut self.stmt {
+            match self.state {
+                RowState::Pending => while stmt.step()?.has_row() {},
+                RowState::Done => {}
+                RowState::Failed => self.tail = "",
+            }
+        }
+        self.stmt = None;
+        while !self.tail.is_empty() {
+            let (stmt, tail) = self.conn.prepare(self.tail);
+            self.tail = tail;
+            let Some(mut stmt) = stmt? else {
+                continue;
+            };
+            if let Some(params) = self.params.next() {
+                params.bind(&mut stmt)?;
+            }
+            self.stmt = Some(stmt);
+            self.state = RowState::Pending;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+impl<'c, 's, I> Debug for QueryMulti<'c, 's, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryMulti")
+            .field("tail", &self.tail)
+            .field("stmt", &self.stmt)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An iterator over the rows produced by a single statement in a SQL script,
+/// returned by [`QueryMulti::next_result_set`].
+/// Each row has one value per column.
+/// Statements that do not return data
+/// (like `INSERT` without a `RETURNING` clause)
+/// produce a result set with no columns and no rows.
+pub struct ResultSet<'q, 'c> {
+    stmt: &'q mut Statement<'c>,
+    state: &'q mut RowState,
+}
+
+impl<'q, 'c> ResultSet<'q, 'c> {
+    /// Returns the names of the result set's columns.
+    pub fn columns(&self) -> Vec<String> {
+        (0..self.stmt.column_count())
+            .map(|i| self.stmt.column_name(i).unwrap_or_default())
+            .collect()
+    }
+
+    /// Returns the statement that produces the result set.
+    #[inline]
+    pub fn statement(&self) -> &Statement<'c> {
+        self.stmt
+    }
+}
+
+impl<'q, 'c> Debug for ResultSet<'q, 'c> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResultSet")
+            .field("stmt", &self.stmt)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'q, 'c> Iterator for ResultSet<'q, 'c> {
+    type Item = Result<Vec<DupValue>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if *self.state != RowState::Pending {
+            return None;
+        }
+        match self.stmt.step() {
+            Ok(StepResult::Row) => Some(Ok((0..self.stmt.column_count())
+                .map(|i| self.stmt.column_value(i).dup())
+                .collect())),
+            Ok(StepResult::Done) => {
+                *self.state = RowState::Done;
+                None
+            }
+            Err(err) => {
+                *self.state = RowState::Failed;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<'q, 'c> FusedIterator for ResultSet<'q, 'c> {}
+
+/// The number of virtual machine instructions between deadline or memory checks.
+const CHECK_OPS: u32 = 1000;
+
+/// Reinstalls a progress handler on drop,
+/// freeing whichever handler was installed in the meantime.
+struct RestoreProgressHandler<'a> {
+    conn: &'a mut Connection,
+    prev: (c_int, *mut ProgressHandlerFn),
+}
+
+impl<'a> Drop for RestoreProgressHandler<'a> {
+    fn drop(&mut self) {
+        let (n_ops, user_data) = self.prev;
+        let temp = self.conn.replace_progress_handler(n_ops, user_data);
+        unsafe {
+            free_progress_handler(temp.1);
+        }
+    }
+}
+
+impl<'c> Statement<'c> {
+    /// Returns an iterator that evaluates the statement,
+    /// calling `f` for each row it returns,
+    /// and fails once the statement returns more than `max` rows.
+    /// This guards against queries that unexpectedly return huge result sets
+    /// (for example, because of a missing `WHERE` clause)
+    /// regardless of whether the SQL has a `LIMIT` clause.
+    ///
+    /// The first `max` rows are returned as usual.
+    /// If the statement has another row after that,
+    /// then the iterator returns a [`ResultCode::TOOBIG`] error,
+    /// [`LimitedRows::is_truncated`] returns `true`,
+    /// and the iterator returns `None` afterward.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags, ResultCode};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let mut stmt = conn.prepare("VALUES (1), (2), (3);").0?.unwrap();
+    /// let mut rows = stmt.limited_rows(2, |stmt| Ok(stmt.column_i64(0)));
+    /// assert_eq!(rows.next().transpose()?, Some(1));
+    /// assert_eq!(rows.next().transpose()?, Some(2));
+    /// let err = rows.next().unwrap().unwrap_err();
+    /// assert_eq!(err.result_code(), ResultCode::TOOBIG);
+    /// assert!(rows.is_truncated());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn limited_rows<T, F>(&mut self, max: usize, f: F) -> LimitedRows<'_, 'c, F>
+    where
+        F: FnMut(&mut Statement<'c>) -> Result<T>,
+    {
+        LimitedRows {
+            stmt: self,
+            f,
+            remaining: max,
+            max,
+            done: false,
+            truncated: false,
+        }
+    }
+}
+
+/// An iterator over at most a fixed number of rows of a statement
+/// returned by [`Statement::limited_rows`].
+pub struct LimitedRows<'s, 'c, F> {
+    stmt: &'s mut Statement<'c>,
+    f: F,
+    remaining: usize,
+    max: usize,
+    done: bool,
+    truncated: bool,
+}
+
+impl<'s, 'c, F> LimitedRows<'s, 'c, F> {
+    /// Reports whether the statement returned more rows than the limit.
+    #[inline]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl<'s, 'c, F> Debug for LimitedRows<'s, 'c, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LimitedRows")
+            .field("stmt", &self.stmt)
+            .field("remaining", &self.remaining)
+            .field("max", &self.max)
+            .field("done", &self.done)
+            .field("truncated", &self.truncated)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'s, 'c, T, F> Iterator for LimitedRows<'s, 'c, F>
+where
+    F: FnMut(&mut Statement<'c>) -> Result<T>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = match self.stmt.step() {
+            Ok(StepResult::Done) => {
+                self.done = true;
+                return None;
+            }
+            Ok(StepResult::Row) if self.remaining == 0 => {
+                self.truncated = true;
+                Err(Error::new(
+                    ResultCode::TOOBIG,
+                    format!("query returned more than {} rows", self.max),
+                ))
+            }
+            Ok(StepResult::Row) => {
+                self.remaining -= 1;
+                (self.f)(self.stmt)
+            }
+            Err(err) => Err(err),
+        };
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+impl<'s, 'c, T, F> FusedIterator for LimitedRows<'s, 'c, F> where
+    F: FnMut(&mut Statement<'c>) -> Result<T>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_query_row() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let got = conn
+            .query_row("SELECT ?1 || 'bar';", ("foo",), |stmt| {
+                Ok(stmt.column_text(0).unwrap().to_string())
+            })
+            .unwrap();
+        assert_eq!(got.as_deref(), Some("foobar"));
+
+        let got = conn
+            .query_row("SELECT 1 WHERE false;", (), |_| Ok(()))
+            .unwrap();
+        assert_eq!(got, None);
+
+        let err = conn
+            .query_row("SELECT 1; SELECT 2;", (), |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+    }
+
+    fn names(conn: &Conn) -> Result<impl Iterator<Item = Result<String>> + '_> {
+        conn.query_stream(
+            "WITH t(name) AS (VALUES ('alice'), ('bob'), ('carol')) SELECT name FROM t;",
+            (),
+            |stmt| Ok(stmt.column_text(0).unwrap().to_string()),
+        )
+    }
+
+    #[test]
+    fn test_query_stream_returned_from_function() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let got = names(&conn).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(got, vec!["alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn test_query_row_timeout() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let prev_calls = Rc::new(Cell::new(0usize));
+        {
+            let prev_calls = prev_calls.clone();
+            conn.set_progress_handler(1, move || {
+                prev_calls.set(prev_calls.get() + 1);
+                ControlFlow::Continue(())
+            });
+        }
+
+        let err = conn
+            .query_row_timeout(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) \
+                SELECT count(*) FROM c;",
+                (),
+                Duration::from_millis(50),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::INTERRUPT);
+        assert_eq!(prev_calls.get(), 0);
+
+        let got = conn
+            .query_row_timeout("SELECT ?1 * 2;", (21,), Duration::from_secs(60), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap();
+        assert_eq!(got, Some(42));
+        assert_eq!(prev_calls.get(), 0);
+
+        // Previous handler should be restored.
+        conn.execute("SELECT 1;", ()).unwrap();
+        assert!(prev_calls.get() > 0);
+    }
+
+    #[test]
+    fn test_query_multi() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut results = conn.query_multi(
+            "CREATE TABLE t (x INTEGER, y TEXT); \
+            -- comment\n; \
+            INSERT INTO t VALUES (1, :name), (2, 'b') RETURNING x; \
+            SELECT y, x * 10 AS z, :name AS n FROM t ORDER BY x;",
+            [&[][..], &[(":name", "a")][..], &[(":name", "c")][..]],
+        );
+
+        let mut result_set = results.next_result_set().unwrap().unwrap();
+        assert!(result_set.columns().is_empty());
+        assert!(result_set.next().is_none());
+
+        let result_set = results.next_result_set().unwrap().unwrap();
+        assert_eq!(result_set.columns(), ["x"]);
+        let xs: Vec<i64> = result_set
+            .map(|row| row.unwrap()[0].as_mut().to_i64())
+            .collect();
+        assert_eq!(xs, [1, 2]);
+
+        let result_set = results.next_result_set().unwrap().unwrap();
+        assert_eq!(result_set.columns(), ["y", "z", "n"]);
+        let rows: Vec<(String, i64, String)> = result_set
+            .map(|row| {
+                let mut row = row.unwrap();
+                (
+                    row[0].as_mut().to_text().unwrap().to_string(),
+                    row[1].as_mut().to_i64(),
+                    row[2].as_mut().to_text().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (String::from("a"), 10, String::from("c")),
+                (String::from("b"), 20, String::from("c")),
+            ]
+        );
+        assert!(results.next_result_set().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_multi_lazy() {
+        let calls = Rc::new(Cell::new(0));
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        {
+            let calls = calls.clone();
+            conn.create_scalar_function(
+                const_cstr!("count_call").as_cstr(),
+                Some(0),
+                FunctionFlags::default(),
+                move |mut ctx, _| {
+                    calls.set(calls.get() + 1);
+                    ctx.result_i64(calls.get());
+                },
+            )
+            .unwrap();
+        }
+        let mut results = conn.query_multi(
+            "SELECT count_call() FROM (VALUES (1), (2), (3)); SELECT 42;",
+            std::iter::empty::<()>(),
+        );
+
+        // Only the rows that have been read are evaluated.
+        let mut result_set = results.next_result_set().unwrap().unwrap();
+        let mut row = result_set.next().unwrap().unwrap();
+        assert_eq!(row[0].as_mut().to_i64(), 1);
+        assert_eq!(calls.get(), 1);
+
+        // Advancing finishes the previous statement.
+        let mut result_set = results.next_result_set().unwrap().unwrap();
+        assert_eq!(calls.get(), 3);
+        let mut row = result_set.next().unwrap().unwrap();
+        assert_eq!(row[0].as_mut().to_i64(), 42);
+    }
+
+    #[test]
+    fn test_query_multi_error_stops() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut results = conn.query_multi(
+            "CREATE TABLE t (x); SELECT * FROM nosuchtable; INSERT INTO t VALUES (1);",
+            std::iter::empty::<()>(),
+        );
+        assert!(results.next_result_set().is_ok());
+        assert!(results.next_result_set().is_err());
+        assert!(results.next_result_set().unwrap().is_none());
+        let n = conn
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(n, Some(0));
+
+        // Errors while reading rows also stop the script.
+        let mut results = conn.query_multi(
+            "SELECT abs(-9223372036854775807 - 1); INSERT INTO t VALUES (1);",
+            std::iter::empty::<()>(),
+        );
+        let mut result_set = results.next_result_set().unwrap().unwrap();
+        assert!(result_set.next().unwrap().is_err());
+        assert!(result_set.next().is_none());
+        assert!(results.next_result_set().unwrap().is_none());
+        let n = conn
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(n, Some(0));
+    }
+
+    #[test]
+    fn test_limited_rows() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare(
+                "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000) \
+                SELECT x FROM c;",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+
+        let mut rows = stmt.limited_rows(100, |stmt| Ok(stmt.column_i64(0)));
+        let got = rows
+            .by_ref()
+            .take_while(Result::is_ok)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(got, (1..=100).collect::<Vec<_>>());
+        assert!(rows.is_truncated());
+        assert!(rows.next().is_none());
+
+        stmt.reset().unwrap();
+        let mut rows = stmt.limited_rows(100, |stmt| Ok(stmt.column_i64(0)));
+        let err = rows.by_ref().collect::<Result<Vec<_>>>().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::TOOBIG);
+        assert_eq!(err.message(), "query returned more than 100 rows");
+
+        stmt.reset().unwrap();
+        let mut rows = stmt.limited_rows(1000, |stmt| Ok(stmt.column_i64(0)));
+        assert_eq!(rows.by_ref().count(), 1000);
+        assert!(!rows.is_truncated());
+    }
+
+    #[test]
+    fn test_warmup() {
+        let dir = TempDir::new("warmup");
+        let path_cstr = dir.join_cstr("test.db");
+        {
+            let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            for i in 0..20 {
+                conn.execute(
+                    &format!("CREATE TABLE t{i} (id INTEGER PRIMARY KEY, x TEXT, y REAL);"),
+                    (),
+                )
+                .unwrap();
+                conn.execute(&format!("CREATE INDEX t{i}_x ON t{i} (x);"), ())
+                    .unwrap();
+            }
+        }
+
+        let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        let (schema_before, _) = conn.db_status(DbStatus::SchemaUsed, false).unwrap();
+        conn.warmup(&[
+            "SELECT y FROM t3 WHERE x = ?1;",
+            "UPDATE t7 SET y = 1; SELECT 1;",
+        ])
+        .unwrap();
+        let (schema_after, _) = conn.db_status(DbStatus::SchemaUsed, false).unwrap();
+        assert!(
+            schema_after > schema_before,
+            "schema memory {} -> {}",
+            schema_before,
+            schema_after
+        );
+        // Nothing was executed.
+        let n = conn
+            .query_row(
+                "SELECT count(*) FROM t7;",
+                (),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap();
+        assert_eq!(n, Some(0));
+
+        let err = conn.warmup(&["SELECT nope FROM t1;"]).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+
+    #[test]
+    fn test_query_is_write() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        assert!(!conn.query_is_write("SELECT x FROM t;").unwrap());
+        assert!(!conn.query_is_write("").unwrap());
+        assert!(conn.query_is_write("UPDATE t SET x = 1;").unwrap());
+        assert!(conn.query_is_write("INSERT INTO t VALUES (1);").unwrap());
+        assert!(conn
+            .query_is_write("SELECT 1; -- comment\nDELETE FROM t;")
+            .unwrap());
+        assert!(conn.query_is_write("CREATE TABLE u (y);").unwrap());
+
+        // Nothing was executed.
+        let n = conn
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(n, Some(0));
+        assert!(conn.query_is_write("SELECT * FROM u;").is_err());
+    }
+
+    #[test]
+    fn test_query_to_channel() {
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let start = Instant::now();
+        let producer = std::thread::spawn(move || {
+            let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+            // Never ends on its own.
+            conn.query_to_channel(
+                "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c) \
+                SELECT n FROM c;",
+                (),
+                tx,
+            )
+        });
+        let got = rx
+            .iter()
+            .take(5)
+            .map(|row| row.unwrap()[0].as_mut().to_i64())
+            .collect::<Vec<_>>();
+        assert_eq!(got, [1, 2, 3, 4, 5]);
+        drop(rx);
+        producer.join().unwrap().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        let producer = std::thread::spawn(move || {
+            let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+            conn.query_to_channel("SELECT * FROM nope;", (), tx)
+        });
+        let err = rx.recv().unwrap().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(rx.recv().is_err());
+        producer.join().unwrap().unwrap_err();
+    }
+}
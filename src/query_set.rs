@@ -0,0 +1,159 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+use crate::*;
+
+impl Conn {
+    /// Prepares a fixed set of SQL statements up front,
+    /// each identified by a key.
+    /// Each SQL string must contain exactly one SQL statement.
+    /// If any statement fails to compile,
+    /// then the error is returned and no statements are kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    /// enum Query {
+    ///     Double,
+    ///     Greet,
+    /// }
+    ///
+    /// let mut queries = conn.prepare_set([
+    ///     (Query::Double, "SELECT ?1 * 2;"),
+    ///     (Query::Greet, "SELECT 'Hello, ' || ?1;"),
+    /// ])?;
+    /// let stmt = queries.get(&Query::Double).expect("query was prepared");
+    /// stmt.bind_i64(1, 21)?;
+    /// stmt.step()?;
+    /// assert_eq!(stmt.column_i64(0), 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prepare_set<'c, K, S>(
+        &'c self,
+        queries: impl IntoIterator<Item = (K, S)>,
+    ) -> Result<QuerySet<'c, K>>
+    where
+        K: Hash + Eq,
+        S: AsRef<str>,
+    {
+        let statements = queries
+            .into_iter()
+            .map(|(key, sql)| Ok((key, self.prepare_single(sql.as_ref())?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        Ok(QuerySet { statements })
+    }
+}
+
+/// A set of prepared statements identified by keys,
+/// returned by [`Conn::prepare_set`].
+pub struct QuerySet<'c, K> {
+    statements: HashMap<K, Statement<'c>>,
+}
+
+impl<'c, K: Hash + Eq> QuerySet<'c, K> {
+    /// Returns the statement for the given key,
+    /// [reset][Statement::reset] so that it is ready to be evaluated,
+    /// or `None` if `key` was not passed to [`Conn::prepare_set`].
+    /// Previously bound parameters are kept.
+    pub fn get(&mut self, key: &K) -> Option<&mut Statement<'c>> {
+        let stmt = self.statements.get_mut(key)?;
+        // Any error from the previous evaluation was already returned by step.
+        let _ = stmt.reset();
+        Some(stmt)
+    }
+
+    /// Reports whether the set has a statement for the given key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.statements.contains_key(key)
+    }
+
+    /// Returns the number of statements in the set.
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    /// Reports whether the set has no statements.
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+impl<'c, K: Debug> Debug for QuerySet<'c, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.statements.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    enum Query {
+        Insert,
+        Count,
+        Names,
+        Delete,
+    }
+
+    #[test]
+    fn test_query_set() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE people (name TEXT);", ())
+            .unwrap();
+        let mut queries = conn
+            .prepare_set([
+                (Query::Insert, "INSERT INTO people (name) VALUES (?1);"),
+                (Query::Count, "SELECT count(*) FROM people;"),
+                (Query::Names, "SELECT name FROM people ORDER BY name;"),
+            ])
+            .unwrap();
+        assert_eq!(queries.len(), 3);
+        assert!(queries.contains_key(&Query::Names));
+        assert!(!queries.contains_key(&Query::Delete));
+        assert!(queries.get(&Query::Delete).is_none());
+
+        for name in ["carol", "alice", "bob"] {
+            let stmt = queries.get(&Query::Insert).unwrap();
+            stmt.bind_text(1, name).unwrap();
+            assert!(!stmt.step().unwrap().has_row());
+        }
+
+        let stmt = queries.get(&Query::Count).unwrap();
+        assert!(stmt.step().unwrap().has_row());
+        assert_eq!(stmt.column_i64(0), 3);
+
+        // Leave the statement mid-evaluation to check that get resets it.
+        let stmt = queries.get(&Query::Names).unwrap();
+        assert!(stmt.step().unwrap().has_row());
+        let stmt = queries.get(&Query::Names).unwrap();
+        let mut names = Vec::new();
+        while stmt.step().unwrap().has_row() {
+            names.push(stmt.column_text(0).unwrap().to_string());
+        }
+        assert_eq!(names, ["alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn test_query_set_error() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let err = conn
+            .prepare_set([(1, "SELECT 1;"), (2, "SELECT * FROM nosuchtable;")])
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+}
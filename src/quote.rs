@@ -1,7 +1,14 @@
 // Copyright 2023 Ross Light
 // SPDX-License-Identifier: MIT
 
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+use std::ffi::{c_char, c_int};
 use std::fmt::{self, Write};
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+use std::{ptr, slice, str};
+
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+use crate::ResultCode;
 
 /// Helper struct for escaping strings as SQL.
 pub struct Quote<'a> {
@@ -85,6 +92,49 @@ impl<'a> fmt::Display for Quote<'a> {
     }
 }
 
+/// Reports whether `word` is an SQL keyword recognized by SQLite
+/// (case-insensitively).
+/// Identifiers that are keywords must be quoted,
+/// such as with [`Quote::as_id`].
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+#[doc(alias = "sqlite3_keyword_check")]
+pub fn is_keyword(word: &str) -> bool {
+    let Ok(n) = c_int::try_from(word.len()) else {
+        return false;
+    };
+    (unsafe { libsqlite3_sys::sqlite3_keyword_check(word.as_ptr().cast(), n) }) != 0
+}
+
+/// Returns the number of distinct SQL keywords recognized by SQLite.
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+#[doc(alias = "sqlite3_keyword_count")]
+pub fn keyword_count() -> usize {
+    (unsafe { libsqlite3_sys::sqlite3_keyword_count() }) as usize
+}
+
+/// Returns the `i`th SQL keyword recognized by SQLite in upper case,
+/// or `None` if `i >= keyword_count()`.
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+#[doc(alias = "sqlite3_keyword_name")]
+pub fn keyword_name(i: usize) -> Option<&'static str> {
+    let i = c_int::try_from(i).ok()?;
+    let mut ptr = ptr::null::<c_char>();
+    let mut n: c_int = 0;
+    let rc = ResultCode(unsafe { libsqlite3_sys::sqlite3_keyword_name(i, &mut ptr, &mut n) });
+    if !rc.is_success() || ptr.is_null() {
+        return None;
+    }
+    // Keywords are static ASCII strings that are not NUL-terminated.
+    let bytes = unsafe { slice::from_raw_parts(ptr.cast::<u8>(), n as usize) };
+    str::from_utf8(bytes).ok()
+}
+
+/// Returns an iterator over the SQL keywords recognized by SQLite in upper case.
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    (0..keyword_count()).filter_map(keyword_name)
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum QuoteType {
@@ -128,4 +178,20 @@ mod tests {
             String::from(r#""qu""ote""#)
         );
     }
+
+    #[test]
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    fn test_keywords() {
+        assert!(is_keyword("select"));
+        assert!(is_keyword("SELECT"));
+        assert!(!is_keyword("mytable"));
+        assert!(!is_keyword(""));
+
+        assert!(keyword_count() > 0);
+        let all = keywords().collect::<Vec<_>>();
+        assert_eq!(all.len(), keyword_count());
+        assert!(all.contains(&"SELECT"), "{:?}", all);
+        assert!(all.iter().all(|k| is_keyword(k)));
+        assert_eq!(keyword_name(keyword_count()), None);
+    }
 }
@@ -0,0 +1,324 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fmt::{self, Debug};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use libsqlite3_sys::{
+    sqlite3, sqlite3_busy_timeout, sqlite3_db_filename, sqlite3_file_control, sqlite3_interrupt,
+    sqlite3_vfs, SQLITE_FCNTL_VFS_POINTER, SQLITE_OPEN_NOMUTEX,
+};
+
+use crate::*;
+
+impl Conn {
+    /// Opens a new read-only connection to the main database file
+    /// and calls `f` with it on a new thread.
+    /// The new connection uses the same VFS, URI parameters, and busy timeout
+    /// as this connection.
+    ///
+    /// Before `spawn_reader` returns,
+    /// the new connection starts a read transaction,
+    /// so `f` sees the database as it was when `spawn_reader` was called,
+    /// even if other connections commit writes afterward.
+    /// This requires the database to be in [WAL mode]:
+    /// in other journal modes, the read transaction blocks writers until `f` returns.
+    ///
+    /// Returns a [`ResultCode::MISUSE`] error if the main database
+    /// is a temporary or in-memory database.
+    ///
+    /// [WAL mode]: https://www.sqlite.org/wal.html
+    pub fn spawn_reader<T, F>(&self, f: F) -> Result<ReaderHandle<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Conn) -> Result<T> + Send + 'static,
+    {
+        let conn = self.reader_options(OpenFlags::default())?.open()?;
+        conn.execute("BEGIN;", ())?;
+        // BEGIN is deferred, so the read transaction starts on first read.
+        conn.execute("SELECT count(*) FROM sqlite_master;", ())?;
+
+        let db = Arc::new(Mutex::new(Some(InterruptPtr(conn.as_ptr()))));
+        let thread_db = db.clone();
+        let thread = thread::spawn(move || {
+            let result = f(&conn);
+            *thread_db.lock().unwrap() = None;
+            let commit_result = conn.execute("COMMIT;", ());
+            drop(conn);
+            let value = result?;
+            commit_result?;
+            Ok(value)
+        });
+        Ok(ReaderHandle { thread, db })
+    }
+}
+
+impl Conn {
+    /// Returns the options for opening read-only connections to the main database
+    /// with the same VFS, URI parameters, and busy timeout as this connection.
+    /// `flags` are the flags this connection was opened with,
+    /// which readers share except that they are read-only.
+    ///
+    /// Returns a [`ResultCode::MISUSE`] error if the main database
+    /// is a temporary or in-memory database,
+    /// since other connections cannot open it.
+    pub(crate) fn reader_options(&self, flags: OpenFlags) -> Result<ReaderOptions> {
+        let main = CString::new("main").unwrap();
+        let filename = unsafe { sqlite3_db_filename(self.as_ptr(), main.as_ptr()) };
+        if filename.is_null() || unsafe { *filename } == 0 {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "cannot open readers for temporary or in-memory database",
+            ));
+        }
+        let path = unsafe { CStr::from_ptr(filename) };
+        let mut uri = DatabaseUri::new(path_from_cstr(path));
+        for (key, value) in unsafe { uri_params(filename) } {
+            // Readers are always read-only and use the VFS found below.
+            if key != "mode" && key != "vfs" {
+                uri.param(&key, &value);
+            }
+        }
+
+        let busy_timeout = self
+            .query_row("PRAGMA busy_timeout;", (), |stmt| Ok(stmt.column_i64(0)))?
+            .unwrap_or(0);
+        Ok(ReaderOptions {
+            uri: uri.to_cstring(),
+            vfs: vfs_name(self),
+            flags: (flags & OpenFlags::SHARED_CACHE) | OpenFlags::READONLY | OpenFlags::URI,
+            busy_timeout: c_int::try_from(busy_timeout).unwrap_or(c_int::MAX),
+        })
+    }
+}
+
+/// How to open read-only connections to the same database as another connection,
+/// as returned by [`Conn::reader_options`].
+#[derive(Clone, Debug)]
+pub(crate) struct ReaderOptions {
+    /// A `file:` URI with the original connection's query parameters.
+    uri: CString,
+    vfs: Option<CString>,
+    flags: OpenFlags,
+    /// The busy timeout in milliseconds.
+    pub(crate) busy_timeout: c_int,
+}
+
+impl ReaderOptions {
+    /// Opens a new read-only connection.
+    pub(crate) fn open(&self) -> Result<Connection> {
+        let conn = Connection::open_v2(
+            &self.uri,
+            self.flags,
+            SQLITE_OPEN_NOMUTEX,
+            self.vfs.as_deref(),
+        )?;
+        set_busy_timeout(&conn, self.busy_timeout);
+        Ok(conn)
+    }
+}
+
+/// Returns the query parameters of a database filename
+/// returned by `sqlite3_db_filename`.
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+unsafe fn uri_params(filename: *const c_char) -> Vec<(String, String)> {
+    use libsqlite3_sys::{sqlite3_uri_key, sqlite3_uri_parameter};
+
+    let mut params = Vec::new();
+    for i in 0.. {
+        let key = sqlite3_uri_key(filename, i);
+        if key.is_null() {
+            break;
+        }
+        let value = sqlite3_uri_parameter(filename, key);
+        if !value.is_null() {
+            params.push((
+                CStr::from_ptr(key).to_string_lossy().into_owned(),
+                CStr::from_ptr(value).to_string_lossy().into_owned(),
+            ));
+        }
+    }
+    params
+}
+
+/// Returns the query parameters of a database filename.
+/// Older versions of SQLite cannot list them.
+#[cfg(not(any(feature = "modern", feature = "buildtime_bindgen")))]
+unsafe fn uri_params(_filename: *const c_char) -> Vec<(String, String)> {
+    Vec::new()
+}
+
+/// Returns the name of the VFS used by the connection's main database,
+/// or `None` if it cannot be determined.
+pub(crate) fn vfs_name(conn: &Conn) -> Option<CString> {
+    let main = CString::new("main").unwrap();
+    let mut vfs: *mut sqlite3_vfs = ptr::null_mut();
+    let rc = ResultCode(unsafe {
+        sqlite3_file_control(
+            conn.as_ptr(),
+            main.as_ptr(),
+            SQLITE_FCNTL_VFS_POINTER,
+            ptr::addr_of_mut!(vfs).cast::<c_void>(),
+        )
+    });
+    match unsafe { vfs.as_ref() } {
+        Some(vfs) if rc.is_success() && !vfs.zName.is_null() => {
+            Some(unsafe { CStr::from_ptr(vfs.zName) }.to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// Sets the connection's busy timeout in milliseconds.
+pub(crate) fn set_busy_timeout(conn: &Conn, ms: c_int) {
+    // sqlite3_busy_timeout can only fail for a NULL connection.
+    unsafe { sqlite3_busy_timeout(conn.as_ptr(), ms) };
+}
+
+#[cfg(unix)]
+fn path_from_cstr(path: &CStr) -> PathBuf {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(OsStr::from_bytes(path.to_bytes()))
+}
+
+#[cfg(not(unix))]
+fn path_from_cstr(path: &CStr) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().into_owned())
+}
+
+/// A handle to a reader started by [`Conn::spawn_reader`].
+pub struct ReaderHandle<T> {
+    thread: JoinHandle<Result<T>>,
+    db: Arc<Mutex<Option<InterruptPtr>>>,
+}
+
+impl<T> ReaderHandle<T> {
+    /// Waits for the reader to finish and returns its result.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the reader's function panicked.
+    pub fn join(self) -> Result<T> {
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+
+    /// Interrupts any statement the reader is running,
+    /// causing it to fail with [`ResultCode::INTERRUPT`].
+    /// Has no effect if the reader has finished.
+    #[doc(alias = "sqlite3_interrupt")]
+    pub fn interrupt(&self) {
+        if let Some(InterruptPtr(db)) = *self.db.lock().unwrap() {
+            unsafe { sqlite3_interrupt(db) };
+        }
+    }
+
+    /// Reports whether the reader has finished.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
+impl<T> Debug for ReaderHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReaderHandle")
+            .field("thread", &self.thread)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A connection pointer that is only used for [`sqlite3_interrupt`],
+/// which is safe to call from any thread while the connection is open.
+#[derive(Clone, Copy)]
+struct InterruptPtr(*mut sqlite3);
+
+unsafe impl Send for InterruptPtr {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_spawn_reader_sees_snapshot() {
+        let dir = TempDir::new("spawn-reader");
+        let conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        conn.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (1), (2);", ()).unwrap();
+
+        let (wrote_tx, wrote_rx) = mpsc::channel::<()>();
+        let reader = conn
+            .spawn_reader(move |conn| {
+                wrote_rx.recv().unwrap();
+                conn.query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            })
+            .unwrap();
+        conn.execute("INSERT INTO t VALUES (3);", ()).unwrap();
+        wrote_tx.send(()).unwrap();
+
+        assert_eq!(reader.join().unwrap(), Some(2));
+        let count = conn
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(count, Some(3));
+    }
+
+    /// Returns the value of the main database's `name` URI parameter.
+    #[cfg(all(unix, any(feature = "modern", feature = "buildtime_bindgen")))]
+    fn uri_parameter(conn: &Conn, name: &str) -> Option<String> {
+        let main = CString::new("main").unwrap();
+        let name = CString::new(name).unwrap();
+        unsafe {
+            let filename = sqlite3_db_filename(conn.as_ptr(), main.as_ptr());
+            let value = libsqlite3_sys::sqlite3_uri_parameter(filename, name.as_ptr());
+            (!value.is_null()).then(|| CStr::from_ptr(value).to_string_lossy().into_owned())
+        }
+    }
+
+    #[cfg(all(unix, any(feature = "modern", feature = "buildtime_bindgen")))]
+    #[test]
+    fn test_spawn_reader_settings() {
+        let dir = TempDir::new("spawn-reader-settings");
+        let uri = DatabaseUri::new(dir.join("test.db"))
+            .param("vfs", "unix-dotfile")
+            .param("app", "a b&c")
+            .to_cstring();
+        let conn = Connection::open(&uri, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        conn.execute("PRAGMA busy_timeout = 1234;", ()).unwrap();
+
+        let reader = conn
+            .spawn_reader(|conn| {
+                let busy_timeout = conn
+                    .query_row("PRAGMA busy_timeout;", (), |stmt| Ok(stmt.column_i64(0)))?
+                    .unwrap();
+                Ok((vfs_name(conn), uri_parameter(conn, "app"), busy_timeout))
+            })
+            .unwrap();
+        let (vfs, app, busy_timeout) = reader.join().unwrap();
+        assert_eq!(vfs.as_deref(), Some(const_cstr!("unix-dotfile").as_cstr()));
+        assert_eq!(app.as_deref(), Some("a b&c"));
+        assert_eq!(busy_timeout, 1234);
+    }
+
+    #[test]
+    fn test_spawn_reader_memory() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let err = conn.spawn_reader(|_| Ok(())).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+    }
+}
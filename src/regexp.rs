@@ -0,0 +1,105 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CString;
+use std::str;
+
+use regex::bytes::Regex;
+
+use crate::*;
+
+impl Connection {
+    /// Registers a `regexp` SQL function
+    /// backed by the [`regex`](https://docs.rs/regex) crate,
+    /// which enables the [`REGEXP` operator].
+    /// `X REGEXP Y` reports whether the string `X`
+    /// contains a match of the regular expression `Y`.
+    /// If either operand is `NULL`, then the result is `NULL`.
+    /// An invalid pattern causes the statement to fail with an error.
+    ///
+    /// Compiled patterns are cached for constant pattern arguments,
+    /// so a pattern is typically compiled once per statement execution.
+    ///
+    /// [`REGEXP` operator]: https://www.sqlite.org/lang_expr.html#the_like_glob_regexp_match_and_extract_operators
+    pub fn enable_regexp(&mut self) -> Result<()> {
+        self.create_scalar_function(
+            &CString::new("regexp").unwrap(),
+            Some(2),
+            FunctionFlags::DETERMINISTIC,
+            |mut ctx, args| {
+                let mut pattern = args.next().unwrap();
+                let mut s = args.next().unwrap();
+                if pattern.is_null() || s.is_null() {
+                    ctx.result_null();
+                    return;
+                }
+                let s = s.to_blob();
+                let is_match = match ctx.auxdata(0).and_then(|data| data.downcast_ref::<Regex>()) {
+                    Some(re) => re.is_match(s),
+                    None => {
+                        let re = match str::from_utf8(pattern.to_blob())
+                            .map_err(|err| err.to_string())
+                            .and_then(|pattern| Regex::new(pattern).map_err(|err| err.to_string()))
+                        {
+                            Ok(re) => re,
+                            Err(msg) => {
+                                ctx.result_error(ResultCode::ERROR, &msg);
+                                return;
+                            }
+                        };
+                        let is_match = re.is_match(s);
+                        ctx.set_auxdata(0, Box::new(re));
+                        is_match
+                    }
+                };
+                ctx.result_i64(is_match.into());
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_regexp() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.enable_regexp().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT column1 FROM (VALUES ('apple'), ('banana'), ('apricot'), (NULL)) \
+                WHERE column1 REGEXP '^ap';",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let mut got = Vec::new();
+        while stmt.step().unwrap().has_row() {
+            got.push(stmt.column_text(0).unwrap().to_string());
+        }
+        assert_eq!(got, vec![String::from("apple"), String::from("apricot")]);
+    }
+
+    #[test]
+    fn test_regexp_invalid_pattern() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.enable_regexp().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT 'abc' REGEXP '(unclosed';")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let err = stmt.step().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert!(
+            err.message().contains("unclosed"),
+            "message = {:?}",
+            err.message()
+        );
+    }
+}
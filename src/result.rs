@@ -4,6 +4,7 @@
 use std::ffi::{c_int, CStr};
 use std::fmt;
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use libsqlite3_sys::{
     sqlite3, sqlite3_errmsg, sqlite3_errstr, sqlite3_extended_errcode, SQLITE_ABORT, SQLITE_AUTH,
@@ -14,6 +15,8 @@ use libsqlite3_sys::{
     SQLITE_READONLY, SQLITE_ROW, SQLITE_SCHEMA, SQLITE_TOOBIG, SQLITE_WARNING,
 };
 
+use crate::statement::redact_sql_literals;
+
 /// The numeric [result code] of a SQLite function.
 ///
 /// [result code]: https://www.sqlite.org/rescode.html
@@ -146,6 +149,7 @@ impl ResultCode {
                 result_code: self,
                 msg: String::new(),
                 error_offset: None,
+                sql: None,
             })
         }
     }
@@ -332,6 +336,7 @@ pub struct Error {
     result_code: ResultCode,
     msg: String,
     error_offset: Option<usize>,
+    sql: Option<String>,
 }
 
 impl Error {
@@ -348,6 +353,7 @@ impl Error {
             result_code,
             msg: msg.into(),
             error_offset: None,
+            sql: None,
         }
     }
 
@@ -364,6 +370,7 @@ impl Error {
             result_code,
             msg,
             error_offset,
+            sql: None,
         })
     }
 
@@ -406,6 +413,51 @@ impl Error {
         self.error_offset = None;
     }
 
+    /// Returns the text of the SQL statement that caused the error, if known.
+    /// The text is only attached if enabled with [`set_error_sql`],
+    /// in which case it is present on errors from compiling a statement
+    /// and from [`Statement::step`][crate::Statement::step],
+    /// such as those returned by [`Conn::execute`][crate::Conn::execute].
+    /// The text is [redacted][crate::Statement::redacted_sql]
+    /// so that literal values are never included,
+    /// and long statements are truncated.
+    /// It is not part of the error's [`Display`][fmt::Display] output.
+    #[inline]
+    pub fn sql(&self) -> Option<&str> {
+        self.sql.as_deref()
+    }
+
+    /// Attaches the redacted form of `sql` to the error
+    /// if [`set_error_sql`] has enabled it.
+    pub(crate) fn with_sql(mut self, sql: &str) -> Self {
+        if !ERROR_SQL.load(Ordering::Relaxed) {
+            return self;
+        }
+        let mut sql = redact_sql_literals(sql.trim());
+        if sql.len() > MAX_ERROR_SQL_LEN {
+            let mut n = MAX_ERROR_SQL_LEN;
+            while !sql.is_char_boundary(n) {
+                n -= 1;
+            }
+            sql.truncate(n);
+            sql.push_str("...");
+        }
+        self.sql = Some(sql);
+        self
+    }
+
+    /// Formats the error's message followed by `sql`
+    /// with the token at the error's [offset][Error::error_offset] underlined,
+    /// as returned by [`highlight_sql_error`].
+    /// `sql` must be the text of the statement that caused the error.
+    /// If the error has no offset, then only the message is returned.
+    pub fn display_with_sql(&self, sql: &str) -> String {
+        match self.error_offset {
+            Some(offset) => format!("{}\n{}", self.message(), highlight_sql_error(sql, offset)),
+            None => self.message().to_string(),
+        }
+    }
+
     /// Returns the error's message.
     /// Guaranteed to not be empty.
     pub fn message(&self) -> &str {
@@ -415,6 +467,111 @@ impl Error {
             &self.msg
         }
     }
+
+    /// Returns a coarse category for the error,
+    /// derived from its [primary result code][ResultCode::to_primary].
+    pub fn category(&self) -> ErrorCategory {
+        match self.result_code.to_primary() {
+            ResultCode::BUSY | ResultCode::LOCKED => ErrorCategory::Transient,
+            ResultCode::CONSTRAINT => ErrorCategory::Constraint,
+            ResultCode::CORRUPT | ResultCode::NOTADB => ErrorCategory::Corruption,
+            ResultCode::NOTFOUND | ResultCode::CANTOPEN => ErrorCategory::NotFound,
+            ResultCode::PERM | ResultCode::READONLY | ResultCode::AUTH => ErrorCategory::Permission,
+            ResultCode::ERROR
+            | ResultCode::MISUSE
+            | ResultCode::RANGE
+            | ResultCode::MISMATCH
+            | ResultCode::TOOBIG => ErrorCategory::Usage,
+            _ => ErrorCategory::Internal,
+        }
+    }
+}
+
+/// Returns `sql` with a line of carets inserted
+/// underneath the token that starts at the byte offset `offset`,
+/// such as one returned by [`Error::error_offset`].
+/// Offsets past the end of `sql` point just after its last character.
+///
+/// # Example
+///
+/// ```
+/// # use zombiezen_sqlite::highlight_sql_error;
+/// assert_eq!(
+///     highlight_sql_error("SELECT *\nFROM foo\nWHERE bar;", 14),
+///     "SELECT *\nFROM foo\n     ^^^\nWHERE bar;",
+/// );
+/// ```
+pub fn highlight_sql_error(sql: &str, offset: usize) -> String {
+    let mut offset = offset.min(sql.len());
+    while !sql.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line_start = sql[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = sql[offset..].find('\n').map_or(sql.len(), |i| offset + i);
+
+    let token = &sql[offset..line_end];
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let token_chars = match token.chars().next() {
+        None => 1,
+        Some(c) if is_word(c) => token.chars().take_while(|&c| is_word(c)).count(),
+        Some(_) => 1,
+    };
+
+    let mut out = String::with_capacity(sql.len() + (offset - line_start) + token_chars + 2);
+    out.push_str(&sql[..line_end]);
+    out.push('\n');
+    // Keep tabs so that the carets line up with the token.
+    out.extend(
+        sql[line_start..offset]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' }),
+    );
+    out.push_str(&"^".repeat(token_chars));
+    out.push_str(&sql[line_end..]);
+    out
+}
+
+/// The maximum number of bytes of SQL text stored in an [`Error`].
+const MAX_ERROR_SQL_LEN: usize = 1024;
+
+static ERROR_SQL: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether errors from SQL statements carry the statement's text,
+/// as returned by [`Error::sql`].
+/// This is off by default,
+/// since statements can be large
+/// and their identifiers and comments may contain sensitive data
+/// even though literal values are redacted.
+/// The setting is global to the process
+/// and applies to errors created after the call.
+pub fn set_error_sql(enabled: bool) {
+    ERROR_SQL.store(enabled, Ordering::Relaxed);
+}
+
+/// A coarse grouping of [`Error`]s,
+/// useful for deciding how to respond to an error
+/// without matching on individual result codes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The operation failed because of concurrent activity
+    /// and may succeed if retried.
+    /// Includes [`ResultCode::BUSY`] and [`ResultCode::LOCKED`].
+    Transient,
+    /// An SQL constraint was violated.
+    Constraint,
+    /// The database file is corrupt or is not a database.
+    Corruption,
+    /// The database file or requested item could not be found.
+    NotFound,
+    /// The operation was not permitted,
+    /// either by the operating system, the database's mode, or an authorizer.
+    Permission,
+    /// The caller made a mistake,
+    /// such as an SQL syntax error or an out-of-range parameter.
+    Usage,
+    /// Any other error,
+    /// such as an I/O error or running out of memory.
+    Internal,
 }
 
 impl From<&Error> for ResultCode {
@@ -434,3 +591,64 @@ impl fmt::Display for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use crate::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_error_category() {
+        assert_eq!(
+            Error::new(ResultCode::BUSY, "").category(),
+            ErrorCategory::Transient
+        );
+        assert_eq!(
+            Error::new(ResultCode::CORRUPT, "").category(),
+            ErrorCategory::Corruption
+        );
+
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x UNIQUE);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        let err = conn.execute("INSERT INTO t VALUES (1);", ()).unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert_eq!(err.category(), ErrorCategory::Constraint);
+    }
+
+    #[test]
+    fn test_highlight_sql_error() {
+        let sql = "SELECT x,\n\tfoo(y) AS z\nFROM t;";
+        assert_eq!(
+            highlight_sql_error(sql, sql.find("foo").unwrap()),
+            "SELECT x,\n\tfoo(y) AS z\n\t^^^\nFROM t;"
+        );
+        assert_eq!(
+            highlight_sql_error(sql, sql.find("AS").unwrap() + 3),
+            "SELECT x,\n\tfoo(y) AS z\n\t          ^\nFROM t;"
+        );
+        assert_eq!(
+            highlight_sql_error(sql, sql.find('(').unwrap()),
+            "SELECT x,\n\tfoo(y) AS z\n\t   ^\nFROM t;"
+        );
+        assert_eq!(
+            highlight_sql_error("SELECT 'é' +", 100),
+            "SELECT 'é' +\n            ^"
+        );
+    }
+
+    #[cfg(feature = "modern")]
+    #[test]
+    fn test_display_with_sql() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let sql = "SELECT 1\nFROM WHERE x;";
+        let err = conn.execute(sql, ()).unwrap_err();
+        assert_eq!(
+            err.display_with_sql(sql),
+            "near \"WHERE\": syntax error\nSELECT 1\nFROM WHERE x;\n     ^^^^^"
+        );
+    }
+}
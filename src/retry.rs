@@ -0,0 +1,285 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::thread;
+use std::time::Duration;
+
+use libsqlite3_sys::{sqlite3_changes, SQLITE_BUSY_SNAPSHOT};
+
+use crate::connection::DbMutexGuard;
+use crate::*;
+
+/// How [`Conn::transaction_with_retry`] and [`Conn::execute_with_retry`]
+/// retry operations that fail because of concurrent activity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// The maximum number of times to run the operation, including the first.
+    pub max_attempts: u32,
+    /// How long to wait before the first retry.
+    /// The wait doubles after each subsequent attempt,
+    /// up to one second or `backoff`, whichever is longer.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Returns the wait that follows a wait of `prev`.
+    fn next_backoff(&self, prev: Duration) -> Duration {
+        const MAX_BACKOFF: Duration = Duration::from_secs(1);
+        prev.saturating_mul(2).min(MAX_BACKOFF.max(self.backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Returns a policy of 5 attempts starting with a 10 millisecond backoff.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            backoff: Duration::from_millis(10),
+        }
+    }
+}
+
+/// What to do after an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RetryAction {
+    /// The error is not caused by concurrent activity.
+    Fail,
+    /// Another connection holds a lock, so waiting may help.
+    Wait,
+    /// The transaction's snapshot is out of date.
+    /// Waiting never helps: the transaction must start over.
+    Restart,
+}
+
+impl RetryAction {
+    fn for_error(err: &Error) -> RetryAction {
+        if err.result_code() == ResultCode(SQLITE_BUSY_SNAPSHOT) {
+            RetryAction::Restart
+        } else if err.category() == ErrorCategory::Transient {
+            RetryAction::Wait
+        } else {
+            RetryAction::Fail
+        }
+    }
+}
+
+impl Conn {
+    /// Runs `f` inside a transaction and commits it,
+    /// starting the transaction over if it fails because of concurrent activity.
+    ///
+    /// If `f` or the commit fails with [`ResultCode::BUSY`] or [`ResultCode::LOCKED`],
+    /// the transaction is rolled back and retried after waiting for [`RetryPolicy::backoff`].
+    /// If the error is the extended code `SQLITE_BUSY_SNAPSHOT`,
+    /// which means that another connection wrote to a [WAL] database
+    /// after this transaction started reading,
+    /// then the transaction is retried immediately,
+    /// since waiting can never bring the snapshot up to date.
+    /// Any other error rolls back the transaction and is returned.
+    /// `f` may be called up to [`RetryPolicy::max_attempts`] times,
+    /// so it should not have side effects outside the database.
+    ///
+    /// Returns a [`ResultCode::MISUSE`] error if called inside a transaction.
+    ///
+    /// [WAL]: https://www.sqlite.org/wal.html
+    pub fn transaction_with_retry<T>(
+        &self,
+        policy: RetryPolicy,
+        mut f: impl FnMut(&Conn) -> Result<T>,
+    ) -> Result<T> {
+        if !self.get_autocommit() {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                "transaction_with_retry called inside a transaction",
+            ));
+        }
+        let mut backoff = policy.backoff;
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .execute("BEGIN;", ())
+                .and_then(|_| f(self))
+                .and_then(|value| self.execute("COMMIT;", ()).map(|_| value));
+            let err = match result {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            if !self.get_autocommit() {
+                let _ = self.execute("ROLLBACK;", ());
+            }
+            if attempt >= policy.max_attempts {
+                return Err(err);
+            }
+            match RetryAction::for_error(&err) {
+                RetryAction::Fail => return Err(err),
+                RetryAction::Wait => {
+                    thread::sleep(backoff);
+                    backoff = policy.next_backoff(backoff);
+                }
+                RetryAction::Restart => {}
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Runs the single statement `sql` like [`Conn::execute`],
+    /// retrying it if it fails because of concurrent activity.
+    ///
+    /// If the statement fails with [`ResultCode::BUSY`] or [`ResultCode::LOCKED`],
+    /// it is retried after waiting for [`RetryPolicy::backoff`].
+    /// If the error is the extended code `SQLITE_BUSY_SNAPSHOT`
+    /// and the connection is not inside a transaction,
+    /// then the statement is retried immediately.
+    /// Inside a transaction, a stale snapshot can only be fixed
+    /// by restarting the whole transaction,
+    /// so the error is returned without retrying.
+    /// Use [`Conn::transaction_with_retry`] for transactions.
+    pub fn execute_with_retry(
+        &self,
+        sql: &str,
+        params: impl Params,
+        policy: RetryPolicy,
+    ) -> Result<usize> {
+        let mut stmt = self.prepare_single(sql)?;
+        params.bind(&mut stmt)?;
+        let mut backoff = policy.backoff;
+        let mut attempt = 1;
+        loop {
+            let result = (|| {
+                let _guard = DbMutexGuard::lock(self.as_ptr());
+                while stmt.step()?.has_row() {}
+                Ok(unsafe { sqlite3_changes(self.as_ptr()) } as usize)
+            })();
+            let err = match result {
+                Ok(n) => return Ok(n),
+                Err(err) => err,
+            };
+            let _ = stmt.reset();
+            if attempt >= policy.max_attempts {
+                return Err(err);
+            }
+            match RetryAction::for_error(&err) {
+                RetryAction::Fail => return Err(err),
+                RetryAction::Wait => {
+                    thread::sleep(backoff);
+                    backoff = policy.next_backoff(backoff);
+                }
+                RetryAction::Restart if self.get_autocommit() => {}
+                RetryAction::Restart => {
+                    return Err(Error::new(
+                        err.result_code(),
+                        "database snapshot is out of date; \
+                        the enclosing transaction must be restarted",
+                    ));
+                }
+            }
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Instant;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    /// Opens two connections to a new database.
+    /// The directory must outlive the connections.
+    fn open_pair(name: &str) -> (TempDir, Connection, Connection) {
+        let dir = TempDir::new(&format!("retry-{}", name));
+        let path_cstr = dir.join_cstr("test.db");
+        let conn1 = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        conn1.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+        conn1.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        let conn2 = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        (dir, conn1, conn2)
+    }
+
+    /// A policy whose waits are long enough to notice.
+    const SLOW: RetryPolicy = RetryPolicy {
+        max_attempts: 3,
+        backoff: Duration::from_secs(10),
+    };
+
+    #[test]
+    fn test_next_backoff() {
+        let policy = RetryPolicy::default();
+        assert_eq!(
+            policy.next_backoff(Duration::from_millis(10)),
+            Duration::from_millis(20)
+        );
+        assert_eq!(
+            policy.next_backoff(Duration::from_millis(800)),
+            Duration::from_secs(1)
+        );
+        let policy = RetryPolicy {
+            max_attempts: 100,
+            backoff: Duration::from_secs(5),
+        };
+        assert_eq!(
+            policy.next_backoff(Duration::from_secs(5)),
+            Duration::from_secs(5)
+        );
+        assert_eq!(policy.next_backoff(Duration::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_transaction_with_retry_busy_snapshot() {
+        let (_dir, conn1, conn2) = open_pair("txn");
+        let attempts = Cell::new(0);
+        let start = Instant::now();
+        conn1
+            .transaction_with_retry(SLOW, |conn| {
+                attempts.set(attempts.get() + 1);
+                conn.execute("SELECT count(*) FROM t;", ())?;
+                if attempts.get() == 1 {
+                    // Another connection writes after this transaction started reading,
+                    // so upgrading to a write transaction fails with SQLITE_BUSY_SNAPSHOT.
+                    conn2.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+                }
+                conn.execute("INSERT INTO t VALUES (2);", ())?;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(attempts.get(), 2);
+        assert!(start.elapsed() < SLOW.backoff, "waited before restarting");
+        let n = conn1
+            .query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            .unwrap();
+        assert_eq!(n, Some(2));
+
+        // Other errors are not retried.
+        attempts.set(0);
+        let err = conn1
+            .transaction_with_retry(SLOW, |conn| {
+                attempts.set(attempts.get() + 1);
+                conn.execute("INSERT INTO nope VALUES (1);", ())
+            })
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+        assert_eq!(attempts.get(), 1);
+        assert!(conn1.get_autocommit());
+    }
+
+    #[test]
+    fn test_execute_with_retry_busy_snapshot() {
+        let (_dir, conn1, conn2) = open_pair("execute");
+        conn1.execute("BEGIN;", ()).unwrap();
+        conn1.execute("SELECT count(*) FROM t;", ()).unwrap();
+        conn2.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        let start = Instant::now();
+        let err = conn1
+            .execute_with_retry("INSERT INTO t VALUES (2);", (), SLOW)
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode(SQLITE_BUSY_SNAPSHOT));
+        assert!(start.elapsed() < SLOW.backoff, "waited on a stale snapshot");
+        conn1.execute("ROLLBACK;", ()).unwrap();
+
+        let n = conn1
+            .execute_with_retry("INSERT INTO t VALUES (?1);", (3,), SLOW)
+            .unwrap();
+        assert_eq!(n, 1);
+    }
+}
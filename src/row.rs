@@ -0,0 +1,438 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use crate::query::QueryStream;
+use crate::*;
+
+/// A type that can be constructed from a row of a statement's results.
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{Affinity, Connection, FromRow, OpenFlags, Result, ResultExt, Statement};
+/// struct User {
+///     id: i64,
+///     name: String,
+/// }
+///
+/// impl FromRow for User {
+///     fn from_row(stmt: &mut Statement<'_>) -> Result<Self> {
+///         Ok(User {
+///             id: stmt.column_i64(0),
+///             name: stmt.column_text(1).to_string_lossy().into_owned(),
+///         })
+///     }
+///
+///     fn validate(stmt: &Statement<'_>) -> Result<()> {
+///         stmt.validate_columns(&[Affinity::Integer, Affinity::Text])
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// conn.execute("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);", ())?;
+/// conn.execute("INSERT INTO users (name) VALUES ('alice');", ())?;
+/// for user in conn.query_as::<User>("SELECT id, name FROM users;", ())? {
+///     let user = user?;
+///     assert_eq!(user.name, "alice");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub trait FromRow: Sized {
+    /// Reads the current row of `stmt`.
+    fn from_row(stmt: &mut Statement<'_>) -> Result<Self>;
+
+    /// Checks that the statement's result columns
+    /// are compatible with the type
+    /// before any rows are read.
+    /// The default implementation accepts any statement.
+    ///
+    /// Implementations will usually call [`Statement::validate_columns`].
+    fn validate(stmt: &Statement<'_>) -> Result<()> {
+        let _ = stmt;
+        Ok(())
+    }
+}
+
+/// A type that can be read from a single result column.
+///
+/// The implementations for integers and `bool` require the column
+/// to hold an integer,
+/// and the implementation for `f64` requires it to hold a number.
+/// Other values, including `NULL`, are reported as [`ResultCode::MISMATCH`] errors
+/// instead of being converted.
+pub trait FromColumn: Sized {
+    /// Reads the `i`th column of the current row of `stmt`.
+    /// The leftmost column is number 0.
+    fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self>;
+}
+
+/// Returns an error if the `i`th column is `NULL`.
+pub(crate) fn check_not_null(stmt: &Statement<'_>, i: usize) -> Result<()> {
+    if stmt.column_type(i) == DataType::Null {
+        Err(Error::new(
+            ResultCode::MISMATCH,
+            format!(
+                "column {} ({}) is NULL",
+                i,
+                stmt.column_name(i).unwrap_or_default()
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns an error unless the `i`th column is stored as one of `types`.
+/// `expected` describes `types` in the error message.
+pub(crate) fn check_column_type(
+    stmt: &Statement<'_>,
+    i: usize,
+    types: &[DataType],
+    expected: &str,
+) -> Result<()> {
+    check_not_null(stmt, i)?;
+    let actual = stmt.column_type(i);
+    if types.contains(&actual) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ResultCode::MISMATCH,
+            format!(
+                "column {} ({}) is {:?}, expected {}",
+                i,
+                stmt.column_name(i).unwrap_or_default(),
+                actual,
+                expected
+            ),
+        ))
+    }
+}
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+        if stmt.column_type(i) == DataType::Null {
+            Ok(None)
+        } else {
+            T::from_column(stmt, i).map(Some)
+        }
+    }
+}
+
+macro_rules! impl_from_column_int {
+    ( $($t:ty),* ) => {
+        $(
+            impl FromColumn for $t {
+                fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+                    check_column_type(stmt, i, &[DataType::Integer], "an integer")?;
+                    let n = stmt.column_i64(i);
+                    <$t>::try_from(n).map_err(|_| {
+                        Error::new(
+                            ResultCode::RANGE,
+                            format!("column {} value {} out of range for {}", i, n, stringify!($t)),
+                        )
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_from_column_int!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl FromColumn for bool {
+    fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+        check_column_type(stmt, i, &[DataType::Integer], "an integer")?;
+        Ok(stmt.column_i64(i) != 0)
+    }
+}
+
+impl FromColumn for f64 {
+    fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+        check_column_type(stmt, i, &[DataType::Float, DataType::Integer], "a number")?;
+        Ok(stmt.column_f64(i))
+    }
+}
+
+impl FromColumn for String {
+    fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+        check_not_null(stmt, i)?;
+        stmt.column_text(i)
+            .map(str::to_string)
+            .map_err(|err| Error::new(ResultCode::MISMATCH, format!("column {}: {}", i, err)))
+    }
+}
+
+impl FromColumn for Vec<u8> {
+    fn from_column(stmt: &mut Statement<'_>, i: usize) -> Result<Self> {
+        check_not_null(stmt, i)?;
+        Ok(stmt.column_blob(i).to_vec())
+    }
+}
+
+/// Returns an error if `stmt` does not return exactly `n` columns.
+fn check_column_count(stmt: &Statement<'_>, n: usize) -> Result<()> {
+    let got = stmt.column_count();
+    if got == n {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ResultCode::MISMATCH,
+            format!("statement returns {} column(s), expected {}", got, n),
+        ))
+    }
+}
+
+macro_rules! impl_from_row_tuple {
+    ( $n:literal; $($t:ident $i:tt),+ ) => {
+        /// Reads each column in order with [`FromColumn`].
+        /// Validation checks that the statement returns exactly as many columns
+        /// as the tuple has elements.
+        impl<$($t: FromColumn),+> FromRow for ($($t,)+) {
+            fn from_row(stmt: &mut Statement<'_>) -> Result<Self> {
+                Ok(($($t::from_column(stmt, $i)?,)+))
+            }
+
+            fn validate(stmt: &Statement<'_>) -> Result<()> {
+                check_column_count(stmt, $n)
+            }
+        }
+    };
+}
+
+impl_from_row_tuple!(1; A 0);
+impl_from_row_tuple!(2; A 0, B 1);
+impl_from_row_tuple!(3; A 0, B 1, C 2);
+impl_from_row_tuple!(4; A 0, B 1, C 2, D 3);
+impl_from_row_tuple!(5; A 0, B 1, C 2, D 3, E 4);
+impl_from_row_tuple!(6; A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_row_tuple!(7; A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_from_row_tuple!(8; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+impl_from_row_tuple!(9; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8);
+impl_from_row_tuple!(10; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9);
+impl_from_row_tuple!(11; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10);
+impl_from_row_tuple!(12; A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11);
+
+type ReadRowFn<'c, T> = fn(&mut Statement<'c>) -> Result<T>;
+
+impl Conn {
+    /// Compiles `sql` (which must contain exactly one SQL statement),
+    /// binds `params` to it,
+    /// and [validates][FromRow::validate] its result columns against `T`,
+    /// returning an iterator that reads each row as a `T`.
+    pub fn query_as<'c, T: FromRow>(
+        &'c self,
+        sql: &str,
+        params: impl Params,
+    ) -> Result<QueryStream<'c, ReadRowFn<'c, T>>> {
+        let stream = self.query_stream(sql, params, T::from_row as ReadRowFn<'c, T>)?;
+        T::validate(stream.statement())?;
+        Ok(stream)
+    }
+}
+
+impl Conn {
+    /// Compiles `sql` (which must contain exactly one SQL statement),
+    /// binds `params` to it,
+    /// and reads every row it returns as an `R`.
+    /// The [`Params`] and [`FromRow`] tuple implementations
+    /// allow a typical typed read to be a single call:
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let rows = conn.query::<_, (String, i64)>("SELECT 'alice', ?1;", (30,))?;
+    /// assert_eq!(rows, vec![(String::from("alice"), 30)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Use [`query_as`][Conn::query_as] to read rows one at a time instead.
+    pub fn query<P: Params, R: FromRow>(&self, sql: &str, params: P) -> Result<Vec<R>> {
+        self.query_as(sql, params)?.collect()
+    }
+}
+
+impl<'c> Statement<'c> {
+    /// Checks that the statement returns exactly `expected.len()` columns
+    /// and that every result column read directly from a table column
+    /// has the expected [affinity][Statement::column_affinity].
+    /// Result columns that are expressions or subqueries
+    /// have no declared type, so only their presence is checked.
+    /// Returns a [`ResultCode::MISMATCH`] error describing the first difference.
+    pub fn validate_columns(&self, expected: &[Affinity]) -> Result<()> {
+        let n = self.column_count();
+        if n != expected.len() {
+            return Err(Error::new(
+                ResultCode::MISMATCH,
+                format!(
+                    "statement returns {} column(s), expected {}",
+                    n,
+                    expected.len()
+                ),
+            ));
+        }
+        for (i, &want) in expected.iter().enumerate() {
+            let Some(decltype) = self.column_decltype(i) else {
+                continue;
+            };
+            let got = Affinity::from_decltype(&decltype);
+            if got != want {
+                return Err(Error::new(
+                    ResultCode::MISMATCH,
+                    format!(
+                        "column {} ({}) has declared type {:?} with {:?} affinity, expected {:?}",
+                        i,
+                        self.column_name(i).unwrap_or_default(),
+                        decltype,
+                        got,
+                        want
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[derive(Debug)]
+    struct Point {
+        x: f64,
+        y: f64,
+        label: String,
+    }
+
+    impl FromRow for Point {
+        fn from_row(stmt: &mut Statement<'_>) -> Result<Self> {
+            Ok(Point {
+                x: stmt.column_f64(0),
+                y: stmt.column_f64(1),
+                label: stmt.column_text(2).to_string_lossy().into_owned(),
+            })
+        }
+
+        fn validate(stmt: &Statement<'_>) -> Result<()> {
+            stmt.validate_columns(&[Affinity::Real, Affinity::Real, Affinity::Text])
+        }
+    }
+
+    fn open() -> Connection {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute(
+            "CREATE TABLE points (x REAL, y REAL, label TEXT, n INTEGER);",
+            (),
+        )
+        .unwrap();
+        conn.execute("INSERT INTO points VALUES (1.0, 2.0, 'a', 3);", ())
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_query_as() {
+        let conn = open();
+        let points = conn
+            .query_as::<Point>("SELECT x, y, label FROM points;", ())
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!((points[0].x, points[0].y), (1.0, 2.0));
+        assert_eq!(points[0].label, "a");
+    }
+
+    #[test]
+    fn test_validate_column_count() {
+        let conn = open();
+        let err = conn
+            .query_as::<Point>("SELECT x, y FROM points;", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        assert!(
+            err.to_string()
+                .contains("statement returns 2 column(s), expected 3"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_validate_column_type() {
+        let conn = open();
+        let err = conn
+            .query_as::<Point>("SELECT x, y, n FROM points;", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        assert!(err.to_string().contains("column 2 (n)"), "{}", err);
+
+        // Expressions have no declared type.
+        conn.query_as::<Point>("SELECT x, y, 'b' FROM points;", ())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_query_tuple() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT, age INTEGER);",
+            (),
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO t VALUES (4, 'alice', 30), (5, 'bob', 42), (6, NULL, NULL);",
+            (),
+        )
+        .unwrap();
+
+        let rows = conn
+            .query::<(i64,), (String, i64)>("SELECT name, age FROM t WHERE id=?", (5,))
+            .unwrap();
+        assert_eq!(rows, vec![(String::from("bob"), 42)]);
+
+        let rows = conn
+            .query::<(), (Option<String>, Option<u8>)>(
+                "SELECT name, age FROM t WHERE id >= 5 ORDER BY id;",
+                (),
+            )
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![(Some(String::from("bob")), Some(42)), (None, None)]
+        );
+
+        let err = conn
+            .query::<(), (String, i64)>("SELECT name FROM t;", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        let err = conn
+            .query::<(), (String,)>("SELECT name FROM t WHERE id = 6;", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        let err = conn.query::<(), (i8,)>("SELECT 1000;", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::RANGE);
+
+        // Values of the wrong type are not coerced.
+        let err = conn.query::<(), (i64,)>("SELECT 'abc';", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        let err = conn.query::<(), (i64,)>("SELECT 1.5;", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        let err = conn.query::<(), (bool,)>("SELECT 'yes';", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        let err = conn.query::<(), (f64,)>("SELECT x'00';", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISMATCH);
+        let rows = conn.query::<(), (f64, bool)>("SELECT 2, 1;", ()).unwrap();
+        assert_eq!(rows, vec![(2.0, true)]);
+    }
+}
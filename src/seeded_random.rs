@@ -0,0 +1,104 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::cell::Cell;
+use std::ffi::CString;
+use std::rc::Rc;
+
+use crate::*;
+
+impl Connection {
+    /// Replaces the built-in `random()` and `randomblob(N)` SQL functions
+    /// with versions backed by a pseudo-random number generator
+    /// initialized from `seed`.
+    /// Two connections seeded with the same value
+    /// produce the same sequence of results,
+    /// which makes queries that use randomness reproducible in tests.
+    ///
+    /// The generator is not cryptographically secure
+    /// and must not be used outside of testing.
+    /// Calling `seed_random` again restarts the sequence from the new seed.
+    pub fn seed_random(&mut self, seed: u64) -> Result<()> {
+        let state = Rc::new(Cell::new(seed));
+        {
+            let state = state.clone();
+            self.create_scalar_function(
+                &CString::new("random").unwrap(),
+                Some(0),
+                FunctionFlags::default(),
+                move |mut ctx, _| ctx.result_i64(next_random(&state) as i64),
+            )?;
+        }
+        self.create_scalar_function(
+            &CString::new("randomblob").unwrap(),
+            Some(1),
+            FunctionFlags::default(),
+            move |mut ctx, args| {
+                // Like the built-in, lengths less than 1 produce a 1-byte blob.
+                let n = args.next().unwrap().to_i64().max(1);
+                let Ok(n) = usize::try_from(n) else {
+                    ctx.result_error(ResultCode::TOOBIG, "string or blob too big");
+                    return;
+                };
+                ctx.result_blob_with(n, |buf| {
+                    for chunk in buf.chunks_mut(8) {
+                        let x = next_random(&state).to_le_bytes();
+                        chunk.copy_from_slice(&x[..chunk.len()]);
+                    }
+                });
+            },
+        )
+    }
+}
+
+/// Advances the SplitMix64 generator in `state` and returns its next output.
+fn next_random(state: &Cell<u64>) -> u64 {
+    let s = state.get().wrapping_add(0x9e3779b97f4a7c15);
+    state.set(s);
+    let mut z = s;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    const QUERY: &str =
+        "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 10) \
+        SELECT random(), hex(randomblob(x)) FROM c;";
+
+    fn sample(conn: &Conn) -> Vec<(i64, String)> {
+        conn.query_stream(QUERY, (), |stmt| {
+            Ok((stmt.column_i64(0), stmt.column_text(1).unwrap().to_string()))
+        })
+        .unwrap()
+        .collect::<Result<_>>()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_seed_random() {
+        let mut conn1 = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn1.seed_random(42).unwrap();
+        let mut conn2 = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn2.seed_random(42).unwrap();
+
+        let got1 = sample(&conn1);
+        assert_eq!(got1, sample(&conn2));
+        assert_eq!(got1[3].1.len(), 8);
+        assert_ne!(got1[0].0, got1[1].0);
+
+        // Subsequent queries continue the sequence.
+        assert_ne!(got1, sample(&conn1));
+
+        let mut conn3 = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn3.seed_random(7).unwrap();
+        assert_ne!(got1, sample(&conn3));
+    }
+}
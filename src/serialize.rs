@@ -0,0 +1,400 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_char, c_uint, CStr};
+use std::fmt::{self, Debug};
+use std::io;
+use std::ops::Deref;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use libsqlite3_sys::{
+    sqlite3_db_filename, sqlite3_deserialize, sqlite3_free, sqlite3_realloc64, sqlite3_serialize,
+    SQLITE_DESERIALIZE_FREEONCLOSE, SQLITE_DESERIALIZE_RESIZEABLE, SQLITE_SERIALIZE_NOCOPY,
+};
+
+use crate::*;
+
+impl Conn {
+    /// Returns a copy of the bytes that would be written to disk
+    /// if the database named `schema` (usually `"main"`) were saved to a file.
+    #[doc(alias = "sqlite3_serialize")]
+    pub fn serialize(&self, schema: &(impl AsRef<CStr> + ?Sized)) -> Result<SerializedDatabase> {
+        let schema = schema.as_ref();
+        let mut size = -1i64;
+        let ptr = unsafe { sqlite3_serialize(self.as_ptr(), schema.as_ptr(), &mut size, 0) };
+        match (NonNull::new(ptr), size) {
+            (Some(ptr), _) => Ok(SerializedDatabase {
+                ptr: Some(ptr),
+                len: size as usize,
+            }),
+            // sqlite3_malloc64 returns NULL for zero-length allocations.
+            (None, 0) => Ok(SerializedDatabase { ptr: None, len: 0 }),
+            (None, -1) => Err(Error::new(
+                ResultCode::ERROR,
+                format!("unknown database {}", schema.to_string_lossy()),
+            )),
+            (None, _) => Err(ResultCode::NOMEM.to_result().unwrap_err()),
+        }
+    }
+}
+
+impl Connection {
+    /// Returns the serialized form of the database named `schema` (usually `"main"`)
+    /// without copying it.
+    /// This is only possible for databases held in contiguous memory
+    /// that are not shared with other connections,
+    /// like those loaded with [`Connection::deserialize`].
+    /// Returns `None` for any other database,
+    /// in which case [`Conn::serialize`] can be used to make a copy.
+    ///
+    /// The connection is borrowed mutably
+    /// so that the database can't be modified while the slice is in use.
+    #[doc(alias = "SQLITE_SERIALIZE_NOCOPY")]
+    pub fn serialize_borrow(&mut self, schema: &(impl AsRef<CStr> + ?Sized)) -> Option<&[u8]> {
+        let schema = schema.as_ref();
+        unsafe {
+            // In-memory databases whose names begin with "/"
+            // can be opened (and modified) by other connections.
+            let filename = sqlite3_db_filename(self.as_ptr(), schema.as_ptr());
+            if !filename.is_null() && *filename == b'/' as c_char {
+                return None;
+            }
+            let mut size = -1i64;
+            let ptr = sqlite3_serialize(
+                self.as_ptr(),
+                schema.as_ptr(),
+                &mut size,
+                SQLITE_SERIALIZE_NOCOPY as c_uint,
+            );
+            if ptr.is_null() {
+                return None;
+            }
+            Some(slice::from_raw_parts(ptr, size as usize))
+        }
+    }
+
+    /// Replaces the database named `schema` (usually `"main"`)
+    /// with an in-memory database holding a copy of `data`,
+    /// which should be in the format returned by [`Conn::serialize`].
+    #[doc(alias = "sqlite3_deserialize")]
+    pub fn deserialize(&mut self, schema: &(impl AsRef<CStr> + ?Sized), data: &[u8]) -> Result<()> {
+        let mut buf = SqliteBuffer::default();
+        buf.extend_from_slice(data)?;
+        self.deserialize_buffer(schema.as_ref(), buf)
+    }
+
+    /// Replaces the database named `schema` with the contents of `buf`,
+    /// handing ownership of `buf` to SQLite.
+    fn deserialize_buffer(&mut self, schema: &CStr, buf: SqliteBuffer) -> Result<()> {
+        let too_big = || ResultCode::TOOBIG.to_result().unwrap_err();
+        let size = i64::try_from(buf.len).map_err(|_| too_big())?;
+        let capacity = i64::try_from(buf.capacity).map_err(|_| too_big())?;
+        let rc = ResultCode(unsafe {
+            // SQLite takes ownership of the buffer, even on failure.
+            sqlite3_deserialize(
+                self.as_ptr(),
+                schema.as_ptr(),
+                buf.into_raw(),
+                size,
+                capacity,
+                (SQLITE_DESERIALIZE_FREEONCLOSE | SQLITE_DESERIALIZE_RESIZEABLE) as c_uint,
+            )
+        });
+        if rc.is_success() {
+            Ok(())
+        } else {
+            Err(self
+                .as_ref()
+                .error()
+                .unwrap_or_else(|| rc.to_result().unwrap_err()))
+        }
+    }
+}
+
+/// A database serialized by [`Conn::serialize`].
+pub struct SerializedDatabase {
+    ptr: Option<NonNull<u8>>,
+    len: usize,
+}
+
+unsafe impl Send for SerializedDatabase {}
+unsafe impl Sync for SerializedDatabase {}
+
+impl Deref for SerializedDatabase {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self.ptr {
+            Some(ptr) => unsafe { slice::from_raw_parts(ptr.as_ptr(), self.len) },
+            None => &[],
+        }
+    }
+}
+
+impl AsRef<[u8]> for SerializedDatabase {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Debug for SerializedDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerializedDatabase")
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Drop for SerializedDatabase {
+    fn drop(&mut self) {
+        if let Some(ptr) = self.ptr {
+            unsafe { sqlite3_free(ptr.as_ptr().cast()) };
+        }
+    }
+}
+
+/// A growable buffer allocated with SQLite's allocator,
+/// so that it can be handed to [`sqlite3_deserialize`].
+#[derive(Default)]
+struct SqliteBuffer {
+    ptr: Option<NonNull<u8>>,
+    len: usize,
+    capacity: usize,
+}
+
+impl SqliteBuffer {
+    fn extend_from_slice(&mut self, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let nomem = || ResultCode::NOMEM.to_result().unwrap_err();
+        let needed = self.len.checked_add(data.len()).ok_or_else(nomem)?;
+        if needed > self.capacity {
+            let capacity = needed.max(self.capacity.saturating_mul(2));
+            let ptr = unsafe {
+                sqlite3_realloc64(
+                    self.ptr.map_or(ptr::null_mut(), |ptr| ptr.as_ptr().cast()),
+                    capacity as u64,
+                )
+            };
+            self.ptr = Some(NonNull::new(ptr.cast::<u8>()).ok_or_else(nomem)?);
+            self.capacity = capacity;
+        }
+        unsafe {
+            let end = self.ptr.unwrap().as_ptr().add(self.len);
+            ptr::copy_nonoverlapping(data.as_ptr(), end, data.len());
+        }
+        self.len = needed;
+        Ok(())
+    }
+
+    /// Releases ownership of the buffer, returning its address (or NULL if empty).
+    fn into_raw(self) -> *mut u8 {
+        let ptr = self.ptr.map_or(ptr::null_mut(), NonNull::as_ptr);
+        std::mem::forget(self);
+        ptr
+    }
+}
+
+impl io::Write for SqliteBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf)
+            .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for SqliteBuffer {
+    fn drop(&mut self) {
+        if let Some(ptr) = self.ptr {
+            unsafe { sqlite3_free(ptr.as_ptr().cast()) };
+        }
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl Connection {
+    /// Writes a gzip-compressed copy of the main database to `writer`.
+    /// The result can be loaded with [`Connection::restore_compressed`].
+    ///
+    /// Databases that SQLite keeps in a single buffer,
+    /// such as those loaded with [`deserialize`][Connection::deserialize]
+    /// or opened with the `memdb` VFS,
+    /// are compressed directly from that buffer.
+    /// Other databases, including plain `:memory:` databases,
+    /// are first copied with `VACUUM INTO` to a file in [`std::env::temp_dir`],
+    /// which is compressed as it is read and removed afterward,
+    /// so that the database is never held in memory in its entirety.
+    /// Because `VACUUM INTO` can't be used inside a transaction,
+    /// neither can this method for such databases.
+    pub fn backup_compressed(&mut self, writer: impl io::Write) -> Result<()> {
+        let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+        match self.serialize_borrow(&main_schema()) {
+            Some(mut data) => io::copy(&mut data, &mut encoder).map_err(io_error)?,
+            None => {
+                let copy = TempCopy::new();
+                let path = copy.0.to_str().ok_or_else(|| {
+                    Error::new(ResultCode::CANTOPEN, "temporary directory is not UTF-8")
+                })?;
+                self.execute("VACUUM main INTO ?1;", (path,))?;
+                std::fs::File::open(&copy.0)
+                    .and_then(|mut file| io::copy(&mut file, &mut encoder))
+                    .map_err(io_error)?
+            }
+        };
+        encoder.finish().map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Replaces the main database with the contents of
+    /// a gzip-compressed database written by [`Connection::backup_compressed`].
+    /// The database is decompressed as it is read
+    /// directly into the buffer that SQLite takes ownership of.
+    pub fn restore_compressed(&mut self, reader: impl io::Read) -> Result<()> {
+        let mut buf = SqliteBuffer::default();
+        io::copy(&mut flate2::read::GzDecoder::new(reader), &mut buf).map_err(io_error)?;
+        self.deserialize_buffer(&main_schema(), buf)
+    }
+}
+
+#[cfg(feature = "flate2")]
+fn main_schema() -> std::ffi::CString {
+    std::ffi::CString::new("main").unwrap()
+}
+
+/// The path of a temporary copy of a database made by [`Connection::backup_compressed`],
+/// removed when dropped.
+#[cfg(feature = "flate2")]
+struct TempCopy(std::path::PathBuf);
+
+#[cfg(feature = "flate2")]
+impl TempCopy {
+    fn new() -> Self {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        TempCopy(std::env::temp_dir().join(format!(
+            "zombiezen-sqlite-backup-{}-{}.db",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+        )))
+    }
+}
+
+#[cfg(feature = "flate2")]
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(feature = "flate2")]
+fn io_error(err: std::io::Error) -> Error {
+    Error::new(ResultCode::IOERR, err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+    const MAIN: ConstCStr = const_cstr!("main");
+
+    fn content_hash(conn: &Conn) -> String {
+        conn.query_row(
+            "SELECT group_concat(x || ':' || hex(y), ',') FROM (SELECT * FROM t ORDER BY x);",
+            (),
+            |stmt| Ok(stmt.column_text(0).unwrap().to_string()),
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    fn populate(conn: &Conn) {
+        conn.execute("CREATE TABLE t (x INTEGER PRIMARY KEY, y BLOB);", ())
+            .unwrap();
+        conn.execute(
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 500) \
+            INSERT INTO t SELECT x, randomblob(100) FROM c;",
+            (),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        populate(&conn);
+        let data = conn.serialize(MAIN.as_cstr()).unwrap();
+        assert!(!data.is_empty());
+
+        let mut conn2 = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn2.deserialize(MAIN.as_cstr(), &data).unwrap();
+        assert_eq!(content_hash(&conn2), content_hash(&conn));
+
+        let err = conn.serialize(const_cstr!("bogus").as_cstr()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+    }
+
+    #[test]
+    fn test_serialize_borrow() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        populate(&conn);
+        let data = conn.serialize(MAIN.as_cstr()).unwrap();
+
+        let mut mem = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        mem.deserialize(MAIN.as_cstr(), &data).unwrap();
+        assert_eq!(mem.serialize_borrow(MAIN.as_cstr()), Some(&data[..]));
+
+        let mut shared = Connection::open(
+            const_cstr!("file:/serialize_borrow_test?vfs=memdb"),
+            OpenFlags::default(),
+        )
+        .unwrap();
+        populate(&shared);
+        assert_eq!(shared.serialize_borrow(MAIN.as_cstr()), None);
+
+        let dir = TempDir::new("serialize-borrow");
+        let mut file = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        populate(&file);
+        assert_eq!(file.serialize_borrow(MAIN.as_cstr()), None);
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_compressed_round_trip() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        populate(&conn);
+        let mut compressed = Vec::new();
+        conn.backup_compressed(&mut compressed).unwrap();
+        assert!(compressed.len() < conn.serialize(MAIN.as_cstr()).unwrap().len());
+
+        let mut conn2 = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn2.restore_compressed(compressed.as_slice()).unwrap();
+        assert_eq!(content_hash(&conn2), content_hash(&conn));
+
+        // The restored database is held in a single buffer,
+        // so it is compressed without a temporary copy.
+        // It can also grow past the restored size.
+        conn2
+            .execute("INSERT INTO t SELECT x + 500, y FROM t;", ())
+            .unwrap();
+        assert!(conn2.serialize_borrow(MAIN.as_cstr()).is_some());
+        let mut compressed2 = Vec::new();
+        conn2.backup_compressed(&mut compressed2).unwrap();
+        let mut conn3 = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn3.restore_compressed(compressed2.as_slice()).unwrap();
+        assert_eq!(content_hash(&conn3), content_hash(&conn2));
+
+        let err = conn3.restore_compressed(&b"not gzip"[..]).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::IOERR);
+    }
+}
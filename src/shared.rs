@@ -0,0 +1,164 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CStr;
+
+use libsqlite3_sys::SQLITE_OPEN_FULLMUTEX;
+
+use crate::connection::DbMutexGuard;
+use crate::*;
+
+impl Connection {
+    /// Opens a read-only connection to the database at `filename`
+    /// that can be shared among threads.
+    ///
+    /// The connection is opened in SQLite's [serialized] threading mode,
+    /// so calls from different threads are serialized by the connection's mutex.
+    /// Each thread should prepare its own statements.
+    /// This offers a simple way to run reads from many threads without a pool,
+    /// but queries from different threads do not run in parallel.
+    ///
+    /// The connection cannot write to the database:
+    /// writes need a separate connection opened with [`Connection::open`].
+    /// In [WAL mode], reads on the shared connection do not block that writer.
+    ///
+    /// [serialized]: https://www.sqlite.org/threadsafe.html
+    /// [WAL mode]: https://www.sqlite.org/wal.html
+    #[doc(alias = "SQLITE_OPEN_FULLMUTEX")]
+    pub fn open_readonly_shared(filename: impl AsRef<CStr>) -> Result<SharedConnection> {
+        let conn = Connection::open_v2(
+            filename.as_ref(),
+            OpenFlags::READONLY | OpenFlags::URI,
+            SQLITE_OPEN_FULLMUTEX,
+            None,
+        )?;
+        Ok(SharedConnection { conn })
+    }
+}
+
+/// A read-only connection that can be used from multiple threads at once,
+/// returned by [`Connection::open_readonly_shared`].
+///
+/// The connection is only reachable through methods
+/// that hold the connection's mutex for the whole call,
+/// so errors and change counts reported by SQLite
+/// cannot be clobbered by calls from other threads.
+#[derive(Debug)]
+pub struct SharedConnection {
+    conn: Connection,
+}
+
+/// Shared connections are opened in serialized mode
+/// and every method holds the connection's mutex while it uses the connection,
+/// so calls from multiple threads never interleave.
+/// The connection is only lent out as `&Conn`,
+/// so the connection's hooks cannot be changed while it is shared.
+unsafe impl Sync for SharedConnection {}
+
+impl SharedConnection {
+    /// Calls `f` with the connection
+    /// while holding the connection's mutex.
+    /// Calls from other threads wait until `f` returns.
+    /// Statements prepared in `f` cannot outlive it.
+    #[doc(alias = "sqlite3_db_mutex")]
+    pub fn with_conn<R>(&self, f: impl FnOnce(&Conn) -> R) -> R {
+        let _guard = DbMutexGuard::lock(self.conn.as_ptr());
+        f(&self.conn)
+    }
+
+    /// Runs [`Conn::query_row`] while holding the connection's mutex.
+    pub fn query_row<T>(
+        &self,
+        sql: &str,
+        params: impl Params,
+        f: impl FnOnce(&mut Statement<'_>) -> Result<T>,
+    ) -> Result<Option<T>> {
+        self.with_conn(|conn| conn.query_row(sql, params, f))
+    }
+
+    /// Runs [`Conn::query`] while holding the connection's mutex.
+    pub fn query<P: Params, R: FromRow>(&self, sql: &str, params: P) -> Result<Vec<R>> {
+        self.with_conn(|conn| conn.query(sql, params))
+    }
+
+    /// Runs [`Conn::execute`] while holding the connection's mutex.
+    /// Since the connection is read-only,
+    /// statements that write to the database fail with [`ResultCode::READONLY`].
+    pub fn execute(&self, sql: &str, params: impl Params) -> Result<usize> {
+        self.with_conn(|conn| conn.execute(sql, params))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_open_readonly_shared() {
+        let dir = TempDir::new("readonly-shared");
+        let path_cstr = dir.join_cstr("test.db");
+        {
+            let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            conn.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+            conn.execute(
+                "CREATE TABLE t AS \
+                WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100) \
+                SELECT x FROM c;",
+                (),
+            )
+            .unwrap();
+        }
+
+        let shared = Connection::open_readonly_shared(&path_cstr).unwrap();
+        thread::scope(|s| {
+            let handles = (0..8i64)
+                .map(|i| {
+                    let shared = &shared;
+                    s.spawn(move || {
+                        for j in 0..50i64 {
+                            let n = i * 50 + j;
+                            let got = shared
+                                .query_row(
+                                    "SELECT sum(x), ?1 FROM t WHERE x <= ?1;",
+                                    (n % 100 + 1,),
+                                    |stmt| Ok((stmt.column_i64(0), stmt.column_i64(1))),
+                                )
+                                .unwrap();
+                            let k = n % 100 + 1;
+                            assert_eq!(got, Some((k * (k + 1) / 2, k)));
+
+                            let missing = format!("missing_{}", n);
+                            let err = shared
+                                .execute(&format!("SELECT {} FROM t;", missing), ())
+                                .unwrap_err();
+                            assert!(
+                                err.to_string().contains(&missing),
+                                "error = {}, want mention of {}",
+                                err,
+                                missing
+                            );
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+
+        let err = shared
+            .execute("INSERT INTO t VALUES (101);", ())
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::READONLY);
+        let n: i64 = shared
+            .with_conn(|conn| {
+                conn.query_row("SELECT count(*) FROM t;", (), |stmt| Ok(stmt.column_i64(0)))
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(n, 100);
+    }
+}
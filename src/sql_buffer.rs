@@ -0,0 +1,116 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CString;
+
+use crate::*;
+
+/// A buffer that accumulates lines of SQL input
+/// until they form one or more complete statements,
+/// as needed by an interactive shell.
+///
+/// Completeness is determined by [`is_complete`],
+/// so statements split across lines, trailing comments,
+/// and semicolons inside strings, identifiers, or comments
+/// are all handled the same way SQLite's own shell handles them.
+///
+/// # Example
+///
+/// ```
+/// # use zombiezen_sqlite::SqlBuffer;
+/// let mut buf = SqlBuffer::new();
+/// assert!(!buf.push_line("SELECT 'a;b'"));
+/// assert!(buf.push_line("  AS x; -- done"));
+/// assert_eq!(buf.take().as_deref(), Some("SELECT 'a;b'\n  AS x; -- done\n"));
+/// assert!(buf.is_empty());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SqlBuffer {
+    sql: String,
+}
+
+impl SqlBuffer {
+    /// Returns a new empty buffer.
+    pub fn new() -> Self {
+        SqlBuffer::default()
+    }
+
+    /// Appends `line` and a newline to the buffer,
+    /// returning whether the buffer now holds complete statements.
+    pub fn push_line(&mut self, line: &str) -> bool {
+        self.sql.push_str(line);
+        self.sql.push('\n');
+        self.is_complete()
+    }
+
+    /// Reports whether the buffer ends with a complete SQL statement.
+    /// A buffer that contains only whitespace and comments is never complete.
+    pub fn is_complete(&self) -> bool {
+        // sqlite3_complete stops at the first NUL byte, so do the same.
+        let sql = self.sql.split('\0').next().unwrap_or_default();
+        is_complete(CString::new(sql).unwrap())
+    }
+
+    /// Reports whether the buffer contains only whitespace.
+    pub fn is_empty(&self) -> bool {
+        self.sql.trim().is_empty()
+    }
+
+    /// Returns the buffered text.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.sql
+    }
+
+    /// Removes and returns the buffered text if it is [complete][SqlBuffer::is_complete].
+    /// Otherwise, the buffer is left unchanged and `None` is returned.
+    pub fn take(&mut self) -> Option<String> {
+        if self.is_complete() {
+            Some(std::mem::take(&mut self.sql))
+        } else {
+            None
+        }
+    }
+
+    /// Discards the buffered text,
+    /// for example when the user cancels a partial statement.
+    pub fn clear(&mut self) {
+        self.sql.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_buffer_lines() {
+        let mut buf = SqlBuffer::new();
+        assert!(buf.is_empty());
+        assert!(!buf.push_line("SELECT"));
+        assert!(buf.take().is_none());
+        assert!(!buf.push_line("1"));
+        assert!(buf.push_line(";"));
+        assert_eq!(buf.take().as_deref(), Some("SELECT\n1\n;\n"));
+        assert!(buf.is_empty());
+        assert!(!buf.is_complete());
+    }
+
+    #[test]
+    fn test_sql_buffer_tricky() {
+        let mut buf = SqlBuffer::new();
+        assert!(!buf.push_line("-- a comment;"));
+        assert!(!buf.push_line("SELECT 'semi;colon', \"x;\" /* ; */"));
+        assert!(!buf.push_line("FROM t"));
+        assert!(buf.push_line("; -- trailing comment"));
+        buf.clear();
+
+        assert!(!buf.push_line("CREATE TRIGGER tr AFTER INSERT ON t BEGIN"));
+        assert!(!buf.push_line("  DELETE FROM u;"));
+        assert!(buf.push_line("END;"));
+        buf.clear();
+
+        assert!(buf.push_line("SELECT 1; SELECT 2;"));
+        assert!(!buf.push_line("SELECT 3"));
+    }
+}
@@ -1,6 +1,9 @@
 // Copyright 2023 Ross Light
 // SPDX-License-Identifier: MIT
 
+use std::borrow::Cow;
+use std::cell::OnceCell;
+use std::collections::HashMap;
 use std::ffi::{c_char, c_int, c_uchar, CStr};
 use std::fmt::{self, Debug};
 use std::marker::PhantomData;
@@ -13,12 +16,17 @@ use libsqlite3_sys::{
     sqlite3_bind_blob64, sqlite3_bind_double, sqlite3_bind_int64, sqlite3_bind_null,
     sqlite3_bind_parameter_count, sqlite3_bind_parameter_name, sqlite3_bind_text64,
     sqlite3_bind_value, sqlite3_bind_zeroblob64, sqlite3_clear_bindings, sqlite3_column_blob,
-    sqlite3_column_bytes, sqlite3_column_count, sqlite3_column_double, sqlite3_column_int64,
-    sqlite3_column_name, sqlite3_column_text, sqlite3_column_type, sqlite3_column_value,
-    sqlite3_complete, sqlite3_db_handle, sqlite3_finalize, sqlite3_prepare_v2, sqlite3_reset,
-    sqlite3_step, sqlite3_stmt, SQLITE_DONE, SQLITE_NOMEM, SQLITE_ROW, SQLITE_UTF8,
+    sqlite3_column_bytes, sqlite3_column_bytes16, sqlite3_column_count, sqlite3_column_decltype,
+    sqlite3_column_double, sqlite3_column_int64, sqlite3_column_name, sqlite3_column_text,
+    sqlite3_column_text16, sqlite3_column_type, sqlite3_column_value, sqlite3_complete,
+    sqlite3_db_handle, sqlite3_finalize, sqlite3_prepare_v2, sqlite3_reset, sqlite3_sql,
+    sqlite3_step, sqlite3_stmt, sqlite3_stmt_readonly, sqlite3_stmt_status, SQLITE_DONE,
+    SQLITE_NOMEM, SQLITE_ROW, SQLITE_STMTSTATUS_AUTOINDEX, SQLITE_STMTSTATUS_FULLSCAN_STEP,
+    SQLITE_STMTSTATUS_SORT, SQLITE_STMTSTATUS_VM_STEP, SQLITE_UTF8,
 };
 
+use crate::connection::DbMutexGuard;
+use crate::tokenize::{tokenize, TokenKind};
 use crate::*;
 
 impl Conn {
@@ -26,7 +34,12 @@ impl Conn {
     /// The first return value is the compiled statement, if one was found.
     /// The second return value is the remaining uncompiled source.
     ///
-    /// # Example
+    /// The remaining source is a subslice of `sql`, not a copy,
+    /// so its lifetime is that of `sql` rather than that of the statement.
+    /// This allows a script of several statements
+    /// to be compiled one statement at a time without allocating.
+    ///
+    /// # Examples
     ///
     /// ```
     /// # use zombiezen_sqlite::{Connection, OpenFlags};
@@ -37,6 +50,25 @@ impl Conn {
     /// # Ok(())
     /// # }
     /// ```
+    ///
+    /// Running a script:
+    ///
+    /// ```
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # use std::ffi::CStr;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let mut script = "CREATE TABLE foo (x); INSERT INTO foo VALUES (1); -- done";
+    /// while !script.is_empty() {
+    ///     let (stmt, tail) = conn.prepare(script);
+    ///     if let Some(mut stmt) = stmt? {
+    ///         while stmt.step()?.has_row() {}
+    ///     }
+    ///     script = tail;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
     #[doc(alias("sqlite3_prepare", "sqlite3_prepare_v2"))]
     pub fn prepare<'c, 's>(&'c self, sql: &'s str) -> (Result<Option<Statement<'c>>>, &'s str) {
         let n_byte: c_int = match sql.len().try_into() {
@@ -46,6 +78,7 @@ impl Conn {
         let mut stmt = MaybeUninit::uninit();
         let z_sql = sql.as_ptr() as *const c_char;
         let mut tail = MaybeUninit::uninit();
+        let _guard = DbMutexGuard::lock(self.as_ptr());
         let rc = ResultCode(unsafe {
             sqlite3_prepare_v2(
                 self.as_ptr(),
@@ -69,7 +102,8 @@ impl Conn {
             )
         } else {
             debug_assert!(unsafe { stmt.assume_init() }.is_null());
-            return (Err(self.error().unwrap()), tail);
+            let stmt_sql = &sql[..tail_start as usize];
+            return (Err(self.error().unwrap().with_sql(stmt_sql)), tail);
         }
     }
 }
@@ -106,6 +140,7 @@ impl Conn {
 pub struct Statement<'c> {
     ptr: *mut sqlite3_stmt,
     has_row: bool,
+    param_indices: OnceCell<HashMap<String, usize>>,
     conn: PhantomData<&'c Connection>,
 }
 
@@ -115,10 +150,16 @@ impl<'c> Statement<'c> {
         Statement {
             ptr: ptr.as_ptr(),
             has_row: false,
+            param_indices: OnceCell::new(),
             conn: PhantomData,
         }
     }
 
+    #[inline(always)]
+    pub(crate) fn as_ptr(&self) -> *mut sqlite3_stmt {
+        self.ptr
+    }
+
     fn error(&self) -> Option<Error> {
         let db = NonNull::new(unsafe { sqlite3_db_handle(self.ptr) });
         db.and_then(|db| Error::get(db))
@@ -130,6 +171,7 @@ impl<'c> Statement<'c> {
     /// and `step` should not be called again
     /// without first calling [`reset`][Statement::reset].
     pub fn step(&mut self) -> Result<StepResult> {
+        let _guard = DbMutexGuard::lock(unsafe { sqlite3_db_handle(self.ptr) });
         let rc = ResultCode(unsafe { sqlite3_step(self.ptr) });
         match rc {
             ResultCode::ROW => {
@@ -142,7 +184,7 @@ impl<'c> Statement<'c> {
             }
             _ => {
                 self.has_row = false;
-                Err(self.error().unwrap())
+                Err(self.error().unwrap().with_sql(self.sql()))
             }
         }
     }
@@ -157,6 +199,7 @@ impl<'c> Statement<'c> {
     /// use [`clear_bindings`][Statement::clear_bindings] to do that.
     pub fn reset(&mut self) -> Result<()> {
         self.has_row = false;
+        let _guard = DbMutexGuard::lock(unsafe { sqlite3_db_handle(self.ptr) });
         let rc = ResultCode(unsafe { sqlite3_reset(self.ptr) });
         match rc {
             ResultCode::OK => Ok(()),
@@ -204,21 +247,38 @@ impl<'c> Statement<'c> {
     /// The index value returned is suitable
     /// for use as the index parameter to the `bind_*` functions.
     ///
+    /// The first call builds a map of all the statement's parameter names,
+    /// so subsequent lookups do not need to scan the parameters.
+    ///
     /// [SQL parameter]: https://www.sqlite.org/c3ref/bind_blob.html
     pub fn bind_parameter_index(&self, name: &str) -> Option<usize> {
-        // Calling sqlite3_bind_parameter_index would require allocating a C string.
-        // Since SQLite will always store these as UTF-8
-        // and we can guarantee the input is valid UTF-8 (more convenient anyway),
-        // we just do the iteration ourselves.
-        (1..=self.bind_parameter_count()).find_map(|i| {
-            self.bind_parameter_name(i).and_then(|name_i| {
-                if name.as_bytes() == name_i.to_bytes() {
-                    Some(i)
-                } else {
-                    None
-                }
+        self.param_indices
+            .get_or_init(|| {
+                // Calling sqlite3_bind_parameter_index would require allocating a C string
+                // and scanning the parameters on every call.
+                // Since SQLite will always store these as UTF-8,
+                // we can build the map ourselves.
+                (1..=self.bind_parameter_count())
+                    .filter_map(|i| {
+                        let name = self.bind_parameter_name(i)?.to_str().ok()?;
+                        Some((name.to_string(), i))
+                    })
+                    .collect()
             })
-        })
+            .get(name)
+            .copied()
+    }
+
+    /// Binds `v` to the [SQL parameter] with the given `name`,
+    /// as determined by [`bind_parameter_index`][Statement::bind_parameter_index].
+    /// Returns a [`ResultCode::RANGE`] error if there is no such parameter.
+    ///
+    /// [SQL parameter]: https://www.sqlite.org/c3ref/bind_blob.html
+    pub fn bind_by_name<V: ToSql + ?Sized>(&mut self, name: &str, v: &V) -> Result<()> {
+        let i = self
+            .bind_parameter_index(name)
+            .ok_or_else(|| Error::new(ResultCode::RANGE, format!("no parameter named {}", name)))?;
+        v.bind_to(self, i)
     }
 
     #[inline(always)]
@@ -227,6 +287,7 @@ impl<'c> Statement<'c> {
         F: FnOnce(*mut sqlite3_stmt, c_int) -> c_int,
     {
         let i = Self::usize_to_int(i)?;
+        let _guard = DbMutexGuard::lock(unsafe { sqlite3_db_handle(self.ptr) });
         let rc = ResultCode(f(self.ptr, i));
         if rc.is_success() {
             Ok(())
@@ -244,6 +305,11 @@ impl<'c> Statement<'c> {
     /// Sets a host parameter in a statement the given value.
     /// The first host parameter has an index of 1.
     /// This function operates on both protected and unprotected values.
+    ///
+    /// The value keeps its type and is copied without being converted to a Rust type,
+    /// so this is an efficient way to bind a [column value][Statement::column_value]
+    /// from another statement or a function argument.
+    #[doc(alias = "sqlite3_bind_value")]
     pub fn bind_value<V: Value + ?Sized>(&mut self, i: usize, v: &V) -> Result<()> {
         self.bind(i, |stmt, i| unsafe {
             sqlite3_bind_value(stmt, i, v.as_ptr())
@@ -325,6 +391,40 @@ impl<'c> Statement<'c> {
         }
     }
 
+    /// Returns the declared type of the table column
+    /// that the `i`th result column was read from.
+    /// The leftmost column is number 0.
+    /// Will be `None` if `i >= self.column_count()`
+    /// or the result column is an expression or subquery
+    /// rather than a table column.
+    #[doc(alias = "sqlite3_column_decltype")]
+    pub fn column_decltype(&self, i: usize) -> Option<String> {
+        if i >= self.column_count() {
+            return None;
+        }
+        // As with column_name, the returned string is only valid
+        // until the statement is reprepared.
+        unsafe {
+            let s = sqlite3_column_decltype(self.ptr, i as c_int);
+            if s.is_null() {
+                return None;
+            }
+            let s = CStr::from_ptr(s);
+            s.to_str().ok().map(|s| s.to_string())
+        }
+    }
+
+    /// Returns the affinity of the table column
+    /// that the `i`th result column was read from,
+    /// as derived from its [declared type][Statement::column_decltype].
+    /// The leftmost column is number 0.
+    /// Result columns that are expressions or subqueries
+    /// have [`Affinity::Blob`] (no affinity).
+    pub fn column_affinity(&self, i: usize) -> Affinity {
+        self.column_decltype(i)
+            .map_or(Affinity::Blob, |t| Affinity::from_decltype(&t))
+    }
+
     #[inline(always)]
     fn check_col(&self, i: usize) {
         assert!(self.has_row);
@@ -385,6 +485,10 @@ impl<'c> Statement<'c> {
     /// [converting it] if necessary.
     /// The leftmost column is number 0.
     ///
+    /// If the database's [encoding][Conn::encoding] is UTF-16,
+    /// then the text is converted to UTF-8 on every call.
+    /// [`Statement::column_text_raw`] can read the text without conversion.
+    ///
     /// [converting it]: https://www.sqlite.org/c3ref/column_blob.html
     ///
     /// # Errors
@@ -432,6 +536,59 @@ impl<'c> Statement<'c> {
             .map_err(|err| TextError::new(bytes_with_nul, err))
     }
 
+    /// Returns the bytes of the value in the `i`th column as `TEXT`
+    /// in the given encoding, without a trailing NUL.
+    /// The leftmost column is number 0.
+    ///
+    /// If `encoding` is the database's [encoding][Conn::encoding]
+    /// and is UTF-8 or native-endian UTF-16,
+    /// then the text is returned as stored and no conversion takes place.
+    /// SQLite only returns UTF-16 in native byte order,
+    /// so other UTF-16 encodings are converted twice:
+    /// SQLite converts the text to native-endian UTF-16
+    /// and then the bytes of each code unit are swapped.
+    /// For a database stored in non-native-endian UTF-16,
+    /// this yields the stored bytes, but in a new buffer.
+    /// Otherwise the text is converted like [`Statement::column_text`] does.
+    /// The bytes are not validated,
+    /// so text stored with invalid UTF-8 or UTF-16 is returned as-is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the statement has not returned a row
+    /// or if `i >= self.column_count()`.
+    pub fn column_text_raw(&mut self, i: usize, encoding: Encoding) -> Cow<'_, [u8]> {
+        self.check_col(i);
+        if encoding == Encoding::Utf8 {
+            return Cow::Borrowed(unsafe {
+                let ptr = sqlite3_column_text(self.ptr, i as c_int);
+                if ptr.is_null() {
+                    return Cow::Borrowed(b"");
+                }
+                let n = sqlite3_column_bytes(self.ptr, i as c_int);
+                slice::from_raw_parts(ptr, n as usize)
+            });
+        }
+        let native = unsafe {
+            let ptr = sqlite3_column_text16(self.ptr, i as c_int);
+            if ptr.is_null() {
+                return Cow::Borrowed(b"");
+            }
+            let n = sqlite3_column_bytes16(self.ptr, i as c_int);
+            slice::from_raw_parts(ptr as *const u8, n as usize)
+        };
+        if encoding == Encoding::utf16_native() {
+            Cow::Borrowed(native)
+        } else {
+            Cow::Owned(
+                native
+                    .chunks_exact(2)
+                    .flat_map(|unit| [unit[1], unit[0]])
+                    .collect(),
+            )
+        }
+    }
+
     /// Returns the value in the `i`th column as a `BLOB` (byte slice),
     /// [converting it] if necessary.
     /// The leftmost column is number 0.
@@ -469,6 +626,176 @@ impl<'c> Statement<'c> {
         }
     }
 
+    /// Returns the SQL text used to create the statement.
+    #[doc(alias = "sqlite3_sql")]
+    pub fn sql(&self) -> &str {
+        unsafe {
+            let ptr = sqlite3_sql(self.ptr);
+            if ptr.is_null() {
+                return "";
+            }
+            // SQLite stores the text as passed to prepare, which was a &str.
+            str::from_utf8_unchecked(CStr::from_ptr(ptr).to_bytes())
+        }
+    }
+
+    /// Reports whether the statement makes no direct changes to the database file.
+    /// Transaction control statements like `BEGIN` and `COMMIT` are read-only
+    /// even though they affect the connection's transaction state,
+    /// and a statement that calls an application-defined function
+    /// may still change the database indirectly.
+    #[doc(alias = "sqlite3_stmt_readonly")]
+    pub fn is_readonly(&self) -> bool {
+        unsafe { sqlite3_stmt_readonly(self.ptr) != 0 }
+    }
+
+    /// Returns the statement's SQL text as it was prepared
+    /// with every string, blob, and numeric literal replaced by `?`
+    /// and comments removed.
+    /// Unlike [`sqlite3_expanded_sql`],
+    /// host parameters are left as written
+    /// rather than being replaced by their bound values,
+    /// so bound values are never included.
+    /// The result can be logged to show the shape of a query
+    /// without exposing the data it operates on.
+    ///
+    /// Only literals are redacted:
+    /// identifiers, keywords, and parameter names are kept,
+    /// so sensitive data must not be placed in those.
+    ///
+    /// [`sqlite3_expanded_sql`]: https://www.sqlite.org/c3ref/expanded_sql.html
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Connection, OpenFlags};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let mut stmt = conn
+    ///     .prepare("SELECT 'hunter2' = :password;")
+    ///     .0?
+    ///     .unwrap();
+    /// stmt.bind_by_name(":password", "hunter2")?;
+    /// assert_eq!(stmt.redacted_sql(), "SELECT ? = :password;");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[doc(alias = "sqlite3_sql")]
+    pub fn redacted_sql(&self) -> String {
+        redact_sql_literals(self.sql())
+    }
+
+    /// Returns the current value of the given counter.
+    /// If `reset` is true, then the counter is reset to zero after being read.
+    #[doc(alias = "sqlite3_stmt_status")]
+    pub fn status(&self, op: StmtStatus, reset: bool) -> i64 {
+        i64::from(unsafe { sqlite3_stmt_status(self.ptr, op as c_int, reset as c_int) })
+    }
+
+    /// Reports whether the statement has stepped through a full table scan
+    /// since it was prepared
+    /// or since the [`StmtStatus::FullscanStep`] counter was last reset.
+    /// Test suites can use this to assert that a query uses an index.
+    /// To check a single execution of a statement that is run multiple times,
+    /// reset the counter with [`Statement::status`] before running it.
+    ///
+    /// A scan of a table with fewer than two rows may not be detected.
+    #[inline]
+    pub fn had_full_scan(&self) -> bool {
+        self.status(StmtStatus::FullscanStep, false) > 0
+    }
+
+    /// Returns the approximate number of bytes of heap memory
+    /// used to store the compiled statement.
+    /// This does not include memory allocated while the statement runs,
+    /// such as for sorting or intermediate results.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[inline]
+    pub fn memory_used(&self) -> i64 {
+        self.status(StmtStatus::MemUsed, false)
+    }
+
+    /// Returns the profiling counters for the `i`-th loop
+    /// of the statement's query plan,
+    /// or `None` if `i` is out of range.
+    /// Loops are numbered from zero in the order that they appear
+    /// in the output of `EXPLAIN QUERY PLAN`.
+    ///
+    /// Counters are only collected if [`ConfigFlag::StmtScanStatus`]
+    /// was enabled on the connection before the statement was run.
+    #[cfg(feature = "buildtime_bindgen")]
+    #[doc(alias = "sqlite3_stmt_scanstatus_v2")]
+    pub fn scan_status(&self, i: usize) -> Option<ScanStatus> {
+        let idx = c_int::try_from(i).ok()?;
+        Some(ScanStatus {
+            loops: self.scan_status_value::<i64>(idx, libsqlite3_sys::SQLITE_SCANSTAT_NLOOP)?,
+            visits: self.scan_status_value::<i64>(idx, libsqlite3_sys::SQLITE_SCANSTAT_NVISIT)?,
+            estimated_rows: self
+                .scan_status_value::<f64>(idx, libsqlite3_sys::SQLITE_SCANSTAT_EST)?,
+            name: self.scan_status_str(idx, libsqlite3_sys::SQLITE_SCANSTAT_NAME),
+            explain: self.scan_status_str(idx, libsqlite3_sys::SQLITE_SCANSTAT_EXPLAIN),
+            select_id: self
+                .scan_status_value::<c_int>(idx, libsqlite3_sys::SQLITE_SCANSTAT_SELECTID)?,
+            parent_id: self
+                .scan_status_value::<c_int>(idx, libsqlite3_sys::SQLITE_SCANSTAT_PARENTID)?,
+            cycles: self.scan_status_value::<i64>(idx, libsqlite3_sys::SQLITE_SCANSTAT_NCYCLE)?,
+        })
+    }
+
+    /// Formats the [`Statement::scan_status`] counters for every loop
+    /// of the statement's query plan as a tree in the style of `EXPLAIN QUERY PLAN`,
+    /// like the `.scanstats` output of the `sqlite3` command-line shell.
+    /// Each loop's line shows the number of times it ran (`loops`),
+    /// the total number of rows it visited (`rows`),
+    /// the actual and estimated number of rows per run (`rpl` and `est`),
+    /// and the CPU cycles spent in it if SQLite can measure them.
+    /// This is intended for embedding query diagnostics in logs or admin interfaces.
+    ///
+    /// Counters are only collected if [`ConfigFlag::StmtScanStatus`]
+    /// was enabled on the connection before the statement was run.
+    #[cfg(feature = "buildtime_bindgen")]
+    pub fn scanstats_report(&self) -> String {
+        let loops = (0..).map_while(|i| self.scan_status(i)).collect::<Vec<_>>();
+        format_scan_report(&loops)
+    }
+
+    /// Resets all of the statement's [`Statement::scan_status`] counters to zero,
+    /// so that subsequent reads only reflect later executions.
+    /// [`Statement::reset`] does not reset the counters.
+    #[cfg(feature = "buildtime_bindgen")]
+    #[doc(alias = "sqlite3_stmt_scanstatus_reset")]
+    pub fn scan_status_reset(&mut self) {
+        unsafe { libsqlite3_sys::sqlite3_stmt_scanstatus_reset(self.ptr) }
+    }
+
+    #[cfg(feature = "buildtime_bindgen")]
+    fn scan_status_value<T: Copy>(&self, idx: c_int, op: c_int) -> Option<T> {
+        let mut out = MaybeUninit::<T>::uninit();
+        let rc = unsafe {
+            libsqlite3_sys::sqlite3_stmt_scanstatus_v2(
+                self.ptr,
+                idx,
+                op,
+                0,
+                out.as_mut_ptr().cast(),
+            )
+        };
+        if rc != 0 {
+            return None;
+        }
+        Some(unsafe { out.assume_init() })
+    }
+
+    #[cfg(feature = "buildtime_bindgen")]
+    fn scan_status_str(&self, idx: c_int, op: c_int) -> Option<String> {
+        let s = self.scan_status_value::<*const c_char>(idx, op)?;
+        if s.is_null() {
+            return None;
+        }
+        Some(unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned())
+    }
+
     /// Releases any resources associated with the statement
     /// and returns any error from the most recent evaluation of the statement.
     /// Even if there were no previous errors, `finalize` may still return an error
@@ -481,11 +808,21 @@ impl<'c> Statement<'c> {
     }
 
     fn finalize_internal(&mut self) -> Result<()> {
-        if ResultCode(unsafe { sqlite3_finalize(self.ptr) }).is_success() {
-            self.ptr = ptr::null_mut(); // sqlite3_finalize specifically no-ops on NULL.
+        if self.ptr.is_null() {
+            return Ok(());
+        }
+        // sqlite3_finalize destroys the statement even if it returns an error,
+        // so the connection must be obtained beforehand.
+        let db = NonNull::new(unsafe { sqlite3_db_handle(self.ptr) });
+        let _guard = db.map(|db| DbMutexGuard::lock(db.as_ptr()));
+        let rc = ResultCode(unsafe { sqlite3_finalize(self.ptr) });
+        self.ptr = ptr::null_mut();
+        if rc.is_success() {
             Ok(())
         } else {
-            Err(self.error().expect("sqlite3_finalize returned an error"))
+            Err(db
+                .and_then(Error::get)
+                .expect("sqlite3_finalize returned an error"))
         }
     }
 }
@@ -496,6 +833,23 @@ impl<'c> Drop for Statement<'c> {
     }
 }
 
+/// Replaces the literals in `sql` with `?` and removes comments.
+pub(crate) fn redact_sql_literals(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    for token in tokenize(sql) {
+        match token.kind {
+            TokenKind::String | TokenKind::Blob | TokenKind::Number => out.push('?'),
+            TokenKind::Comment => {
+                if !out.ends_with(|c: char| c.is_ascii_whitespace()) {
+                    out.push(' ');
+                }
+            }
+            _ => out.push_str(token.text),
+        }
+    }
+    out
+}
+
 /// An error value encountered when a value contains
 /// [invalid UTF-8](https://www.sqlite.org/invalidutf.html).
 #[derive(Clone, Copy, Debug)]
@@ -574,6 +928,118 @@ impl From<StepResult> for ResultCode {
     }
 }
 
+/// A counter that can be read with [`Statement::status`].
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum StmtStatus {
+    /// The number of times that SQLite has stepped forward in a table
+    /// as part of a full table scan.
+    FullscanStep = SQLITE_STMTSTATUS_FULLSCAN_STEP,
+    /// The number of sort operations that have occurred.
+    Sort = SQLITE_STMTSTATUS_SORT,
+    /// The number of rows inserted into transient indices
+    /// that were created automatically in order to help joins run faster.
+    Autoindex = SQLITE_STMTSTATUS_AUTOINDEX,
+    /// The number of virtual machine operations executed by the statement.
+    VmStep = SQLITE_STMTSTATUS_VM_STEP,
+    /// The number of times that the statement has been automatically regenerated
+    /// due to schema changes or changes to bound parameters.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    Reprepare = libsqlite3_sys::SQLITE_STMTSTATUS_REPREPARE,
+    /// The number of times that the statement has been run.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    Run = libsqlite3_sys::SQLITE_STMTSTATUS_RUN,
+    /// The number of times that a join step was bypassed
+    /// because a Bloom filter returned not-found.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    FilterMiss = libsqlite3_sys::SQLITE_STMTSTATUS_FILTER_MISS,
+    /// The number of times that a Bloom filter returned a find,
+    /// and thus the join step had to be processed as normal.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    FilterHit = libsqlite3_sys::SQLITE_STMTSTATUS_FILTER_HIT,
+    /// The approximate number of bytes of heap memory used to store the statement.
+    /// This is not reset.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    MemUsed = libsqlite3_sys::SQLITE_STMTSTATUS_MEMUSED,
+}
+
+/// Profiling counters for one loop of a statement's query plan
+/// returned by [`Statement::scan_status`].
+#[cfg(feature = "buildtime_bindgen")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanStatus {
+    /// The number of times that the loop has run.
+    pub loops: i64,
+    /// The total number of rows visited by the loop across all runs.
+    pub visits: i64,
+    /// The query planner's estimate of the average number of rows
+    /// output by each run of the loop.
+    pub estimated_rows: f64,
+    /// The name of the table or index that the loop reads from.
+    pub name: Option<String>,
+    /// The `EXPLAIN QUERY PLAN` description of the loop.
+    pub explain: Option<String>,
+    /// The identifier of the `SELECT` that the loop belongs to.
+    pub select_id: i32,
+    /// The identifier of the loop's parent in the query plan,
+    /// or zero if it is a top-level loop.
+    pub parent_id: i32,
+    /// The number of CPU cycles spent in the loop,
+    /// or zero if SQLite cannot measure cycles on this platform.
+    pub cycles: i64,
+}
+
+/// Formats `loops` as a tree for [`Statement::scanstats_report`].
+#[cfg(feature = "buildtime_bindgen")]
+fn format_scan_report(loops: &[ScanStatus]) -> String {
+    // Loops nested in a subquery or co-routine have a parent
+    // that is not itself a loop, so treat them as top-level.
+    let is_root = |s: &ScanStatus| !loops.iter().any(|p| p.select_id == s.parent_id);
+    let mut report = String::from("QUERY PLAN\n");
+    write_scan_loops(&mut report, loops, &is_root, "");
+    report
+}
+
+#[cfg(feature = "buildtime_bindgen")]
+fn write_scan_loops(
+    report: &mut String,
+    loops: &[ScanStatus],
+    is_child: &dyn Fn(&ScanStatus) -> bool,
+    prefix: &str,
+) {
+    let children = loops.iter().filter(|s| is_child(s)).collect::<Vec<_>>();
+    for (i, s) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let rows_per_loop = if s.loops > 0 {
+            s.visits as f64 / s.loops as f64
+        } else {
+            0.0
+        };
+        report.push_str(&format!(
+            "{}{}{} (loops={} rows={} rpl={:.1} est={:.1}",
+            prefix,
+            if last { "`--" } else { "|--" },
+            s.explain.as_deref().or(s.name.as_deref()).unwrap_or("?"),
+            s.loops,
+            s.visits,
+            rows_per_loop,
+            s.estimated_rows
+        ));
+        if s.cycles > 0 {
+            report.push_str(&format!(" cycles={}", s.cycles));
+        }
+        report.push_str(")\n");
+        let child_prefix = format!("{}{}", prefix, if last { "   " } else { "|  " });
+        write_scan_loops(
+            report,
+            loops,
+            &|c| c.parent_id == s.select_id && c.select_id != s.select_id,
+            &child_prefix,
+        );
+    }
+}
+
 /// Reports if the input string appears to be a complete SQL statement.
 #[doc(alias = "sqlite3_complete")]
 pub fn is_complete(s: impl AsRef<CStr>) -> bool {
@@ -594,6 +1060,310 @@ mod tests {
 
     const MEMORY: ConstCStr = const_cstr!(":memory:");
 
+    #[test]
+    fn test_redacted_sql() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT 'it''s a secret', x'DEADBEEF', 12345, 0x1F, 1.5e-3, -- hunter2\n\
+                \"col1\", ?1, :name, @other, $dollar, t1.c2 /* 987 */ FROM (SELECT 1 AS c2, 2 AS col1) AS t1;",
+            )
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        stmt.bind_text(1, "hunter2").unwrap();
+        stmt.bind_i64(2, 424242).unwrap();
+        stmt.bind_text(3, "password").unwrap();
+        stmt.bind_blob(4, b"swordfish".to_vec()).unwrap();
+
+        let got = stmt.redacted_sql();
+        assert_eq!(
+            got,
+            "SELECT ?, ?, ?, ?, ?, \n\"col1\", ?1, :name, @other, $dollar, t1.c2  \
+            FROM (SELECT ? AS c2, ? AS col1) AS t1;"
+        );
+        for secret in [
+            "secret",
+            "DEADBEEF",
+            "12345",
+            "1F",
+            "hunter2",
+            "424242",
+            "password",
+            "swordfish",
+            "987",
+        ] {
+            assert!(!got.contains(secret), "{:?} contains {:?}", got, secret);
+        }
+
+        // Quotes inside identifiers and comments do not start literals.
+        assert_eq!(
+            redact_sql_literals(
+                "SELECT X'00' AS \"a\"\"'b\", 'c' /* 'd */ FROM t -- 'e\nWHERE x = 1;"
+            ),
+            "SELECT ? AS \"a\"\"'b\", ?  FROM t \nWHERE x = ?;"
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT column1 FROM (VALUES (3), (1), (2)) ORDER BY 1;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        while stmt.step().unwrap().has_row() {}
+        assert_eq!(stmt.status(StmtStatus::Sort, true), 1);
+        assert_eq!(stmt.status(StmtStatus::Sort, false), 0);
+        assert!(stmt.status(StmtStatus::VmStep, false) > 0);
+        #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+        assert!(stmt.memory_used() > 0);
+    }
+
+    #[test]
+    fn test_column_text_raw_utf16() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        assert_eq!(conn.encoding().unwrap(), Encoding::Utf8);
+        conn.execute("PRAGMA encoding = 'UTF-16le';", ()).unwrap();
+        conn.execute("CREATE TABLE t (s TEXT);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES ('héllo');", ()).unwrap();
+        assert_eq!(conn.encoding().unwrap(), Encoding::Utf16le);
+
+        let utf16le = "héllo"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        let utf16be = "héllo"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        let mut stmt = conn
+            .prepare("SELECT s FROM t;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_text_raw(0, Encoding::Utf16le), utf16le);
+        assert_eq!(stmt.column_text_raw(0, Encoding::Utf16be), utf16be);
+        assert_eq!(stmt.column_text(0).unwrap(), "héllo");
+        assert_eq!(stmt.column_text_raw(0, Encoding::Utf8), "héllo".as_bytes());
+    }
+
+    #[test]
+    fn test_had_full_scan() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute(
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, x INTEGER, y INTEGER);",
+            (),
+        )
+        .unwrap();
+        conn.execute("CREATE INDEX t_x ON t (x);", ()).unwrap();
+        conn.execute(
+            "INSERT INTO t (x, y) VALUES (1, 10), (2, 20), (3, 30), (4, 40);",
+            (),
+        )
+        .unwrap();
+
+        let mut indexed = conn
+            .prepare("SELECT y FROM t WHERE x = ?1;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        indexed.bind_i64(1, 3).unwrap();
+        while indexed.step().unwrap().has_row() {}
+        assert!(!indexed.had_full_scan());
+
+        let mut unindexed = conn
+            .prepare("SELECT x FROM t WHERE y = ?1;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        unindexed.bind_i64(1, 30).unwrap();
+        while unindexed.step().unwrap().has_row() {}
+        assert!(unindexed.had_full_scan());
+
+        unindexed.status(StmtStatus::FullscanStep, true);
+        assert!(!unindexed.had_full_scan());
+    }
+
+    #[cfg(feature = "buildtime_bindgen")]
+    #[test]
+    fn test_scan_status() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.config(ConfigFlag::StmtScanStatus, true).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (1), (2), (3), (4), (5);", ())
+            .unwrap();
+        let mut stmt = conn
+            .prepare("SELECT x FROM t;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        while stmt.step().unwrap().has_row() {}
+        let status = stmt.scan_status(0).expect("query plan has a loop");
+        assert_eq!(status.loops, 1);
+        assert_eq!(status.visits, 5);
+        assert_eq!(status.name.as_deref(), Some("t"));
+        assert!(stmt.scan_status(1).is_none());
+
+        stmt.reset().unwrap();
+        while stmt.step().unwrap().has_row() {}
+        let status = stmt.scan_status(0).unwrap();
+        assert_eq!(status.loops, 2);
+        assert_eq!(status.visits, 10);
+    }
+
+    #[cfg(feature = "buildtime_bindgen")]
+    #[test]
+    fn test_scan_status_reset() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.config(ConfigFlag::StmtScanStatus, true).unwrap();
+        conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES (1), (2), (3);", ())
+            .unwrap();
+        let mut stmt = conn
+            .prepare("SELECT x FROM t;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        while stmt.step().unwrap().has_row() {}
+        stmt.reset().unwrap();
+
+        stmt.scan_status_reset();
+        let status = stmt.scan_status(0).unwrap();
+        assert_eq!(status.loops, 0);
+        assert_eq!(status.visits, 0);
+
+        conn.execute("INSERT INTO t VALUES (4);", ()).unwrap();
+        while stmt.step().unwrap().has_row() {}
+        let status = stmt.scan_status(0).unwrap();
+        assert_eq!(status.loops, 1);
+        assert_eq!(status.visits, 4);
+    }
+
+    #[cfg(feature = "buildtime_bindgen")]
+    #[test]
+    fn test_scanstats_report() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.config(ConfigFlag::StmtScanStatus, true).unwrap();
+        conn.execute("CREATE TABLE a (id INTEGER PRIMARY KEY, x INTEGER);", ())
+            .unwrap();
+        conn.execute("CREATE TABLE b (a_id INTEGER, y INTEGER);", ())
+            .unwrap();
+        conn.execute("INSERT INTO a VALUES (1, 10), (2, 20), (3, 30);", ())
+            .unwrap();
+        conn.execute(
+            "INSERT INTO b VALUES (1, 100), (2, 200), (3, 300), (3, 301);",
+            (),
+        )
+        .unwrap();
+        let mut stmt = conn
+            .prepare("SELECT a.x, b.y FROM b JOIN a ON a.id = b.a_id;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        while stmt.step().unwrap().has_row() {}
+
+        let report = stmt.scanstats_report();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3, "{}", report);
+        assert_eq!(lines[0], "QUERY PLAN");
+        assert!(
+            lines[1].starts_with("|--SCAN b (loops=1 rows=4 "),
+            "{}",
+            report
+        );
+        assert!(lines[2].starts_with("`--SEARCH a "), "{}", report);
+        assert!(
+            lines[2].contains("(loops=4 rows=4 rpl=1.0 est="),
+            "{}",
+            report
+        );
+    }
+
+    #[cfg(feature = "buildtime_bindgen")]
+    #[test]
+    fn test_format_scan_report() {
+        let status =
+            |select_id, parent_id, explain: &str, loops, visits, estimated_rows| ScanStatus {
+                loops,
+                visits,
+                estimated_rows,
+                name: None,
+                explain: Some(String::from(explain)),
+                select_id,
+                parent_id,
+                cycles: 0,
+            };
+        let loops = [
+            status(2, 0, "SCAN t", 1, 3, 4.0),
+            status(5, 3, "SCAN u", 3, 6, 2.0),
+            status(3, 2, "SEARCH v USING INDEX v_x (x=?)", 3, 3, 10.0),
+            status(8, 0, "SCAN w", 0, 0, 1.0),
+        ];
+        assert_eq!(
+            format_scan_report(&loops),
+            "QUERY PLAN\n\
+            |--SCAN t (loops=1 rows=3 rpl=3.0 est=4.0)\n\
+            |  `--SEARCH v USING INDEX v_x (x=?) (loops=3 rows=3 rpl=1.0 est=10.0)\n\
+            |     `--SCAN u (loops=3 rows=6 rpl=2.0 est=2.0)\n\
+            `--SCAN w (loops=0 rows=0 rpl=0.0 est=1.0)\n"
+        );
+        assert_eq!(format_scan_report(&[]), "QUERY PLAN\n");
+    }
+
+    #[test]
+    fn test_bind_value_copies_row() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE src (a, b, c, d);", ()).unwrap();
+        conn.execute("CREATE TABLE dst (a, b, c, d);", ()).unwrap();
+        conn.execute(
+            "INSERT INTO src VALUES (42, 'hello', x'00ff10', NULL), (1.5, '', x'', 7);",
+            (),
+        )
+        .unwrap();
+
+        let mut select = conn
+            .prepare("SELECT a, b, c, d FROM src ORDER BY rowid;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let mut insert = conn
+            .prepare("INSERT INTO dst VALUES (?1, ?2, ?3, ?4);")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        while select.step().unwrap().has_row() {
+            for i in 0..select.column_count() {
+                insert.bind_value(i + 1, &select.column_value(i)).unwrap();
+            }
+            insert.step().unwrap();
+            insert.reset().unwrap();
+        }
+        drop(select);
+        drop(insert);
+
+        let mismatches = conn
+            .query_row(
+                "SELECT count(*) FROM src JOIN dst ON src.rowid = dst.rowid \
+                WHERE typeof(src.a) <> typeof(dst.a) OR src.a IS NOT dst.a \
+                OR typeof(src.b) <> typeof(dst.b) OR src.b IS NOT dst.b \
+                OR typeof(src.c) <> typeof(dst.c) OR src.c IS NOT dst.c \
+                OR typeof(src.d) <> typeof(dst.d) OR src.d IS NOT dst.d;",
+                (),
+                |stmt| Ok(stmt.column_i64(0)),
+            )
+            .unwrap();
+        assert_eq!(mismatches, Some(0));
+        let blob = conn
+            .query_row("SELECT c FROM dst WHERE rowid = 1;", (), |stmt| {
+                Ok(stmt.column_blob(0).to_vec())
+            })
+            .unwrap();
+        assert_eq!(blob.as_deref(), Some(&[0x00, 0xff, 0x10][..]));
+    }
+
     #[test]
     fn test_prepare_empty() {
         let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
@@ -605,12 +1375,31 @@ mod tests {
         assert_eq!(tail, "");
     }
 
+    #[test]
+    fn test_prepare_tail() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let sql = "SELECT 1; SELECT 2; SELECT 3;";
+        let (result, tail) = conn.prepare(sql);
+        let stmt = result.unwrap().expect("statement is not empty");
+        assert_eq!(stmt.sql(), "SELECT 1;");
+        assert_eq!(tail, " SELECT 2; SELECT 3;");
+        // The tail is borrowed from the input.
+        assert_eq!(tail.as_ptr(), sql[9..].as_ptr());
+        drop(stmt);
+
+        let (result, tail) = conn.prepare(tail);
+        assert_eq!(result.unwrap().unwrap().sql(), " SELECT 2;");
+        assert_eq!(tail.as_ptr(), sql[19..].as_ptr());
+    }
+
     #[test]
     fn test_prepare_junk_statement() {
         let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
         let (stmt_result, tail) = conn.prepare("values (; select 42;");
         assert!(stmt_result.is_err(), "Received statement: {stmt_result:?}");
         assert_eq!(tail, " select 42;");
+        // SQL is only attached to errors if enabled with set_error_sql.
+        assert_eq!(stmt_result.unwrap_err().sql(), None);
     }
 
     #[test]
@@ -657,6 +1446,59 @@ mod tests {
         assert_eq!(stmt.step().unwrap(), StepResult::Done);
     }
 
+    #[test]
+    fn test_bind_parameter_index() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT :a, ?, $b, ?5, @c, :a;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        for _ in 0..2 {
+            assert_eq!(stmt.bind_parameter_index(":a"), Some(1));
+            assert_eq!(stmt.bind_parameter_index("$b"), Some(3));
+            assert_eq!(stmt.bind_parameter_index("?5"), Some(5));
+            assert_eq!(stmt.bind_parameter_index("@c"), Some(6));
+            assert_eq!(stmt.bind_parameter_index("a"), None);
+            assert_eq!(stmt.bind_parameter_index(":missing"), None);
+        }
+
+        stmt.bind_by_name(":a", "x").unwrap();
+        stmt.bind_by_name("@c", &42).unwrap();
+        let err = stmt.bind_by_name(":missing", &1).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::RANGE);
+        assert_eq!(stmt.step().unwrap(), StepResult::Row);
+        assert_eq!(stmt.column_text(0).unwrap(), "x");
+        assert_eq!(stmt.column_i64(4), 42);
+        assert_eq!(stmt.column_text(5).unwrap(), "x");
+    }
+
+    #[test]
+    fn test_column_affinity() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.prepare(
+            "CREATE TABLE t (a VARCHAR(10), b BIGINT, c FLOATING POINT, d, e DOUBLE, f DECIMAL(10,5));",
+        )
+        .0
+        .unwrap()
+        .expect("statement is not empty")
+        .step()
+        .unwrap();
+        let stmt = conn
+            .prepare("SELECT a, b, c, d, e, f, 1 + 1 FROM t;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        assert_eq!(stmt.column_affinity(0), Affinity::Text);
+        assert_eq!(stmt.column_affinity(1), Affinity::Integer);
+        // "FLOATING POINT" contains "INT".
+        assert_eq!(stmt.column_affinity(2), Affinity::Integer);
+        assert_eq!(stmt.column_affinity(3), Affinity::Blob);
+        assert_eq!(stmt.column_affinity(4), Affinity::Real);
+        assert_eq!(stmt.column_affinity(5), Affinity::Numeric);
+        assert_eq!(stmt.column_affinity(6), Affinity::Blob);
+    }
+
     #[test]
     fn test_is_complete() {
         assert!(!is_complete(const_cstr!("")));
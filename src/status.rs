@@ -0,0 +1,195 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::c_int;
+
+use libsqlite3_sys::sqlite3_db_status;
+
+use crate::*;
+
+/// A [database connection status counter] reported by [`Conn::db_status`].
+///
+/// [database connection status counter]: https://www.sqlite.org/c3ref/c_dbstatus_options.html
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DbStatus {
+    /// The number of lookaside memory slots currently checked out.
+    LookasideUsed = libsqlite3_sys::SQLITE_DBSTATUS_LOOKASIDE_USED,
+    /// The approximate number of bytes of heap memory used by the pager caches.
+    CacheUsed = libsqlite3_sys::SQLITE_DBSTATUS_CACHE_USED,
+    /// The approximate number of bytes of heap memory used to store the schemas.
+    SchemaUsed = libsqlite3_sys::SQLITE_DBSTATUS_SCHEMA_USED,
+    /// The approximate number of bytes of heap and lookaside memory
+    /// used by prepared statements.
+    StmtUsed = libsqlite3_sys::SQLITE_DBSTATUS_STMT_USED,
+    /// The number of malloc attempts that were satisfied using lookaside memory.
+    /// Only the highwater value is meaningful.
+    LookasideHit = libsqlite3_sys::SQLITE_DBSTATUS_LOOKASIDE_HIT,
+    /// The number of malloc attempts that might have been satisfied
+    /// using lookaside memory but failed due to the amount of memory requested.
+    /// Only the highwater value is meaningful.
+    LookasideMissSize = libsqlite3_sys::SQLITE_DBSTATUS_LOOKASIDE_MISS_SIZE,
+    /// The number of malloc attempts that might have been satisfied
+    /// using lookaside memory but failed because all lookaside memory was in use.
+    /// Only the highwater value is meaningful.
+    LookasideMissFull = libsqlite3_sys::SQLITE_DBSTATUS_LOOKASIDE_MISS_FULL,
+    /// The number of pager cache hits.
+    /// The highwater value is always zero.
+    CacheHit = libsqlite3_sys::SQLITE_DBSTATUS_CACHE_HIT,
+    /// The number of pager cache misses.
+    /// The highwater value is always zero.
+    CacheMiss = libsqlite3_sys::SQLITE_DBSTATUS_CACHE_MISS,
+    /// The number of dirty cache entries that have been written to disk.
+    /// The highwater value is always zero.
+    CacheWrite = libsqlite3_sys::SQLITE_DBSTATUS_CACHE_WRITE,
+    /// Whether any foreign key constraints are currently unresolved:
+    /// 1 if so, 0 if not.
+    DeferredFks = libsqlite3_sys::SQLITE_DBSTATUS_DEFERRED_FKS,
+    /// Like [`DbStatus::CacheUsed`],
+    /// but memory for caches shared with other connections
+    /// is divided evenly among the connections.
+    CacheUsedShared = libsqlite3_sys::SQLITE_DBSTATUS_CACHE_USED_SHARED,
+    /// The number of dirty cache entries that have been written to disk
+    /// in the middle of a transaction because the cache was full.
+    /// The highwater value is always zero.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    CacheSpill = libsqlite3_sys::SQLITE_DBSTATUS_CACHE_SPILL,
+}
+
+impl Conn {
+    /// Returns the current and highwater values of a status counter
+    /// for the connection.
+    /// If `reset` is true, then the highwater value is reset to the current value
+    /// (or for the cache counters, the current value is reset to zero).
+    #[doc(alias = "sqlite3_db_status")]
+    pub fn db_status(&self, op: DbStatus, reset: bool) -> Result<(i64, i64)> {
+        let mut current: c_int = 0;
+        let mut highwater: c_int = 0;
+        let rc = ResultCode(unsafe {
+            sqlite3_db_status(
+                self.as_ptr(),
+                op as c_int,
+                &mut current,
+                &mut highwater,
+                reset as c_int,
+            )
+        });
+        rc.to_result()?;
+        Ok((current.into(), highwater.into()))
+    }
+
+    /// Returns the connection's current pager cache counters.
+    /// See [`CacheStats`] for details.
+    pub fn cache_stats(&self) -> Result<CacheStats> {
+        Ok(CacheStats {
+            hits: self.db_status(DbStatus::CacheHit, false)?.0 as u64,
+            misses: self.db_status(DbStatus::CacheMiss, false)?.0 as u64,
+            writes: self.db_status(DbStatus::CacheWrite, false)?.0 as u64,
+        })
+    }
+}
+
+/// A sample of a connection's pager cache counters
+/// returned by [`Conn::cache_stats`].
+///
+/// The counters are cumulative for the life of the connection,
+/// so services that monitor cache effectiveness over time
+/// should keep the last sample and call [`CacheStats::delta`] periodically.
+/// A low [hit ratio][CacheStats::hit_ratio] suggests
+/// that the connection's [`cache_size`] is too small for its workload.
+///
+/// [`cache_size`]: https://www.sqlite.org/pragma.html#pragma_cache_size
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{Connection, OpenFlags};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// let mut stats = conn.cache_stats()?;
+/// conn.execute("SELECT count(*) FROM sqlite_master;", ())?;
+/// let delta = stats.delta(&conn)?;
+/// if let Some(ratio) = delta.hit_ratio() {
+///     println!("cache hit ratio: {:.1}%", ratio * 100.0);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CacheStats {
+    /// The number of pager cache hits.
+    pub hits: u64,
+    /// The number of pager cache misses.
+    pub misses: u64,
+    /// The number of dirty pages written to disk.
+    pub writes: u64,
+}
+
+impl CacheStats {
+    /// Returns the fraction of cache lookups that were hits,
+    /// or `None` if there were no lookups.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            None
+        } else {
+            Some(self.hits as f64 / lookups as f64)
+        }
+    }
+
+    /// Samples the connection's cache counters,
+    /// returning the changes since `self` was sampled
+    /// and replacing `self` with the new sample.
+    pub fn delta(&mut self, conn: &Conn) -> Result<CacheStats> {
+        let now = conn.cache_stats()?;
+        let delta = CacheStats {
+            hits: now.hits.saturating_sub(self.hits),
+            misses: now.misses.saturating_sub(self.misses),
+            writes: now.writes.saturating_sub(self.writes),
+        };
+        *self = now;
+        Ok(delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_cache_stats() {
+        let dir = TempDir::new("cache-stats");
+        let path_cstr = dir.join_cstr("test.db");
+        {
+            let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+            conn.execute("CREATE TABLE t (x TEXT);", ()).unwrap();
+            conn.execute(
+                "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 2000) \
+                INSERT INTO t SELECT printf('%.100c', n) FROM c;",
+                (),
+            )
+            .unwrap();
+        }
+
+        let conn = Connection::open(&path_cstr, OpenFlags::default()).unwrap();
+        conn.execute("BEGIN;", ()).unwrap();
+        let mut stats = conn.cache_stats().unwrap();
+        let count = |conn: &Conn| {
+            conn.query_row("SELECT count(*) FROM t WHERE x <> '';", (), |stmt| {
+                Ok(stmt.column_i64(0))
+            })
+            .unwrap()
+        };
+        assert_eq!(count(&conn), Some(2000));
+        let cold = stats.delta(&conn).unwrap();
+        assert!(cold.misses > 0, "cold = {:?}", cold);
+        assert_eq!(count(&conn), Some(2000));
+        let warm = stats.delta(&conn).unwrap();
+        assert_eq!(warm.misses, 0, "warm = {:?}", warm);
+        assert!(warm.hit_ratio().unwrap() > cold.hit_ratio().unwrap());
+        assert_eq!(stats, conn.cache_stats().unwrap());
+        conn.execute("COMMIT;", ()).unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! Helpers shared by the unit tests.
+
+use std::ffi::CString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A temporary directory for a test's database files.
+/// The directory and everything in it are removed when the `TempDir` is dropped,
+/// even if the test panics.
+/// Declare it before any connections that use it
+/// so that the connections are closed first.
+#[derive(Debug)]
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates a new empty directory whose name includes `name`.
+    /// The directory is unique to the process and the call,
+    /// so tests running in parallel don't share files.
+    pub(crate) fn new(name: &str) -> TempDir {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "zombiezen-sqlite-{}-{}-{}",
+            name,
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir(&path).unwrap();
+        TempDir { path }
+    }
+
+    /// Returns the path of the directory.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the path of `name` inside the directory.
+    pub(crate) fn join(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+
+    /// Returns the path of `name` inside the directory
+    /// as a filename for `Connection::open`.
+    pub(crate) fn join_cstr(&self, name: &str) -> CString {
+        CString::new(self.join(name).to_str().unwrap()).unwrap()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! A tokenizer for SQLite's dialect of SQL,
+//! shared by the functions that need to inspect or rewrite statement text.
+//! It follows [SQLite's tokenizer] closely enough
+//! to never mistake the inside of a literal, identifier, or comment for code,
+//! but does not check that the tokens form valid SQL.
+//!
+//! [SQLite's tokenizer]: https://www.sqlite.org/lang_keywords.html
+
+/// The kind of a [`Token`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// A run of whitespace.
+    Space,
+    /// A `--` comment (excluding the newline that ends it)
+    /// or a `/* */` comment.
+    Comment,
+    /// A keyword or an unquoted identifier.
+    Word,
+    /// An identifier quoted with `"`, `` ` ``, or `[]`.
+    QuotedId,
+    /// A string literal.
+    String,
+    /// A blob literal, like `x'CAFE'`.
+    Blob,
+    /// A numeric literal.
+    Number,
+    /// A host parameter, like `?1` or `:name`.
+    Variable,
+    /// Any other single character, like an operator or punctuation.
+    Other,
+}
+
+/// A token of SQL text returned by [`tokenize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub(crate) kind: TokenKind,
+    /// The byte offset of the token in the tokenized text.
+    pub(crate) start: usize,
+    pub(crate) text: &'a str,
+}
+
+impl<'a> Token<'a> {
+    /// Returns the byte offset just past the end of the token.
+    pub(crate) fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Reports whether the token is whitespace or a comment.
+    pub(crate) fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Space | TokenKind::Comment)
+    }
+
+    /// Reports whether the token is the given keyword, ignoring case.
+    pub(crate) fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(keyword)
+    }
+}
+
+/// Returns an iterator over the tokens of `sql`, including whitespace and comments.
+/// Unterminated literals, identifiers, and comments extend to the end of `sql`.
+pub(crate) fn tokenize(sql: &str) -> Tokens<'_> {
+    Tokens { sql, pos: 0 }
+}
+
+/// An iterator over the tokens of SQL text, returned by [`tokenize`].
+#[derive(Clone, Debug)]
+pub(crate) struct Tokens<'a> {
+    sql: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let start = self.pos;
+        let rest = &self.sql.as_bytes()[start..];
+        let (kind, len) = match *rest {
+            [] => return None,
+            [b, ..] if b.is_ascii_whitespace() => (
+                TokenKind::Space,
+                count_while(rest, |b| b.is_ascii_whitespace()),
+            ),
+            [b'-', b'-', ..] => (
+                TokenKind::Comment,
+                rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len()),
+            ),
+            [b'/', b'*', ..] => (
+                TokenKind::Comment,
+                rest[2..]
+                    .windows(2)
+                    .position(|w| w == b"*/")
+                    .map_or(rest.len(), |i| i + 4),
+            ),
+            [b'\'', ..] => (TokenKind::String, quoted_len(rest, b'\'', true)),
+            [b'"', ..] => (TokenKind::QuotedId, quoted_len(rest, b'"', true)),
+            [b'`', ..] => (TokenKind::QuotedId, quoted_len(rest, b'`', true)),
+            [b'[', ..] => (TokenKind::QuotedId, quoted_len(rest, b']', false)),
+            [b'x' | b'X', b'\'', ..] => (TokenKind::Blob, 1 + quoted_len(&rest[1..], b'\'', false)),
+            [b'0'..=b'9', ..] | [b'.', b'0'..=b'9', ..] => (TokenKind::Number, number_len(rest)),
+            [b'?', ..] => (
+                TokenKind::Variable,
+                1 + count_while(&rest[1..], |b| b.is_ascii_digit()),
+            ),
+            [b':' | b'@' | b'$', b, ..] if is_id_byte(b) => {
+                (TokenKind::Variable, 1 + count_while(&rest[1..], is_id_byte))
+            }
+            [b, ..] if is_id_byte(b) && b != b'$' => {
+                (TokenKind::Word, count_while(rest, is_id_byte))
+            }
+            _ => (TokenKind::Other, 1),
+        };
+        self.pos += len;
+        Some(Token {
+            kind,
+            start,
+            text: &self.sql[start..self.pos],
+        })
+    }
+}
+
+/// Reports whether `b` can appear in an unquoted identifier.
+/// Bytes of non-ASCII characters always can,
+/// so identifiers never end in the middle of a character.
+fn is_id_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn count_while(bytes: &[u8], f: impl Fn(u8) -> bool) -> usize {
+    bytes.iter().take_while(|&&b| f(b)).count()
+}
+
+/// Returns the length of the quoted token at the start of `bytes`,
+/// which ends at the first `close` byte after the opening byte.
+/// If `doubled_escapes` is true, then two `close` bytes in a row
+/// are part of the token instead of ending it.
+fn quoted_len(bytes: &[u8], close: u8, doubled_escapes: bool) -> usize {
+    let mut i = 1;
+    while i < bytes.len() {
+        i += 1;
+        if bytes[i - 1] == close {
+            if doubled_escapes && bytes.get(i) == Some(&close) {
+                i += 1;
+            } else {
+                return i;
+            }
+        }
+    }
+    bytes.len()
+}
+
+/// Returns the length of the numeric literal at the start of `bytes`.
+/// Like SQLite, any identifier characters that directly follow the number
+/// are included in the token.
+fn number_len(bytes: &[u8]) -> usize {
+    let mut i;
+    if bytes.len() > 2 && matches!(bytes[..2], [b'0', b'x' | b'X']) && bytes[2].is_ascii_hexdigit()
+    {
+        i = 2 + count_while(&bytes[2..], |b| b.is_ascii_hexdigit() || b == b'_');
+    } else {
+        let digits = |b: u8| b.is_ascii_digit() || b == b'_';
+        i = count_while(bytes, digits);
+        if bytes.get(i) == Some(&b'.') {
+            i += 1;
+            i += count_while(&bytes[i..], digits);
+        }
+        if let [b'e' | b'E', rest @ ..] = &bytes[i..] {
+            let sign = usize::from(matches!(rest.first(), Some(b'+' | b'-')));
+            if rest.get(sign).is_some_and(u8::is_ascii_digit) {
+                i += 1 + sign;
+                i += count_while(&bytes[i..], digits);
+            }
+        }
+    }
+    i + count_while(&bytes[i..], is_id_byte)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let sql = "SELECT x'CAFE', 'it''s', \"a\"\"b\", [c]]d], `e`, 1.5e-3, 0x1F, .5, \
+            ?1, :name, $d, -- done\n/* a 'quote */ café FROM t;";
+        let got = tokenize(sql)
+            .filter(|token| token.kind != TokenKind::Space)
+            .map(|token| (token.kind, token.text))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            [
+                (TokenKind::Word, "SELECT"),
+                (TokenKind::Blob, "x'CAFE'"),
+                (TokenKind::Other, ","),
+                (TokenKind::String, "'it''s'"),
+                (TokenKind::Other, ","),
+                (TokenKind::QuotedId, "\"a\"\"b\""),
+                (TokenKind::Other, ","),
+                (TokenKind::QuotedId, "[c]"),
+                (TokenKind::Other, "]"),
+                (TokenKind::Word, "d"),
+                (TokenKind::Other, "]"),
+                (TokenKind::Other, ","),
+                (TokenKind::QuotedId, "`e`"),
+                (TokenKind::Other, ","),
+                (TokenKind::Number, "1.5e-3"),
+                (TokenKind::Other, ","),
+                (TokenKind::Number, "0x1F"),
+                (TokenKind::Other, ","),
+                (TokenKind::Number, ".5"),
+                (TokenKind::Other, ","),
+                (TokenKind::Variable, "?1"),
+                (TokenKind::Other, ","),
+                (TokenKind::Variable, ":name"),
+                (TokenKind::Other, ","),
+                (TokenKind::Variable, "$d"),
+                (TokenKind::Other, ","),
+                (TokenKind::Comment, "-- done"),
+                (TokenKind::Comment, "/* a 'quote */"),
+                (TokenKind::Word, "café"),
+                (TokenKind::Word, "FROM"),
+                (TokenKind::Word, "t"),
+                (TokenKind::Other, ";"),
+            ]
+        );
+
+        // Tokens cover the whole input, even if unterminated.
+        for sql in [
+            "SELECT 'abc",
+            "SELECT \"abc",
+            "SELECT /* abc",
+            "SELECT x'ab",
+        ] {
+            let text = tokenize(sql).map(|token| token.text).collect::<String>();
+            assert_eq!(text, sql);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::CString;
+
+use crate::*;
+
+impl Connection {
+    /// Overrides the built-in `like` SQL function
+    /// (and thus the [`LIKE` operator])
+    /// with one that compares strings case-insensitively
+    /// for all of Unicode rather than only ASCII.
+    /// Both the two-argument form and the three-argument form
+    /// used by `LIKE ... ESCAPE` are replaced.
+    ///
+    /// Strings are case-folded by converting them to uppercase and then to lowercase,
+    /// which maps characters like `ß` to `ss` and `İ` to `i̇`.
+    /// Matching is performed on the folded strings,
+    /// so `_` matches a single character of the folded string.
+    /// If any operand is `NULL`, then the result is `NULL`.
+    ///
+    /// Overriding `like` prevents SQLite from using indexes
+    /// to optimize `LIKE` expressions.
+    ///
+    /// [`LIKE` operator]: https://www.sqlite.org/lang_expr.html#like
+    pub fn enable_unicode_like(&mut self) -> Result<()> {
+        let name = CString::new("like").unwrap();
+        let flags = FunctionFlags::DETERMINISTIC;
+        self.create_scalar_function(&name, Some(2), flags, like_function)?;
+        self.create_scalar_function(&name, Some(3), flags, like_function)
+    }
+}
+
+fn like_function(mut ctx: Context, args: &mut dyn ExactSizeIterator<Item = ProtectedValue>) {
+    // X LIKE Y ESCAPE Z is evaluated as like(Y, X, Z).
+    let mut values = args.collect::<Vec<_>>();
+    if values.iter().any(ProtectedValue::is_null) {
+        ctx.result_null();
+        return;
+    }
+    let escape = match values.get_mut(2) {
+        Some(escape) => {
+            let escape = escape.to_text().to_string_lossy();
+            let mut chars = escape.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => {
+                    ctx.result_error(
+                        ResultCode::ERROR,
+                        "ESCAPE expression must be a single character",
+                    );
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
+    let pattern = parse_pattern(&values[0].to_text().to_string_lossy(), escape);
+    let text = fold(&values[1].to_text().to_string_lossy()).collect::<Vec<_>>();
+    let is_match = pattern.is_some_and(|pattern| like_match(&pattern, &text));
+    ctx.result_i64(is_match.into());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `_`
+    One,
+    /// `%`
+    Many,
+}
+
+fn fold(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars()
+        .flat_map(char::to_uppercase)
+        .flat_map(char::to_lowercase)
+}
+
+/// Parses a `LIKE` pattern into folded tokens,
+/// returning `None` if the pattern ends with an escape character,
+/// which never matches.
+fn parse_pattern(pattern: &str, escape: Option<char>) -> Option<Vec<Token>> {
+    let mut tokens = Vec::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let c = if Some(c) == escape {
+            chars.next()?
+        } else if c == '%' {
+            tokens.push(Token::Many);
+            continue;
+        } else if c == '_' {
+            tokens.push(Token::One);
+            continue;
+        } else {
+            c
+        };
+        tokens.extend(fold(c.encode_utf8(&mut [0; 4])).map(Token::Literal));
+    }
+    Some(tokens)
+}
+
+fn like_match(pattern: &[Token], text: &[char]) -> bool {
+    let mut p = 0;
+    let mut t = 0;
+    // The position after the most recent `%` and the text position it was tried at.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(Token::Many) => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(Token::One) => {
+                p += 1;
+                t += 1;
+            }
+            Some(&Token::Literal(c)) if c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    p = bp;
+                    t = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&tok| tok == Token::Many)
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    fn like(conn: &Conn, sql: &str) -> Option<bool> {
+        conn.query_row(sql, (), |stmt| {
+            Ok(match stmt.column_type(0) {
+                DataType::Null => None,
+                _ => Some(stmt.column_i64(0) != 0),
+            })
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_unicode_like() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        assert_eq!(like(&conn, "SELECT 'ÉCOLE' LIKE 'école';"), Some(false));
+
+        conn.enable_unicode_like().unwrap();
+        let tests = [
+            ("SELECT 'İstanbul' LIKE 'i%';", Some(true)),
+            ("SELECT 'ÉCOLE' LIKE 'école';", Some(true)),
+            ("SELECT 'École' LIKE 'éc_le';", Some(true)),
+            ("SELECT 'STRASSE' LIKE 'straße';", Some(true)),
+            ("SELECT 'Straße' LIKE '%SS%';", Some(true)),
+            ("SELECT 'ΣΊΣΥΦΟΣ' LIKE 'σίσυφος';", Some(true)),
+            ("SELECT 'abc' LIKE 'ABD';", Some(false)),
+            ("SELECT 'abc' LIKE 'a%c%';", Some(true)),
+            ("SELECT 'abc' LIKE '%b';", Some(false)),
+            ("SELECT '' LIKE '%';", Some(true)),
+            ("SELECT 'a' LIKE '';", Some(false)),
+            ("SELECT 'x' LIKE NULL;", None),
+            ("SELECT NULL LIKE '%';", None),
+            ("SELECT '10% OFF' LIKE '10\\% off' ESCAPE '\\';", Some(true)),
+            (
+                "SELECT '10X OFF' LIKE '10\\% off' ESCAPE '\\';",
+                Some(false),
+            ),
+            ("SELECT 'a_b' LIKE 'A!_B' ESCAPE '!';", Some(true)),
+            ("SELECT 'a' LIKE 'a!' ESCAPE '!';", Some(false)),
+            ("SELECT 'É' NOT LIKE 'é';", Some(false)),
+        ];
+        for (sql, want) in tests {
+            assert_eq!(like(&conn, sql), want, "{}", sql);
+        }
+
+        let err = conn
+            .execute("SELECT 'a' LIKE 'a' ESCAPE 'ab';", ())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::ERROR);
+
+        let got = conn
+            .query_stream(
+                "SELECT column1 FROM (VALUES ('Ärger'), ('ärgern'), ('Arger')) \
+                WHERE column1 LIKE 'ÄRG%' ORDER BY 1;",
+                (),
+                |stmt| Ok(stmt.column_text(0).unwrap().to_string()),
+            )
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(got, ["Ärger", "ärgern"]);
+    }
+}
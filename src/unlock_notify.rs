@@ -0,0 +1,143 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_int, c_void};
+use std::slice;
+use std::sync::{Condvar, Mutex};
+
+use libsqlite3_sys::{
+    sqlite3, sqlite3_db_handle, sqlite3_unlock_notify, SQLITE_LOCKED_SHAREDCACHE,
+};
+
+use crate::*;
+
+impl Conn {
+    /// Blocks the current thread until the connection holding the [shared cache] lock
+    /// that caused the last [`SQLITE_LOCKED_SHAREDCACHE`] error on this connection
+    /// finishes its current transaction.
+    /// If the blocking connection has already finished its transaction,
+    /// then this returns immediately.
+    ///
+    /// This is only meaningful for connections opened with [`OpenFlags::SHARED_CACHE`].
+    /// After `wait_for_unlock_notify` returns,
+    /// the caller should retry the operation that failed.
+    /// [`Statement::blocking_step`] does this for you.
+    ///
+    /// Returns a [`ResultCode::LOCKED`] error without blocking
+    /// if waiting would cause a deadlock.
+    ///
+    /// [shared cache]: https://www.sqlite.org/sharedcache.html
+    /// [`SQLITE_LOCKED_SHAREDCACHE`]: https://www.sqlite.org/rescode.html#locked_sharedcache
+    #[doc(alias = "sqlite3_unlock_notify")]
+    pub fn wait_for_unlock_notify(&self) -> Result<()> {
+        unsafe { wait_for_unlock_notify(self.as_ptr()) }
+    }
+}
+
+impl<'c> Statement<'c> {
+    /// Same as [`step`][Statement::step],
+    /// but if the statement fails because another [shared cache] connection
+    /// holds a conflicting lock,
+    /// then it waits for the lock to be released
+    /// with [`Conn::wait_for_unlock_notify`]
+    /// and tries again.
+    ///
+    /// [shared cache]: https://www.sqlite.org/sharedcache.html
+    pub fn blocking_step(&mut self) -> Result<StepResult> {
+        loop {
+            match self.step() {
+                Err(err) if err.result_code() == ResultCode(SQLITE_LOCKED_SHAREDCACHE) => {
+                    unsafe { wait_for_unlock_notify(sqlite3_db_handle(self.as_ptr()))? };
+                    // The statement has to be reset before it can be stepped again.
+                    // The error from the reset will be the one we just handled.
+                    let _ = self.reset();
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// A one-shot event shared between the blocked thread and the unlock notify callback.
+#[derive(Default)]
+struct UnlockNotification {
+    fired: Mutex<bool>,
+    cond: Condvar,
+}
+
+unsafe fn wait_for_unlock_notify(db: *mut sqlite3) -> Result<()> {
+    let un = UnlockNotification::default();
+    let rc = ResultCode(sqlite3_unlock_notify(
+        db,
+        Some(unlock_notify_callback),
+        (&un as *const UnlockNotification).cast_mut().cast(),
+    ));
+    if rc != ResultCode::OK {
+        debug_assert_eq!(rc, ResultCode::LOCKED);
+        return Err(Error::new(
+            rc,
+            "waiting for unlock notification would deadlock",
+        ));
+    }
+    let mut fired = un.fired.lock().unwrap();
+    while !*fired {
+        fired = un.cond.wait(fired).unwrap();
+    }
+    Ok(())
+}
+
+unsafe extern "C" fn unlock_notify_callback(args: *mut *mut c_void, n_args: c_int) {
+    for &arg in slice::from_raw_parts(args, n_args as usize) {
+        let un = &*arg.cast::<UnlockNotification>();
+        *un.fired.lock().unwrap() = true;
+        un.cond.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const FILENAME: ConstCStr = const_cstr!("file:unlock_notify_test?mode=memory&cache=shared");
+
+    #[test]
+    fn test_blocking_step_waits_for_commit() {
+        let flags = OpenFlags::default() | OpenFlags::SHARED_CACHE;
+        let writer = Connection::open(FILENAME, flags).unwrap();
+        writer.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+        writer.execute("INSERT INTO t VALUES (1);", ()).unwrap();
+        writer.execute("BEGIN;", ()).unwrap();
+        writer.execute("INSERT INTO t VALUES (2);", ()).unwrap();
+
+        let reader = Connection::open(FILENAME, flags).unwrap();
+        let mut stmt = reader
+            .prepare("SELECT count(*) FROM t;")
+            .0
+            .unwrap()
+            .expect("statement is not empty");
+        let err = stmt.step().unwrap_err();
+        assert_eq!(err.result_code(), ResultCode(SQLITE_LOCKED_SHAREDCACHE));
+        let _ = stmt.reset();
+
+        let committed = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let committed = committed.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                committed.store(true, Ordering::SeqCst);
+                writer.execute("COMMIT;", ()).unwrap();
+            })
+        };
+        assert_eq!(stmt.blocking_step().unwrap(), StepResult::Row);
+        assert!(committed.load(Ordering::SeqCst));
+        assert_eq!(stmt.column_i64(0), 2);
+        handle.join().unwrap();
+    }
+}
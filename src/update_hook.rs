@@ -0,0 +1,248 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::mem;
+use std::ptr;
+use std::rc::Rc;
+
+use libsqlite3_sys::{
+    sqlite3_free, sqlite3_int64, sqlite3_malloc, sqlite3_update_hook, SQLITE_DELETE, SQLITE_INSERT,
+    SQLITE_UPDATE,
+};
+
+use crate::*;
+
+pub(crate) type UpdateHookFn = Box<dyn FnMut(UpdateAction, &str, &str, i64) + 'static>;
+
+/// The kind of row change reported to an [update hook][Connection::on_update].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UpdateAction {
+    /// A row was inserted.
+    Insert,
+    /// A row was updated.
+    Update,
+    /// A row was deleted.
+    Delete,
+}
+
+impl Connection {
+    /// Register a callback that is invoked whenever a row is inserted, updated, or deleted
+    /// in a rowid table,
+    /// replacing any previously set callback on the connection.
+    /// The callback is passed the kind of change,
+    /// the name of the database and the table that was changed,
+    /// and the rowid of the row.
+    ///
+    /// The callback is invoked while the change is in progress,
+    /// so it must not use the connection.
+    /// It is not invoked for changes to `WITHOUT ROWID` tables or internal system tables,
+    /// for rows deleted by the truncate optimization,
+    /// or for rows deleted by an `ON CONFLICT REPLACE` clause.
+    #[doc(alias = "sqlite3_update_hook")]
+    pub fn on_update(&mut self, f: impl FnMut(UpdateAction, &str, &str, i64) + 'static) {
+        const BOX_SIZE: c_int = mem::size_of::<UpdateHookFn>() as c_int;
+        let f: UpdateHookFn = Box::new(f);
+        unsafe {
+            let user_data = sqlite3_malloc(BOX_SIZE).cast::<UpdateHookFn>();
+            ptr::write(user_data, f);
+            sqlite3_update_hook(self.as_ptr(), Some(update_hook_callback), user_data.cast());
+            free_update_hook(mem::replace(&mut self.update_hook, user_data));
+        }
+    }
+
+    /// Remove the callback set by [`Connection::on_update`].
+    pub fn clear_update_hook(&mut self) {
+        unsafe {
+            sqlite3_update_hook(self.as_ptr(), None, ptr::null_mut());
+            free_update_hook(mem::replace(&mut self.update_hook, ptr::null_mut()));
+        }
+    }
+
+    /// Starts recording the rows changed by each committed transaction,
+    /// as a simple feed for logical replication or auditing.
+    /// See [`ChangeStream`] for details.
+    ///
+    /// This replaces the connection's [update hook][Connection::on_update]
+    /// and [transaction end hook][Connection::on_transaction_end].
+    /// Recording stops when either hook is replaced or cleared.
+    pub fn change_stream(&mut self) -> ChangeStream {
+        let state = Rc::new(RefCell::new(ChangeState::default()));
+        {
+            let state = state.clone();
+            self.on_update(move |action, schema, table, rowid| {
+                state.borrow_mut().pending.push(Change {
+                    action,
+                    schema: schema.to_string(),
+                    table: table.to_string(),
+                    rowid,
+                });
+            });
+        }
+        {
+            let state = state.clone();
+            self.on_transaction_end(move |committed| {
+                let mut state = state.borrow_mut();
+                let mut pending = mem::take(&mut state.pending);
+                if committed {
+                    state.committed.append(&mut pending);
+                }
+            });
+        }
+        ChangeStream { state }
+    }
+}
+
+/// A row change recorded by a [`ChangeStream`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Change {
+    /// The kind of change.
+    pub action: UpdateAction,
+    /// The name of the database containing the table, like `main`.
+    pub schema: String,
+    /// The name of the table.
+    pub table: String,
+    /// The rowid of the changed row.
+    pub rowid: i64,
+}
+
+/// A record of the rows changed by committed transactions
+/// returned by [`Connection::change_stream`].
+///
+/// Changes are buffered while a transaction is in progress
+/// and become visible through [`ChangeStream::take_committed`]
+/// once the transaction commits.
+/// The changes of a transaction that is rolled back are discarded.
+/// Only the rowids of changed rows are recorded,
+/// so a consumer that needs the new values must read them itself.
+///
+/// The changes come from the connection's [update hook][Connection::on_update],
+/// so the same exclusions apply.
+/// In addition, changes undone by `ROLLBACK TO`
+/// or by a failed statement inside a larger transaction
+/// are still reported if the transaction eventually commits.
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{Connection, OpenFlags, UpdateAction};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// conn.execute("CREATE TABLE foo (x INTEGER);", ())?;
+/// let changes = conn.change_stream();
+/// conn.execute("INSERT INTO foo VALUES (42);", ())?;
+/// for change in changes.take_committed() {
+///     assert_eq!(change.action, UpdateAction::Insert);
+///     assert_eq!(change.table, "foo");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ChangeStream {
+    state: Rc<RefCell<ChangeState>>,
+}
+
+#[derive(Debug, Default)]
+struct ChangeState {
+    /// Changes made by the transaction in progress.
+    pending: Vec<Change>,
+    /// Changes made by committed transactions, in commit order.
+    committed: Vec<Change>,
+}
+
+impl ChangeStream {
+    /// Removes and returns the changes made by transactions
+    /// that have committed since the last call.
+    pub fn take_committed(&self) -> Vec<Change> {
+        mem::take(&mut self.state.borrow_mut().committed)
+    }
+}
+
+unsafe extern "C" fn update_hook_callback(
+    user_data: *mut c_void,
+    op: c_int,
+    schema: *const c_char,
+    table: *const c_char,
+    rowid: sqlite3_int64,
+) {
+    let f = user_data.cast::<UpdateHookFn>().as_mut().unwrap();
+    let action = match op {
+        SQLITE_INSERT => UpdateAction::Insert,
+        SQLITE_UPDATE => UpdateAction::Update,
+        SQLITE_DELETE => UpdateAction::Delete,
+        _ => return,
+    };
+    f(
+        action,
+        &CStr::from_ptr(schema).to_string_lossy(),
+        &CStr::from_ptr(table).to_string_lossy(),
+        rowid,
+    );
+}
+
+unsafe fn free_update_hook(p: *mut UpdateHookFn) {
+    if p.is_null() {
+        return;
+    }
+    ptr::drop_in_place(p);
+    sqlite3_free(p.cast());
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_change_stream() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.execute("CREATE TABLE t (x TEXT);", ()).unwrap();
+        let changes = conn.change_stream();
+
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES ('a'), ('b');", ())
+            .unwrap();
+        conn.execute("ROLLBACK;", ()).unwrap();
+        assert_eq!(changes.take_committed(), []);
+
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("INSERT INTO t VALUES ('a'), ('b');", ())
+            .unwrap();
+        assert_eq!(changes.take_committed(), []);
+        conn.execute("COMMIT;", ()).unwrap();
+        let insert = |rowid| Change {
+            action: UpdateAction::Insert,
+            schema: "main".to_string(),
+            table: "t".to_string(),
+            rowid,
+        };
+        assert_eq!(changes.take_committed(), [insert(1), insert(2)]);
+        assert_eq!(changes.take_committed(), []);
+
+        // Autocommit statements are their own transactions.
+        conn.execute("UPDATE t SET x = 'c' WHERE rowid = 2;", ())
+            .unwrap();
+        conn.execute("DELETE FROM t WHERE rowid = 1;", ()).unwrap();
+        let actions = changes
+            .take_committed()
+            .into_iter()
+            .map(|c| (c.action, c.rowid))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            [(UpdateAction::Update, 2), (UpdateAction::Delete, 1)]
+        );
+
+        conn.clear_update_hook();
+        conn.execute("INSERT INTO t VALUES ('d');", ()).unwrap();
+        assert_eq!(changes.take_committed(), []);
+        conn.clear_transaction_end_hook();
+        assert_eq!(Rc::strong_count(&changes.state), 1);
+    }
+}
@@ -0,0 +1,323 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::borrow::Cow;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+/// Checks the query parameters of a [URI filename]
+/// against the parameters that SQLite recognizes.
+/// SQLite ignores unknown parameters,
+/// so a misspelled parameter name like `cahce=shared`
+/// would otherwise silently have no effect.
+/// Returns a [`ResultCode::MISUSE`] error
+/// for an unknown parameter name or an invalid value for a known parameter.
+/// Filenames that do not begin with `file:` are not URIs and are always valid.
+///
+/// [URI filename]: https://www.sqlite.org/uri.html
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CString;
+/// # use zombiezen_sqlite::validate_uri;
+/// assert!(validate_uri(&CString::new("file:data.db?mode=ro").unwrap()).is_ok());
+/// assert!(validate_uri(&CString::new("file:data.db?mode=readonly").unwrap()).is_err());
+/// ```
+pub fn validate_uri(filename: &(impl AsRef<CStr> + ?Sized)) -> Result<()> {
+    let filename = filename.as_ref().to_string_lossy();
+    let Some(rest) = filename.strip_prefix("file:") else {
+        return Ok(());
+    };
+    let rest = rest.split('#').next().unwrap_or_default();
+    let Some((_, query)) = rest.split_once('?') else {
+        return Ok(());
+    };
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        let (name, value) = param.split_once('=').unwrap_or((param, ""));
+        let name = percent_decode(name);
+        let value = percent_decode(value);
+        let valid_values: &[&str] = match name.as_ref() {
+            "vfs" | "modeof" => continue,
+            "mode" => &["ro", "rw", "rwc", "memory"],
+            "cache" => &["shared", "private"],
+            "psow" | "nolock" | "immutable" => {
+                &["1", "yes", "true", "on", "0", "no", "false", "off"]
+            }
+            _ => {
+                return Err(Error::new(
+                    ResultCode::MISUSE,
+                    format!("unknown URI parameter {:?}", name),
+                ))
+            }
+        };
+        if !valid_values
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(value.as_ref()))
+        {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                format!("invalid value {:?} for URI parameter {:?}", value, name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A builder for a [URI filename] that names a database file on disk
+/// and sets query parameters for opening it.
+/// Pass the result of [`DatabaseUri::to_cstring`] to [`Connection::open`]
+/// with [`OpenFlags::URI`] set.
+///
+/// [URI filename]: https://www.sqlite.org/uri.html
+///
+/// # Example
+///
+/// ```
+/// # use zombiezen_sqlite::DatabaseUri;
+/// let uri = DatabaseUri::new("/media/archive 2020.db")
+///     .read_only(true)
+///     .immutable(true)
+///     .to_cstring();
+/// assert_eq!(
+///     uri.to_str().unwrap(),
+///     "file:/media/archive%202020.db?mode=ro&immutable=1",
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct DatabaseUri {
+    path: PathBuf,
+    read_only: bool,
+    immutable: bool,
+    /// Other query parameters, in order.
+    params: Vec<(String, String)>,
+}
+
+impl DatabaseUri {
+    /// Returns a builder for the database file at `path`
+    /// with no query parameters.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        DatabaseUri {
+            path: path.into(),
+            read_only: false,
+            immutable: false,
+            params: Vec::new(),
+        }
+    }
+
+    /// Sets whether the database is opened read-only (`mode=ro`).
+    #[inline]
+    pub fn read_only(&mut self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Sets whether SQLite assumes that the database file cannot change (`immutable=1`),
+    /// for example because it is stored on read-only media.
+    /// SQLite then skips all locking and change detection
+    /// and never creates `-wal` or `-shm` files.
+    ///
+    /// If the file does change while an immutable connection is open,
+    /// for example because another process writes to it,
+    /// then queries can return incorrect results or [`ResultCode::CORRUPT`] errors.
+    #[inline]
+    pub fn immutable(&mut self, immutable: bool) -> &mut Self {
+        self.immutable = immutable;
+        self
+    }
+
+    /// Adds a query parameter that has no method of its own.
+    pub(crate) fn param(&mut self, name: &str, value: &str) -> &mut Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the URI filename.
+    pub fn to_cstring(&self) -> CString {
+        let mut uri = String::from("file:");
+        let path = path_bytes(&self.path);
+        #[cfg(windows)]
+        let path = path
+            .iter()
+            .map(|&b| if b == b'\\' { b'/' } else { b })
+            .collect::<Vec<u8>>();
+        #[cfg(windows)]
+        if path.get(1) == Some(&b':') {
+            // Absolute paths with a drive letter need a leading slash.
+            uri.push('/');
+        }
+        percent_encode(&mut uri, &path, b"/-._~:");
+        let mut params = Vec::new();
+        if self.read_only {
+            params.push(String::from("mode=ro"));
+        }
+        if self.immutable {
+            params.push(String::from("immutable=1"));
+        }
+        for (name, value) in &self.params {
+            let mut param = String::new();
+            percent_encode(&mut param, name.as_bytes(), b"-._~");
+            param.push('=');
+            percent_encode(&mut param, value.as_bytes(), b"-._~");
+            params.push(param);
+        }
+        if !params.is_empty() {
+            uri.push('?');
+            uri.push_str(&params.join("&"));
+        }
+        // Every byte that is not printable ASCII was escaped.
+        CString::new(uri).unwrap()
+    }
+}
+
+/// Appends `bytes` to `uri`, escaping every byte
+/// that is not an ASCII letter or digit or one of `allowed`.
+fn percent_encode(uri: &mut String, bytes: &[u8], allowed: &[u8]) {
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || allowed.contains(&b) {
+            uri.push(char::from(b));
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+impl Connection {
+    /// Opens the database file at `path` read-only
+    /// with the [`immutable`][DatabaseUri::immutable] URI parameter,
+    /// so that it can be read from read-only media
+    /// even if it is in WAL mode
+    /// and its `-wal` and `-shm` files cannot be created.
+    ///
+    /// The file must not change while the connection is open:
+    /// SQLite does not detect changes to an immutable database,
+    /// so queries could return incorrect results or [`ResultCode::CORRUPT`] errors.
+    pub fn open_immutable(path: impl AsRef<Path>) -> Result<Connection> {
+        let uri = DatabaseUri::new(path.as_ref())
+            .read_only(true)
+            .immutable(true)
+            .to_cstring();
+        Connection::open(uri, OpenFlags::READONLY | OpenFlags::URI)
+    }
+}
+
+/// Decodes `%HH` escape sequences in a URI component.
+fn percent_decode(s: &str) -> Cow<'_, str> {
+    if !s.contains('%') {
+        return Cow::Borrowed(s);
+    }
+    let mut out = Vec::with_capacity(s.len());
+    let mut bytes = s.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'%' {
+            let hex = bytes.clone().take(2).collect::<Vec<u8>>();
+            if let Some(decoded) = std::str::from_utf8(&hex)
+                .ok()
+                .filter(|hex| hex.len() == 2)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                out.push(decoded);
+                bytes.nth(1);
+                continue;
+            }
+        }
+        out.push(b);
+    }
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn validate(s: &str) -> Result<()> {
+        validate_uri(&CString::new(s).unwrap())
+    }
+
+    #[test]
+    fn test_validate_uri() {
+        validate("foo.db").unwrap();
+        validate("file:foo.db").unwrap();
+        validate("file:foo.db?mode=ro&cache=shared&vfs=unix-none&immutable=1").unwrap();
+        validate("file:foo.db?m%6Fde=rwc#fragment").unwrap();
+        validate("file:foo.db?nolock=YES").unwrap();
+
+        let err = validate("file:foo.db?mode=readonly").unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        assert!(err.message().contains("\"mode\""), "{}", err);
+        let err = validate("file:foo.db?cahce=shared").unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+        assert!(err.message().contains("\"cahce\""), "{}", err);
+        validate("file:foo.db?immutable=maybe").unwrap_err();
+    }
+
+    #[test]
+    fn test_builder_validate_uri() {
+        let err = ConnectionBuilder::new()
+            .validate_uri(true)
+            .open(CString::new("file:foo.db?mode=readonly").unwrap())
+            .unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::MISUSE);
+
+        ConnectionBuilder::new()
+            .validate_uri(true)
+            .open(CString::new("file:validate_uri_test?mode=memory&cache=shared").unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_database_uri() {
+        let uri = DatabaseUri::new("dir/a?b#c%d.db").to_cstring();
+        assert_eq!(uri.to_str().unwrap(), "file:dir/a%3Fb%23c%25d.db");
+        validate_uri(&uri).unwrap();
+        let uri = DatabaseUri::new("x.db").read_only(true).to_cstring();
+        assert_eq!(uri.to_str().unwrap(), "file:x.db?mode=ro");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_immutable() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new("immutable");
+        let path = dir.join("data.db");
+        {
+            let conn = Connection::open(dir.join_cstr("data.db"), OpenFlags::default()).unwrap();
+            conn.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+            conn.execute("CREATE TABLE t (x INTEGER);", ()).unwrap();
+            conn.execute("INSERT INTO t VALUES (42);", ()).unwrap();
+        }
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o555)).unwrap();
+
+        let result = Connection::open_immutable(&path)
+            .and_then(|conn| conn.query_row("SELECT x FROM t;", (), |stmt| Ok(stmt.column_i64(0))));
+        let names = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(result.unwrap(), Some(42));
+        assert_eq!(names, ["data.db"]);
+    }
+}
@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: MIT
 
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::ffi::c_int;
 use std::marker::PhantomData;
 use std::mem;
@@ -43,6 +44,15 @@ impl<'a> ProtectedValue<'a> {
         (unsafe { sqlite3_value_type(self.as_ptr()) }) == SQLITE_NULL
     }
 
+    /// Reports whether the value originated from a bound parameter
+    /// rather than a literal or a computed expression.
+    /// Functions can use this to only act on values supplied by the application.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_value_frombind")]
+    pub fn is_from_bind(&self) -> bool {
+        (unsafe { libsqlite3_sys::sqlite3_value_frombind(self.as_ptr()) }) != 0
+    }
+
     /// Returns the datatype of the value.
     /// The return value is undefined after calling any of
     /// [`to_i64`][ProtectedValue::to_i64],
@@ -120,6 +130,10 @@ impl DupValue {
     }
 }
 
+// A duplicated value owns a copy of its contents
+// and is not associated with any connection.
+unsafe impl Send for DupValue {}
+
 impl Value for DupValue {
     fn as_ptr(&self) -> *mut sqlite3_value {
         self.ptr.as_ptr()
@@ -197,10 +211,157 @@ pub trait Value: private::Sealed {
         }
     }
 
+    /// Compares the value to `other` using the same ordering as SQLite's `ORDER BY`:
+    /// `NULL` sorts first, followed by numbers in numeric order,
+    /// then `TEXT` values ordered by `collation`,
+    /// then `BLOB` values ordered by their bytes.
+    /// Integers and floating point numbers are compared exactly,
+    /// so applications can merge or sort query results in Rust
+    /// in the same order that SQLite would produce.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::cmp::Ordering;
+    /// # use std::ffi::CStr;
+    /// # use zombiezen_sqlite::{Collation, Connection, OpenFlags, Value};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+    /// let mut stmt = conn.prepare("SELECT 2.5, 'a';").0?.unwrap();
+    /// stmt.step()?;
+    /// let (x, y) = (stmt.column_value(0), stmt.column_value(1));
+    /// assert_eq!(x.compare(&y, Collation::Binary), Ordering::Less);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn compare<V: Value + ?Sized>(&self, other: &V, collation: Collation) -> Ordering {
+        unsafe { compare_values(self.as_ptr(), other.as_ptr(), collation) }
+    }
+
     #[doc(hidden)]
     fn as_ptr(&self) -> *mut sqlite3_value;
 }
 
+/// Compares two rows of values lexicographically
+/// using [`Value::compare`] for each column,
+/// like an `ORDER BY` clause that lists every column in ascending order.
+/// If one row is a prefix of the other, the shorter row sorts first.
+pub fn compare_rows<V: Value>(a: &[V], b: &[V], collation: Collation) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| x.compare(y, collation))
+        .find(|ord| ord.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+/// The order of each fundamental datatype in SQLite's sort order.
+fn type_rank(t: c_int) -> u8 {
+    match t {
+        SQLITE_NULL => 0,
+        SQLITE_INTEGER | SQLITE_FLOAT => 1,
+        SQLITE_TEXT => 2,
+        _ => 3,
+    }
+}
+
+unsafe fn compare_values(
+    a: *mut sqlite3_value,
+    b: *mut sqlite3_value,
+    collation: Collation,
+) -> Ordering {
+    let (ta, tb) = (sqlite3_value_type(a), sqlite3_value_type(b));
+    let rank = type_rank(ta).cmp(&type_rank(tb));
+    if rank.is_ne() {
+        return rank;
+    }
+    match (ta, tb) {
+        (SQLITE_NULL, _) => Ordering::Equal,
+        (SQLITE_INTEGER, SQLITE_INTEGER) => sqlite3_value_int64(a).cmp(&sqlite3_value_int64(b)),
+        (SQLITE_INTEGER, SQLITE_FLOAT) => {
+            compare_int_float(sqlite3_value_int64(a), sqlite3_value_double(b))
+        }
+        (SQLITE_FLOAT, SQLITE_INTEGER) => {
+            compare_int_float(sqlite3_value_int64(b), sqlite3_value_double(a)).reverse()
+        }
+        (SQLITE_FLOAT, SQLITE_FLOAT) => sqlite3_value_double(a)
+            .partial_cmp(&sqlite3_value_double(b))
+            .unwrap_or(Ordering::Equal),
+        (SQLITE_TEXT, _) => collation.compare(value_bytes(a, true), value_bytes(b, true)),
+        _ => value_bytes(a, false).cmp(value_bytes(b, false)),
+    }
+}
+
+/// Compares an integer to a floating point number without loss of precision,
+/// as SQLite does.
+fn compare_int_float(i: i64, r: f64) -> Ordering {
+    if r.is_nan() {
+        return Ordering::Greater;
+    }
+    if r < -9223372036854775808.0 {
+        return Ordering::Greater;
+    }
+    if r >= 9223372036854775808.0 {
+        return Ordering::Less;
+    }
+    i.cmp(&(r as i64))
+        .then_with(|| (i as f64).partial_cmp(&r).unwrap_or(Ordering::Equal))
+}
+
+/// Returns the bytes of a `TEXT` or `BLOB` value without converting its type.
+unsafe fn value_bytes<'a>(v: *mut sqlite3_value, text: bool) -> &'a [u8] {
+    let ptr = if text {
+        sqlite3_value_text(v)
+    } else {
+        sqlite3_value_blob(v).cast::<u8>()
+    };
+    if ptr.is_null() {
+        return b"";
+    }
+    slice::from_raw_parts(ptr, sqlite3_value_bytes(v) as usize)
+}
+
+/// One of SQLite's built-in [collating sequences],
+/// which determine how [`Value::compare`] orders `TEXT` values.
+///
+/// [collating sequences]: https://www.sqlite.org/datatype3.html#collating_sequences
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Collation {
+    /// Compares string data byte-by-byte.
+    Binary,
+    /// Like [`Collation::Binary`],
+    /// except that the 26 upper case ASCII characters
+    /// are folded to their lower case equivalents.
+    NoCase,
+    /// Like [`Collation::Binary`],
+    /// except that trailing space characters are ignored.
+    RTrim,
+}
+
+impl Collation {
+    fn compare(self, a: &[u8], b: &[u8]) -> Ordering {
+        match self {
+            Collation::Binary => a.cmp(b),
+            Collation::NoCase => a
+                .iter()
+                .map(u8::to_ascii_lowercase)
+                .cmp(b.iter().map(u8::to_ascii_lowercase)),
+            Collation::RTrim => trim_trailing_spaces(a).cmp(trim_trailing_spaces(b)),
+        }
+    }
+}
+
+impl Default for Collation {
+    /// Returns [`Collation::Binary`].
+    fn default() -> Self {
+        Collation::Binary
+    }
+}
+
+fn trim_trailing_spaces(s: &[u8]) -> &[u8] {
+    let n = s.iter().rposition(|&c| c != b' ').map_or(0, |i| i + 1);
+    &s[..n]
+}
+
 mod private {
     pub trait Sealed {}
 
@@ -250,3 +411,146 @@ impl Default for DataType {
         DataType::Null
     }
 }
+
+/// The [type affinity] of a column:
+/// the recommended type for data stored in the column.
+///
+/// [type affinity]: https://www.sqlite.org/datatype3.html#type_affinity
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Affinity {
+    /// Values are stored as [`DataType::Text`] when possible.
+    Text,
+    /// Values are stored as [`DataType::Integer`] or [`DataType::Float`] when possible.
+    Numeric,
+    /// Same as [`Affinity::Numeric`],
+    /// except that `REAL` values that can be represented as integers
+    /// are only converted in `CAST` expressions.
+    Integer,
+    /// Values are stored as [`DataType::Float`] when possible.
+    Real,
+    /// No conversions are performed.
+    /// Also known as "none".
+    Blob,
+}
+
+impl Affinity {
+    /// Determines the affinity of a column from its declared type
+    /// using the [rules] SQLite uses when creating tables.
+    ///
+    /// [rules]: https://www.sqlite.org/datatype3.html#determination_of_column_affinity
+    pub fn from_decltype(decltype: &str) -> Affinity {
+        let decltype = decltype.to_ascii_uppercase();
+        if decltype.contains("INT") {
+            Affinity::Integer
+        } else if ["CHAR", "CLOB", "TEXT"]
+            .iter()
+            .any(|s| decltype.contains(s))
+        {
+            Affinity::Text
+        } else if decltype.contains("BLOB") || decltype.is_empty() {
+            Affinity::Blob
+        } else if ["REAL", "FLOA", "DOUB"]
+            .iter()
+            .any(|s| decltype.contains(s))
+        {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    #[test]
+    fn test_compare_merge_matches_order_by() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        for sql in [
+            "CREATE TABLE a (x);",
+            "CREATE TABLE b (x);",
+            "INSERT INTO a VALUES (NULL), (1), (2.5), (-1e300), ('abc'), ('B'), (x'00ff'), \
+            (9223372036854775807), ('x  '), (x'');",
+            "INSERT INTO b VALUES (2), (2.0), (9223372036854775807.0), ('ABD'), ('a'), ('x'), \
+            (x'00'), (NULL), (-3), ('');",
+        ] {
+            conn.execute(sql, ()).unwrap();
+        }
+        let query = |sql: &str| {
+            conn.query_stream(sql, (), |stmt| Ok(stmt.column_value(0).dup()))
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap()
+        };
+
+        for (collation, name) in [
+            (Collation::Binary, "BINARY"),
+            (Collation::NoCase, "NOCASE"),
+            (Collation::RTrim, "RTRIM"),
+        ] {
+            let a = query(&format!("SELECT x FROM a ORDER BY x COLLATE {};", name));
+            let b = query(&format!("SELECT x FROM b ORDER BY x COLLATE {};", name));
+            let want = query(&format!(
+                "SELECT x FROM (SELECT x FROM a UNION ALL SELECT x FROM b) ORDER BY x COLLATE {};",
+                name
+            ));
+
+            let mut merged = Vec::with_capacity(a.len() + b.len());
+            let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+            while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+                if y.compare(x, collation).is_lt() {
+                    merged.push(b.next().unwrap());
+                } else {
+                    merged.push(a.next().unwrap());
+                }
+            }
+            merged.extend(a.chain(b));
+
+            assert_eq!(merged.len(), want.len());
+            for (i, (got, want)) in merged.iter().zip(&want).enumerate() {
+                assert_eq!(
+                    got.compare(want, collation),
+                    Ordering::Equal,
+                    "{}: row {}: got {:?}, want {:?}",
+                    name,
+                    i,
+                    got.r#type(),
+                    want.r#type()
+                );
+            }
+            for pair in want.windows(2) {
+                assert!(pair[0].compare(&pair[1], collation).is_le());
+            }
+        }
+    }
+
+    #[test]
+    fn test_compare_rows() {
+        let conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT 1, 'a', 1, 'B', 1;")
+            .0
+            .unwrap()
+            .unwrap();
+        stmt.step().unwrap();
+        let row = (0..5)
+            .map(|i| stmt.column_value(i).dup())
+            .collect::<Vec<_>>();
+        let (x, y) = (&row[..2], &row[2..4]);
+        assert_eq!(compare_rows(x, y, Collation::Binary), Ordering::Greater);
+        assert_eq!(compare_rows(x, y, Collation::NoCase), Ordering::Less);
+        assert_eq!(
+            compare_rows(&row[..1], x, Collation::Binary),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_rows(&row[..1], &row[4..], Collation::Binary),
+            Ordering::Equal
+        );
+    }
+}
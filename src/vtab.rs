@@ -0,0 +1,1457 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+use libsqlite3_sys::{
+    sqlite3, sqlite3_context, sqlite3_create_module_v2, sqlite3_declare_vtab, sqlite3_free,
+    sqlite3_index_info, sqlite3_int64, sqlite3_malloc64, sqlite3_module, sqlite3_value,
+    sqlite3_vtab, sqlite3_vtab_config, sqlite3_vtab_cursor, sqlite3_vtab_on_conflict, SQLITE_FAIL,
+    SQLITE_IGNORE, SQLITE_OK, SQLITE_REPLACE, SQLITE_ROLLBACK, SQLITE_VTAB_CONSTRAINT_SUPPORT,
+};
+
+use crate::*;
+
+/// A [virtual table] implementation
+/// registered with [`Connection::create_module`].
+///
+/// SQLite calls [`connect`][VirtualTable::connect]
+/// to create an instance for each `CREATE VIRTUAL TABLE` statement
+/// and each time a connection opens a database that contains one.
+/// Each query then asks the table for a query plan with
+/// [`best_index`][VirtualTable::best_index]
+/// and reads rows through a [cursor][VirtualTableCursor]
+/// returned by [`open`][VirtualTable::open].
+///
+/// Writable tables should also implement the transaction methods,
+/// such as [`rollback`][VirtualTable::rollback]
+/// and [`rollback_to`][VirtualTable::rollback_to],
+/// so that SQLite can undo a statement that fails partway through.
+/// The default implementations do nothing.
+///
+/// [virtual table]: https://www.sqlite.org/vtab.html
+///
+/// # Example
+///
+/// ```
+/// # use std::ffi::CStr;
+/// # use zombiezen_sqlite::{
+/// #     Connection, Context, IndexInfo, OpenFlags, ProtectedValue, Result, VTabContext,
+/// #     VirtualTable, VirtualTableCursor,
+/// # };
+/// /// A table of the integers from 1 to 10.
+/// struct Ten;
+///
+/// struct TenCursor(i64);
+///
+/// impl VirtualTable for Ten {
+///     type Aux = ();
+///     type Cursor = TenCursor;
+///
+///     fn connect(ctx: &mut VTabContext, _aux: &(), _args: &[&str]) -> Result<Self> {
+///         ctx.declare_vtab("CREATE TABLE x(n INTEGER);")?;
+///         Ok(Ten)
+///     }
+///
+///     fn best_index(&self, info: &mut IndexInfo<'_>) -> Result<()> {
+///         info.set_estimated_rows(10);
+///         Ok(())
+///     }
+///
+///     fn open(&self) -> Result<TenCursor> {
+///         Ok(TenCursor(1))
+///     }
+/// }
+///
+/// impl VirtualTableCursor for TenCursor {
+///     fn filter(&mut self, _idx_num: i32, _args: &mut [ProtectedValue<'_>]) -> Result<()> {
+///         self.0 = 1;
+///         Ok(())
+///     }
+///
+///     fn next(&mut self) -> Result<()> {
+///         self.0 += 1;
+///         Ok(())
+///     }
+///
+///     fn eof(&self) -> bool {
+///         self.0 > 10
+///     }
+///
+///     fn column(&self, ctx: &mut Context, _i: usize) -> Result<()> {
+///         ctx.result_i64(self.0);
+///         Ok(())
+///     }
+///
+///     fn rowid(&self) -> Result<i64> {
+///         Ok(self.0)
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut conn = Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY)?;
+/// conn.create_module::<Ten>(CStr::from_bytes_with_nul(b"ten\0")?, ())?;
+/// conn.execute("CREATE VIRTUAL TABLE temp.ten USING ten;", ())?;
+/// let sum = conn.query_row("SELECT sum(n) FROM ten;", (), |stmt| Ok(stmt.column_i64(0)))?;
+/// assert_eq!(sum, Some(55));
+/// # Ok(())
+/// # }
+/// ```
+pub trait VirtualTable: Sized + 'static {
+    /// The type of the data given to [`Connection::create_module`],
+    /// which is shared by every instance of the table.
+    type Aux: 'static;
+
+    /// The type of the table's cursors.
+    type Cursor: VirtualTableCursor;
+
+    /// Creates an instance of the table.
+    /// `aux` is the data given to [`Connection::create_module`].
+    /// `args` are the module name, the database name, the table name,
+    /// and then the arguments given in the `CREATE VIRTUAL TABLE` statement, if any.
+    ///
+    /// Implementations must call [`VTabContext::declare_vtab`]
+    /// to declare the table's columns.
+    #[doc(alias = "xCreate")]
+    #[doc(alias = "xConnect")]
+    fn connect(ctx: &mut VTabContext, aux: &Self::Aux, args: &[&str]) -> Result<Self>;
+
+    /// Called instead of dropping the instance
+    /// when the table is removed with `DROP TABLE`,
+    /// so that the table can delete any storage that backs it.
+    /// If `destroy` returns an error, the table is not dropped.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xDestroy")]
+    fn destroy(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Chooses a query plan for a scan of the table.
+    /// The plan is described to SQLite by modifying `info`
+    /// and is passed back to [`VirtualTableCursor::filter`] as the index number
+    /// and the constraint values given argument positions.
+    #[doc(alias = "xBestIndex")]
+    fn best_index(&self, info: &mut IndexInfo<'_>) -> Result<()>;
+
+    /// Returns a new cursor for reading the table.
+    #[doc(alias = "xOpen")]
+    fn open(&self) -> Result<Self::Cursor>;
+
+    /// Inserts, updates, or deletes a row.
+    /// For an insert, returns the rowid of the new row;
+    /// otherwise the return value is ignored.
+    /// `on_conflict` is the conflict resolution algorithm of the statement,
+    /// which is only meaningful if the table called
+    /// [`VTabContext::vtab_config_constraint`].
+    ///
+    /// The default implementation returns a [`ResultCode::READONLY`] error.
+    #[doc(alias = "xUpdate")]
+    fn update(&mut self, change: VTabChange<'_>, on_conflict: Conflict) -> Result<i64> {
+        let _ = (change, on_conflict);
+        Err(Error::new(
+            ResultCode::READONLY,
+            "virtual table is read-only",
+        ))
+    }
+
+    /// Starts a transaction on the table.
+    /// SQLite calls `begin` before the first change to the table in a transaction.
+    /// The transaction ends with a call to [`commit`][VirtualTable::commit]
+    /// or [`rollback`][VirtualTable::rollback].
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xBegin")]
+    fn begin(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Starts a two-phase commit of the current transaction.
+    /// If `sync` returns an error, the transaction is rolled back.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xSync")]
+    fn sync(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Commits the current transaction.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xCommit")]
+    fn commit(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Undoes every change made during the current transaction.
+    /// In autocommit mode, SQLite also calls `rollback`
+    /// to undo a statement that fails partway through.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xRollback")]
+    fn rollback(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Marks the current state of the table as savepoint `n`.
+    /// Savepoints are numbered from zero in the order they are opened.
+    /// Inside a transaction, SQLite also opens a savepoint around each statement
+    /// so that a statement that fails partway through can be undone.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xSavepoint")]
+    fn savepoint(&mut self, n: i32) -> Result<()> {
+        let _ = n;
+        Ok(())
+    }
+
+    /// Discards savepoint `n` and any savepoints opened after it,
+    /// keeping the changes made since.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xRelease")]
+    fn release(&mut self, n: i32) -> Result<()> {
+        let _ = n;
+        Ok(())
+    }
+
+    /// Restores the table to its state at savepoint `n`
+    /// and discards any savepoints opened after it.
+    /// Savepoint `n` itself remains open.
+    ///
+    /// The default implementation does nothing.
+    #[doc(alias = "xRollbackTo")]
+    fn rollback_to(&mut self, n: i32) -> Result<()> {
+        let _ = n;
+        Ok(())
+    }
+}
+
+/// A cursor that reads the rows of a [`VirtualTable`].
+pub trait VirtualTableCursor: Sized + 'static {
+    /// Begins a scan of the table using a plan chosen by [`VirtualTable::best_index`].
+    /// `idx_num` is the number set with [`IndexInfo::set_idx_num`]
+    /// and `args` are the values of the constraints
+    /// given argument positions with [`IndexInfo::set_constraint_usage`].
+    /// After `filter` returns, the cursor must be positioned on the first row
+    /// or [`eof`][VirtualTableCursor::eof] must return true.
+    #[doc(alias = "xFilter")]
+    fn filter(&mut self, idx_num: i32, args: &mut [ProtectedValue<'_>]) -> Result<()>;
+
+    /// Advances the cursor to the next row.
+    #[doc(alias = "xNext")]
+    fn next(&mut self) -> Result<()>;
+
+    /// Reports whether the cursor has moved past the last row.
+    #[doc(alias = "xEof")]
+    fn eof(&self) -> bool;
+
+    /// Sets the result of `ctx` to the value of the `i`th column of the current row.
+    /// The leftmost column is number 0.
+    #[doc(alias = "xColumn")]
+    fn column(&self, ctx: &mut Context, i: usize) -> Result<()>;
+
+    /// Returns the rowid of the current row.
+    #[doc(alias = "xRowid")]
+    fn rowid(&self) -> Result<i64>;
+}
+
+/// A change to a row of a [`VirtualTable`]
+/// passed to [`VirtualTable::update`].
+#[derive(Debug)]
+pub enum VTabChange<'a> {
+    /// Delete the row with the given rowid.
+    Delete {
+        /// The rowid of the row to delete.
+        rowid: i64,
+    },
+    /// Insert a new row.
+    Insert {
+        /// The rowid of the new row,
+        /// or `None` if the table should choose one.
+        rowid: Option<i64>,
+        /// The values of the row's columns in order.
+        values: &'a mut [ProtectedValue<'a>],
+    },
+    /// Replace the values of an existing row.
+    Update {
+        /// The rowid of the row before the update.
+        old_rowid: i64,
+        /// The rowid of the row after the update.
+        /// This is usually the same as `old_rowid`.
+        rowid: i64,
+        /// The new values of the row's columns in order.
+        values: &'a mut [ProtectedValue<'a>],
+    },
+}
+
+impl Connection {
+    /// Registers the virtual table module `T` under the given name,
+    /// so that `CREATE VIRTUAL TABLE ... USING name` creates instances of `T`.
+    /// `aux` is passed to every call of [`VirtualTable::connect`]
+    /// and is dropped when the module is replaced or the connection is closed.
+    /// Any existing module with the same name is replaced.
+    #[doc(alias = "sqlite3_create_module_v2")]
+    pub fn create_module<T: VirtualTable>(
+        &mut self,
+        name: &(impl AsRef<CStr> + ?Sized),
+        aux: T::Aux,
+    ) -> Result<()> {
+        let module = Box::into_raw(Box::new(Module::<T> {
+            base: new_module::<T>(),
+            aux,
+        }));
+        // SQLite calls destroy_module if registration fails.
+        let rc = ResultCode(unsafe {
+            sqlite3_create_module_v2(
+                self.as_ptr(),
+                name.as_ref().as_ptr(),
+                ptr::addr_of!((*module).base),
+                module.cast(),
+                Some(destroy_module::<T>),
+            )
+        });
+        if rc.is_success() {
+            Ok(())
+        } else {
+            Err(self.as_ref().error().unwrap())
+        }
+    }
+}
+
+/// The connection passed to [`VirtualTable::connect`].
+#[derive(Debug)]
+pub struct VTabContext {
+    db: Conn,
+}
+
+impl VTabContext {
+    /// Declares the columns of the table with a `CREATE TABLE` statement.
+    /// The table name in the statement is ignored.
+    #[doc(alias = "sqlite3_declare_vtab")]
+    pub fn declare_vtab(&mut self, sql: &str) -> Result<()> {
+        let sql = CString::new(sql).map_err(|_| {
+            Error::new(
+                ResultCode::MISUSE,
+                "virtual table declaration contains a NUL byte",
+            )
+        })?;
+        let rc = ResultCode(unsafe { sqlite3_declare_vtab(self.db.as_ptr(), sql.as_ptr()) });
+        self.check(rc)
+    }
+
+    /// Declares that the table handles `ON CONFLICT` clauses itself.
+    /// [`VirtualTable::update`] is then given the statement's conflict resolution algorithm
+    /// and may return a [`ResultCode::CONSTRAINT`] error for a conflicting row:
+    /// for `OR IGNORE`, SQLite skips the row and continues the statement,
+    /// and for `OR ROLLBACK`, `OR ABORT`, and `OR FAIL`, SQLite handles the error
+    /// as it would for an ordinary table.
+    /// For `OR REPLACE`, the table must replace the conflicting row itself.
+    #[doc(alias = "SQLITE_VTAB_CONSTRAINT_SUPPORT")]
+    pub fn vtab_config_constraint(&mut self) -> Result<()> {
+        let rc = ResultCode(unsafe {
+            sqlite3_vtab_config(self.db.as_ptr(), SQLITE_VTAB_CONSTRAINT_SUPPORT, 1 as c_int)
+        });
+        self.check(rc)
+    }
+
+    /// Declares that the table cannot be used to cause harm,
+    /// so that it may be used from triggers and views
+    /// even when `PRAGMA trusted_schema` is off.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "SQLITE_VTAB_INNOCUOUS")]
+    pub fn vtab_config_innocuous(&mut self) -> Result<()> {
+        let rc = ResultCode(unsafe {
+            sqlite3_vtab_config(self.db.as_ptr(), libsqlite3_sys::SQLITE_VTAB_INNOCUOUS)
+        });
+        self.check(rc)
+    }
+
+    fn check(&self, rc: ResultCode) -> Result<()> {
+        if rc.is_success() {
+            Ok(())
+        } else {
+            Err(self.db.error().unwrap_or_else(|| Error::new(rc, "")))
+        }
+    }
+}
+
+/// The query planning information passed to [`VirtualTable::best_index`].
+#[derive(Debug)]
+#[doc(alias = "sqlite3_index_info")]
+pub struct IndexInfo<'a> {
+    ptr: NonNull<sqlite3_index_info>,
+    phantom: PhantomData<&'a mut sqlite3_index_info>,
+}
+
+impl<'a> IndexInfo<'a> {
+    fn info(&self) -> &sqlite3_index_info {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns the `WHERE` clause constraints on the table's columns.
+    pub fn constraints(&self) -> impl ExactSizeIterator<Item = IndexConstraint> + '_ {
+        let info = self.info();
+        let constraints = if info.nConstraint > 0 {
+            unsafe { slice::from_raw_parts(info.aConstraint, info.nConstraint as usize) }
+        } else {
+            &[]
+        };
+        constraints.iter().map(|c| IndexConstraint {
+            column: c.iColumn,
+            op: ConstraintOp::from_code(c.op),
+            usable: c.usable != 0,
+        })
+    }
+
+    /// Returns the `ORDER BY` terms of the scan
+    /// as column numbers and whether the term is descending.
+    pub fn order_by(&self) -> impl ExactSizeIterator<Item = (i32, bool)> + '_ {
+        let info = self.info();
+        let terms = if info.nOrderBy > 0 {
+            unsafe { slice::from_raw_parts(info.aOrderBy, info.nOrderBy as usize) }
+        } else {
+            &[]
+        };
+        terms.iter().map(|t| (t.iColumn, t.desc != 0))
+    }
+
+    /// Requests that the value of the `i`th [constraint][IndexInfo::constraints]
+    /// be passed to [`VirtualTableCursor::filter`] at (1-based) position `argv_index`.
+    /// If `omit` is true, then SQLite trusts the table to enforce the constraint
+    /// and does not check it again.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is out of range.
+    pub fn set_constraint_usage(&mut self, i: usize, argv_index: i32, omit: bool) {
+        let info = unsafe { self.ptr.as_mut() };
+        assert!(
+            i < info.nConstraint as usize,
+            "constraint index out of range"
+        );
+        let usage = unsafe { &mut *info.aConstraintUsage.add(i) };
+        usage.argvIndex = argv_index;
+        usage.omit = omit as _;
+    }
+
+    /// Sets the number passed to [`VirtualTableCursor::filter`] to identify the plan.
+    pub fn set_idx_num(&mut self, idx_num: i32) {
+        unsafe { self.ptr.as_mut() }.idxNum = idx_num;
+    }
+
+    /// Reports that the scan returns rows in the requested [order][IndexInfo::order_by],
+    /// so SQLite does not need to sort them.
+    pub fn set_order_by_consumed(&mut self, consumed: bool) {
+        unsafe { self.ptr.as_mut() }.orderByConsumed = consumed as c_int;
+    }
+
+    /// Sets the estimated cost of the plan,
+    /// roughly the number of disk accesses needed.
+    pub fn set_estimated_cost(&mut self, cost: f64) {
+        unsafe { self.ptr.as_mut() }.estimatedCost = cost;
+    }
+
+    /// Sets the estimated number of rows returned by the plan.
+    pub fn set_estimated_rows(&mut self, rows: i64) {
+        unsafe { self.ptr.as_mut() }.estimatedRows = rows;
+    }
+
+    /// Reports how the scan's rows will be used
+    /// by a `DISTINCT` or `GROUP BY` clause,
+    /// which determines how much of the [order][IndexInfo::order_by]
+    /// the table must honor when it sets
+    /// [`set_order_by_consumed`][IndexInfo::set_order_by_consumed].
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_vtab_distinct")]
+    pub fn distinct(&self) -> IndexDistinct {
+        match unsafe { libsqlite3_sys::sqlite3_vtab_distinct(self.ptr.as_ptr()) } {
+            1 => IndexDistinct::Grouped,
+            2 => IndexDistinct::Distinct,
+            3 => IndexDistinct::DistinctOrdered,
+            _ => IndexDistinct::Ordered,
+        }
+    }
+
+    /// Reports whether the `i`th [constraint][IndexInfo::constraints]
+    /// is an `IN` operator that the table can process all at once
+    /// with [`handle_in`][IndexInfo::handle_in].
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_vtab_in")]
+    pub fn is_in(&self, i: usize) -> bool {
+        (unsafe { libsqlite3_sys::sqlite3_vtab_in(self.ptr.as_ptr(), i as c_int, -1) }) != 0
+    }
+
+    /// Sets whether the `i`th [constraint][IndexInfo::constraints],
+    /// an `IN` operator, should be passed to [`VirtualTableCursor::filter`]
+    /// as a single value whose right-hand side is read with
+    /// [`ProtectedValue::vtab_in_values`]
+    /// instead of calling `filter` once per value.
+    /// The constraint must also be given an argument position with
+    /// [`set_constraint_usage`][IndexInfo::set_constraint_usage].
+    /// Returns whether the constraint is an `IN` operator that can be handled this way.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_vtab_in")]
+    pub fn handle_in(&mut self, i: usize, handle: bool) -> bool {
+        (unsafe { libsqlite3_sys::sqlite3_vtab_in(self.ptr.as_ptr(), i as c_int, handle as c_int) })
+            != 0
+    }
+}
+
+/// How the rows of a virtual table scan are used,
+/// as returned by [`IndexInfo::distinct`].
+#[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IndexDistinct {
+    /// The rows must be returned in the requested order.
+    Ordered,
+    /// Rows with equal values in the `ORDER BY` columns
+    /// must be adjacent, but may be in any order.
+    Grouped,
+    /// Only one of each set of rows with equal values in the `ORDER BY` columns
+    /// is needed, in any order.
+    Distinct,
+    /// Only one of each set of rows with equal values in the `ORDER BY` columns
+    /// is needed, and the rows must be in the requested order.
+    DistinctOrdered,
+}
+
+impl<'a> ProtectedValue<'a> {
+    /// Returns the values on the right-hand side of an `IN` constraint
+    /// passed to [`VirtualTableCursor::filter`]
+    /// after [`IndexInfo::handle_in`] was called for the constraint.
+    /// Returns a [`ResultCode::MISUSE`] error for any other value.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[doc(alias = "sqlite3_vtab_in_first")]
+    #[doc(alias = "sqlite3_vtab_in_next")]
+    pub fn vtab_in_values(&mut self) -> Result<Vec<DupValue>> {
+        use libsqlite3_sys::{sqlite3_vtab_in_first, sqlite3_vtab_in_next, SQLITE_DONE};
+
+        let mut values = Vec::new();
+        let mut elem = ptr::null_mut();
+        let mut rc = unsafe { sqlite3_vtab_in_first(self.as_ptr(), &mut elem) };
+        while rc == SQLITE_OK {
+            if let Some(elem) = NonNull::new(elem) {
+                values.push(unsafe { UnprotectedValue::new(elem) }.dup());
+            }
+            rc = unsafe { sqlite3_vtab_in_next(self.as_ptr(), &mut elem) };
+        }
+        if rc == SQLITE_DONE {
+            Ok(values)
+        } else {
+            Err(Error::new(
+                ResultCode(rc),
+                "value is not the right-hand side of an IN constraint",
+            ))
+        }
+    }
+}
+
+/// A constraint on a column of a [`VirtualTable`]
+/// returned by [`IndexInfo::constraints`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexConstraint {
+    /// The constrained column, or -1 for the rowid.
+    pub column: i32,
+    /// The constraint's operator.
+    pub op: ConstraintOp,
+    /// Whether the constraint can be used by this plan.
+    /// Constraints that depend on tables later in the join are not usable.
+    pub usable: bool,
+}
+
+/// The operator of an [`IndexConstraint`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ConstraintOp {
+    /// `=`
+    Eq,
+    /// `>`
+    Gt,
+    /// `<=`
+    Le,
+    /// `<`
+    Lt,
+    /// `>=`
+    Ge,
+    /// `MATCH`
+    Match,
+    /// `LIKE`
+    Like,
+    /// `GLOB`
+    Glob,
+    /// `REGEXP`
+    Regexp,
+    /// `!=` or `<>`
+    Ne,
+    /// `IS NOT`
+    IsNot,
+    /// `IS NOT NULL`
+    IsNotNull,
+    /// `IS NULL`
+    IsNull,
+    /// `IS`
+    Is,
+    /// `LIMIT`
+    Limit,
+    /// `OFFSET`
+    Offset,
+    /// An operator with an unrecognized code,
+    /// such as an overloaded function.
+    Other(u8),
+}
+
+impl ConstraintOp {
+    fn from_code(code: u8) -> Self {
+        // Values of the SQLITE_INDEX_CONSTRAINT_* constants,
+        // not all of which are in older bindings.
+        match code {
+            2 => ConstraintOp::Eq,
+            4 => ConstraintOp::Gt,
+            8 => ConstraintOp::Le,
+            16 => ConstraintOp::Lt,
+            32 => ConstraintOp::Ge,
+            64 => ConstraintOp::Match,
+            65 => ConstraintOp::Like,
+            66 => ConstraintOp::Glob,
+            67 => ConstraintOp::Regexp,
+            68 => ConstraintOp::Ne,
+            69 => ConstraintOp::IsNot,
+            70 => ConstraintOp::IsNotNull,
+            71 => ConstraintOp::IsNull,
+            72 => ConstraintOp::Is,
+            73 => ConstraintOp::Limit,
+            74 => ConstraintOp::Offset,
+            code => ConstraintOp::Other(code),
+        }
+    }
+}
+
+/// A [`VirtualTable`] module and its auxiliary data
+/// as registered with SQLite.
+struct Module<T: VirtualTable> {
+    base: sqlite3_module,
+    aux: T::Aux,
+}
+
+/// A [`VirtualTable`] as allocated for SQLite.
+#[repr(C)]
+struct VTab<T> {
+    base: sqlite3_vtab,
+    db: NonNull<sqlite3>,
+    table: T,
+}
+
+/// A [`VirtualTableCursor`] as allocated for SQLite.
+#[repr(C)]
+struct VTabCursor<C> {
+    base: sqlite3_vtab_cursor,
+    cursor: C,
+}
+
+fn new_module<T: VirtualTable>() -> sqlite3_module {
+    sqlite3_module {
+        // Version 2 adds the savepoint methods.
+        iVersion: 2,
+        xCreate: Some(vtab_connect::<T>),
+        xConnect: Some(vtab_connect::<T>),
+        xBestIndex: Some(vtab_best_index::<T>),
+        xDisconnect: Some(vtab_disconnect::<T>),
+        xDestroy: Some(vtab_destroy::<T>),
+        xOpen: Some(vtab_open::<T>),
+        xClose: Some(vtab_close::<T>),
+        xFilter: Some(vtab_filter::<T>),
+        xNext: Some(vtab_next::<T>),
+        xEof: Some(vtab_eof::<T>),
+        xColumn: Some(vtab_column::<T>),
+        xRowid: Some(vtab_rowid::<T>),
+        xUpdate: Some(vtab_update::<T>),
+        xBegin: Some(vtab_begin::<T>),
+        xSync: Some(vtab_sync::<T>),
+        xCommit: Some(vtab_commit::<T>),
+        xRollback: Some(vtab_rollback::<T>),
+        xSavepoint: Some(vtab_savepoint::<T>),
+        xRelease: Some(vtab_release::<T>),
+        xRollbackTo: Some(vtab_rollback_to::<T>),
+        // The remaining methods differ between SQLite versions
+        // and are all optional.
+        ..unsafe { mem::zeroed() }
+    }
+}
+
+unsafe extern "C" fn destroy_module<T: VirtualTable>(module: *mut c_void) {
+    drop(Box::from_raw(module.cast::<Module<T>>()));
+}
+
+unsafe extern "C" fn vtab_connect<T: VirtualTable>(
+    db: *mut sqlite3,
+    module: *mut c_void,
+    argc: c_int,
+    argv: *const *const c_char,
+    pp_vtab: *mut *mut sqlite3_vtab,
+    pz_err: *mut *mut c_char,
+) -> c_int {
+    let db = NonNull::new(db).unwrap();
+    let args = slice::from_raw_parts(argv, argc as usize)
+        .iter()
+        .map(|&arg| CStr::from_ptr(arg).to_string_lossy())
+        .collect::<Vec<_>>();
+    let args = args.iter().map(|arg| arg.as_ref()).collect::<Vec<_>>();
+    let mut ctx = VTabContext { db: Conn::new(db) };
+    let aux = &(*module.cast::<Module<T>>()).aux;
+    match T::connect(&mut ctx, aux, &args) {
+        Ok(table) => {
+            let vtab = Box::new(VTab {
+                base: mem::zeroed(),
+                db,
+                table,
+            });
+            *pp_vtab = Box::into_raw(vtab).cast();
+            SQLITE_OK
+        }
+        Err(err) => {
+            *pz_err = sqlite_string(err.message());
+            err.result_code().0
+        }
+    }
+}
+
+unsafe extern "C" fn vtab_disconnect<T: VirtualTable>(vtab: *mut sqlite3_vtab) -> c_int {
+    let vtab = Box::from_raw(vtab.cast::<VTab<T>>());
+    sqlite3_free(vtab.base.zErrMsg.cast());
+    SQLITE_OK
+}
+
+unsafe extern "C" fn vtab_destroy<T: VirtualTable>(vtab: *mut sqlite3_vtab) -> c_int {
+    match (*vtab.cast::<VTab<T>>()).table.destroy() {
+        Ok(()) => vtab_disconnect::<T>(vtab),
+        Err(err) => vtab_result(vtab, Err(err)),
+    }
+}
+
+unsafe extern "C" fn vtab_best_index<T: VirtualTable>(
+    vtab: *mut sqlite3_vtab,
+    info: *mut sqlite3_index_info,
+) -> c_int {
+    let mut info = IndexInfo {
+        ptr: NonNull::new(info).unwrap(),
+        phantom: PhantomData,
+    };
+    let result = (*vtab.cast::<VTab<T>>()).table.best_index(&mut info);
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_open<T: VirtualTable>(
+    vtab: *mut sqlite3_vtab,
+    pp_cursor: *mut *mut sqlite3_vtab_cursor,
+) -> c_int {
+    match (*vtab.cast::<VTab<T>>()).table.open() {
+        Ok(cursor) => {
+            let cursor = Box::new(VTabCursor {
+                base: mem::zeroed(),
+                cursor,
+            });
+            *pp_cursor = Box::into_raw(cursor).cast();
+            SQLITE_OK
+        }
+        Err(err) => vtab_result(vtab, Err(err)),
+    }
+}
+
+unsafe extern "C" fn vtab_close<T: VirtualTable>(cursor: *mut sqlite3_vtab_cursor) -> c_int {
+    drop(Box::from_raw(cursor.cast::<VTabCursor<T::Cursor>>()));
+    SQLITE_OK
+}
+
+unsafe fn cursor_mut<'a, T: VirtualTable>(cursor: *mut sqlite3_vtab_cursor) -> &'a mut T::Cursor {
+    &mut (*cursor.cast::<VTabCursor<T::Cursor>>()).cursor
+}
+
+unsafe extern "C" fn vtab_filter<T: VirtualTable>(
+    cursor: *mut sqlite3_vtab_cursor,
+    idx_num: c_int,
+    _idx_str: *const c_char,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) -> c_int {
+    let mut args = values_from_argv(argc, argv);
+    let result = cursor_mut::<T>(cursor).filter(idx_num, &mut args);
+    vtab_result((*cursor).pVtab, result)
+}
+
+unsafe extern "C" fn vtab_next<T: VirtualTable>(cursor: *mut sqlite3_vtab_cursor) -> c_int {
+    let result = cursor_mut::<T>(cursor).next();
+    vtab_result((*cursor).pVtab, result)
+}
+
+unsafe extern "C" fn vtab_eof<T: VirtualTable>(cursor: *mut sqlite3_vtab_cursor) -> c_int {
+    cursor_mut::<T>(cursor).eof() as c_int
+}
+
+unsafe extern "C" fn vtab_column<T: VirtualTable>(
+    cursor: *mut sqlite3_vtab_cursor,
+    ctx: *mut sqlite3_context,
+    i: c_int,
+) -> c_int {
+    let mut ctx = Context::new(NonNull::new(ctx).unwrap());
+    match cursor_mut::<T>(cursor).column(&mut ctx, i as usize) {
+        Ok(()) => SQLITE_OK,
+        Err(err) => {
+            ctx.result_error(err.result_code(), err.message());
+            err.result_code().0
+        }
+    }
+}
+
+unsafe extern "C" fn vtab_rowid<T: VirtualTable>(
+    cursor: *mut sqlite3_vtab_cursor,
+    p_rowid: *mut sqlite3_int64,
+) -> c_int {
+    match cursor_mut::<T>(cursor).rowid() {
+        Ok(rowid) => {
+            *p_rowid = rowid;
+            SQLITE_OK
+        }
+        Err(err) => vtab_result((*cursor).pVtab, Err(err)),
+    }
+}
+
+unsafe extern "C" fn vtab_update<T: VirtualTable>(
+    vtab: *mut sqlite3_vtab,
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+    p_rowid: *mut sqlite3_int64,
+) -> c_int {
+    let v = &mut *vtab.cast::<VTab<T>>();
+    let mut args = values_from_argv(argc, argv);
+    let (old, rest) = args.split_first_mut().unwrap();
+    let (is_insert, change) = match rest.split_first_mut() {
+        None => (
+            false,
+            VTabChange::Delete {
+                rowid: old.to_i64(),
+            },
+        ),
+        Some((new_rowid, values)) if old.is_null() => (
+            true,
+            VTabChange::Insert {
+                rowid: (!new_rowid.is_null()).then(|| new_rowid.to_i64()),
+                values,
+            },
+        ),
+        Some((new_rowid, values)) => (
+            false,
+            VTabChange::Update {
+                old_rowid: old.to_i64(),
+                rowid: new_rowid.to_i64(),
+                values,
+            },
+        ),
+    };
+    let on_conflict = match sqlite3_vtab_on_conflict(v.db.as_ptr()) {
+        SQLITE_ROLLBACK => Conflict::Rollback,
+        SQLITE_IGNORE => Conflict::Ignore,
+        SQLITE_FAIL => Conflict::Fail,
+        SQLITE_REPLACE => Conflict::Replace,
+        _ => Conflict::Abort,
+    };
+    match v.table.update(change, on_conflict) {
+        Ok(rowid) => {
+            if is_insert {
+                *p_rowid = rowid;
+            }
+            SQLITE_OK
+        }
+        Err(err) => vtab_result(vtab, Err(err)),
+    }
+}
+
+unsafe fn table_mut<'a, T: VirtualTable>(vtab: *mut sqlite3_vtab) -> &'a mut T {
+    &mut (*vtab.cast::<VTab<T>>()).table
+}
+
+unsafe extern "C" fn vtab_begin<T: VirtualTable>(vtab: *mut sqlite3_vtab) -> c_int {
+    let result = table_mut::<T>(vtab).begin();
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_sync<T: VirtualTable>(vtab: *mut sqlite3_vtab) -> c_int {
+    let result = table_mut::<T>(vtab).sync();
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_commit<T: VirtualTable>(vtab: *mut sqlite3_vtab) -> c_int {
+    let result = table_mut::<T>(vtab).commit();
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_rollback<T: VirtualTable>(vtab: *mut sqlite3_vtab) -> c_int {
+    let result = table_mut::<T>(vtab).rollback();
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_savepoint<T: VirtualTable>(vtab: *mut sqlite3_vtab, n: c_int) -> c_int {
+    let result = table_mut::<T>(vtab).savepoint(n);
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_release<T: VirtualTable>(vtab: *mut sqlite3_vtab, n: c_int) -> c_int {
+    let result = table_mut::<T>(vtab).release(n);
+    vtab_result(vtab, result)
+}
+
+unsafe extern "C" fn vtab_rollback_to<T: VirtualTable>(vtab: *mut sqlite3_vtab, n: c_int) -> c_int {
+    let result = table_mut::<T>(vtab).rollback_to(n);
+    vtab_result(vtab, result)
+}
+
+unsafe fn values_from_argv<'a>(
+    argc: c_int,
+    argv: *mut *mut sqlite3_value,
+) -> Vec<ProtectedValue<'a>> {
+    if argc <= 0 {
+        return Vec::new();
+    }
+    slice::from_raw_parts(argv, argc as usize)
+        .iter()
+        .map(|&ptr| ProtectedValue::new(NonNull::new(ptr).unwrap()))
+        .collect()
+}
+
+/// Converts `result` to a result code,
+/// replacing the error message of `vtab` if it is an error.
+unsafe fn vtab_result(vtab: *mut sqlite3_vtab, result: Result<()>) -> c_int {
+    match result {
+        Ok(()) => SQLITE_OK,
+        Err(err) => {
+            sqlite3_free((*vtab).zErrMsg.cast());
+            (*vtab).zErrMsg = sqlite_string(err.message());
+            err.result_code().0
+        }
+    }
+}
+
+/// Copies `s` (up to the first NUL byte) into memory obtained from SQLite,
+/// as required for error messages that SQLite frees.
+fn sqlite_string(s: &str) -> *mut c_char {
+    let bytes = s.as_bytes();
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    unsafe {
+        let p = sqlite3_malloc64(len as u64 + 1).cast::<c_char>();
+        if !p.is_null() {
+            ptr::copy_nonoverlapping(bytes.as_ptr().cast(), p, len);
+            *p.add(len) = 0;
+        }
+        p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    use zombiezen_const_cstr::{const_cstr, ConstCStr};
+
+    use super::*;
+
+    const MEMORY: ConstCStr = const_cstr!(":memory:");
+
+    /// A writable table of text values keyed by rowid
+    /// that handles conflicts itself.
+    struct KeyValue {
+        rows: Rc<RefCell<BTreeMap<i64, String>>>,
+        committed: BTreeMap<i64, String>,
+        savepoints: Vec<(i32, BTreeMap<i64, String>)>,
+        destroyed: Rc<Cell<usize>>,
+    }
+
+    struct KeyValueCursor {
+        rows: Vec<(i64, String)>,
+        pos: usize,
+        source: Rc<RefCell<BTreeMap<i64, String>>>,
+    }
+
+    impl VirtualTable for KeyValue {
+        type Aux = Rc<Cell<usize>>;
+        type Cursor = KeyValueCursor;
+
+        fn connect(
+            ctx: &mut VTabContext,
+            destroyed: &Rc<Cell<usize>>,
+            args: &[&str],
+        ) -> Result<Self> {
+            assert_eq!(args[..3], ["kv", "main", "kv"]);
+            ctx.declare_vtab("CREATE TABLE x(value TEXT);")?;
+            ctx.vtab_config_constraint()?;
+            Ok(KeyValue {
+                rows: Rc::default(),
+                committed: BTreeMap::new(),
+                savepoints: Vec::new(),
+                destroyed: destroyed.clone(),
+            })
+        }
+
+        fn destroy(&mut self) -> Result<()> {
+            self.destroyed.set(self.destroyed.get() + 1);
+            Ok(())
+        }
+
+        fn best_index(&self, info: &mut IndexInfo<'_>) -> Result<()> {
+            info.set_estimated_rows(self.rows.borrow().len() as i64);
+            Ok(())
+        }
+
+        fn open(&self) -> Result<KeyValueCursor> {
+            Ok(KeyValueCursor {
+                rows: Vec::new(),
+                pos: 0,
+                source: self.rows.clone(),
+            })
+        }
+
+        fn update(&mut self, change: VTabChange<'_>, on_conflict: Conflict) -> Result<i64> {
+            let mut rows = self.rows.borrow_mut();
+            match change {
+                VTabChange::Delete { rowid } => {
+                    rows.remove(&rowid);
+                    Ok(rowid)
+                }
+                VTabChange::Insert { rowid, values } => {
+                    let rowid = rowid.unwrap_or_else(|| rows.keys().last().map_or(1, |k| k + 1));
+                    if rows.contains_key(&rowid) && on_conflict != Conflict::Replace {
+                        return Err(Error::new(
+                            ResultCode::CONSTRAINT,
+                            format!("rowid {} already exists", rowid),
+                        ));
+                    }
+                    let value = values[0].to_text().unwrap().to_string();
+                    rows.insert(rowid, value);
+                    Ok(rowid)
+                }
+                VTabChange::Update { .. } => {
+                    Err(Error::new(ResultCode::READONLY, "updates not supported"))
+                }
+            }
+        }
+
+        fn begin(&mut self) -> Result<()> {
+            self.committed = self.rows.borrow().clone();
+            self.savepoints.clear();
+            Ok(())
+        }
+
+        fn commit(&mut self) -> Result<()> {
+            self.savepoints.clear();
+            Ok(())
+        }
+
+        fn rollback(&mut self) -> Result<()> {
+            *self.rows.borrow_mut() = self.committed.clone();
+            self.savepoints.clear();
+            Ok(())
+        }
+
+        fn savepoint(&mut self, n: i32) -> Result<()> {
+            self.savepoints.retain(|&(i, _)| i < n);
+            self.savepoints.push((n, self.rows.borrow().clone()));
+            Ok(())
+        }
+
+        fn release(&mut self, n: i32) -> Result<()> {
+            self.savepoints.retain(|&(i, _)| i < n);
+            Ok(())
+        }
+
+        fn rollback_to(&mut self, n: i32) -> Result<()> {
+            self.savepoints.retain(|&(i, _)| i <= n);
+            let rows = match self.savepoints.last() {
+                Some((_, rows)) => rows.clone(),
+                None => self.committed.clone(),
+            };
+            *self.rows.borrow_mut() = rows;
+            Ok(())
+        }
+    }
+
+    impl VirtualTableCursor for KeyValueCursor {
+        fn filter(&mut self, _idx_num: i32, _args: &mut [ProtectedValue<'_>]) -> Result<()> {
+            self.rows = self
+                .source
+                .borrow()
+                .iter()
+                .map(|(&k, v)| (k, v.clone()))
+                .collect();
+            self.pos = 0;
+            Ok(())
+        }
+
+        fn next(&mut self) -> Result<()> {
+            self.pos += 1;
+            Ok(())
+        }
+
+        fn eof(&self) -> bool {
+            self.pos >= self.rows.len()
+        }
+
+        fn column(&self, ctx: &mut Context, _i: usize) -> Result<()> {
+            ctx.result_text(self.rows[self.pos].1.as_str());
+            Ok(())
+        }
+
+        fn rowid(&self) -> Result<i64> {
+            Ok(self.rows[self.pos].0)
+        }
+    }
+
+    fn rows(conn: &Conn) -> Vec<(i64, String)> {
+        conn.query("SELECT rowid, value FROM kv ORDER BY rowid;", ())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_vtab_config_constraint() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.create_module::<KeyValue>(const_cstr!("kv").as_cstr(), Rc::default())
+            .unwrap();
+        conn.execute("CREATE VIRTUAL TABLE kv USING kv;", ())
+            .unwrap();
+        conn.execute(
+            "INSERT INTO kv (rowid, value) VALUES (1, 'a'), (2, 'b');",
+            (),
+        )
+        .unwrap();
+
+        let err = conn
+            .execute("INSERT INTO kv (rowid, value) VALUES (1, 'x');", ())
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert!(err.message().contains("rowid 1 already exists"), "{}", err);
+
+        let n = conn
+            .execute(
+                "INSERT OR REPLACE INTO kv (rowid, value) VALUES (2, 'c'), (3, 'd');",
+                (),
+            )
+            .unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(
+            rows(&conn),
+            [
+                (1, String::from("a")),
+                (2, String::from("c")),
+                (3, String::from("d"))
+            ]
+        );
+
+        // With constraint support, OR IGNORE skips the conflicting row
+        // and continues the statement.
+        conn.execute(
+            "INSERT OR IGNORE INTO kv (rowid, value) VALUES (1, 'x'), (4, 'e');",
+            (),
+        )
+        .unwrap();
+        assert_eq!(rows(&conn).len(), 4);
+        assert_eq!(rows(&conn)[0].1, "a");
+
+        conn.execute("DELETE FROM kv WHERE rowid > 2;", ()).unwrap();
+        assert_eq!(rows(&conn).len(), 2);
+        let err = conn.execute("UPDATE kv SET value = 'z';", ()).unwrap_err();
+        assert_eq!(err.result_code(), ResultCode::READONLY);
+        assert!(err.message().contains("updates not supported"), "{}", err);
+    }
+
+    #[test]
+    fn test_vtab_transactions() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let destroyed = Rc::new(Cell::new(0));
+        conn.create_module::<KeyValue>(const_cstr!("kv").as_cstr(), destroyed.clone())
+            .unwrap();
+        conn.execute("CREATE VIRTUAL TABLE kv USING kv;", ())
+            .unwrap();
+        conn.execute("INSERT INTO kv (rowid, value) VALUES (1, 'a');", ())
+            .unwrap();
+
+        // A failed statement in autocommit mode is undone with a rollback.
+        let err = conn
+            .execute(
+                "INSERT OR ABORT INTO kv (rowid, value) VALUES (5, 'x'), (1, 'y');",
+                (),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert_eq!(rows(&conn), [(1, String::from("a"))]);
+
+        // Inside a transaction, only the failed statement is undone.
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("INSERT INTO kv (rowid, value) VALUES (2, 'b');", ())
+            .unwrap();
+        let err = conn
+            .execute(
+                "INSERT OR ABORT INTO kv (rowid, value) VALUES (5, 'x'), (1, 'y');",
+                (),
+            )
+            .unwrap_err();
+        assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+        assert_eq!(
+            rows(&conn),
+            [(1, String::from("a")), (2, String::from("b"))]
+        );
+        conn.execute("SAVEPOINT sp;", ()).unwrap();
+        conn.execute("INSERT INTO kv (rowid, value) VALUES (3, 'c');", ())
+            .unwrap();
+        conn.execute("ROLLBACK TO sp;", ()).unwrap();
+        conn.execute("RELEASE sp;", ()).unwrap();
+        assert_eq!(
+            rows(&conn),
+            [(1, String::from("a")), (2, String::from("b"))]
+        );
+        conn.execute("ROLLBACK;", ()).unwrap();
+        assert_eq!(rows(&conn), [(1, String::from("a"))]);
+
+        conn.execute("BEGIN;", ()).unwrap();
+        conn.execute("INSERT INTO kv (rowid, value) VALUES (2, 'b');", ())
+            .unwrap();
+        conn.execute("COMMIT;", ()).unwrap();
+        assert_eq!(
+            rows(&conn),
+            [(1, String::from("a")), (2, String::from("b"))]
+        );
+
+        assert_eq!(destroyed.get(), 0);
+        conn.execute("DROP TABLE kv;", ()).unwrap();
+        assert_eq!(destroyed.get(), 1);
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[test]
+    fn test_vtab_config_innocuous() {
+        struct Innocuous;
+
+        impl VirtualTable for Innocuous {
+            type Aux = ();
+            type Cursor = KeyValueCursor;
+
+            fn connect(ctx: &mut VTabContext, _aux: &(), args: &[&str]) -> Result<Self> {
+                ctx.declare_vtab("CREATE TABLE x(value TEXT);")?;
+                if args.get(3) == Some(&"innocuous") {
+                    ctx.vtab_config_innocuous()?;
+                }
+                Ok(Innocuous)
+            }
+
+            fn best_index(&self, _info: &mut IndexInfo<'_>) -> Result<()> {
+                Ok(())
+            }
+
+            fn open(&self) -> Result<KeyValueCursor> {
+                Ok(KeyValueCursor {
+                    rows: Vec::new(),
+                    pos: 0,
+                    source: Rc::default(),
+                })
+            }
+        }
+
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.create_module::<Innocuous>(const_cstr!("innocuous").as_cstr(), ())
+            .unwrap();
+        conn.execute(
+            "CREATE VIRTUAL TABLE trusted USING innocuous(innocuous);",
+            (),
+        )
+        .unwrap();
+        conn.execute("CREATE VIRTUAL TABLE untrusted USING innocuous;", ())
+            .unwrap();
+        conn.execute("CREATE VIEW v1 AS SELECT * FROM trusted;", ())
+            .unwrap();
+        conn.execute("CREATE VIEW v2 AS SELECT * FROM untrusted;", ())
+            .unwrap();
+        conn.execute("PRAGMA trusted_schema = OFF;", ()).unwrap();
+
+        conn.execute("SELECT * FROM v1;", ()).unwrap();
+        let err = conn.execute("SELECT * FROM v2;", ()).unwrap_err();
+        assert!(
+            err.message().contains("unsafe use of virtual table"),
+            "{}",
+            err
+        );
+    }
+
+    /// A table of the integers from 1 to 1000
+    /// that handles `IN` constraints on its value in one pass.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    struct Series {
+        stats: Rc<SeriesStats>,
+    }
+
+    /// What the query planner and the cursors of a [`Series`] have seen.
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[derive(Default)]
+    struct SeriesStats {
+        filters: Cell<usize>,
+        distinct: Cell<Option<IndexDistinct>>,
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    struct SeriesCursor {
+        values: Vec<i64>,
+        pos: usize,
+        stats: Rc<SeriesStats>,
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    impl VirtualTable for Series {
+        type Aux = Rc<SeriesStats>;
+        type Cursor = SeriesCursor;
+
+        fn connect(ctx: &mut VTabContext, stats: &Rc<SeriesStats>, _args: &[&str]) -> Result<Self> {
+            ctx.declare_vtab("CREATE TABLE x(value INTEGER);")?;
+            Ok(Series {
+                stats: stats.clone(),
+            })
+        }
+
+        fn best_index(&self, info: &mut IndexInfo<'_>) -> Result<()> {
+            self.stats.distinct.set(Some(info.distinct()));
+            let in_constraint = info.constraints().enumerate().position(|(i, c)| {
+                c.usable && c.column == 0 && c.op == ConstraintOp::Eq && info.is_in(i)
+            });
+            match in_constraint {
+                Some(i) => {
+                    assert!(info.handle_in(i, true));
+                    info.set_constraint_usage(i, 1, true);
+                    info.set_idx_num(1);
+                    info.set_estimated_cost(10.0);
+                }
+                None => info.set_estimated_cost(1000.0),
+            }
+            Ok(())
+        }
+
+        fn open(&self) -> Result<SeriesCursor> {
+            Ok(SeriesCursor {
+                values: Vec::new(),
+                pos: 0,
+                stats: self.stats.clone(),
+            })
+        }
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    impl VirtualTableCursor for SeriesCursor {
+        fn filter(&mut self, idx_num: i32, args: &mut [ProtectedValue<'_>]) -> Result<()> {
+            self.values = if idx_num == 1 {
+                self.stats.filters.set(self.stats.filters.get() + 1);
+                let mut values = args[0]
+                    .vtab_in_values()?
+                    .iter_mut()
+                    .map(|v| v.as_mut().to_i64())
+                    .filter(|n| (1..=1000).contains(n))
+                    .collect::<Vec<_>>();
+                values.sort_unstable();
+                values.dedup();
+                values
+            } else {
+                (1..=1000).collect()
+            };
+            self.pos = 0;
+            Ok(())
+        }
+
+        fn next(&mut self) -> Result<()> {
+            self.pos += 1;
+            Ok(())
+        }
+
+        fn eof(&self) -> bool {
+            self.pos >= self.values.len()
+        }
+
+        fn column(&self, ctx: &mut Context, _i: usize) -> Result<()> {
+            ctx.result_i64(self.values[self.pos]);
+            Ok(())
+        }
+
+        fn rowid(&self) -> Result<i64> {
+            Ok(self.values[self.pos])
+        }
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[test]
+    fn test_vtab_in() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let stats = Rc::<SeriesStats>::default();
+        conn.create_module::<Series>(const_cstr!("series").as_cstr(), stats.clone())
+            .unwrap();
+        conn.execute("CREATE VIRTUAL TABLE temp.series USING series;", ())
+            .unwrap();
+
+        let got: Vec<(i64,)> = conn
+            .query(
+                "SELECT value FROM series WHERE value IN (7, 3, 5, 2000) ORDER BY value;",
+                (),
+            )
+            .unwrap();
+        assert_eq!(got, [(3,), (5,), (7,)]);
+        assert_eq!(stats.filters.get(), 1, "IN filter calls");
+    }
+
+    #[cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+    #[test]
+    fn test_vtab_distinct() {
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        let stats = Rc::<SeriesStats>::default();
+        conn.create_module::<Series>(const_cstr!("series").as_cstr(), stats.clone())
+            .unwrap();
+        conn.execute("CREATE VIRTUAL TABLE temp.series USING series;", ())
+            .unwrap();
+
+        conn.query::<_, (i64,)>("SELECT value FROM series ORDER BY value;", ())
+            .unwrap();
+        assert_eq!(stats.distinct.get(), Some(IndexDistinct::Ordered));
+        conn.query::<_, (i64,)>("SELECT DISTINCT value FROM series;", ())
+            .unwrap();
+        assert_eq!(stats.distinct.get(), Some(IndexDistinct::Distinct));
+        conn.query::<_, (i64, i64)>("SELECT value, count(*) FROM series GROUP BY value;", ())
+            .unwrap();
+        assert_eq!(stats.distinct.get(), Some(IndexDistinct::Grouped));
+    }
+
+    #[test]
+    fn test_vtab_connect_error() {
+        struct Broken;
+
+        impl VirtualTable for Broken {
+            type Aux = ();
+            type Cursor = KeyValueCursor;
+
+            fn connect(_ctx: &mut VTabContext, _aux: &(), _args: &[&str]) -> Result<Self> {
+                Err(Error::new(ResultCode::ERROR, "no schema for you"))
+            }
+
+            fn best_index(&self, _info: &mut IndexInfo<'_>) -> Result<()> {
+                Ok(())
+            }
+
+            fn open(&self) -> Result<KeyValueCursor> {
+                unreachable!()
+            }
+        }
+
+        let mut conn = Connection::open(MEMORY, OpenFlags::default()).unwrap();
+        conn.create_module::<Broken>(const_cstr!("broken").as_cstr(), ())
+            .unwrap();
+        let err = conn
+            .execute("CREATE VIRTUAL TABLE b USING broken;", ())
+            .unwrap_err();
+        assert!(err.message().contains("no schema for you"), "{}", err);
+    }
+}
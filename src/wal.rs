@@ -0,0 +1,331 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::mem;
+use std::ptr::{self, NonNull};
+
+use libsqlite3_sys::{
+    sqlite3, sqlite3_free, sqlite3_malloc, sqlite3_wal_checkpoint_v2, sqlite3_wal_hook,
+    SQLITE_CHECKPOINT_FULL, SQLITE_CHECKPOINT_PASSIVE, SQLITE_CHECKPOINT_RESTART,
+    SQLITE_CHECKPOINT_TRUNCATE,
+};
+
+use crate::connection::DbMutexGuard;
+use crate::*;
+
+pub(crate) type WalHookFn = Box<dyn FnMut(&Conn, &CStr, usize) -> Result<()> + 'static>;
+
+impl Conn {
+    /// Runs a [checkpoint] on the database named `schema`,
+    /// or on all attached databases if `schema` is `None`.
+    ///
+    /// [checkpoint]: https://www.sqlite.org/wal.html#ckpt
+    #[doc(alias = "sqlite3_wal_checkpoint_v2")]
+    pub fn wal_checkpoint(
+        &self,
+        schema: Option<&CStr>,
+        mode: CheckpointMode,
+    ) -> Result<Checkpoint> {
+        let mut wal_frames: c_int = -1;
+        let mut checkpointed_frames: c_int = -1;
+        let _guard = DbMutexGuard::lock(self.as_ptr());
+        let rc = ResultCode(unsafe {
+            sqlite3_wal_checkpoint_v2(
+                self.as_ptr(),
+                schema.map_or(ptr::null(), CStr::as_ptr),
+                mode as c_int,
+                &mut wal_frames,
+                &mut checkpointed_frames,
+            )
+        });
+        if !rc.is_success() {
+            return Err(self.error().unwrap_or_else(|| rc.to_result().unwrap_err()));
+        }
+        Ok(Checkpoint {
+            wal_frames: wal_frames.try_into().unwrap_or(0),
+            checkpointed_frames: checkpointed_frames.try_into().unwrap_or(0),
+        })
+    }
+
+    /// Calls `f` and returns its result along with the number of frames
+    /// that it appended to the main database's write-ahead log.
+    /// Each frame is one page written by a commit,
+    /// so this measures how many pages the transactions in `f` dirtied,
+    /// which can help diagnose write-heavy access patterns.
+    ///
+    /// The size of the log is read before and after `f` runs
+    /// with [passive][CheckpointMode::Passive] checkpoints,
+    /// which never wait for other connections,
+    /// but do copy frames into the database if they can.
+    /// Once a checkpoint has copied the entire log,
+    /// the next write restarts the log from the beginning
+    /// unless another connection is reading from it.
+    /// If the log is shorter after `f` runs than before,
+    /// then it must have restarted:
+    /// [`WalFrames::reset`] is set
+    /// and only the frames written since the restart are counted.
+    /// A restart followed by more frames than the log held before `f` ran
+    /// cannot be detected this way,
+    /// so the count is too low by the size of the log before `f` ran.
+    /// Returns a [`ResultCode::MISUSE`] error if the main database is not in WAL mode.
+    pub fn measure_wal_frames<T>(
+        &self,
+        f: impl FnOnce(&Conn) -> Result<T>,
+    ) -> Result<(T, WalFrames)> {
+        let journal_mode = self
+            .query_row("PRAGMA main.journal_mode;", (), |stmt| {
+                Ok(stmt.column_text(0).to_string_lossy().into_owned())
+            })?
+            .unwrap_or_default();
+        if !journal_mode.eq_ignore_ascii_case("wal") {
+            return Err(Error::new(
+                ResultCode::MISUSE,
+                format!(
+                    "measure_wal_frames requires WAL mode (journal mode is {})",
+                    journal_mode
+                ),
+            ));
+        }
+        let main = CString::new("main").unwrap();
+        let before = self.wal_checkpoint(Some(&main), CheckpointMode::Passive)?;
+        let value = f(self)?;
+        let after = self.wal_checkpoint(Some(&main), CheckpointMode::Passive)?;
+        let frames = match after.wal_frames.checked_sub(before.wal_frames) {
+            Some(n) => WalFrames {
+                frames: n,
+                reset: false,
+            },
+            None => WalFrames {
+                frames: after.wal_frames,
+                reset: true,
+            },
+        };
+        Ok((value, frames))
+    }
+}
+
+impl Connection {
+    /// Register a callback that is invoked each time a transaction is committed
+    /// to a database in [WAL mode],
+    /// replacing any previously set callback on the connection.
+    /// The callback is passed the connection,
+    /// the name of the database that was written to,
+    /// and the number of frames currently in the write-ahead log.
+    /// If the callback returns an error,
+    /// its result code is returned from the commit,
+    /// although the transaction has already been committed.
+    ///
+    /// Setting a WAL hook disables SQLite's [automatic checkpointing],
+    /// which is implemented in terms of the same callback.
+    ///
+    /// [WAL mode]: https://www.sqlite.org/wal.html
+    /// [automatic checkpointing]: https://www.sqlite.org/wal.html#automatic_checkpoint
+    #[doc(alias = "sqlite3_wal_hook")]
+    pub fn set_wal_hook(&mut self, f: impl FnMut(&Conn, &CStr, usize) -> Result<()> + 'static) {
+        const BOX_SIZE: c_int = mem::size_of::<WalHookFn>() as c_int;
+        let f: WalHookFn = Box::new(f);
+        unsafe {
+            let user_data = sqlite3_malloc(BOX_SIZE).cast::<WalHookFn>();
+            ptr::write(user_data, f);
+            sqlite3_wal_hook(self.as_ptr(), Some(wal_hook_callback), user_data.cast());
+            free_wal_hook(mem::replace(&mut self.wal_hook, user_data));
+        }
+    }
+
+    /// Remove the WAL hook.
+    /// This does not re-enable automatic checkpointing.
+    pub fn clear_wal_hook(&mut self) {
+        unsafe {
+            sqlite3_wal_hook(self.as_ptr(), None, ptr::null_mut());
+            free_wal_hook(mem::replace(&mut self.wal_hook, ptr::null_mut()));
+        }
+    }
+
+    /// Replaces automatic checkpointing with a [WAL hook][Connection::set_wal_hook]
+    /// that runs a [passive][CheckpointMode::Passive] checkpoint
+    /// after any commit that leaves at least `frames_threshold` frames
+    /// in the write-ahead log.
+    /// `f` is called with the result of each checkpoint.
+    pub fn checkpoint_on_idle(
+        &mut self,
+        frames_threshold: usize,
+        mut f: impl FnMut(Result<Checkpoint>) + 'static,
+    ) {
+        self.set_wal_hook(move |conn, schema, n_frames| {
+            if n_frames >= frames_threshold {
+                f(conn.wal_checkpoint(Some(schema), CheckpointMode::Passive));
+            }
+            Ok(())
+        });
+    }
+}
+
+unsafe extern "C" fn wal_hook_callback(
+    user_data: *mut c_void,
+    db: *mut sqlite3,
+    schema: *const c_char,
+    n_frames: c_int,
+) -> c_int {
+    let f = user_data.cast::<WalHookFn>().as_mut().unwrap();
+    let conn = Conn::new(NonNull::new(db).unwrap());
+    match f(
+        &conn,
+        CStr::from_ptr(schema),
+        n_frames.try_into().unwrap_or(0),
+    ) {
+        Ok(()) => ResultCode::OK.0,
+        Err(err) => err.result_code().0,
+    }
+}
+
+unsafe fn free_wal_hook(p: *mut WalHookFn) {
+    if p.is_null() {
+        return;
+    }
+    ptr::drop_in_place(p);
+    sqlite3_free(p.cast());
+}
+
+/// A [checkpoint] mode for [`Conn::wal_checkpoint`].
+///
+/// [checkpoint]: https://www.sqlite.org/c3ref/wal_checkpoint_v2.html
+#[repr(i32)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CheckpointMode {
+    /// Checkpoint as many frames as possible without waiting for any readers or writers.
+    Passive = SQLITE_CHECKPOINT_PASSIVE,
+    /// Wait for writers and for readers of older snapshots,
+    /// then checkpoint all frames.
+    Full = SQLITE_CHECKPOINT_FULL,
+    /// Same as [`CheckpointMode::Full`],
+    /// but also wait for readers so that the next writer restarts the log.
+    Restart = SQLITE_CHECKPOINT_RESTART,
+    /// Same as [`CheckpointMode::Restart`],
+    /// but also truncate the log file to zero bytes.
+    Truncate = SQLITE_CHECKPOINT_TRUNCATE,
+}
+
+/// The result of a successful [`Conn::wal_checkpoint`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Checkpoint {
+    /// The number of frames in the write-ahead log.
+    /// Zero if the database is not in WAL mode.
+    pub wal_frames: usize,
+    /// The number of frames in the write-ahead log
+    /// that have been copied into the database.
+    pub checkpointed_frames: usize,
+}
+
+/// The number of write-ahead log frames written while
+/// [`Conn::measure_wal_frames`] ran its function.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WalFrames {
+    /// The number of frames appended to the write-ahead log.
+    /// If [`reset`][WalFrames::reset] is true,
+    /// then only the frames appended since the log restarted are counted.
+    pub frames: usize,
+    /// Whether the write-ahead log was restarted from the beginning.
+    pub reset: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_checkpoint_on_idle() {
+        let dir = TempDir::new("checkpoint-on-idle");
+        let mut conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        let mode = conn
+            .query_row("PRAGMA journal_mode = wal;", (), |stmt| {
+                Ok(stmt.column_text(0).unwrap().to_string())
+            })
+            .unwrap();
+        assert_eq!(mode.as_deref(), Some("wal"));
+        conn.execute("CREATE TABLE t (x);", ()).unwrap();
+
+        const THRESHOLD: usize = 20;
+        let checkpoints = Rc::new(RefCell::new(Vec::new()));
+        {
+            let checkpoints = checkpoints.clone();
+            conn.checkpoint_on_idle(THRESHOLD, move |result| {
+                checkpoints.borrow_mut().push(result.unwrap());
+            });
+        }
+        for i in 0..100 {
+            conn.execute("INSERT INTO t VALUES (?1);", (i,)).unwrap();
+        }
+
+        let final_state = conn.wal_checkpoint(None, CheckpointMode::Passive).unwrap();
+        drop(conn);
+
+        let checkpoints = checkpoints.borrow();
+        assert!(!checkpoints.is_empty());
+        for c in checkpoints.iter() {
+            assert!(c.wal_frames >= THRESHOLD, "{:?}", c);
+            assert_eq!(c.checkpointed_frames, c.wal_frames);
+        }
+        assert!(final_state.wal_frames < THRESHOLD, "{:?}", final_state);
+    }
+
+    #[test]
+    fn test_measure_wal_frames() {
+        let dir = TempDir::new("measure-wal-frames");
+        let conn = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        assert_eq!(
+            conn.measure_wal_frames(|_| Ok(()))
+                .unwrap_err()
+                .result_code(),
+            ResultCode::MISUSE
+        );
+        conn.execute("PRAGMA journal_mode = wal;", ()).unwrap();
+        conn.execute("CREATE TABLE t (x TEXT);", ()).unwrap();
+        conn.execute("CREATE INDEX t_x ON t (x);", ()).unwrap();
+
+        let (n, small) = conn
+            .measure_wal_frames(|conn| conn.execute("INSERT INTO t VALUES ('a');", ()))
+            .unwrap();
+        assert_eq!(n, 1);
+        let (n, large) = conn
+            .measure_wal_frames(|conn| {
+                conn.execute(
+                    "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 500) \
+                    INSERT INTO t SELECT printf('%.200c', n) FROM c;",
+                    (),
+                )
+            })
+            .unwrap();
+        assert_eq!(n, 500);
+        assert!(small.frames > 0, "small = {:?}", small);
+        assert!(
+            large.frames > small.frames * 10,
+            "small = {:?}, large = {:?}",
+            small,
+            large
+        );
+        let (_, none) = conn
+            .measure_wal_frames(|conn| conn.execute("SELECT count(*) FROM t;", ()))
+            .unwrap();
+        assert_eq!(none.frames, 0);
+        assert!(!none.reset);
+
+        // A reader whose snapshot includes log frames
+        // keeps the log from restarting,
+        // but does not block the measurement.
+        conn.execute("INSERT INTO t VALUES ('b');", ()).unwrap();
+        let reader = Connection::open(dir.join_cstr("test.db"), OpenFlags::default()).unwrap();
+        reader.execute("BEGIN;", ()).unwrap();
+        reader.execute("SELECT count(*) FROM t;", ()).unwrap();
+        let (_, shared) = conn
+            .measure_wal_frames(|conn| conn.execute("INSERT INTO t VALUES ('c');", ()))
+            .unwrap();
+        assert!(shared.frames > 0, "shared = {:?}", shared);
+        assert!(!shared.reset);
+        reader.execute("COMMIT;", ()).unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! Attaching SQL to errors is a process-wide setting,
+//! so this test runs in its own binary
+//! to keep the setting from leaking into unrelated tests.
+
+use std::ffi::CStr;
+
+use zombiezen_sqlite::{set_error_sql, Connection, OpenFlags, ResultCode};
+
+#[test]
+fn test_error_sql() {
+    let conn =
+        Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+    conn.execute("CREATE TABLE users (name TEXT NOT NULL UNIQUE);", ())
+        .unwrap();
+    assert_eq!(
+        conn.execute("INSERT INTO users (name) VALUES (?1);", ("alice",))
+            .unwrap(),
+        1
+    );
+
+    // Off by default.
+    let err = conn
+        .execute("INSERT INTO users (name) VALUES (?1);", ("alice",))
+        .unwrap_err();
+    assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+    assert_eq!(err.sql(), None);
+
+    set_error_sql(true);
+    let err = conn
+        .execute("INSERT INTO users (name) VALUES (?1);", ("alice",))
+        .unwrap_err();
+    assert_eq!(err.result_code().to_primary(), ResultCode::CONSTRAINT);
+    assert_eq!(err.sql(), Some("INSERT INTO users (name) VALUES (?1);"));
+
+    // Literals are redacted.
+    let err = conn
+        .execute("INSERT INTO users (name) VALUES ('alice');", ())
+        .unwrap_err();
+    assert_eq!(err.sql(), Some("INSERT INTO users (name) VALUES (?);"));
+
+    // Errors during preparation also carry the statement.
+    let err = conn
+        .execute("SELECT * FROM nonexistent WHERE x = 'secret';", ())
+        .unwrap_err();
+    assert_eq!(err.result_code(), ResultCode::ERROR);
+    assert_eq!(err.sql(), Some("SELECT * FROM nonexistent WHERE x = ?;"));
+    let (result, tail) = conn.prepare("values (; select 42;");
+    assert_eq!(tail, " select 42;");
+    assert_eq!(result.unwrap_err().sql(), Some("values (;"));
+
+    set_error_sql(false);
+    let err = conn.execute("SELECT * FROM nonexistent;", ()).unwrap_err();
+    assert_eq!(err.sql(), None);
+}
@@ -0,0 +1,46 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! The heap limit is global to the process,
+//! so this test runs in its own binary
+//! to keep it from failing allocations in unrelated tests.
+
+#![cfg(any(feature = "modern", feature = "buildtime_bindgen"))]
+
+use std::ffi::CStr;
+
+use libsqlite3_sys::sqlite3_hard_heap_limit64;
+use zombiezen_sqlite::{Connection, OpenFlags, ResultCode};
+
+#[test]
+fn test_with_memory_limit() {
+    // Builds a ~10 MB string.
+    const HUNGRY: &str =
+        "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100000) \
+        SELECT length(group_concat(hex(zeroblob(50)))) FROM c;";
+    let conn =
+        Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+    let prev_hard = unsafe { sqlite3_hard_heap_limit64(-1) };
+
+    let err = conn
+        .with_memory_limit(1 << 20, |conn| {
+            assert!(unsafe { sqlite3_hard_heap_limit64(-1) } > 0);
+            // Nesting does not deadlock.
+            conn.with_memory_limit(2 << 20, |_| Ok(()))?;
+            conn.query_row(HUNGRY, (), |stmt| Ok(stmt.column_i64(0)))
+        })
+        .unwrap_err();
+    assert_eq!(err.result_code(), ResultCode::NOMEM);
+    assert_eq!(unsafe { sqlite3_hard_heap_limit64(-1) }, prev_hard);
+
+    let got = conn
+        .query_row(HUNGRY, (), |stmt| Ok(stmt.column_i64(0)))
+        .unwrap();
+    assert!(got.unwrap() > 1 << 20);
+    let got = conn
+        .with_memory_limit(1 << 20, |conn| {
+            conn.query_row("SELECT ?1 * 2;", (21,), |stmt| Ok(stmt.column_i64(0)))
+        })
+        .unwrap();
+    assert_eq!(got, Some(42));
+}
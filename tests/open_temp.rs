@@ -0,0 +1,66 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! SQLite's temporary file directory is global to the process,
+//! so this test runs in its own binary
+//! to keep temporary files from unrelated tests out of its directory.
+
+use std::fs;
+
+use zombiezen_sqlite::{Connection, Quote};
+
+#[allow(dead_code)]
+#[path = "../src/test_util.rs"]
+mod test_util;
+
+use test_util::TempDir;
+
+/// Returns the number of open file descriptors that refer to files in `dir`.
+#[cfg(target_os = "linux")]
+fn open_files_in(dir: &std::path::Path) -> usize {
+    fs::read_dir("/proc/self/fd")
+        .unwrap()
+        .filter_map(|entry| fs::read_link(entry.ok()?.path()).ok())
+        .filter(|target| target.starts_with(dir))
+        .count()
+}
+
+#[test]
+fn test_open_temp_removes_file() {
+    let temp_dir = TempDir::new("open-temp");
+    let dir = temp_dir.path().canonicalize().unwrap();
+
+    let conn = Connection::open_temp().unwrap();
+    conn.execute(
+        &format!(
+            "PRAGMA temp_store_directory = {};",
+            Quote::as_text(dir.to_str().unwrap())
+        ),
+        (),
+    )
+    .unwrap();
+    conn.execute("PRAGMA cache_size = 10;", ()).unwrap();
+    conn.execute("CREATE TABLE t (x TEXT);", ()).unwrap();
+    // Much larger than the cache, so the database spills to disk.
+    conn.execute(
+        "WITH RECURSIVE c(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM c WHERE n < 5000) \
+        INSERT INTO t SELECT printf('%.200c', n) FROM c;",
+        (),
+    )
+    .unwrap();
+    #[cfg(target_os = "linux")]
+    assert!(
+        open_files_in(&dir) > 0,
+        "database did not spill to {:?}",
+        dir
+    );
+
+    drop(conn);
+    #[cfg(target_os = "linux")]
+    assert_eq!(open_files_in(&dir), 0);
+    let left = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect::<Vec<_>>();
+    assert!(left.is_empty(), "files left in {:?}: {:?}", dir, left);
+}
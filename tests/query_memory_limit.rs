@@ -0,0 +1,40 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! The memory limit is measured with SQLite's process-wide memory counter,
+//! so this test runs in its own binary
+//! to keep allocations by unrelated tests from affecting it.
+
+use std::ffi::CStr;
+
+use zombiezen_sqlite::{Connection, OpenFlags, ResultCode};
+
+#[test]
+fn test_query_row_memory_limit() {
+    const LIMIT: usize = 1 << 20;
+    let mut conn =
+        Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+
+    // Builds a ~10 MB string.
+    let err = conn
+        .query_row_memory_limit(
+            "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100000) \
+            SELECT length(group_concat(hex(zeroblob(50)))) FROM c;",
+            (),
+            LIMIT,
+            |stmt| Ok(stmt.column_i64(0)),
+        )
+        .unwrap_err();
+    assert_eq!(err.result_code(), ResultCode::INTERRUPT);
+    assert!(err.message().contains("memory limit"), "{}", err);
+
+    let got = conn
+        .query_row_memory_limit(
+            "SELECT ?1 * 2;",
+            (21,),
+            LIMIT,
+            |stmt| Ok(stmt.column_i64(0)),
+        )
+        .unwrap();
+    assert_eq!(got, Some(42));
+}
@@ -0,0 +1,68 @@
+// Copyright 2026 Ross Light
+// SPDX-License-Identifier: MIT
+
+//! SQLite's memory high-water mark is global to the process,
+//! so this test runs in its own binary
+//! to keep allocations by unrelated tests out of the measurement.
+
+use std::cell::Cell;
+use std::ffi::CStr;
+use std::rc::Rc;
+
+use libsqlite3_sys::{sqlite3_memory_highwater, sqlite3_memory_used};
+use zombiezen_const_cstr::const_cstr;
+use zombiezen_sqlite::{Connection, DataType, FunctionFlags, OpenFlags, StepResult};
+
+#[test]
+fn test_result_blob_with_peak_memory() {
+    const SIZE: usize = 1 << 20;
+    let mut conn =
+        Connection::open(<&CStr>::default(), OpenFlags::default() | OpenFlags::MEMORY).unwrap();
+    // The callback only records what it saw;
+    // the assertions happen after the query returns.
+    let peak_in_callback = Rc::new(Cell::new(0i64));
+    {
+        let peak_in_callback = peak_in_callback.clone();
+        conn.create_scalar_function(
+            const_cstr!("big_blob").as_cstr(),
+            Some(1),
+            // Deterministic functions with constant arguments
+            // are evaluated once and then copied into the result row.
+            FunctionFlags::default(),
+            move |mut ctx, args| {
+                let n = args.next().unwrap().to_i64() as usize;
+                ctx.result_blob_with(n, |buf| {
+                    for (i, b) in buf.iter_mut().enumerate() {
+                        *b = i as u8;
+                    }
+                    peak_in_callback.set(unsafe { sqlite3_memory_highwater(0) });
+                });
+            },
+        )
+        .unwrap();
+    }
+    let mut stmt = conn
+        .prepare("SELECT big_blob(?1);")
+        .0
+        .unwrap()
+        .expect("statement is not empty");
+    stmt.bind_i64(1, SIZE as i64).unwrap();
+
+    let before = unsafe { sqlite3_memory_used() };
+    unsafe { sqlite3_memory_highwater(1) };
+    assert_eq!(stmt.step().unwrap(), StepResult::Row);
+    let peak = unsafe { sqlite3_memory_highwater(0) } - before;
+
+    // The buffer must have been allocated by the time the callback ran,
+    // and a copy of the result would require at least twice the blob's size.
+    assert!(
+        peak_in_callback.get() - before >= SIZE as i64,
+        "peak in callback = {}",
+        peak_in_callback.get() - before
+    );
+    assert!(peak < 2 * SIZE as i64, "peak = {}", peak);
+    assert_eq!(stmt.column_type(0), DataType::Blob);
+    let blob = stmt.column_blob(0);
+    assert_eq!(blob.len(), SIZE);
+    assert!(blob.iter().enumerate().all(|(i, &b)| b == i as u8));
+}